@@ -50,5 +50,20 @@ fn main() -> Result<(), Box<dyn Error>> {
             }
         }
     }
+    #[cfg(feature = "ffi")]
+    {
+        println!("cargo:rerun-if-changed=src/ffi.rs");
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+        let crate_dir = std::env::var("CARGO_MANIFEST_DIR")?;
+        let out_dir = std::env::var("OUT_DIR")?;
+        let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir))?;
+        // Only the bindings, not the public items of the other modules. The checked in header is
+        // compared to this by the `ffi_header` test.
+        cbindgen::Builder::new()
+            .with_src(format!("{}/src/ffi.rs", crate_dir))
+            .with_config(config)
+            .generate()?
+            .write_to_file(format!("{}/fractal_renderer.h", out_dir));
+    }
     Ok(())
 }
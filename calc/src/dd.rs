@@ -0,0 +1,258 @@
+//! Double-double arithmetic: numbers stored as the unevaluated sum of two `f64`s, which gives
+//! about 32 significant digits.
+//!
+//! This lets renders zoom to about 1e28 instead of 1e13, at a fraction of the speed.
+//! The operations use error-free transformations, see Hida, Li & Bailey, "Library for
+//! Double-Double and Quad-Double Arithmetic" (2007). They don't use fused multiply-add, so they
+//! work the same on every target.
+
+use crate::Imaginary;
+use core::ops::{Add, Div, Mul, Neg, Sub};
+#[cfg(not(feature = "spirv"))]
+use core::str::FromStr;
+
+/// A double-double number, `hi + lo` where `|lo| <= ulp(hi) / 2`.
+///
+/// # Examples
+///
+/// ```
+/// use fractal_renderer_calc::dd::DoubleDouble;
+///
+/// let one = DoubleDouble::from(1.0);
+/// let tiny = DoubleDouble::from(1e-20);
+/// // In `f64`, 1 + 1e-20 is 1.
+/// assert_eq!((one + tiny - one).to_f64(), 1e-20);
+///
+/// // 0.1 × 10 is exactly 1 to the precision of double-doubles.
+/// let tenth: DoubleDouble = "0.1".parse().unwrap();
+/// assert!(((tenth * DoubleDouble::from(10.0)) - one).to_f64().abs() < 1e-31);
+///
+/// // (a + b)² = a² + 2ab + b²
+/// let a: DoubleDouble = "1.000000000000000000001".parse().unwrap();
+/// let b = DoubleDouble::from(3.0);
+/// let lhs = (a + b) * (a + b);
+/// let rhs = a * a + DoubleDouble::from(2.0) * a * b + b * b;
+/// assert!((lhs - rhs).to_f64().abs() < 1e-30);
+/// ```
+#[cfg_attr(not(feature = "spirv"), derive(Debug))]
+#[derive(Clone, Copy, PartialEq)]
+pub struct DoubleDouble {
+    pub hi: f64,
+    pub lo: f64,
+}
+impl DoubleDouble {
+    pub const ZERO: Self = Self { hi: 0.0, lo: 0.0 };
+
+    /// The nearest `f64`.
+    #[inline(always)]
+    pub fn to_f64(self) -> f64 {
+        self.hi + self.lo
+    }
+    #[inline(always)]
+    pub fn mul_f64(self, rhs: f64) -> Self {
+        let (p, e) = two_prod(self.hi, rhs);
+        let (hi, lo) = quick_two_sum(p, e + self.lo * rhs);
+        Self { hi, lo }
+    }
+}
+impl From<f64> for DoubleDouble {
+    #[inline(always)]
+    fn from(hi: f64) -> Self {
+        Self { hi, lo: 0.0 }
+    }
+}
+
+/// `a + b` and the rounding error, if `|a| >= |b|`.
+#[inline(always)]
+fn quick_two_sum(a: f64, b: f64) -> (f64, f64) {
+    let s = a + b;
+    (s, b - (s - a))
+}
+/// `a + b` and the rounding error.
+#[inline(always)]
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let s = a + b;
+    let v = s - a;
+    (s, (a - (s - v)) + (b - v))
+}
+/// Splits `a` into two halves of 26 bits (Dekker's algorithm).
+#[inline(always)]
+fn split(a: f64) -> (f64, f64) {
+    let t = 134_217_729.0 * a;
+    let hi = t - (t - a);
+    (hi, a - hi)
+}
+/// `a × b` and the rounding error.
+#[inline(always)]
+fn two_prod(a: f64, b: f64) -> (f64, f64) {
+    let p = a * b;
+    let (a_hi, a_lo) = split(a);
+    let (b_hi, b_lo) = split(b);
+    let e = ((a_hi * b_hi - p) + a_hi * b_lo + a_lo * b_hi) + a_lo * b_lo;
+    (p, e)
+}
+
+impl Add for DoubleDouble {
+    type Output = Self;
+    #[inline(always)]
+    fn add(self, rhs: Self) -> Self::Output {
+        let (s, e) = two_sum(self.hi, rhs.hi);
+        let (t, f) = two_sum(self.lo, rhs.lo);
+        let (s, e) = quick_two_sum(s, e + t);
+        let (hi, lo) = quick_two_sum(s, e + f);
+        Self { hi, lo }
+    }
+}
+impl Neg for DoubleDouble {
+    type Output = Self;
+    #[inline(always)]
+    fn neg(self) -> Self::Output {
+        Self {
+            hi: -self.hi,
+            lo: -self.lo,
+        }
+    }
+}
+impl Sub for DoubleDouble {
+    type Output = Self;
+    #[inline(always)]
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + -rhs
+    }
+}
+impl Mul for DoubleDouble {
+    type Output = Self;
+    #[inline(always)]
+    fn mul(self, rhs: Self) -> Self::Output {
+        let (p, e) = two_prod(self.hi, rhs.hi);
+        let (hi, lo) = quick_two_sum(p, e + (self.hi * rhs.lo + self.lo * rhs.hi));
+        Self { hi, lo }
+    }
+}
+impl Div for DoubleDouble {
+    type Output = Self;
+    /// Long division, with three correction steps.
+    #[inline(always)]
+    fn div(self, rhs: Self) -> Self::Output {
+        let q1 = self.hi / rhs.hi;
+        let r = self - rhs.mul_f64(q1);
+        let q2 = r.hi / rhs.hi;
+        let r = r - rhs.mul_f64(q2);
+        let q3 = r.hi / rhs.hi;
+        let (hi, lo) = quick_two_sum(q1, q2);
+        Self { hi, lo } + Self::from(q3)
+    }
+}
+
+#[cfg(not(feature = "spirv"))]
+#[derive(Debug)]
+pub enum ParseError {
+    /// The string isn't a decimal number, like `-1.25e-3`.
+    Invalid,
+}
+#[cfg(not(feature = "spirv"))]
+impl core::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "invalid decimal number")
+    }
+}
+#[cfg(not(feature = "spirv"))]
+impl FromStr for DoubleDouble {
+    type Err = ParseError;
+    /// Parses a decimal number, keeping about 32 significant digits.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (negative, s) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+        let (mantissa, exponent) = match s.find(|c| c == 'e' || c == 'E') {
+            Some(index) => (
+                &s[..index],
+                s[index + 1..]
+                    .parse::<i32>()
+                    .map_err(|_| ParseError::Invalid)?,
+            ),
+            None => (s, 0),
+        };
+        let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        if integer.is_empty() && fraction.is_empty() {
+            return Err(ParseError::Invalid);
+        }
+        let mut value = Self::ZERO;
+        for c in integer.chars().chain(fraction.chars()) {
+            let digit = c.to_digit(10).ok_or(ParseError::Invalid)?;
+            value = value.mul_f64(10.0) + Self::from(digit as f64);
+        }
+        let exponent = exponent
+            .checked_sub(fraction.len() as i32)
+            .ok_or(ParseError::Invalid)?;
+        let mut power = Self::from(1.0);
+        // Larger powers overflow anyway.
+        for _ in 0..exponent.unsigned_abs().min(400) {
+            power = power.mul_f64(10.0);
+        }
+        value = if !power.hi.is_finite() && exponent < 0 {
+            Self::ZERO
+        } else if exponent < 0 {
+            value / power
+        } else {
+            value * power
+        };
+        if !value.hi.is_finite() {
+            return Err(ParseError::Invalid);
+        }
+        Ok(if negative { -value } else { value })
+    }
+}
+
+/// A complex number of [`DoubleDouble`]s.
+#[cfg_attr(not(feature = "spirv"), derive(Debug))]
+#[derive(Clone, Copy, PartialEq)]
+pub struct ImaginaryDD {
+    pub re: DoubleDouble,
+    pub im: DoubleDouble,
+}
+impl ImaginaryDD {
+    #[inline(always)]
+    pub fn square(self) -> Self {
+        let re = self.re * self.re - self.im * self.im;
+        let im = (self.re * self.im).mul_f64(2.0);
+        Self { re, im }
+    }
+    /// The squared distance from 0, which is precise enough in `f64`.
+    #[inline(always)]
+    pub fn squared_distance(self) -> f64 {
+        let re = self.re.to_f64();
+        let im = self.im.to_f64();
+        re * re + im * im
+    }
+    /// The nearest point in `f64`.
+    #[inline(always)]
+    pub fn to_imaginary(self) -> Imaginary {
+        Imaginary {
+            re: self.re.to_f64(),
+            im: self.im.to_f64(),
+        }
+    }
+}
+impl From<Imaginary> for ImaginaryDD {
+    #[inline(always)]
+    fn from(z: Imaginary) -> Self {
+        Self {
+            re: z.re.into(),
+            im: z.im.into(),
+        }
+    }
+}
+impl Add for ImaginaryDD {
+    type Output = Self;
+    #[inline(always)]
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            re: self.re + rhs.re,
+            im: self.im + rhs.im,
+        }
+    }
+}
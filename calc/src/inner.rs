@@ -0,0 +1,305 @@
+//! [`InnerConfig`], the plain data version of [`Config`] which is shared with the GPU.
+//!
+//! The layout is fixed with `#[repr(C)]` and checked when compiling, so the CPU and the shader
+//! can't disagree on it. Enums are `u32`s (see the constants of [`InnerConfig`]) and `bool`s are
+//! `u32`s which are 0 or 1, as neither has a defined layout.
+
+use crate::{
+    Algo, Backend, Background, Bailout, Config, Imaginary, InteriorMapping, Orientation, Precision,
+    Rotation, SmoothMode, RGB,
+};
+use core::mem::{align_of, size_of};
+
+/// [`Config`] without enums and `bool`s, to upload to the GPU.
+///
+/// The `u32`s come first, in an even number, so the `f64`s are aligned without any padding.
+/// [`Config::backend`] isn't included, as it's chosen before uploading, so [`Self::to_config`]
+/// gives [`Backend::Auto`]. Neither is [`Config::background`], as the GPU only renders the
+/// escape-time fractals, which cover it, so it's [`Background::Auto`].
+/// Colors are `0x00RRGGBB`. The algorithm is or'ed with [`Self::JULIA`] in Julia mode, and then
+/// [`Config::julia`] is `julia_re` and `julia_im`, which are 0 otherwise. The orientation is the number of quarter turns in the low two bits,
+/// then [`Self::FLIP_H`], [`Self::FLIP_V`] and [`Self::Y_UP`] for [`Config::y_up`]. The semi-axes of [`Bailout::Ellipse`] are
+/// `bailout_a` and `bailout_b`, which are 1 for the other bailouts.
+///
+/// # Examples
+///
+/// ```
+/// use fractal_renderer_calc::{inner::InnerConfig, Algo, Config, Imaginary};
+///
+/// let config = Config::new_julia(Algo::Mandelbrot, Imaginary { re: -0.8, im: 0.156 });
+/// let inner = InnerConfig::new(&config);
+/// assert_eq!(inner.algo, InnerConfig::MANDELBROT | InnerConfig::JULIA);
+/// assert_eq!(inner.to_config(), Some(config));
+/// ```
+#[cfg_attr(not(feature = "spirv"), derive(Debug))]
+#[cfg_attr(feature = "bytemuck", derive(bytemuck::Pod, bytemuck::Zeroable))]
+#[derive(Clone, Copy, PartialEq)]
+#[repr(C)]
+pub struct InnerConfig {
+    pub algo: u32,
+    pub width: u32,
+    pub height: u32,
+    pub iterations: u32,
+    pub bailout: u32,
+    pub precision: u32,
+    pub inside: u32,
+    pub smooth: u32,
+    pub fern_normalize: u32,
+    pub exp_map: u32,
+    pub primary_color: u32,
+    pub secondary_color: u32,
+    pub orientation: u32,
+    pub interior_mapping: u32,
+    pub smooth_mode: u32,
+    pub pixel_centers: u32,
+
+    pub limit: f64,
+    pub stable_limit: f64,
+    pub pos_re: f64,
+    pub pos_im: f64,
+    pub pos_low_re: f64,
+    pub pos_low_im: f64,
+    pub scale_re: f64,
+    pub scale_im: f64,
+    pub exposure: f64,
+    pub interior_exposure: f64,
+    pub color_weight: f64,
+    pub julia_re: f64,
+    pub julia_im: f64,
+    pub bailout_a: f64,
+    pub bailout_b: f64,
+}
+
+/// The size of [`InnerConfig`] in bytes. The shader relies on this layout, so changing it has to
+/// change this too.
+pub const INNER_CONFIG_SIZE: usize = 184;
+/// The alignment of [`InnerConfig`], that of `f64`.
+pub const INNER_CONFIG_ALIGN: usize = 8;
+
+// Fails to compile if the layout drifts.
+const _: [(); INNER_CONFIG_SIZE] = [(); size_of::<InnerConfig>()];
+const _: [(); INNER_CONFIG_ALIGN] = [(); align_of::<InnerConfig>()];
+// No padding: 16 `u32`s and 15 `f64`s.
+const _: [(); INNER_CONFIG_SIZE] = [(); 16 * size_of::<u32>() + 15 * size_of::<f64>()];
+
+impl InnerConfig {
+    pub const MANDELBROT: u32 = 0;
+    pub const BARNSLEY_FERN: u32 = 1;
+    /// The flag of Julia mode. The Julia sets of Mandelbrot were the algorithm `2` before, which
+    /// this keeps.
+    pub const JULIA: u32 = 1 << 1;
+
+    pub const BAILOUT_CIRCLE: u32 = 0;
+    pub const BAILOUT_SQUARE: u32 = 1;
+    pub const BAILOUT_REAL: u32 = 2;
+    pub const BAILOUT_IMAG: u32 = 3;
+    pub const BAILOUT_ELLIPSE: u32 = 4;
+
+    pub const PRECISION_AUTO: u32 = 0;
+    pub const PRECISION_F64: u32 = 1;
+    pub const PRECISION_DOUBLE_DOUBLE: u32 = 2;
+
+    pub const INTERIOR_LINEAR: u32 = 0;
+    pub const INTERIOR_SQRT: u32 = 1;
+
+    pub const SMOOTH_CLASSIC: u32 = 0;
+    pub const SMOOTH_NORMALIZED: u32 = 1;
+    pub const SMOOTH_LOG: u32 = 2;
+
+    pub const FLIP_H: u32 = 1 << 2;
+    pub const FLIP_V: u32 = 1 << 3;
+    pub const Y_UP: u32 = 1 << 4;
+
+    pub fn new(config: &Config) -> Self {
+        let color = |color: RGB| (color.r as u32) << 16 | (color.g as u32) << 8 | color.b as u32;
+        let (bailout_a, bailout_b) = match config.bailout {
+            Bailout::Ellipse { a, b } => (a, b),
+            _ => (1.0, 1.0),
+        };
+        let julia = config.julia.unwrap_or(Imaginary::ZERO);
+        Self {
+            algo: match config.algo {
+                Algo::Mandelbrot => Self::MANDELBROT,
+                Algo::BarnsleyFern => Self::BARNSLEY_FERN,
+            } | if config.julia.is_some() {
+                Self::JULIA
+            } else {
+                0
+            },
+            width: config.width,
+            height: config.height,
+            iterations: config.iterations,
+            bailout: match config.bailout {
+                Bailout::Circle => Self::BAILOUT_CIRCLE,
+                Bailout::Square => Self::BAILOUT_SQUARE,
+                Bailout::Real => Self::BAILOUT_REAL,
+                Bailout::Imag => Self::BAILOUT_IMAG,
+                Bailout::Ellipse { .. } => Self::BAILOUT_ELLIPSE,
+            },
+            precision: match config.precision {
+                Precision::Auto => Self::PRECISION_AUTO,
+                Precision::F64 => Self::PRECISION_F64,
+                Precision::DoubleDouble => Self::PRECISION_DOUBLE_DOUBLE,
+            },
+            inside: config.inside as u32,
+            smooth: config.smooth as u32,
+            fern_normalize: config.fern_normalize as u32,
+            exp_map: config.exp_map as u32,
+            primary_color: color(config.primary_color),
+            secondary_color: color(config.secondary_color),
+            orientation: config.orientation.rotation.quarter_turns()
+                | if config.orientation.flip_h {
+                    Self::FLIP_H
+                } else {
+                    0
+                }
+                | if config.orientation.flip_v {
+                    Self::FLIP_V
+                } else {
+                    0
+                }
+                | if config.y_up { Self::Y_UP } else { 0 },
+            interior_mapping: match config.interior_mapping {
+                InteriorMapping::Linear => Self::INTERIOR_LINEAR,
+                InteriorMapping::Sqrt => Self::INTERIOR_SQRT,
+            },
+            smooth_mode: match config.smooth_mode {
+                SmoothMode::Classic => Self::SMOOTH_CLASSIC,
+                SmoothMode::Normalized => Self::SMOOTH_NORMALIZED,
+                SmoothMode::Log => Self::SMOOTH_LOG,
+            },
+            pixel_centers: config.pixel_centers as u32,
+            limit: config.limit,
+            stable_limit: config.stable_limit,
+            pos_re: config.pos.re,
+            pos_im: config.pos.im,
+            pos_low_re: config.pos_low.re,
+            pos_low_im: config.pos_low.im,
+            scale_re: config.scale.re,
+            scale_im: config.scale.im,
+            exposure: config.exposure,
+            interior_exposure: config.interior_exposure,
+            color_weight: config.color_weight,
+            julia_re: julia.re,
+            julia_im: julia.im,
+            bailout_a,
+            bailout_b,
+        }
+    }
+    /// The [`Config`], or [`None`] if an enum or `bool` has an unknown value.
+    pub fn to_config(&self) -> Option<Config> {
+        let flag = |value: u32| match value {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        };
+        let color = |color: u32| {
+            if color > 0xff_ff_ff {
+                return None;
+            }
+            Some(RGB::new(
+                (color >> 16) as u8,
+                (color >> 8) as u8,
+                color as u8,
+            ))
+        };
+        if self.orientation & !(3 | Self::FLIP_H | Self::FLIP_V) != 0 {
+            return None;
+        }
+        Some(Config {
+            algo: match self.algo & !Self::JULIA {
+                Self::MANDELBROT => Algo::Mandelbrot,
+                Self::BARNSLEY_FERN => Algo::BarnsleyFern,
+                _ => return None,
+            },
+            width: self.width,
+            height: self.height,
+            iterations: self.iterations,
+            limit: self.limit,
+            bailout: match self.bailout {
+                Self::BAILOUT_CIRCLE => Bailout::Circle,
+                Self::BAILOUT_SQUARE => Bailout::Square,
+                Self::BAILOUT_REAL => Bailout::Real,
+                Self::BAILOUT_IMAG => Bailout::Imag,
+                Self::BAILOUT_ELLIPSE => Bailout::Ellipse {
+                    a: self.bailout_a,
+                    b: self.bailout_b,
+                },
+                _ => return None,
+            },
+            stable_limit: self.stable_limit,
+            pos: Imaginary {
+                re: self.pos_re,
+                im: self.pos_im,
+            },
+            pos_low: Imaginary {
+                re: self.pos_low_re,
+                im: self.pos_low_im,
+            },
+            scale: Imaginary {
+                re: self.scale_re,
+                im: self.scale_im,
+            },
+            exposure: self.exposure,
+            interior_exposure: self.interior_exposure,
+            interior_mapping: match self.interior_mapping {
+                Self::INTERIOR_LINEAR => InteriorMapping::Linear,
+                Self::INTERIOR_SQRT => InteriorMapping::Sqrt,
+                _ => return None,
+            },
+            inside: flag(self.inside)?,
+            smooth: flag(self.smooth)?,
+            smooth_mode: match self.smooth_mode {
+                Self::SMOOTH_CLASSIC => SmoothMode::Classic,
+                Self::SMOOTH_NORMALIZED => SmoothMode::Normalized,
+                Self::SMOOTH_LOG => SmoothMode::Log,
+                _ => return None,
+            },
+            precision: match self.precision {
+                Self::PRECISION_AUTO => Precision::Auto,
+                Self::PRECISION_F64 => Precision::F64,
+                Self::PRECISION_DOUBLE_DOUBLE => Precision::DoubleDouble,
+                _ => return None,
+            },
+            backend: Backend::Auto,
+            primary_color: color(self.primary_color)?,
+            secondary_color: color(self.secondary_color)?,
+            color_weight: self.color_weight,
+            fern_normalize: flag(self.fern_normalize)?,
+            exp_map: flag(self.exp_map)?,
+            orientation: Orientation {
+                rotation: match self.orientation & 3 {
+                    0 => Rotation::None,
+                    1 => Rotation::Quarter,
+                    2 => Rotation::Half,
+                    _ => Rotation::ThreeQuarters,
+                },
+                flip_h: self.orientation & Self::FLIP_H != 0,
+                flip_v: self.orientation & Self::FLIP_V != 0,
+            },
+            y_up: self.orientation & Self::Y_UP != 0,
+            pixel_centers: flag(self.pixel_centers)?,
+            background: Background::Auto,
+            julia: if self.algo & Self::JULIA != 0 {
+                Some(Imaginary {
+                    re: self.julia_re,
+                    im: self.julia_im,
+                })
+            } else {
+                None
+            },
+        })
+    }
+}
+#[cfg(feature = "bytemuck")]
+impl InnerConfig {
+    /// The bytes to upload, in the native byte order.
+    pub fn as_bytes(&self) -> &[u8] {
+        bytemuck::bytes_of(self)
+    }
+    /// Reads a config written by [`Self::as_bytes`], or returns [`None`] if `bytes` isn't
+    /// [`INNER_CONFIG_SIZE`] long. `bytes` doesn't have to be aligned.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        bytemuck::try_pod_read_unaligned(bytes).ok()
+    }
+}
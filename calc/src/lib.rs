@@ -10,6 +10,14 @@ use core::prelude::rust_2021::*;
 #[cfg(feature = "spirv")]
 use spirv_std::num_traits::Float;
 
+pub mod dd;
+pub mod inner;
+pub mod mapping;
+pub mod resolved;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+use core::f64::consts::TAU;
 #[cfg(not(feature = "spirv"))]
 use core::fmt::Display;
 use core::ops::{Add, AddAssign, Mul};
@@ -17,65 +25,1040 @@ use core::ops::{Add, AddAssign, Mul};
 use core::str::FromStr;
 
 #[cfg_attr(not(feature = "spirv"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
 #[derive(Clone, PartialEq)]
 pub struct Config {
     pub algo: Algo,
     pub width: u32,
     pub height: u32,
     pub iterations: u32,
+    /// The radius outside of which points are treated as escaped, see [`Self::bailout`].
     pub limit: f64,
+    /// The shape of the region inside [`Self::limit`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bailout: Bailout,
+    /// The radius inside of which points are colored as inside the fractal.
+    /// Should not be larger than [`Self::limit`].
     pub stable_limit: f64,
+    /// The center of the image.
     pub pos: Imaginary,
+    /// What's lost when rounding the center to [`Self::pos`], for double-double precision.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pos_low: Imaginary,
+    /// The image covers `1 / scale.im` vertically and `aspect_ratio / scale.re` horizontally,
+    /// so equal components keep the fractal undistorted. See [`Self::viewport`].
     pub scale: Imaginary,
     pub exposure: f64,
+    /// The multiplier of the color of the inside, see [`Self::interior_mapping`].
+    #[cfg_attr(feature = "serde", serde(default = "default_interior_exposure"))]
+    pub interior_exposure: f64,
+    /// How the squared distance of the last point of the orbit brightens the inside.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub interior_mapping: InteriorMapping,
     pub inside: bool,
     pub smooth: bool,
+    /// How the iterations are smoothed and mapped to colors.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub smooth_mode: SmoothMode,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub precision: Precision,
+    /// Where to render the image.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub backend: Backend,
     pub primary_color: RGB,
     pub secondary_color: RGB,
+    /// How much each hit darkens the fern, or `k` of the density mapping if
+    /// [`Self::fern_normalize`] is set.
     pub color_weight: f64,
-    pub julia_set: Imaginary,
+    /// Color the fern by the density of the hits instead of darkening it with every hit, so it
+    /// looks the same for any number of iterations.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fern_normalize: bool,
+    /// Map the image to log-polar coordinates around [`Self::pos`], a strip covering a whole range
+    /// of zooms, instead of a rectangle. See [`mapping`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub exp_map: bool,
+    /// How the image is rotated and flipped, see [`Orientation`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub orientation: Orientation,
+    /// Put the positive imaginary axis up, like in mathematics, instead of down the rows of the
+    /// image. See [`mapping`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub y_up: bool,
+    /// Sample the center of every pixel instead of its top left corner, so the pixels of an
+    /// image twice the size are spread evenly around them. See [`mapping::sample_position`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pixel_centers: bool,
+    /// What the fern and the Buddhabrot are plotted on, see [`Background`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub background: Background,
+    /// Render the Julia set of this constant with the formula of [`Self::algo`]: the pixel is the
+    /// start of the orbit and the constant is added every iteration. The fern has no Julia set
+    /// and ignores it.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub julia: Option<Imaginary>,
 }
 impl Config {
+    /// The defaults, with those of `algo` from [`defaults`].
     pub fn new(algo: Algo) -> Self {
+        let defaults = defaults(&algo);
         Self {
             width: 2000,
             height: 1000,
-            iterations: if let Algo::BarnsleyFern = algo {
-                10_000_000
-            } else {
-                50
-            },
+            iterations: defaults.iterations,
             limit: 2.0_f64.powi(16),
+            bailout: Bailout::Circle,
             stable_limit: 2.0,
-            pos: Imaginary::ZERO,
-            scale: Imaginary::ONE * 0.4,
+            pos: defaults.pos,
+            pos_low: Imaginary::ZERO,
+            scale: defaults.scale,
             exposure: 2.0,
+            interior_exposure: 1.0,
+            interior_mapping: InteriorMapping::Linear,
             inside: true,
             smooth: true,
-            primary_color: if let Algo::BarnsleyFern = algo {
-                RGB::new(4, 100, 3)
-            } else {
-                RGB::new(40, 40, 255)
-            },
-            secondary_color: if let Algo::BarnsleyFern = algo {
-                RGB::new(240, 240, 240)
-            } else {
-                RGB::new(240, 170, 0)
-            },
+            smooth_mode: SmoothMode::Classic,
+            precision: Precision::Auto,
+            backend: Backend::Auto,
+            primary_color: defaults.primary_color,
+            secondary_color: defaults.secondary_color,
             color_weight: 0.01,
-            julia_set: Imaginary::ZERO,
+            fern_normalize: false,
+            exp_map: false,
+            orientation: Orientation::default(),
+            y_up: false,
+            pixel_centers: false,
+            background: Background::Auto,
+            julia: None,
             algo,
         }
     }
+    /// The defaults of the Julia set of `c` with the formula of `algo`, centered like
+    /// [`AlgoDefaults::julia_pos`].
+    pub fn new_julia(algo: Algo, c: Imaginary) -> Self {
+        Self {
+            pos: defaults(&algo).julia_pos,
+            julia: Some(c),
+            ..Self::new(algo)
+        }
+    }
+}
+#[cfg(feature = "serde")]
+fn default_interior_exposure() -> f64 {
+    1.0
 }
+
+/// The default [`Config::color_weight`] of [`Config::fern_normalize`].
+pub const FERN_NORMALIZED_WEIGHT: f64 = 64.0;
+
 impl Default for Config {
     fn default() -> Self {
         Self::new(Algo::Mandelbrot)
     }
 }
+impl Config {
+    /// The rectangle of the complex plane covered by the image.
+    ///
+    /// With [`Self::exp_map`], that's the square around the circle of the top row.
+    pub fn viewport(&self) -> ComplexRect {
+        let (width, height) = self.unoriented_size();
+        let aspect_ratio = width as f64 / height as f64;
+        let half = if self.exp_map {
+            Imaginary::ONE * (0.5 / self.scale.im)
+        } else {
+            Imaginary {
+                re: aspect_ratio / 2.0 / self.scale.re,
+                im: 0.5 / self.scale.im,
+            }
+        };
+        ComplexRect {
+            min: Imaginary {
+                re: self.pos.re - half.re,
+                im: self.pos.im - half.im,
+            },
+            max: self.pos + half,
+        }
+    }
+    /// Sets [`Self::pos`] and [`Self::scale`] so exactly `rect` is covered by the image.
+    ///
+    /// If the aspect ratio of `rect` differs from the one of the image, the fractal is stretched.
+    /// With a quarter turn, the width of the image covers the height of `rect`.
+    pub fn set_viewport(&mut self, rect: ComplexRect) {
+        let (width, height) = self.unoriented_size();
+        let aspect_ratio = width as f64 / height as f64;
+        self.pos = rect.center();
+        self.pos_low = Imaginary::ZERO;
+        self.scale = Imaginary {
+            re: aspect_ratio / rect.width(),
+            im: 1.0 / rect.height(),
+        };
+    }
+    /// The point iterated for pixel (`x`, `y`), at its top left corner, which is
+    /// `viewport().min + (x / width × viewport().width(), y / height × viewport().height())`,
+    /// or its center with [`Self::pixel_centers`].
+    ///
+    /// See [`mapping::pixel_point`] for fractional pixels.
+    #[inline(always)]
+    pub fn pixel_to_point(&self, x: u32, y: u32) -> Imaginary {
+        mapping::pixel_point(self, x as f64, y as f64)
+    }
+    /// Whether points are iterated with [`dd::DoubleDouble`]s. With [`Precision::Auto`], that's
+    /// when the pixels are smaller than [`DOUBLE_DOUBLE_PIXEL`].
+    #[inline(always)]
+    pub fn uses_double_double(&self) -> bool {
+        match self.precision {
+            Precision::Auto => {
+                let scale = if self.scale.re > self.scale.im {
+                    self.scale.re
+                } else {
+                    self.scale.im
+                };
+                let pixel = if self.exp_map {
+                    // The pixels shrink with the radius, to the smallest at the bottom.
+                    let turns = self.height as f64 / self.width as f64;
+                    0.5 / self.scale.im * (-turns * TAU).exp() * TAU / self.width as f64
+                } else {
+                    1.0 / (scale * self.unoriented_size().1 as f64)
+                };
+                pixel < DOUBLE_DOUBLE_PIXEL
+            }
+            Precision::F64 => false,
+            Precision::DoubleDouble => true,
+        }
+    }
+    /// The size of the image before [`Self::orientation`] is applied, with the width and height
+    /// swapped by a quarter turn.
+    #[inline(always)]
+    pub fn unoriented_size(&self) -> (u32, u32) {
+        if self.orientation.rotation.is_quarter_turn() {
+            (self.height, self.width)
+        } else {
+            (self.width, self.height)
+        }
+    }
+    /// [`Self::set_viewport`] as a builder.
+    pub fn with_viewport(mut self, rect: ComplexRect) -> Self {
+        self.set_viewport(rect);
+        self
+    }
+    /// Checks that the config can be rendered: a scale of zero maps every pixel to infinity, and
+    /// a negative one mirrors the image.
+    ///
+    /// ```
+    /// # use fractal_renderer_calc::{Algo, ComplexRect, Config, ConfigError, Imaginary};
+    /// assert_eq!(Config::new(Algo::Mandelbrot).validate(), Ok(()));
+    ///
+    /// // A line has no height.
+    /// let line = ComplexRect {
+    ///     min: Imaginary { re: -1.0, im: 0.0 },
+    ///     max: Imaginary { re: 1.0, im: 0.0 },
+    /// };
+    /// let config = Config::new(Algo::Mandelbrot).with_viewport(line);
+    /// assert!(matches!(config.validate(), Err(ConfigError::Scale(_))));
+    ///
+    /// let mut config = Config::new_julia(Algo::Mandelbrot, Imaginary::ZERO);
+    /// config.iterations = 0;
+    /// assert_eq!(config.validate(), Err(ConfigError::NoIterations));
+    /// ```
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let valid = |scale: f64| scale > 0.0 && scale.is_finite();
+        if !valid(self.scale.re) || !valid(self.scale.im) {
+            return Err(ConfigError::Scale(self.scale));
+        }
+        if self.iterations == 0 {
+            return Err(ConfigError::NoIterations);
+        }
+        Ok(())
+    }
+}
+
+/// Why a [`Config`] can't be rendered, see [`Config::validate`].
+#[cfg_attr(not(feature = "spirv"), derive(Debug))]
+#[derive(Clone, Copy, PartialEq)]
+pub enum ConfigError {
+    /// A part of [`Config::scale`] isn't positive and finite.
+    Scale(Imaginary),
+    /// [`Config::iterations`] is zero, so nothing is iterated or plotted.
+    NoIterations,
+}
+#[cfg(not(feature = "spirv"))]
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Scale(scale) => write!(
+                f,
+                "the scale must be positive, but is {} × {}",
+                scale.re, scale.im
+            ),
+            Self::NoIterations => write!(f, "there must be at least one iteration"),
+        }
+    }
+}
+
+/// Pixels smaller than this use double-double precision with [`Precision::Auto`].
+///
+/// That's about 50 times the spacing of `f64`s around 1, so with `f64`, the pixels would
+/// soon be rounded to the same points and show up as blocks.
+pub const DOUBLE_DOUBLE_PIXEL: f64 = 1e-14;
+
+/// The precision used to iterate points.
+#[cfg_attr(not(feature = "spirv"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[derive(Clone, Copy, PartialEq)]
+pub enum Precision {
+    /// `f64`, or double-double precision when zoomed in far enough, see
+    /// [`Config::uses_double_double`].
+    Auto,
+    F64,
+    /// [`dd::DoubleDouble`], which is good to zooms of about 1e28, but 5-10 times slower.
+    #[cfg_attr(feature = "serde", serde(rename = "dd"))]
+    DoubleDouble,
+}
+impl Default for Precision {
+    fn default() -> Self {
+        Self::Auto
+    }
+}
+#[cfg(not(feature = "spirv"))]
+impl FromStr for Precision {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(if s.eq_ignore_ascii_case("auto") {
+            Self::Auto
+        } else if s.eq_ignore_ascii_case("f64") {
+            Self::F64
+        } else if s.eq_ignore_ascii_case("dd") {
+            Self::DoubleDouble
+        } else {
+            return Err(format!("unknown precision {:?}; use auto, f64 or dd", s));
+        })
+    }
+}
+
+/// The shape of the region points escape from, of size [`Config::limit`].
+///
+/// Only [`Self::Circle`] and [`Self::Ellipse`] can be smoothed, as the escape boundary of the
+/// others isn't a level set of a norm, see [`Self::is_smoothed`]. [`finish_sample`] keeps the
+/// integer iterations for them.
+///
+/// Serialized as the name of the shape, like `"circle"`, or `{ ellipse = { a, b } }`.
+#[cfg_attr(not(feature = "spirv"), derive(Debug))]
+#[derive(Clone, Copy, PartialEq)]
+pub enum Bailout {
+    /// `|z| > limit`.
+    Circle,
+    /// `max(|re|, |im|) > limit`.
+    Square,
+    /// `|re| > limit`.
+    Real,
+    /// `|im| > limit`.
+    Imag,
+    /// `(re / a)² + (im / b)² > limit²`, an ellipse with the semi-axes `a × limit` and
+    /// `b × limit`, for renders with different [`Config::scale`]s along the axes, see
+    /// [`Self::stretched`].
+    Ellipse { a: f64, b: f64 },
+}
+impl Bailout {
+    /// Whether `z`, with the squared distance to 0 `squared_distance`, has escaped.
+    ///
+    /// Points which overflow to infinity or NaN have always escaped.
+    #[inline(always)]
+    pub fn escaped(self, z: Imaginary, squared_distance: f64, limit: f64) -> bool {
+        match self {
+            Self::Circle => return squared_distance > limit * limit,
+            Self::Square => {
+                if z.re.abs() > limit || z.im.abs() > limit {
+                    return true;
+                }
+            }
+            Self::Real => {
+                if z.re.abs() > limit {
+                    return true;
+                }
+            }
+            Self::Imag => {
+                if z.im.abs() > limit {
+                    return true;
+                }
+            }
+            Self::Ellipse { .. } => {
+                if self.squared_norm(z, squared_distance) > limit * limit {
+                    return true;
+                }
+            }
+        }
+        // A component can grow without bounds while the other stays small.
+        !squared_distance.is_finite()
+    }
+    /// Whether the iterations can be smoothed, as the boundary is a level set of
+    /// [`Self::squared_norm`].
+    ///
+    /// The smoothed iterations of a stretched render change continuously, unlike the whole
+    /// iterations of a square:
+    ///
+    /// ```
+    /// use fractal_renderer_calc::{sample_point, Bailout, Config, Imaginary};
+    ///
+    /// let mut config = Config::default();
+    /// config.scale = Imaginary { re: 2.0, im: 0.5 };
+    /// // The largest difference between neighbours on a ray outside of the set.
+    /// let largest_step = |config: &Config| {
+    ///     let iterations: Vec<f64> = (0..2000)
+    ///         .map(|i| {
+    ///             let t = 2.0 + i as f64 * 0.001;
+    ///             sample_point(config, Imaginary { re: -0.6 * t, im: 0.8 * t }).iterations
+    ///         })
+    ///         .collect();
+    ///     iterations
+    ///         .windows(2)
+    ///         .map(|pair| (pair[1] - pair[0]).abs())
+    ///         .fold(0.0, f64::max)
+    /// };
+    /// config.bailout = Bailout::stretched(config.scale);
+    /// assert!(config.bailout.is_smoothed());
+    /// assert!(largest_step(&config) < 0.1);
+    /// config.bailout = Bailout::Square;
+    /// assert_eq!(largest_step(&config), 1.0);
+    ///
+    /// // An ellipse with equal axes is a circle.
+    /// let point = Imaginary { re: 0.3, im: 0.6 };
+    /// config.bailout = Bailout::Ellipse { a: 1.0, b: 1.0 };
+    /// let ellipse = sample_point(&config, point);
+    /// config.bailout = Bailout::Circle;
+    /// assert_eq!(ellipse, sample_point(&config, point));
+    /// ```
+    #[inline(always)]
+    pub fn is_smoothed(self) -> bool {
+        matches!(self, Self::Circle | Self::Ellipse { .. })
+    }
+    /// The square of the distance of `z` to 0 in the shape of the bailout, which is `limit²` on
+    /// its boundary. `squared_distance` is that of a circle, `|z|²`.
+    ///
+    /// Only meaningful for the shapes which are [smoothed](Self::is_smoothed).
+    #[inline(always)]
+    pub fn squared_norm(self, z: Imaginary, squared_distance: f64) -> f64 {
+        match self {
+            Self::Ellipse { a, b } => (z.re / a) * (z.re / a) + (z.im / b) * (z.im / b),
+            _ => squared_distance,
+        }
+    }
+    /// An [`Self::Ellipse`] stretched like the view of `scale`, so it's round in the image. The
+    /// shorter semi-axis is [`Config::limit`], so it contains the circle of the same limit.
+    ///
+    /// ```
+    /// use fractal_renderer_calc::{Bailout, Imaginary};
+    ///
+    /// // 4 times as many pixels per unit horizontally, so the ellipse is 4 times as tall.
+    /// let scale = Imaginary { re: 2.0, im: 0.5 };
+    /// assert_eq!(Bailout::stretched(scale), Bailout::Ellipse { a: 1.0, b: 4.0 });
+    /// assert_eq!(
+    ///     Bailout::stretched(Imaginary { re: 3.0, im: 3.0 }),
+    ///     Bailout::Ellipse { a: 1.0, b: 1.0 }
+    /// );
+    /// ```
+    pub fn stretched(scale: Imaginary) -> Self {
+        let max = scale.re.max(scale.im);
+        Self::Ellipse {
+            a: max / scale.re,
+            b: max / scale.im,
+        }
+    }
+}
+#[cfg(feature = "serde")]
+mod bailout_serde {
+    use super::Bailout;
+    use core::fmt;
+    use serde::de::{self, MapAccess, Visitor};
+    use serde::ser::SerializeMap;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Axes {
+        a: f64,
+        b: f64,
+    }
+    #[derive(Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Shape {
+        Circle,
+        Square,
+        Real,
+        Imag,
+        Ellipse,
+    }
+
+    // TOML has no representation of enum variants with fields, so the ellipse is a table.
+    impl Serialize for Bailout {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let name = match self {
+                Self::Circle => "circle",
+                Self::Square => "square",
+                Self::Real => "real",
+                Self::Imag => "imag",
+                Self::Ellipse { a, b } => {
+                    let mut map = serializer.serialize_map(Some(1))?;
+                    map.serialize_entry("ellipse", &Axes { a: *a, b: *b })?;
+                    return map.end();
+                }
+            };
+            serializer.serialize_str(name)
+        }
+    }
+    impl<'de> Deserialize<'de> for Bailout {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct BailoutVisitor;
+            impl<'de> Visitor<'de> for BailoutVisitor {
+                type Value = Bailout;
+                fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    f.write_str("circle, square, real, imag or { ellipse = { a, b } }")
+                }
+                fn visit_str<E: de::Error>(self, v: &str) -> Result<Bailout, E> {
+                    let shape = Shape::deserialize(de::value::StrDeserializer::<E>::new(v))?;
+                    Ok(match shape {
+                        Shape::Circle => Bailout::Circle,
+                        Shape::Square => Bailout::Square,
+                        Shape::Real => Bailout::Real,
+                        Shape::Imag => Bailout::Imag,
+                        Shape::Ellipse => return Err(E::custom("the ellipse needs its axes")),
+                    })
+                }
+                fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Bailout, A::Error> {
+                    match map.next_key::<Shape>()? {
+                        Some(Shape::Ellipse) => {}
+                        _ => return Err(de::Error::custom("expected an ellipse")),
+                    }
+                    let Axes { a, b } = map.next_value()?;
+                    if map.next_key::<Shape>()?.is_some() {
+                        return Err(de::Error::custom("expected only the ellipse"));
+                    }
+                    Ok(Bailout::Ellipse { a, b })
+                }
+            }
+            deserializer.deserialize_any(BailoutVisitor)
+        }
+    }
+}
+impl Default for Bailout {
+    fn default() -> Self {
+        Self::Circle
+    }
+}
+#[cfg(not(feature = "spirv"))]
+impl FromStr for Bailout {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(if s.eq_ignore_ascii_case("circle") {
+            Self::Circle
+        } else if s.eq_ignore_ascii_case("square") {
+            Self::Square
+        } else if s.eq_ignore_ascii_case("real") {
+            Self::Real
+        } else if s.eq_ignore_ascii_case("imag") {
+            Self::Imag
+        } else if s.eq_ignore_ascii_case("ellipse") {
+            // A circle until it's stretched, see `Bailout::stretched`.
+            Self::Ellipse { a: 1.0, b: 1.0 }
+        } else {
+            return Err(format!(
+                "unknown bailout {:?}; use circle, square, real, imag or ellipse",
+                s
+            ));
+        })
+    }
+}
+
+/// The side of the squares of [`Background::Checkerboard`], in pixels.
+pub const CHECKERBOARD_SQUARE: u32 = 8;
 
+/// What the scatter-style algorithms, the fern and the Buddhabrot, plot their points on. The
+/// escape-time fractals color every pixel, so they ignore it.
+///
+/// Serialized as `"auto"`, `"transparent"`, `"checkerboard"` or `{ solid = { r, g, b } }`.
 #[cfg_attr(not(feature = "spirv"), derive(Debug))]
 #[derive(Clone, Copy, PartialEq)]
+pub enum Background {
+    /// What the algorithm has always been plotted on: [`Config::secondary_color`] for the fern
+    /// and black for the Buddhabrot.
+    Auto,
+    Solid(RGB),
+    /// Nothing, which needs images with an alpha channel. Until there are, it's shown like
+    /// [`Self::Checkerboard`].
+    Transparent,
+    /// Light and dark gray squares of [`CHECKERBOARD_SQUARE`] pixels, to preview
+    /// [`Self::Transparent`].
+    Checkerboard,
+}
+impl Background {
+    /// The color of pixel (`x`, `y`), with `auto` the color of [`Self::Auto`].
+    ///
+    /// ```
+    /// use fractal_renderer_calc::{Background, RGB, CHECKERBOARD_SQUARE};
+    ///
+    /// let auto = RGB::new(240, 240, 240);
+    /// assert_eq!(Background::Auto.color(5, 9, auto), auto);
+    /// let solid = Background::Solid(RGB::new(10, 20, 30));
+    /// assert_eq!(solid.color(5, 9, auto), RGB::new(10, 20, 30));
+    ///
+    /// let square = CHECKERBOARD_SQUARE;
+    /// let checkerboard = Background::Checkerboard;
+    /// assert_eq!(checkerboard.color(0, 0, auto), checkerboard.color(square - 1, square - 1, auto));
+    /// assert_ne!(checkerboard.color(0, 0, auto), checkerboard.color(square, 0, auto));
+    /// assert_eq!(checkerboard.color(0, 0, auto), checkerboard.color(square, square, auto));
+    /// assert_eq!(Background::Transparent.color(3, 20, auto), checkerboard.color(3, 20, auto));
+    /// ```
+    #[inline(always)]
+    pub fn color(self, x: u32, y: u32, auto: RGB) -> RGB {
+        match self {
+            Self::Auto => auto,
+            Self::Solid(color) => color,
+            Self::Transparent | Self::Checkerboard => {
+                if (x / CHECKERBOARD_SQUARE + y / CHECKERBOARD_SQUARE) % 2 == 0 {
+                    RGB::new(204, 204, 204)
+                } else {
+                    RGB::new(153, 153, 153)
+                }
+            }
+        }
+    }
+}
+impl Default for Background {
+    fn default() -> Self {
+        Self::Auto
+    }
+}
+#[cfg(feature = "serde")]
+mod background_serde {
+    use super::{Background, RGB};
+    use core::fmt;
+    use serde::de::{self, MapAccess, Visitor};
+    use serde::ser::SerializeMap;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Kind {
+        Auto,
+        Solid,
+        Transparent,
+        Checkerboard,
+    }
+
+    // Like `Bailout`, the solid color is a table, as TOML has no enum variants with fields.
+    impl Serialize for Background {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let name = match self {
+                Self::Auto => "auto",
+                Self::Transparent => "transparent",
+                Self::Checkerboard => "checkerboard",
+                Self::Solid(color) => {
+                    let mut map = serializer.serialize_map(Some(1))?;
+                    map.serialize_entry("solid", color)?;
+                    return map.end();
+                }
+            };
+            serializer.serialize_str(name)
+        }
+    }
+    impl<'de> Deserialize<'de> for Background {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct BackgroundVisitor;
+            impl<'de> Visitor<'de> for BackgroundVisitor {
+                type Value = Background;
+                fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    f.write_str("auto, transparent, checkerboard or { solid = { r, g, b } }")
+                }
+                fn visit_str<E: de::Error>(self, v: &str) -> Result<Background, E> {
+                    let kind = Kind::deserialize(de::value::StrDeserializer::<E>::new(v))?;
+                    Ok(match kind {
+                        Kind::Auto => Background::Auto,
+                        Kind::Transparent => Background::Transparent,
+                        Kind::Checkerboard => Background::Checkerboard,
+                        Kind::Solid => return Err(E::custom("the solid background needs a color")),
+                    })
+                }
+                fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Background, A::Error> {
+                    match map.next_key::<Kind>()? {
+                        Some(Kind::Solid) => {}
+                        _ => return Err(de::Error::custom("expected a solid color")),
+                    }
+                    let color: RGB = map.next_value()?;
+                    if map.next_key::<Kind>()?.is_some() {
+                        return Err(de::Error::custom("expected only the solid color"));
+                    }
+                    Ok(Background::Solid(color))
+                }
+            }
+            deserializer.deserialize_any(BackgroundVisitor)
+        }
+    }
+}
+
+/// Where an image is rendered. [`Config::backend`] is a preference, and a render reports the
+/// backend which was used, which is never [`Self::Auto`].
+#[cfg_attr(not(feature = "spirv"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[derive(Clone, Copy, PartialEq)]
+pub enum Backend {
+    /// The GPU if it's available, else the CPU.
+    Auto,
+    Cpu,
+    Gpu,
+}
+impl Default for Backend {
+    fn default() -> Self {
+        Self::Auto
+    }
+}
+#[cfg(not(feature = "spirv"))]
+impl Display for Backend {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            Self::Auto => "auto",
+            Self::Cpu => "cpu",
+            Self::Gpu => "gpu",
+        })
+    }
+}
+#[cfg(not(feature = "spirv"))]
+impl FromStr for Backend {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(if s.eq_ignore_ascii_case("auto") {
+            Self::Auto
+        } else if s.eq_ignore_ascii_case("cpu") {
+            Self::Cpu
+        } else if s.eq_ignore_ascii_case("gpu") {
+            Self::Gpu
+        } else {
+            return Err(format!("unknown backend {:?}; use auto, cpu or gpu", s));
+        })
+    }
+}
+
+/// How the iterations of escaping points are smoothed and mapped to colors.
+///
+/// [`Self::Classic`] adds `2 - log2(log2(|z|))`, which assumes a limit of about 2. With larger
+/// limits, such as the default, it shifts the counts down by up to `log2(log2(limit))`, and the
+/// bands don't line up with the integer counts. [`Self::Normalized`] divides by the limit, so the
+/// smoothed count is always from the integer count to the next.
+///
+/// Both map the iterations linearly to the color: `iterations / Config::iterations × exposure`.
+/// As most points escape quickly, images with many iterations, like most Julia sets, are often dim
+/// everywhere but close to the set. [`Self::Log`] maps them through `ln(1 + iterations)` instead,
+/// which brightens the quick escapes.
+#[cfg_attr(not(feature = "spirv"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[derive(Clone, Copy, PartialEq)]
+pub enum SmoothMode {
+    /// `n + 2 - log2(log2(|z|))`.
+    Classic,
+    /// `n + 1 - log2(log2(|z|) / log2(limit))`.
+    Normalized,
+    /// [`Self::Normalized`], then `ln(1 + iterations)` before the exposure.
+    Log,
+}
+impl SmoothMode {
+    /// The iterations of an orbit escaping after `iterations` at `squared_distance` from 0, beyond
+    /// `limit`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fractal_renderer_calc::SmoothMode;
+    ///
+    /// // Escaping just beyond the limit counts as the integer count, whatever the limit.
+    /// for limit in [2.0_f64, 256.0, 65536.0] {
+    ///     let smoothed = SmoothMode::Normalized.smooth(10, limit * limit * 1.0001, limit);
+    ///     assert!((smoothed - 11.0).abs() < 1e-3);
+    /// }
+    /// // The classic formula drifts with the limit.
+    /// let classic = SmoothMode::Classic.smooth(10, 65536.0 * 65536.0 * 1.0001, 65536.0);
+    /// assert!((classic - 8.0).abs() < 1e-3);
+    /// ```
+    #[inline(always)]
+    pub fn smooth(self, iterations: u32, squared_distance: f64, limit: f64) -> f64 {
+        // https://en.wikipedia.org/wiki/Plotting_algorithms_for_the_Mandelbrot_set#Continuous_(smooth)_coloring
+        let nu = match self {
+            Self::Classic => f64::log2(f64::log2(squared_distance.sqrt()) / 2.0),
+            Self::Normalized | Self::Log => {
+                f64::log2(f64::log2(squared_distance) / 2.0 / f64::log2(limit))
+            }
+        };
+        // When escaping within the unit circle (a low `limit`), `log2(|z|)` isn't positive and
+        // `nu` is NaN or infinite. Keep the integer count then.
+        if nu.is_finite() {
+            iterations as f64 + (1.0 - nu)
+        } else {
+            iterations as f64
+        }
+    }
+    /// The brightness of a point escaping after `iterations` of `max_iterations`, before the
+    /// exposure: 0 for no iterations and 1 at the maximum.
+    #[inline(always)]
+    pub fn brightness(self, iterations: f64, max_iterations: u32) -> f64 {
+        match self {
+            Self::Classic | Self::Normalized => iterations / max_iterations as f64,
+            Self::Log => (1.0 + iterations).ln() / (1.0 + max_iterations as f64).ln(),
+        }
+    }
+}
+impl Default for SmoothMode {
+    fn default() -> Self {
+        Self::Classic
+    }
+}
+#[cfg(not(feature = "spirv"))]
+impl FromStr for SmoothMode {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(if s.eq_ignore_ascii_case("classic") {
+            Self::Classic
+        } else if s.eq_ignore_ascii_case("normalized") {
+            Self::Normalized
+        } else if s.eq_ignore_ascii_case("log") {
+            Self::Log
+        } else {
+            return Err(format!(
+                "unknown smooth mode {:?}; use classic, normalized or log",
+                s
+            ));
+        })
+    }
+}
+
+/// How the inside of the fractal is brightened by the squared distance `d²` from 0 of the last
+/// point of the orbit, which is at most [`Config::stable_limit`] squared.
+#[cfg_attr(not(feature = "spirv"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[derive(Clone, Copy, PartialEq)]
+pub enum InteriorMapping {
+    /// `d²`, which darkens everything closer than 1.
+    Linear,
+    /// `d`, the distance itself.
+    Sqrt,
+}
+impl InteriorMapping {
+    /// The brightness of the inside at the squared distance `squared_distance`.
+    #[inline(always)]
+    pub fn map(self, squared_distance: f64) -> f64 {
+        match self {
+            Self::Linear => squared_distance,
+            Self::Sqrt => squared_distance.sqrt(),
+        }
+    }
+}
+impl Default for InteriorMapping {
+    fn default() -> Self {
+        Self::Linear
+    }
+}
+#[cfg(not(feature = "spirv"))]
+impl FromStr for InteriorMapping {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(if s.eq_ignore_ascii_case("linear") {
+            Self::Linear
+        } else if s.eq_ignore_ascii_case("sqrt") {
+            Self::Sqrt
+        } else {
+            return Err(format!(
+                "unknown interior mapping {:?}; use linear or sqrt",
+                s
+            ));
+        })
+    }
+}
+
+/// How the image is turned, clockwise.
+#[cfg_attr(not(feature = "spirv"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, PartialEq)]
+pub enum Rotation {
+    #[cfg_attr(feature = "serde", serde(rename = "0"))]
+    None,
+    #[cfg_attr(feature = "serde", serde(rename = "90"))]
+    Quarter,
+    #[cfg_attr(feature = "serde", serde(rename = "180"))]
+    Half,
+    #[cfg_attr(feature = "serde", serde(rename = "270"))]
+    ThreeQuarters,
+}
+impl Rotation {
+    /// The number of quarter turns, `0..4`.
+    #[inline(always)]
+    pub fn quarter_turns(self) -> u32 {
+        match self {
+            Self::None => 0,
+            Self::Quarter => 1,
+            Self::Half => 2,
+            Self::ThreeQuarters => 3,
+        }
+    }
+    /// Whether this swaps the width and height.
+    #[inline(always)]
+    pub fn is_quarter_turn(self) -> bool {
+        matches!(self, Self::Quarter | Self::ThreeQuarters)
+    }
+}
+impl Default for Rotation {
+    fn default() -> Self {
+        Self::None
+    }
+}
+#[cfg(not(feature = "spirv"))]
+impl FromStr for Rotation {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "0" => Self::None,
+            "90" => Self::Quarter,
+            "180" => Self::Half,
+            "270" => Self::ThreeQuarters,
+            _ => return Err(format!("unknown rotation {:?}; use 0, 90, 180 or 270", s)),
+        })
+    }
+}
+
+/// The orientation of the image: the unoriented image, with the positive imaginary axis down, is
+/// turned by [`Self::rotation`] and then flipped.
+///
+/// This changes which points the pixels show, so the image is never resampled. The flips mirror
+/// the pixels around the middle of the first and last pixel, so an oriented image has exactly the
+/// pixels of the unoriented image, moved. See [`mapping::orient`].
+///
+/// # Examples
+///
+/// Every orientation shows the pixels of the unoriented image, moved.
+///
+/// ```
+/// use fractal_renderer_calc::{sample, Config, Imaginary, Orientation, Rotation};
+///
+/// let render = |config: &Config| {
+///     let mut iterations = Vec::new();
+///     for y in 0..config.height {
+///         for x in 0..config.width {
+///             iterations.push(sample(config, x as f64, y as f64).iterations);
+///         }
+///     }
+///     iterations
+/// };
+///
+/// let (width, height) = (30, 20);
+/// // Off the real axis, so the image isn't symmetric.
+/// let config = Config {
+///     width,
+///     height,
+///     pos: Imaginary { re: -0.75, im: 0.3 },
+///     ..Config::default()
+/// };
+/// let image = render(&config);
+/// // The image of `width` with every pixel taken from the unoriented image at `from(x, y)`.
+/// let moved = |width: u32, from: &dyn Fn(u32, u32) -> (u32, u32)| {
+///     let mut moved = Vec::new();
+///     for y in 0..image.len() as u32 / width {
+///         for x in 0..width {
+///             let (x, y) = from(x, y);
+///             moved.push(image[(y * config.width + x) as usize]);
+///         }
+///     }
+///     moved
+/// };
+/// let oriented = |orientation: Orientation| {
+///     let turned = matches!(
+///         orientation.rotation,
+///         Rotation::Quarter | Rotation::ThreeQuarters
+///     );
+///     let (width, height) = if turned { (height, width) } else { (width, height) };
+///     render(&Config {
+///         width,
+///         height,
+///         orientation,
+///         ..config.clone()
+///     })
+/// };
+/// let turned = |rotation| Orientation {
+///     rotation,
+///     ..Default::default()
+/// };
+///
+/// // Clockwise.
+/// assert_eq!(
+///     oriented(turned(Rotation::Quarter)),
+///     moved(height, &|x, y| (y, height - 1 - x))
+/// );
+/// assert_eq!(
+///     oriented(turned(Rotation::Half)),
+///     moved(width, &|x, y| (width - 1 - x, height - 1 - y))
+/// );
+/// assert_eq!(
+///     oriented(turned(Rotation::ThreeQuarters)),
+///     moved(height, &|x, y| (width - 1 - y, x))
+/// );
+/// let flip_h = Orientation {
+///     flip_h: true,
+///     ..Default::default()
+/// };
+/// assert_eq!(oriented(flip_h), moved(width, &|x, y| (width - 1 - x, y)));
+/// let flip_v = Orientation {
+///     flip_v: true,
+///     ..Default::default()
+/// };
+/// assert_eq!(oriented(flip_v), moved(width, &|x, y| (x, height - 1 - y)));
+/// ```
+#[cfg_attr(not(feature = "spirv"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[derive(Clone, Copy, PartialEq, Default)]
+pub struct Orientation {
+    pub rotation: Rotation,
+    /// Mirror the image left to right, after rotating it.
+    pub flip_h: bool,
+    /// Mirror the image top to bottom, after rotating it.
+    pub flip_v: bool,
+}
+impl Orientation {
+    /// Whether this leaves the image as it is.
+    #[inline(always)]
+    pub fn is_identity(&self) -> bool {
+        self.rotation == Rotation::None && !self.flip_h && !self.flip_v
+    }
+}
+
+/// An axis-aligned rectangle in the complex plane.
+#[cfg_attr(not(feature = "spirv"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, PartialEq)]
+pub struct ComplexRect {
+    pub min: Imaginary,
+    pub max: Imaginary,
+}
+impl ComplexRect {
+    #[inline(always)]
+    pub fn width(&self) -> f64 {
+        self.max.re - self.min.re
+    }
+    #[inline(always)]
+    pub fn height(&self) -> f64 {
+        self.max.im - self.min.im
+    }
+    #[inline(always)]
+    pub fn center(&self) -> Imaginary {
+        Imaginary {
+            re: (self.min.re + self.max.re) / 2.0,
+            im: (self.min.im + self.max.im) / 2.0,
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "spirv"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, PartialEq, Default)]
 pub struct Imaginary {
     pub re: f64,
     pub im: f64,
@@ -94,6 +1077,27 @@ impl Imaginary {
     pub fn squared_distance(self) -> f64 {
         self.re * self.re + self.im * self.im
     }
+    /// The principal square root, with a real part of at least 0. The other root is its
+    /// negation.
+    ///
+    /// ```
+    /// use fractal_renderer_calc::Imaginary;
+    ///
+    /// let z = Imaginary { re: -3.0, im: 4.0 };
+    /// assert_eq!(z.sqrt(), Imaginary { re: 1.0, im: 2.0 });
+    /// assert_eq!(z.sqrt().square(), z);
+    /// assert_eq!(Imaginary { re: -4.0, im: 0.0 }.sqrt(), Imaginary { re: 0.0, im: 2.0 });
+    /// ```
+    #[inline(always)]
+    pub fn sqrt(self) -> Self {
+        let radius = self.squared_distance().sqrt();
+        let re = ((radius + self.re) / 2.0).sqrt();
+        let im = ((radius - self.re) / 2.0).sqrt();
+        Self {
+            re,
+            im: if self.im < 0.0 { -im } else { im },
+        }
+    }
 }
 impl Add for Imaginary {
     type Output = Self;
@@ -117,7 +1121,10 @@ impl Mul<f64> for Imaginary {
 }
 
 #[cfg_attr(not(feature = "spirv"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(bytemuck::Pod, bytemuck::Zeroable))]
 #[derive(Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
 pub struct RGB {
     pub r: u8,
     pub g: u8,
@@ -126,7 +1133,7 @@ pub struct RGB {
 impl RGB {
     const BLACK: Self = Self::new(0, 0, 0);
     #[inline(always)]
-    pub const fn new(r: u8, b: u8, g: u8) -> Self {
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
         Self { r, g, b }
     }
 }
@@ -146,12 +1153,95 @@ impl AddAssign for RGB {
 }
 
 #[cfg_attr(not(feature = "spirv"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 #[derive(Clone, PartialEq)]
 pub enum Algo {
     Mandelbrot,
+    #[cfg_attr(feature = "serde", serde(alias = "fern"))]
     BarnsleyFern,
-    Julia,
 }
+/// The parts of the [`Config`] which differ between the algorithms, see [`defaults`].
+#[cfg_attr(not(feature = "spirv"), derive(Debug))]
+#[derive(Clone, PartialEq)]
+pub struct AlgoDefaults {
+    /// Also the number of points the fern plots.
+    pub iterations: u32,
+    /// The [`Config::iterations`] worth rendering with, as `(min, max)`. Below it, the fern is
+    /// almost blank, and above it, escape-time renders take very long for little more detail.
+    pub iterations_range: (u32, u32),
+    /// The center of the view.
+    pub pos: Imaginary,
+    /// The center of the view of the Julia sets of the formula, see [`Config::julia`].
+    pub julia_pos: Imaginary,
+    pub scale: Imaginary,
+    pub primary_color: RGB,
+    /// Also the background of the fern.
+    pub secondary_color: RGB,
+    /// The range of [`Config::exposure`] and [`Config::interior_exposure`] worth offering, as
+    /// `(min, max)`. Beyond it, the image is all black or all white.
+    pub exposure_range: (f64, f64),
+    /// The range of [`Config::color_weight`] with [`Config::fern_normalize`] worth offering, as
+    /// `(min, max)`.
+    pub color_weight_range: (f64, f64),
+}
+/// The defaults of `algo`. [`Config::new`], the command line and the GUI all start from these.
+///
+/// ```
+/// use fractal_renderer_calc::{defaults, Algo, Imaginary, RGB};
+///
+/// let mandelbrot = defaults(&Algo::Mandelbrot);
+/// assert_eq!(mandelbrot.iterations, 50);
+/// assert_eq!(mandelbrot.iterations_range, (1, 10_000_000));
+/// assert_eq!(mandelbrot.pos, Imaginary { re: -0.6, im: 0.0 });
+/// assert_eq!(mandelbrot.scale, Imaginary { re: 0.4, im: 0.4 });
+/// assert_eq!(mandelbrot.primary_color, RGB::new(40, 40, 255));
+/// assert_eq!(mandelbrot.secondary_color, RGB::new(240, 170, 0));
+/// assert_eq!(mandelbrot.exposure_range, (0.01, 50.0));
+/// // Its Julia sets are symmetric around the origin.
+/// assert_eq!(mandelbrot.julia_pos, Imaginary::ZERO);
+///
+/// let fern = defaults(&Algo::BarnsleyFern);
+/// assert_eq!(fern.iterations, 10_000_000);
+/// assert_eq!(fern.iterations_range, (100_000, u32::MAX));
+/// assert_eq!(fern.pos, Imaginary::ZERO);
+/// assert_eq!(fern.scale, Imaginary { re: 0.4, im: 0.4 });
+/// assert_eq!(fern.primary_color, RGB::new(4, 100, 3));
+/// assert_eq!(fern.secondary_color, RGB::new(240, 240, 240));
+/// assert_eq!(fern.color_weight_range, (0.1, 1000.0));
+/// ```
+pub fn defaults(algo: &Algo) -> AlgoDefaults {
+    let scale = Imaginary::ONE * 0.4;
+    let exposure_range = (0.01, 50.0);
+    let color_weight_range = (0.1, 1000.0);
+    let escape_time_iterations = (1, 10_000_000);
+    match algo {
+        Algo::Mandelbrot => AlgoDefaults {
+            iterations: 50,
+            iterations_range: escape_time_iterations,
+            // The main cardioid is centered, not the origin.
+            pos: Imaginary { re: -0.6, im: 0.0 },
+            julia_pos: Imaginary::ZERO,
+            scale,
+            primary_color: RGB::new(40, 40, 255),
+            secondary_color: RGB::new(240, 170, 0),
+            exposure_range,
+            color_weight_range,
+        },
+        Algo::BarnsleyFern => AlgoDefaults {
+            iterations: 10_000_000,
+            iterations_range: (100_000, u32::MAX),
+            pos: Imaginary::ZERO,
+            julia_pos: Imaginary::ZERO,
+            scale,
+            primary_color: RGB::new(4, 100, 3),
+            secondary_color: RGB::new(240, 240, 240),
+            exposure_range,
+            color_weight_range,
+        },
+    }
+}
+
 pub enum AlgoParseError {
     /// Use one of the variants.
     Incorrect,
@@ -170,71 +1260,186 @@ impl FromStr for Algo {
             Self::Mandelbrot
         } else if s.eq_ignore_ascii_case("fern") || s.eq_ignore_ascii_case("barnsleyfern") {
             Self::BarnsleyFern
-        } else if s.eq_ignore_ascii_case("julia") {
-            Self::Julia
         } else {
             return Err(AlgoParseError::Incorrect);
         })
     }
 }
 
-#[inline(always)]
-fn coord_to_space(coord: f64, max: f64, offset: f64, pos: f64, scale: f64) -> f64 {
-    ((coord / max) - offset) / scale + pos
+/// The result of iterating a point.
+#[cfg_attr(not(feature = "spirv"), derive(Debug))]
+#[derive(Clone, Copy, PartialEq)]
+pub struct Sample {
+    /// Whether the orbit left [`Config::limit`] within [`Config::iterations`].
+    pub escaped: bool,
+    /// The number of iterations, smoothed if [`Config::smooth`] is set.
+    /// Always in `0..=config.iterations`.
+    pub iterations: f64,
+    /// The last point of the orbit.
+    pub final_z: Imaginary,
+    /// The smallest distance from 0 of the points of the orbit, including the start.
+    pub min_orbit_radius: f64,
 }
+
+/// Iterates the pixel (`x`, `y`), or a supersample around it if they're fractional, see
+/// [`mapping::pixel_point`].
+///
+/// Uses double-double precision if [`Config::uses_double_double`].
 #[inline(always)]
-fn xy_to_imaginary(
-    x: u32,
-    y: u32,
-    width: f64,
-    height: f64,
-    pos: &Imaginary,
-    scale: &Imaginary,
-) -> Imaginary {
-    let re = coord_to_space(x as f64, height, (width / height) / 2.0, pos.re, scale.re);
-    let im = coord_to_space(y as f64, height, 0.5, pos.im, scale.im);
-    Imaginary { re, im }
+pub fn sample(config: &Config, x: f64, y: f64) -> Sample {
+    if config.uses_double_double() {
+        sample_point_dd(config, mapping::pixel_point_dd(config, x, y))
+    } else {
+        sample_point(config, mapping::pixel_point(config, x, y))
+    }
 }
 
-pub fn get_recursive_pixel(config: &Config, x: u32, y: u32) -> RGB {
-    let start = xy_to_imaginary(
-        x,
-        y,
-        config.width as f64,
-        config.height as f64,
-        &config.pos,
-        &config.scale,
-    );
-    let (pos, iters) = match config.algo {
-        Algo::Mandelbrot => recursive(config.iterations, start, start, config.limit),
-        Algo::Julia => recursive(config.iterations, start, config.julia_set, config.limit),
-        _ => return RGB::BLACK,
+/// Iterates `point`. For Mandelbrot, the point is `c`. In Julia mode, the point is the start and
+/// `c` is [`Config::julia`].
+///
+/// The fern has no orbits, so `point` is returned as the final point, without iterating.
+///
+/// # Examples
+///
+/// The escape time of a point, without rendering an image:
+///
+/// ```
+/// use fractal_renderer_calc::{sample_point, Config, Imaginary};
+///
+/// let config = Config::default();
+/// let sample = sample_point(&config, Imaginary { re: 0.5, im: 0.5 });
+/// assert!(sample.escaped);
+/// assert!(sample.iterations < 10.0);
+///
+/// // 0 is in the Mandelbrot set.
+/// let sample = sample_point(&config, Imaginary::ZERO);
+/// assert!(!sample.escaped);
+/// assert_eq!(sample.min_orbit_radius, 0.0);
+/// ```
+#[inline(always)]
+pub fn sample_point(config: &Config, point: Imaginary) -> Sample {
+    let (final_z, iters, min_squared) = match config.algo {
+        Algo::Mandelbrot => recursive_with_min(
+            config.iterations,
+            point,
+            config.julia.unwrap_or(point),
+            config.limit,
+            config.bailout,
+        ),
+        Algo::BarnsleyFern => (point, 0, point.squared_distance()),
     };
+    finish_sample(config, final_z, iters, min_squared)
+}
+/// [`sample_point`] in double-double precision. The results are rounded to `f64`.
+#[inline(always)]
+pub fn sample_point_dd(config: &Config, point: dd::ImaginaryDD) -> Sample {
+    let (final_z, iters, min_squared) = match config.algo {
+        Algo::Mandelbrot => recursive_dd(
+            config.iterations,
+            point,
+            config.julia.map_or(point, Into::into),
+            config.limit,
+            config.bailout,
+        ),
+        Algo::BarnsleyFern => (point.to_imaginary(), 0, point.squared_distance()),
+    };
+    finish_sample(config, final_z, iters, min_squared)
+}
+/// Continues iterating `previous`, a sample of the pixel at (`x`, `y`) which didn't escape within
+/// `previous_iterations`, up to [`Config::iterations`]. This gives the same sample as iterating
+/// the pixel from the start, in `f64` precision.
+///
+/// # Examples
+///
+/// ```
+/// use fractal_renderer_calc::{continue_sample, sample, Config};
+///
+/// let mut config = Config::default();
+/// config.iterations = 20;
+/// let (x, y) = (config.width as f64 * 0.4, config.height as f64 * 0.45);
+/// let previous = sample(&config, x, y);
+/// config.iterations = 500;
+/// assert_eq!(continue_sample(&config, x, y, previous, 20), sample(&config, x, y));
+/// ```
+#[inline(always)]
+pub fn continue_sample(
+    config: &Config,
+    x: f64,
+    y: f64,
+    previous: Sample,
+    previous_iterations: u32,
+) -> Sample {
+    let c = match config.algo {
+        Algo::Mandelbrot => config
+            .julia
+            .unwrap_or_else(|| mapping::pixel_point(config, x, y)),
+        Algo::BarnsleyFern => return previous,
+    };
+    let (final_z, iters, min_squared) = recursive_with_min(
+        config.iterations.saturating_sub(previous_iterations),
+        previous.final_z,
+        c,
+        config.limit,
+        config.bailout,
+    );
+    let mut sample = finish_sample(config, final_z, previous_iterations + iters, min_squared);
+    sample.min_orbit_radius = sample.min_orbit_radius.min(previous.min_orbit_radius);
+    sample
+}
+/// Smooths the iterations of an orbit.
+#[inline(always)]
+fn finish_sample(config: &Config, final_z: Imaginary, iters: u32, min_squared: f64) -> Sample {
+    let dist = final_z.squared_distance();
 
-    let dist = pos.squared_distance();
-
-    if dist > config.stable_limit {
-        let mut iters = iters as f64;
-
-        if config.smooth {
-            // https://en.wikipedia.org/wiki/Plotting_algorithms_for_the_Mandelbrot_set#Continuous_(smooth)_coloring
-
-            let log_zn = f64::log2(dist.sqrt()) / 2.0;
-            let nu = f64::log2(log_zn);
+    let mut iterations = iters as f64;
+    // The smoothing assumes the points escape from a level set of the norm, which is a circle
+    // unless the bailout is stretched.
+    if config.smooth && config.bailout.is_smoothed() {
+        let norm = config.bailout.squared_norm(final_z, dist);
+        iterations = config.smooth_mode.smooth(iters, norm, config.limit);
+    }
+    Sample {
+        escaped: iters < config.iterations,
+        iterations: iterations.clamp(0.0, config.iterations as f64),
+        final_z,
+        min_orbit_radius: min_squared.sqrt(),
+    }
+}
 
-            iters += 1.0 - nu;
-        }
+/// The color of `sample`, which is of `config`. Black for the fern.
+#[inline(always)]
+pub fn colorize(sample: Sample, config: &Config) -> RGB {
+    colorize_with(sample, config, config.stable_limit * config.stable_limit)
+}
+/// [`colorize`] with the square of [`Config::stable_limit`] worked out.
+#[inline(always)]
+fn colorize_with(sample: Sample, config: &Config, stable_limit_squared: f64) -> RGB {
+    if let Algo::BarnsleyFern = config.algo {
+        return RGB::BLACK;
+    }
+    let dist = sample.final_z.squared_distance();
 
-        let mult = iters as f64 / config.iterations as f64 * config.exposure;
+    if dist > stable_limit_squared {
+        let mult = config
+            .smooth_mode
+            .brightness(sample.iterations, config.iterations)
+            * config.exposure;
         color_multiply(config.primary_color, mult)
     } else if config.inside {
-        color_multiply(config.secondary_color, dist)
+        let mult = config.interior_mapping.map(dist) * config.interior_exposure;
+        color_multiply(config.secondary_color, mult)
     } else {
         RGB::BLACK
     }
 }
 
-/// `limit` is distance from center considered out of bounds.
+/// The color of the pixel (`x`, `y`). Renders of many pixels should use
+/// [`ResolvedConfig::pixel`](resolved::ResolvedConfig::pixel), which gives the same colors.
+pub fn get_recursive_pixel(config: &Config, x: u32, y: u32) -> RGB {
+    colorize(sample(config, x as f64, y as f64), config)
+}
+
+/// `limit` is distance from center considered out of bounds, in the shape of `bailout`.
 ///
 /// If `c == start`, this is a Mandelbrot set. If `c` is constant, it's a Julia set.
 ///
@@ -242,16 +1447,58 @@ pub fn get_recursive_pixel(config: &Config, x: u32, y: u32) -> RGB {
 ///
 /// Returns the final position and the number of iterations to get there.
 #[inline(always)]
-pub fn recursive(iterations: u32, start: Imaginary, c: Imaginary, limit: f64) -> (Imaginary, u32) {
-    let squared = limit * limit;
+pub fn recursive(
+    iterations: u32,
+    start: Imaginary,
+    c: Imaginary,
+    limit: f64,
+    bailout: Bailout,
+) -> (Imaginary, u32) {
+    let (pos, iters, _) = recursive_with_min(iterations, start, c, limit, bailout);
+    (pos, iters)
+}
+/// [`recursive`], also returning the smallest squared distance from 0 of the orbit.
+#[inline(always)]
+pub fn recursive_with_min(
+    iterations: u32,
+    start: Imaginary,
+    c: Imaginary,
+    limit: f64,
+    bailout: Bailout,
+) -> (Imaginary, u32, f64) {
+    let mut previous = start;
+    let mut min = start.squared_distance();
+    for i in 0..iterations {
+        let next = previous.square() + c;
+        let dist = next.squared_distance();
+        if bailout.escaped(next, dist, limit) {
+            return (next, i, min);
+        }
+        min = min.min(dist);
+        previous = next;
+    }
+    (previous, iterations, min)
+}
+/// [`recursive_with_min`] in double-double precision. The final position is rounded to `f64`.
+#[inline(always)]
+pub fn recursive_dd(
+    iterations: u32,
+    start: dd::ImaginaryDD,
+    c: dd::ImaginaryDD,
+    limit: f64,
+    bailout: Bailout,
+) -> (Imaginary, u32, f64) {
     let mut previous = start;
+    let mut min = start.squared_distance();
     for i in 0..iterations {
         let next = previous.square() + c;
         let dist = next.squared_distance();
-        if dist > squared {
-            return (next, i);
+        // Rounding doesn't matter for the bailout.
+        if bailout.escaped(next.to_imaginary(), dist, limit) {
+            return (next.to_imaginary(), i, min);
         }
+        min = min.min(dist);
         previous = next;
     }
-    (previous, iterations)
+    (previous.to_imaginary(), iterations, min)
 }
@@ -0,0 +1,294 @@
+//! Conversions between pixels of the image and points in the complex plane.
+//!
+//! Pixel coordinates are fractional, with (0, 0) at the top left corner of the image and
+//! (`width`, `height`) at the bottom right. The image covers [`viewport`].
+//!
+//! # Pixel centers
+//!
+//! Pixel `x` covers the coordinates from `x` to `x + 1`. By default, it's sampled at `x`, its
+//! top left corner, so the samples of an image of twice the size aren't centered on those of the
+//! smaller image, and downscaling it doesn't give the smaller image. With
+//! [`Config::pixel_centers`], it's sampled at `x + 0.5`, which is recommended for new renders;
+//! the corners are the default to keep existing renders the same. Everything which samples pixels
+//! goes through [`sample_position`], so they can't disagree on it.
+//!
+//! # Exponential maps
+//!
+//! With [`Config::exp_map`], the image is a strip in log-polar coordinates around
+//! [`Config::pos`]: `x` is the angle, one turn over the width, and `y` the logarithm of the
+//! distance to the center. The top row is the circle of radius `r0 = 0.5 / scale.im`, half the
+//! height of the normal image, and every row down shrinks the radius by `e^(-2π / width)`, so the
+//! strip covers radii from `r0` down to `r0 × e^(-2π × height / width)`. The pixels stay square.
+//!
+//! Unrolling the strip gives the normal images of every scale in that range: the point at angle
+//! `θ` and distance `r` of the image of scale `s` is at `x = θ / 2π × width` and
+//! `y = ln(r0 / r) × width / 2π` in the strip. Zooming in by a factor `f` moves down by
+//! `ln(f) × width / 2π` rows.
+//!
+//! # Direction of the imaginary axis
+//!
+//! By default, the imaginary part grows down the rows of the image, so the complex plane is upside
+//! down compared to how it's drawn in mathematics. [`Config::y_up`] mirrors the offsets from
+//! [`Config::pos`] along the real axis, so it grows upwards. Everything else is about the default
+//! direction.
+//!
+//! # Orientation
+//!
+//! [`Config::orientation`] is applied to the pixel coordinates before anything else, with
+//! [`unorient`], so the image shows the same points as the unoriented image of
+//! [`Config::unoriented_size`], moved. Everything above is about the unoriented image.
+
+use crate::dd::{DoubleDouble, ImaginaryDD};
+use crate::{ComplexRect, Config, Imaginary, Orientation, Rotation};
+use core::f64::consts::TAU;
+#[cfg(feature = "spirv")]
+use spirv_std::num_traits::Float;
+
+/// The rectangle of the complex plane covered by the image.
+#[inline(always)]
+pub fn viewport(config: &Config) -> ComplexRect {
+    config.viewport()
+}
+
+/// The position in the unoriented image of (`x`, `y`) in the image with `orientation` of
+/// `width` × `height`. The inverse of [`orient`].
+///
+/// The flips mirror around the middle of the first and last pixel, `x` to `width - 1 - x`, so
+/// pixels move to pixels.
+#[inline(always)]
+pub fn unorient(
+    orientation: Orientation,
+    width: f64,
+    height: f64,
+    mut x: f64,
+    mut y: f64,
+) -> (f64, f64) {
+    if orientation.flip_h {
+        x = width - 1.0 - x;
+    }
+    if orientation.flip_v {
+        y = height - 1.0 - y;
+    }
+    match orientation.rotation {
+        Rotation::None => (x, y),
+        Rotation::Quarter => (y, width - 1.0 - x),
+        Rotation::Half => (width - 1.0 - x, height - 1.0 - y),
+        Rotation::ThreeQuarters => (height - 1.0 - y, x),
+    }
+}
+/// The position in the image with `orientation` of `width` × `height` of (`x`, `y`) in the
+/// unoriented image. The inverse of [`unorient`].
+#[inline(always)]
+pub fn orient(orientation: Orientation, width: f64, height: f64, x: f64, y: f64) -> (f64, f64) {
+    let (mut x, mut y) = match orientation.rotation {
+        Rotation::None => (x, y),
+        Rotation::Quarter => (width - 1.0 - y, x),
+        Rotation::Half => (width - 1.0 - x, height - 1.0 - y),
+        Rotation::ThreeQuarters => (y, height - 1.0 - x),
+    };
+    if orientation.flip_h {
+        x = width - 1.0 - x;
+    }
+    if orientation.flip_v {
+        y = height - 1.0 - y;
+    }
+    (x, y)
+}
+
+/// The offset of the point at (`x`, `y`) in the image from [`Config::pos`]. The inverse of
+/// [`offset_to_pixel`].
+///
+/// Unlike [`pixel_to_complex`], this is precise at any zoom.
+#[inline(always)]
+pub fn pixel_offset(config: &Config, x: f64, y: f64) -> Imaginary {
+    let offset = downwards_offset(config, x, y);
+    // Catches configs which [`Config::validate`] should have rejected, like a scale of zero.
+    #[cfg(not(feature = "spirv"))]
+    debug_assert!(
+        offset.re.is_finite() && offset.im.is_finite(),
+        "pixel ({}, {}) maps to the offset {:?}",
+        x,
+        y,
+        offset
+    );
+    if config.y_up {
+        conjugate(offset)
+    } else {
+        offset
+    }
+}
+#[inline(always)]
+fn conjugate(z: Imaginary) -> Imaginary {
+    Imaginary {
+        re: z.re,
+        im: -z.im,
+    }
+}
+/// [`pixel_offset`] without [`Config::y_up`].
+#[inline(always)]
+fn downwards_offset(config: &Config, x: f64, y: f64) -> Imaginary {
+    let (x, y) = unorient(
+        config.orientation,
+        config.width as f64,
+        config.height as f64,
+        x,
+        y,
+    );
+    let (width, height) = config.unoriented_size();
+    let width = width as f64;
+    let height = height as f64;
+    if config.exp_map {
+        let angle = x / width * TAU;
+        let radius = 0.5 / config.scale.im * (-y / width * TAU).exp();
+        return Imaginary {
+            re: radius * angle.cos(),
+            im: radius * angle.sin(),
+        };
+    }
+    // Relative to the height and center, as this is less prone to rounding errors.
+    Imaginary {
+        re: ((x / height) - (width / height) / 2.0) / config.scale.re,
+        im: ((y / height) - 0.5) / config.scale.im,
+    }
+}
+
+/// The point at (`x`, `y`) in the image. The inverse of [`complex_to_pixel`].
+///
+/// `y` grows with the imaginary part, or shrinks with the distance to the center with
+/// [`Config::exp_map`]. With [`Config::y_up`], it shrinks with the imaginary part.
+///
+/// ```
+/// use fractal_renderer_calc::{mapping::pixel_to_complex, Config};
+///
+/// let mut config = Config::default();
+/// let top = pixel_to_complex(&config, 1000.0, 0.0);
+/// assert!(top.im < 0.0);
+/// config.y_up = true;
+/// let top = pixel_to_complex(&config, 1000.0, 0.0);
+/// assert!(top.im > 0.0);
+/// assert_eq!(top.re, config.pos.re);
+/// ```
+#[inline(always)]
+pub fn pixel_to_complex(config: &Config, x: f64, y: f64) -> Imaginary {
+    let offset = pixel_offset(config, x, y);
+    Imaginary {
+        re: offset.re + config.pos.re,
+        im: offset.im + config.pos.im,
+    }
+}
+
+/// [`pixel_to_complex`] in double-double precision, with the center [`Config::pos`] +
+/// [`Config::pos_low`].
+#[inline(always)]
+pub fn pixel_to_complex_dd(config: &Config, x: f64, y: f64) -> ImaginaryDD {
+    // The offset from the center is small, so it's precise in f64.
+    let offset = pixel_offset(config, x, y);
+    ImaginaryDD {
+        re: DoubleDouble::from(config.pos.re) + config.pos_low.re.into() + offset.re.into(),
+        im: DoubleDouble::from(config.pos.im) + config.pos_low.im.into() + offset.im.into(),
+    }
+}
+
+/// The position of the sample of pixel (`x`, `y`), fractional for supersamples around it: its
+/// top left corner, or its center with [`Config::pixel_centers`]. The inverse of
+/// [`sample_index`].
+///
+/// ```
+/// use fractal_renderer_calc::{mapping::{pixel_point, sample_position}, Config};
+///
+/// let mut config = Config::default();
+/// assert_eq!(sample_position(&config, 3.0, 4.0), (3.0, 4.0));
+/// config.pixel_centers = true;
+/// assert_eq!(sample_position(&config, 3.0, 4.0), (3.5, 4.5));
+///
+/// // The four pixels of twice the size are centered on the pixel.
+/// let mut double = config.clone();
+/// double.width *= 2;
+/// double.height *= 2;
+/// let point = pixel_point(&config, 3.0, 4.0);
+/// let mut mean = [0.0; 2];
+/// for (x, y) in [(6.0, 8.0), (7.0, 8.0), (6.0, 9.0), (7.0, 9.0)] {
+///     let point = pixel_point(&double, x, y);
+///     mean[0] += point.re / 4.0;
+///     mean[1] += point.im / 4.0;
+/// }
+/// assert!((mean[0] - point.re).abs() < 1e-12 && (mean[1] - point.im).abs() < 1e-12);
+/// ```
+#[inline(always)]
+pub fn sample_position(config: &Config, x: f64, y: f64) -> (f64, f64) {
+    if config.pixel_centers {
+        (x + 0.5, y + 0.5)
+    } else {
+        (x, y)
+    }
+}
+/// The pixel, fractional, whose sample is at the position (`x`, `y`). The inverse of
+/// [`sample_position`].
+#[inline(always)]
+pub fn sample_index(config: &Config, x: f64, y: f64) -> (f64, f64) {
+    if config.pixel_centers {
+        (x - 0.5, y - 0.5)
+    } else {
+        (x, y)
+    }
+}
+/// The point iterated for pixel (`x`, `y`), see [`sample_position`].
+#[inline(always)]
+pub fn pixel_point(config: &Config, x: f64, y: f64) -> Imaginary {
+    let (x, y) = sample_position(config, x, y);
+    pixel_to_complex(config, x, y)
+}
+/// [`pixel_point`] in double-double precision, see [`pixel_to_complex_dd`].
+#[inline(always)]
+pub fn pixel_point_dd(config: &Config, x: f64, y: f64) -> ImaginaryDD {
+    let (x, y) = sample_position(config, x, y);
+    pixel_to_complex_dd(config, x, y)
+}
+
+/// The position of the point `offset` from [`Config::pos`] in the image. The inverse of
+/// [`pixel_offset`].
+#[inline(always)]
+pub fn offset_to_pixel(config: &Config, offset: Imaginary) -> (f64, f64) {
+    let offset = if config.y_up {
+        conjugate(offset)
+    } else {
+        offset
+    };
+    let (width, height) = config.unoriented_size();
+    let width = width as f64;
+    let height = height as f64;
+    let (x, y) = if config.exp_map {
+        let mut angle = offset.im.atan2(offset.re);
+        if angle < 0.0 {
+            angle += TAU;
+        }
+        let radius = offset.re.hypot(offset.im);
+        (
+            angle / TAU * width,
+            -(radius * 2.0 * config.scale.im).ln() / TAU * width,
+        )
+    } else {
+        (
+            (offset.re * config.scale.re + (width / height) / 2.0) * height,
+            (offset.im * config.scale.im + 0.5) * height,
+        )
+    };
+    orient(
+        config.orientation,
+        config.width as f64,
+        config.height as f64,
+        x,
+        y,
+    )
+}
+
+/// The position of `z` in the image, which is outside of `0..width` and `0..height` if `z` isn't
+/// in the [`viewport`]. The inverse of [`pixel_to_complex`].
+#[inline(always)]
+pub fn complex_to_pixel(config: &Config, z: Imaginary) -> (f64, f64) {
+    let offset = Imaginary {
+        re: z.re - config.pos.re,
+        im: z.im - config.pos.im,
+    };
+    offset_to_pixel(config, offset)
+}
@@ -0,0 +1,94 @@
+//! [`ResolvedConfig`], the parts of a [`Config`] which every pixel needs, worked out once per
+//! render instead of once per pixel.
+
+use crate::{
+    colorize_with, mapping, sample_point, sample_point_dd, Config, Imaginary, Sample, RGB,
+};
+
+/// A [`Config`] with what [`sample`](crate::sample) and
+/// [`get_recursive_pixel`](crate::get_recursive_pixel) work out for every pixel resolved: the
+/// precision, the squared [`Config::stable_limit`] and the constants of the coordinate mapping.
+///
+/// The samples and colors are exactly the same as those of the functions for single pixels.
+///
+/// ```
+/// use fractal_renderer_calc::{get_recursive_pixel, resolved::ResolvedConfig, sample, Config};
+///
+/// let mut config = Config::default();
+/// config.width = 40;
+/// config.height = 30;
+/// config.pixel_centers = true;
+/// let resolved = ResolvedConfig::new(&config);
+/// for y in 0..30 {
+///     for x in 0..40 {
+///         assert_eq!(resolved.sample(x as f64, y as f64), sample(&config, x as f64, y as f64));
+///         assert_eq!(resolved.pixel(x, y), get_recursive_pixel(&config, x, y));
+///     }
+/// }
+/// ```
+#[cfg_attr(not(feature = "spirv"), derive(Debug))]
+#[derive(Clone, PartialEq)]
+pub struct ResolvedConfig<'a> {
+    pub config: &'a Config,
+    /// [`Config::uses_double_double`].
+    double_double: bool,
+    stable_limit_squared: f64,
+    /// The width and height of the image.
+    size: (f64, f64),
+    /// The width and height of the image before it's oriented, see [`Config::unoriented_size`].
+    unoriented: (f64, f64),
+    /// Half the aspect ratio of the unoriented image.
+    half_aspect: f64,
+}
+impl<'a> ResolvedConfig<'a> {
+    pub fn new(config: &'a Config) -> Self {
+        let (width, height) = config.unoriented_size();
+        let unoriented = (width as f64, height as f64);
+        Self {
+            config,
+            double_double: config.uses_double_double(),
+            stable_limit_squared: config.stable_limit * config.stable_limit,
+            size: (config.width as f64, config.height as f64),
+            unoriented,
+            half_aspect: (unoriented.0 / unoriented.1) / 2.0,
+        }
+    }
+    /// The point iterated for pixel (`x`, `y`), see [`mapping::pixel_point`].
+    #[inline(always)]
+    pub fn point(&self, x: f64, y: f64) -> Imaginary {
+        let config = self.config;
+        if config.exp_map {
+            return mapping::pixel_point(config, x, y);
+        }
+        let (x, y) = mapping::sample_position(config, x, y);
+        let (x, y) = mapping::unorient(config.orientation, self.size.0, self.size.1, x, y);
+        let height = self.unoriented.1;
+        // The same as `mapping::pixel_offset`, so the points are exactly the same.
+        let re = ((x / height) - self.half_aspect) / config.scale.re;
+        let im = ((y / height) - 0.5) / config.scale.im;
+        let im = if config.y_up { -im } else { im };
+        Imaginary {
+            re: re + config.pos.re,
+            im: im + config.pos.im,
+        }
+    }
+    /// [`sample`](crate::sample) of `self.config`.
+    #[inline(always)]
+    pub fn sample(&self, x: f64, y: f64) -> Sample {
+        if self.double_double {
+            sample_point_dd(self.config, mapping::pixel_point_dd(self.config, x, y))
+        } else {
+            sample_point(self.config, self.point(x, y))
+        }
+    }
+    /// [`colorize`](crate::colorize) of `self.config`.
+    #[inline(always)]
+    pub fn colorize(&self, sample: Sample) -> RGB {
+        colorize_with(sample, self.config, self.stable_limit_squared)
+    }
+    /// [`get_recursive_pixel`](crate::get_recursive_pixel) of `self.config`.
+    #[inline(always)]
+    pub fn pixel(&self, x: u32, y: u32) -> RGB {
+        self.colorize(self.sample(x as f64, y as f64))
+    }
+}
@@ -0,0 +1,93 @@
+//! Bindings for JavaScript, enabled by the `wasm` feature.
+//!
+//! Only the escape-time fractals are supported, as the fern needs a random number generator.
+//!
+//! The config is given as JSON, with the keys of [`Config`]. Missing keys use the defaults of
+//! the algorithm, so `{"julia": {"re": -0.8, "im": 0.156}}` is enough.
+//! Images are returned as RGBA, for `new ImageData(pixels, width)`.
+
+use crate::{get_recursive_pixel, Algo, Config};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::Clamped;
+
+fn parse_config(json: &str) -> Result<Config, JsValue> {
+    let error = |err: serde_json::Error| JsValue::from_str(&err.to_string());
+    let overrides: serde_json::Value = serde_json::from_str(json).map_err(error)?;
+    let algo = match overrides.get("algo") {
+        Some(algo) => serde_json::from_value(algo.clone()).map_err(error)?,
+        None => Algo::Mandelbrot,
+    };
+    let mut config = serde_json::to_value(Config::new(algo)).map_err(error)?;
+    if let (Some(config), Some(overrides)) = (config.as_object_mut(), overrides.as_object()) {
+        for (key, value) in overrides {
+            config.insert(key.clone(), value.clone());
+        }
+    }
+    let config: Config = serde_json::from_value(config).map_err(error)?;
+    if let Algo::BarnsleyFern = config.algo {
+        return Err(JsValue::from_str("the fern isn't supported in the browser"));
+    }
+    Ok(config)
+}
+
+fn push_row(config: &Config, y: u32, pixels: &mut Vec<u8>) {
+    for x in 0..config.width {
+        let pixel = get_recursive_pixel(config, x, y);
+        pixels.extend_from_slice(&[pixel.r, pixel.g, pixel.b, 255]);
+    }
+}
+
+/// Renders the whole image described by `config_json`.
+#[wasm_bindgen]
+pub fn render(config_json: &str) -> Result<Clamped<Vec<u8>>, JsValue> {
+    let config = parse_config(config_json)?;
+    let mut pixels = Vec::with_capacity(config.width as usize * config.height as usize * 4);
+    for y in 0..config.height {
+        push_row(&config, y, &mut pixels);
+    }
+    Ok(Clamped(pixels))
+}
+
+/// Renders an image a few rows at a time, so the page can stay responsive in between.
+#[wasm_bindgen]
+pub struct Renderer {
+    config: Config,
+    next_row: u32,
+}
+#[wasm_bindgen]
+impl Renderer {
+    #[wasm_bindgen(constructor)]
+    pub fn new(config_json: &str) -> Result<Renderer, JsValue> {
+        Ok(Self {
+            config: parse_config(config_json)?,
+            next_row: 0,
+        })
+    }
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.config.width
+    }
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.config.height
+    }
+    /// The first row of the next chunk.
+    #[wasm_bindgen(getter)]
+    pub fn next_row(&self) -> u32 {
+        self.next_row
+    }
+    pub fn done(&self) -> bool {
+        self.next_row >= self.config.height
+    }
+    /// Renders the next `rows` rows (fewer at the bottom), starting at [`Self::next_row`].
+    pub fn render_rows(&mut self, rows: u32) -> Clamped<Vec<u8>> {
+        let end = self.next_row.saturating_add(rows).min(self.config.height);
+        let mut pixels =
+            Vec::with_capacity((end - self.next_row) as usize * self.config.width as usize * 4);
+        for y in self.next_row..end {
+            push_row(&self.config, y, &mut pixels);
+        }
+        self.next_row = end;
+        Clamped(pixels)
+    }
+}
@@ -0,0 +1,59 @@
+/*
+ * Renders the Mandelbrot set using the C bindings and writes it to `mandelbrot.ppm`.
+ *
+ * Build the static library and the example from the root of the repository:
+ *
+ *     cargo rustc --release --lib --features ffi --crate-type staticlib
+ *     cc examples/ffi/render.c -Iinclude target/release/libfractal_renderer.a \
+ *         -lpthread -ldl -lm -o render
+ *     ./render
+ */
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "fractal_renderer.h"
+
+static int fail(const char *what) {
+    const char *message = fr_last_error_message();
+    fprintf(stderr, "%s failed: %s\n", what, message ? message : "unknown error");
+    return 1;
+}
+
+int main(void) {
+    const uint32_t width = 750, height = 500;
+
+    FrConfig *config = fr_config_new(FR_ALGO_MANDELBROT);
+    if (!config) {
+        return fail("fr_config_new");
+    }
+    if (fr_config_set_size(config, width, height) != FR_OK ||
+        fr_config_set_position(config, -0.6, 0.0) != FR_OK ||
+        fr_config_set_iterations(config, 100) != FR_OK) {
+        fr_config_free(config);
+        return fail("configuring");
+    }
+
+    size_t len = (size_t)width * height * 3;
+    uint8_t *pixels = malloc(len);
+    if (!pixels) {
+        fr_config_free(config);
+        return 1;
+    }
+    if (fr_render(config, pixels, len) != FR_OK) {
+        free(pixels);
+        fr_config_free(config);
+        return fail("fr_render");
+    }
+    fr_config_free(config);
+
+    FILE *file = fopen("mandelbrot.ppm", "wb");
+    if (!file) {
+        free(pixels);
+        return 1;
+    }
+    fprintf(file, "P6\n%u %u\n255\n", width, height);
+    fwrite(pixels, 1, len, file);
+    fclose(file);
+    free(pixels);
+    return 0;
+}
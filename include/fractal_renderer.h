@@ -0,0 +1,149 @@
+#ifndef FRACTAL_RENDERER_H
+#define FRACTAL_RENDERER_H
+
+/* Generated by cbindgen from src/ffi.rs. Don't edit manually. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define FR_OK 0
+
+/**
+ * A pointer argument was null.
+ */
+#define FR_NULL_POINTER -1
+
+/**
+ * An argument was out of range.
+ */
+#define FR_INVALID_ARGUMENT -2
+
+/**
+ * The output buffer is smaller than `width * height * 3` bytes.
+ */
+#define FR_BUFFER_TOO_SMALL -3
+
+/**
+ * The renderer panicked. This is a bug.
+ */
+#define FR_PANIC -4
+
+#define FR_ALGO_MANDELBROT 0
+
+/**
+ * [`FR_ALGO_MANDELBROT`] in Julia mode, with the constant 0 until [`fr_config_set_julia`].
+ */
+#define FR_ALGO_JULIA 1
+
+#define FR_ALGO_BARNSLEY_FERN 2
+
+/**
+ * An opaque configuration, created by [`fr_config_new`] and freed by [`fr_config_free`].
+ */
+typedef struct FrConfig FrConfig;
+
+/**
+ * The message of the last error on this thread, or null if there hasn't been one.
+ *
+ * The string is valid until the next call on this thread.
+ */
+const char *fr_last_error_message(void);
+
+/**
+ * Creates a config with the defaults of `algo`, one of the `FR_ALGO_*` constants.
+ *
+ * Returns null if `algo` is invalid.
+ */
+struct FrConfig *fr_config_new(uint32_t algo);
+
+/**
+ * Frees a config created by [`fr_config_new`]. Does nothing if `config` is null.
+ *
+ * # Safety
+ *
+ * `config` must not be used after this.
+ */
+void fr_config_free(struct FrConfig *config);
+
+/**
+ * # Safety
+ *
+ * `config` must be null or a valid config.
+ */
+int32_t fr_config_set_size(struct FrConfig *config, uint32_t width, uint32_t height);
+
+/**
+ * Sets the maximum number of iterations, at least 1.
+ *
+ * # Safety
+ *
+ * `config` must be null or a valid config.
+ */
+int32_t fr_config_set_iterations(struct FrConfig *config, uint32_t iterations);
+
+/**
+ * Sets the center of the image.
+ *
+ * # Safety
+ *
+ * `config` must be null or a valid config.
+ */
+int32_t fr_config_set_position(struct FrConfig *config, double re, double im);
+
+/**
+ * # Safety
+ *
+ * `config` must be null or a valid config.
+ */
+int32_t fr_config_set_scale(struct FrConfig *config, double re, double im);
+
+/**
+ * Sets the escape radius and the radius of points colored as inside.
+ *
+ * # Safety
+ *
+ * `config` must be null or a valid config.
+ */
+int32_t fr_config_set_limits(struct FrConfig *config, double limit, double stable_limit);
+
+/**
+ * # Safety
+ *
+ * `config` must be null or a valid config.
+ */
+int32_t fr_config_set_exposure(struct FrConfig *config, double exposure);
+
+/**
+ * Sets the constant of the Julia set, which renders the Julia set of the algorithm.
+ *
+ * # Safety
+ *
+ * `config` must be null or a valid config.
+ */
+int32_t fr_config_set_julia(struct FrConfig *config, double re, double im);
+
+/**
+ * Sets the primary and secondary colors, as `0xRRGGBB`.
+ *
+ * # Safety
+ *
+ * `config` must be null or a valid config.
+ */
+int32_t fr_config_set_colors(struct FrConfig *config, uint32_t primary, uint32_t secondary);
+
+/**
+ * Renders `config` into `out`, as `width * height` RGB8 pixels in rows from the top left.
+ *
+ * Fails with [`FR_INVALID_ARGUMENT`] if the config can't be rendered.
+ *
+ * # Safety
+ *
+ * `config` must be null or a valid config and `out` must be null or valid for writes of
+ * `out_len` bytes.
+ */
+int32_t fr_render(const struct FrConfig *config, uint8_t *out, size_t out_len);
+
+#endif  /* FRACTAL_RENDERER_H */
@@ -0,0 +1,204 @@
+//! Adaptive anti-aliasing of escape-time fractals, see `--adaptive-aa`.
+//!
+//! Only the pixels which differ from a neighbour by more than [`AaOptions::threshold`] in any
+//! channel are supersampled, first with 2×2 samples, then with [`MAX_SIDE`]×[`MAX_SIDE`] if
+//! those still differ by more than it. The flat areas, most of most images, keep their one sample.
+//!
+//! With [`apply`]'s `record`, the number of samples of each pixel is kept, see `--aa-debug` and
+//! [`counts_image`], to tune the threshold for a scene.
+
+use crate::palette::{self, Palette};
+use crate::{Config, RGB};
+use rayon::iter::{IndexedParallelIterator, ParallelIterator};
+use rayon::slice::ParallelSliceMut;
+
+/// The most samples along each side of a pixel.
+pub const MAX_SIDE: u32 = 4;
+/// The most samples of a pixel.
+pub const MAX_SAMPLES: u8 = (MAX_SIDE * MAX_SIDE) as u8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AaOptions {
+    /// The largest difference of a channel, from 0 to 255, which isn't supersampled.
+    pub threshold: u8,
+    /// Also write the map of [`counts_image`] to this file.
+    pub debug: Option<String>,
+}
+
+/// The result of [`apply`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Antialiased {
+    /// The number of pixels which were supersampled.
+    pub supersampled: usize,
+    /// The number of samples of each pixel: 1, 4 or [`MAX_SAMPLES`]. Only recorded if asked
+    /// for.
+    pub counts: Option<Vec<u8>>,
+}
+
+fn color(sample: crate::Sample, config: &Config, palette: Option<&Palette>) -> RGB {
+    match palette {
+        Some(palette) => palette::colorize(sample, config, palette),
+        None => crate::colorize(sample, config),
+    }
+}
+/// The largest difference of a channel between `a` and `b`.
+fn difference(a: RGB, b: RGB) -> u8 {
+    let channel = |a: u8, b: u8| if a > b { a - b } else { b - a };
+    channel(a.r, b.r)
+        .max(channel(a.g, b.g))
+        .max(channel(a.b, b.b))
+}
+fn mean(colors: &[RGB]) -> RGB {
+    let sum = colors.iter().fold([0_u32; 3], |sum, color| {
+        [
+            sum[0] + color.r as u32,
+            sum[1] + color.g as u32,
+            sum[2] + color.b as u32,
+        ]
+    });
+    let n = colors.len() as u32;
+    let channel = |sum: u32| ((sum + n / 2) / n) as u8;
+    RGB::new(channel(sum[0]), channel(sum[1]), channel(sum[2]))
+}
+/// The colors of `side`×`side` samples evenly spread over the pixel (`x`, `y`).
+fn grid(config: &Config, palette: Option<&Palette>, x: usize, y: usize, side: u32) -> Vec<RGB> {
+    let offset = |i: u32| (i as f64 + 0.5) / side as f64 - 0.5;
+    (0..side * side)
+        .map(|i| {
+            let sample = crate::sample(
+                config,
+                x as f64 + offset(i % side),
+                y as f64 + offset(i / side),
+            );
+            color(sample, config, palette)
+        })
+        .collect()
+}
+
+/// Supersamples the pixels of `contents`, the colors of `config` with `palette`, which differ
+/// from a neighbour by more than `threshold`. With `record`, the number of samples of every
+/// pixel is returned.
+///
+/// ```
+/// # use fractal_renderer::{aa, get_image, Algo, Config};
+/// let mut config = Config::new(Algo::Mandelbrot);
+/// config.width = 60;
+/// config.height = 40;
+/// let image = get_image(&config);
+///
+/// let mut antialiased = image.clone();
+/// let result = aa::apply(&mut antialiased, &config, None, 32, true);
+/// let counts = result.counts.unwrap();
+/// assert_eq!(counts.len(), image.len());
+/// assert_eq!(
+///     counts.iter().filter(|count| **count > 1).count(),
+///     result.supersampled
+/// );
+/// assert!(result.supersampled > 0 && result.supersampled < image.len() / 2);
+/// // The pixels with one sample are kept.
+/// for ((before, after), count) in image.iter().zip(&antialiased).zip(&counts) {
+///     assert!(matches!(count, 1 | 4 | 16));
+///     if *count == 1 {
+///         assert_eq!(before, after);
+///     }
+/// }
+///
+/// // Nothing is supersampled if every difference is allowed.
+/// let mut same = image.clone();
+/// assert_eq!(aa::apply(&mut same, &config, None, 255, false).supersampled, 0);
+/// assert_eq!(same, image);
+/// ```
+pub fn apply(
+    contents: &mut [RGB],
+    config: &Config,
+    palette: Option<&Palette>,
+    threshold: u8,
+    record: bool,
+) -> Antialiased {
+    let width = config.width as usize;
+    if width == 0 || contents.is_empty() {
+        return Antialiased {
+            supersampled: 0,
+            counts: record.then(Vec::new),
+        };
+    }
+    let height = contents.len() / width;
+    let base = contents.to_vec();
+    let contrast = |x: usize, y: usize| {
+        let here = base[y * width + x];
+        let neighbours = [
+            (x > 0).then(|| (x - 1, y)),
+            (x + 1 < width).then(|| (x + 1, y)),
+            (y > 0).then(|| (x, y - 1)),
+            (y + 1 < height).then(|| (x, y + 1)),
+        ];
+        neighbours
+            .iter()
+            .flatten()
+            .map(|&(x, y)| difference(here, base[y * width + x]))
+            .max()
+            .unwrap_or(0)
+    };
+
+    let rows: Vec<(usize, Vec<u8>)> = contents
+        .par_chunks_mut(width)
+        .enumerate()
+        .map(|(y, row)| {
+            let mut supersampled = 0;
+            // Only allocated when debugging.
+            let mut counts = if record {
+                Vec::with_capacity(width)
+            } else {
+                Vec::new()
+            };
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let mut count = 1;
+                if contrast(x, y) > threshold {
+                    let mut colors = grid(config, palette, x, y, 2);
+                    let spread = colors
+                        .iter()
+                        .flat_map(|a| colors.iter().map(move |b| difference(*a, *b)))
+                        .max()
+                        .unwrap_or(0);
+                    if spread > threshold {
+                        colors = grid(config, palette, x, y, MAX_SIDE);
+                    }
+                    *pixel = mean(&colors);
+                    count = colors.len() as u8;
+                    supersampled += 1;
+                }
+                if record {
+                    counts.push(count);
+                }
+            }
+            (supersampled, counts)
+        })
+        .collect();
+
+    let supersampled = rows.iter().map(|(supersampled, _)| supersampled).sum();
+    let counts = record.then(|| rows.into_iter().flat_map(|(_, counts)| counts).collect());
+    Antialiased {
+        supersampled,
+        counts,
+    }
+}
+
+/// The `counts` of [`Antialiased`] in grayscale, from black for one sample to white for
+/// [`MAX_SAMPLES`].
+///
+/// ```
+/// # use fractal_renderer::{aa::counts_image, RGB};
+/// assert_eq!(
+///     counts_image(&[1, 4, 16]),
+///     [RGB::new(0, 0, 0), RGB::new(51, 51, 51), RGB::new(255, 255, 255)]
+/// );
+/// ```
+pub fn counts_image(counts: &[u8]) -> Vec<RGB> {
+    counts
+        .iter()
+        .map(|count| {
+            let value = (count.saturating_sub(1) as u32 * 255 / (MAX_SAMPLES as u32 - 1)) as u8;
+            RGB::new(value, value, value)
+        })
+        .collect()
+}
@@ -0,0 +1,531 @@
+//! Zoom animations: frames zooming from the scale of the config to [`AnimationOptions::zoom_to`],
+//! towards the center of the config, or following the [`camera`] path of
+//! [`AnimationOptions::path`].
+//!
+//! The frames are written to `<output>-0000.<ext>`, `<output>-0001.<ext>` and so on, which e.g.
+//! `ffmpeg -i output-%04d.png` turns into a video.
+//!
+//! Frames deep inside or far outside of the fractal are a single color. Unless disabled with
+//! [`AnimationOptions::frame_skip`], a sparse grid of points is probed first, and if they all have
+//! the same color, the frame is filled with it instead of being rendered. This is conservative:
+//! any difference between the probes renders the whole frame.
+//!
+//! With [`AnimationOptions::exp_map`], one exponential map strip covering every frame is rendered
+//! instead (see [`calc::mapping`]), and the frames are resampled from it. That iterates about as
+//! many points as a few frames, whatever the number of frames, but the centers of the frames are
+//! blurrier than the edges, as they're sampled more sparsely.
+//!
+//! # Reusing frames
+//!
+//! With [`AnimationOptions::reuse_frames`], every frame takes the samples of the pixels which
+//! are, within [`REUSE_TOLERANCE`] of a pixel, the same point as a pixel of the previous frame,
+//! see [`reuse`]. The pixels near the edges of the previous frame, near the edges of the fractal
+//! and close to the iteration cap are iterated again. A few of the reused pixels are iterated
+//! anyway to estimate the PSNR of the frame, and if it's below
+//! [`AnimationOptions::reuse_min_psnr`], the whole frame is rendered.
+
+use crate::camera::{self, CameraPath};
+use crate::template::{Field, Template, Values};
+use crate::{
+    colorize, mapping, sample, sample_point, Algo, Config, Imaginary, Options, Orientation, Sample,
+    RGB,
+};
+use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
+use rayon::slice::{ParallelSlice, ParallelSliceMut};
+use std::f64::consts::TAU;
+use std::path::Path;
+use std::{cmp, io};
+
+/// The number of probes along each axis of a frame.
+pub const PROBES: u32 = 16;
+/// How far, in pixels of the previous frame, a pixel can be from one of them to reuse its
+/// sample.
+pub const REUSE_TOLERANCE: f64 = 0.25;
+/// Samples which escaped after more than this fraction of [`Config::iterations`] are iterated
+/// again, as the points close to them might not escape.
+pub const REUSE_CAP_FRACTION: f64 = 0.9;
+/// Samples are only reused if the colors of their neighbours differ by at most this much in every
+/// channel.
+pub const REUSE_MAX_DIFFERENCE: u8 = 8;
+/// One in this many reused pixels is iterated again to check the reuse.
+pub const REUSE_CHECK_STRIDE: usize = 16;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationOptions {
+    pub frames: u32,
+    /// The scale of the last frame. The scale changes by the same factor between every frame.
+    pub zoom_to: f64,
+    /// Move the camera through keyframes instead of zooming towards the center.
+    pub path: Option<CameraPath>,
+    /// Write the path over an overview to this file instead of rendering the frames, see
+    /// [`camera::plot`].
+    pub plot_path: Option<String>,
+    /// Fill frames which are a single color without rendering them.
+    pub frame_skip: bool,
+    /// Resample the frames from one exponential map strip instead of rendering them.
+    pub exp_map: bool,
+    /// Reuse the samples of the previous frame where they're the same points, see [`reuse`].
+    pub reuse_frames: bool,
+    /// The PSNR in dB a frame with reused samples must reach, or it's rendered fully.
+    pub reuse_min_psnr: f64,
+}
+
+/// The config of `frame` (starting at 0) of `options`.
+pub fn frame_config(config: &Config, options: &AnimationOptions, frame: u32) -> Config {
+    let progress = if options.frames > 1 {
+        frame as f64 / (options.frames - 1) as f64
+    } else {
+        1.0
+    };
+    if let Some(path) = &options.path {
+        return camera::config_at(
+            config,
+            &path.keyframes,
+            path.interpolation.interpolator(),
+            progress,
+        );
+    }
+    let factor = (options.zoom_to / config.scale.im).powf(progress);
+    let mut config = config.clone();
+    config.scale = config.scale * factor;
+    config
+}
+
+/// The exponential map strip which covers every frame of `options`, from the corners of the
+/// widest frame to a pixel around the center of the narrowest.
+///
+/// The strip has about as many pixels around as the circle through the corners of a frame, so
+/// the corners are as sharp as when rendered.
+pub fn strip_config(config: &Config, options: &AnimationOptions) -> Config {
+    let (first, last) = (
+        frame_config(config, options, 0),
+        frame_config(config, options, options.frames.saturating_sub(1)),
+    );
+    let (widest, narrowest) = if first.scale.im <= last.scale.im {
+        (first, last)
+    } else {
+        (last, first)
+    };
+    let (width, height) = config.unoriented_size();
+    let (width, height) = (width as f64, height as f64);
+    let top = (width / height / 2.0 / widest.scale.re).hypot(0.5 / widest.scale.im);
+    let bottom = 1.0 / (narrowest.scale.im * height);
+    let strip_width = (TAU * width.hypot(height) / 2.0).ceil();
+    let strip_height = (strip_width / TAU * (top / bottom).ln()).ceil().max(1.0);
+    Config {
+        width: strip_width as u32,
+        height: strip_height as u32,
+        scale: Imaginary {
+            re: 0.5 / top,
+            im: 0.5 / top,
+        },
+        exp_map: true,
+        // The frames orient themselves when resampling.
+        orientation: Orientation::default(),
+        ..config.clone()
+    }
+}
+
+/// The image of `frame` resampled from `strip`, rendered with [`strip_config`], with bilinear
+/// interpolation.
+pub fn resample(strip: &[RGB], strip_config: &Config, frame: &Config) -> Vec<RGB> {
+    let (strip_width, strip_height) = (strip_config.width as usize, strip_config.height as usize);
+    let pixel = |x: usize, y: usize| strip[y.min(strip_height - 1) * strip_width + x % strip_width];
+    (0..frame.height)
+        .into_par_iter()
+        .map(|y| {
+            (0..frame.width)
+                .map(|x| {
+                    let (x, y) = mapping::sample_position(frame, x as f64, y as f64);
+                    let offset = mapping::pixel_offset(frame, x, y);
+                    let (x, y) = mapping::offset_to_pixel(strip_config, offset);
+                    let (x, y) = mapping::sample_index(strip_config, x, y);
+                    // The angle goes around, and the center itself is infinitely far down.
+                    let x = x.rem_euclid(strip_width as f64);
+                    let y = y.clamp(0.0, (strip_height - 1) as f64);
+                    let (x0, y0) = (x.floor(), y.floor());
+                    let (fx, fy) = (x - x0, y - y0);
+                    let (x0, y0) = (x0 as usize, y0 as usize);
+                    let lerp = |a: RGB, b: RGB, t: f64| {
+                        let channel =
+                            |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+                        RGB::new(channel(a.r, b.r), channel(a.g, b.g), channel(a.b, b.b))
+                    };
+                    let top = lerp(pixel(x0, y0), pixel(x0 + 1, y0), fx);
+                    let bottom = lerp(pixel(x0, y0 + 1), pixel(x0 + 1, y0 + 1), fx);
+                    lerp(top, bottom, fy)
+                })
+                .collect::<Vec<_>>()
+        })
+        .flatten()
+        .collect()
+}
+
+/// The samples of a frame, partly reused from the previous frame by [`reuse`].
+#[derive(Debug, Clone)]
+pub struct ReusedFrame {
+    pub samples: Vec<Sample>,
+    /// Whether each of the samples was reused, and not iterated.
+    pub reused: Vec<bool>,
+    /// The estimated PSNR in dB of the colors of the samples compared to iterating every pixel,
+    /// or [`f64::INFINITY`] if the checked pixels are exact.
+    pub psnr: f64,
+}
+impl ReusedFrame {
+    /// A frame where every sample was iterated.
+    pub fn iterated(samples: Vec<Sample>) -> Self {
+        Self {
+            reused: vec![false; samples.len()],
+            samples,
+            psnr: f64::INFINITY,
+        }
+    }
+    /// Iterates the reused samples of `config` too, giving the samples of the full render.
+    pub fn iterate_reused(mut self, config: &Config) -> Self {
+        let width = config.width as usize;
+        self.samples
+            .par_chunks_mut(width)
+            .zip(self.reused.par_chunks(width))
+            .enumerate()
+            .for_each(|(y, (row, reused))| {
+                for (x, (pixel, reused)) in row.iter_mut().zip(reused).enumerate() {
+                    if *reused {
+                        *pixel = sample(config, x as f64, y as f64);
+                    }
+                }
+            });
+        Self::iterated(self.samples)
+    }
+}
+
+/// The samples of `config`, taken from `previous` of `previous_config` where possible.
+///
+/// A pixel reuses the sample of the nearest pixel of the previous frame if:
+///
+/// - it's within [`REUSE_TOLERANCE`] of it,
+/// - that sample was iterated, not reused itself, so the errors don't add up over the frames,
+/// - that pixel isn't on the edge of the previous frame,
+/// - the colors of its four neighbours are within [`REUSE_MAX_DIFFERENCE`] of its color and
+///   they all escaped or all didn't, so it isn't on the edge of the fractal,
+/// - and it didn't escape after more than [`REUSE_CAP_FRACTION`] of the iterations.
+///
+/// Every [`REUSE_CHECK_STRIDE`]th reused pixel is iterated again, to estimate the PSNR.
+///
+/// The frames must have the same center, as those of [`frame_config`] without a
+/// [`AnimationOptions::path`].
+pub fn reuse(previous: &ReusedFrame, previous_config: &Config, config: &Config) -> ReusedFrame {
+    let (previous_width, previous_height) = (
+        previous_config.width as usize,
+        previous_config.height as usize,
+    );
+    let cap = config.iterations as f64 * REUSE_CAP_FRACTION;
+    let close = |a: RGB, b: RGB| {
+        [(a.r, b.r), (a.g, b.g), (a.b, b.b)]
+            .iter()
+            .all(|(a, b)| (*a as i16 - *b as i16).unsigned_abs() <= REUSE_MAX_DIFFERENCE as u16)
+    };
+    let reusable = |x: f64, y: f64| {
+        let (nearest_x, nearest_y) = (x.round(), y.round());
+        if (x - nearest_x).abs() > REUSE_TOLERANCE || (y - nearest_y).abs() > REUSE_TOLERANCE {
+            return None;
+        }
+        // Also rejects NaN.
+        if !(nearest_x >= 1.0
+            && nearest_y >= 1.0
+            && nearest_x < previous_width.saturating_sub(1) as f64
+            && nearest_y < previous_height.saturating_sub(1) as f64)
+        {
+            return None;
+        }
+        let (x, y) = (nearest_x as usize, nearest_y as usize);
+        if previous.reused[y * previous_width + x] {
+            return None;
+        }
+        let at = |x: usize, y: usize| previous.samples[y * previous_width + x];
+        let sample = at(x, y);
+        if sample.escaped && sample.iterations > cap {
+            return None;
+        }
+        let color = colorize(sample, config);
+        let neighbours = [at(x - 1, y), at(x + 1, y), at(x, y - 1), at(x, y + 1)];
+        if neighbours.iter().any(|neighbour| {
+            neighbour.escaped != sample.escaped || !close(colorize(*neighbour, config), color)
+        }) {
+            return None;
+        }
+        Some(sample)
+    };
+    let rows: Vec<(Vec<Sample>, Vec<bool>, f64, usize)> = (0..config.height)
+        .into_par_iter()
+        .map(|y| {
+            let mut row = Vec::with_capacity(config.width as usize);
+            let mut reused = Vec::with_capacity(config.width as usize);
+            let (mut squared_error, mut checked, mut reusable_count) = (0.0, 0, 0);
+            for x in 0..config.width {
+                let (sample_x, sample_y) = mapping::sample_position(config, x as f64, y as f64);
+                let offset = mapping::pixel_offset(config, sample_x, sample_y);
+                let (previous_x, previous_y) = mapping::offset_to_pixel(previous_config, offset);
+                let (previous_x, previous_y) =
+                    mapping::sample_index(previous_config, previous_x, previous_y);
+                let reused_sample = if previous.samples.is_empty() {
+                    None
+                } else {
+                    reusable(previous_x, previous_y)
+                };
+                let fresh = || sample(config, x as f64, y as f64);
+                match reused_sample {
+                    // Spread over the rows, so every part of the frame is checked.
+                    Some(reused_sample)
+                        if (reusable_count + y as usize) % REUSE_CHECK_STRIDE == 0 =>
+                    {
+                        reusable_count += 1;
+                        let fresh = fresh();
+                        let (a, b) = (colorize(fresh, config), colorize(reused_sample, config));
+                        for (a, b) in [(a.r, b.r), (a.g, b.g), (a.b, b.b)] {
+                            squared_error += (a as f64 - b as f64).powi(2);
+                        }
+                        checked += 1;
+                        row.push(fresh);
+                        reused.push(false);
+                    }
+                    Some(reused_sample) => {
+                        reusable_count += 1;
+                        row.push(reused_sample);
+                        reused.push(true);
+                    }
+                    None => {
+                        row.push(fresh());
+                        reused.push(false);
+                    }
+                }
+            }
+            (row, reused, squared_error, checked)
+        })
+        .collect();
+
+    let pixels = config.width as usize * config.height as usize;
+    let (mut samples, mut reused) = (Vec::with_capacity(pixels), Vec::with_capacity(pixels));
+    let (mut squared_error, mut checked) = (0.0, 0);
+    for (row, row_reused, row_squared_error, row_checked) in rows {
+        samples.extend(row);
+        reused.extend(row_reused);
+        squared_error += row_squared_error;
+        checked += row_checked;
+    }
+    let psnr = if checked == 0 || squared_error == 0.0 {
+        f64::INFINITY
+    } else {
+        // The iterated pixels are exact, so only the reused ones add to the error. The checked
+        // pixels stand in for the reused ones.
+        let estimated = reused.iter().filter(|reused| **reused).count() + checked;
+        let mean_squared_error =
+            squared_error / (checked * 3) as f64 * estimated as f64 / pixels as f64;
+        10.0 * (255.0 * 255.0 / mean_squared_error).log10()
+    };
+    ReusedFrame {
+        samples,
+        reused,
+        psnr,
+    }
+}
+
+/// `output-0007.png` for frame 7 of the output `output.png`. The number has at least 4 digits,
+/// or as many as the last frame needs.
+pub fn frame_path(output: &str, frame: u32, frames: u32) -> String {
+    let path = Path::new(output);
+    let digits = cmp::max(4, frames.saturating_sub(1).to_string().len());
+    let mut name = path.with_extension("").into_os_string();
+    name.push(format!("-{:0width$}", frame, width = digits));
+    if let Some(extension) = path.extension() {
+        name.push(".");
+        name.push(extension);
+    }
+    name.to_string_lossy().into_owned()
+}
+
+/// The color of every pixel of `config`, if [`PROBES`]×[`PROBES`] points spread over the image,
+/// including the edges, all have the same color and either all escape or none do.
+///
+/// Returns [`None`] for the fern.
+pub fn uniform_color(config: &Config) -> Option<RGB> {
+    if let Algo::BarnsleyFern = config.algo {
+        return None;
+    }
+    let probe = |i: u32, size: u32| (i as u64 * (size - 1) as u64 / (PROBES - 1) as u64) as u32;
+    let mut first: Option<(RGB, bool)> = None;
+    for row in 0..PROBES {
+        for column in 0..PROBES {
+            let (x, y) = (probe(column, config.width), probe(row, config.height));
+            let sample = sample_point(config, config.pixel_to_point(x, y));
+            let probe = (colorize(sample, config), sample.escaped);
+            match first {
+                None => first = Some(probe),
+                Some(first) if first == probe => {}
+                Some(_) => return None,
+            }
+        }
+    }
+    first.map(|(color, _)| color)
+}
+
+/// The overview of [`camera::plot`] for the frames of `animation`, which must have a
+/// [`AnimationOptions::path`].
+pub fn plot_path(config: &Config, animation: &AnimationOptions) -> (Vec<RGB>, Config) {
+    let frames: Vec<Config> = (0..animation.frames)
+        .map(|frame| frame_config(config, animation, frame))
+        .collect();
+    let keyframes: Vec<Config> = animation.path.as_ref().map_or_else(Vec::new, |path| {
+        let last = path.keyframes.len() - 1;
+        (0..=last)
+            .map(|i| {
+                camera::config_at(
+                    config,
+                    &path.keyframes,
+                    path.interpolation.interpolator(),
+                    i as f64 / last as f64,
+                )
+            })
+            .collect()
+    });
+    camera::plot(&frames, &keyframes)
+}
+
+/// Renders `config` with [`crate::render`], or [`crate::render::render_seeded`] with a `seed`.
+fn render_image(config: &Config, seed: Option<u64>) -> io::Result<Vec<RGB>> {
+    let output = match seed {
+        Some(seed) => crate::render::render_seeded(config, seed),
+        None => crate::render(config),
+    }
+    .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))?;
+    log::debug!(
+        "Rendered {}×{} on the {} in {:.2?}.",
+        config.width,
+        config.height,
+        output.stats.used_backend,
+        output.stats.duration
+    );
+    Ok(output.image.into_contents())
+}
+
+/// Renders and writes the frames of `animation`, with the overlays of `options`.
+///
+/// The frames are named by the template of the output, see [`crate::template`]. If it has no
+/// `{frame}`, the number is appended like [`frame_path`].
+///
+/// Returns the number of frames which were filled without rendering.
+pub fn run(options: &Options, animation: &AnimationOptions) -> io::Result<u32> {
+    let strip = if animation.exp_map {
+        let config = strip_config(&options.config, animation);
+        if crate::resources::image_bytes(&config).is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!(
+                    "the {}×{} strip of the zoom is too large to be addressed",
+                    config.width, config.height
+                ),
+            ));
+        }
+        log::debug!(
+            "Rendering a {}×{} strip to resample the frames from.",
+            config.width,
+            config.height
+        );
+        Some((render_image(&config, options.seed())?, config))
+    } else {
+        None
+    };
+    let numbered = Template::parse(&options.output.filename)
+        .map_or(false, |template| template.uses(Field::Frame));
+    let mut skipped = 0;
+    // The samples and config of the previous frame, for `animation.reuse_frames`.
+    let mut previous: Option<(ReusedFrame, Config)> = None;
+    for frame in 0..animation.frames {
+        let mut options = options.clone();
+        options.config = frame_config(&options.config, animation, frame);
+        let mut values = Values::from_options(&options);
+        values.frame = Some(frame);
+        let filename = options
+            .output
+            .expanded(&values)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        options.output.filename = if numbered {
+            filename
+        } else {
+            frame_path(&filename, frame, animation.frames)
+        };
+        options.output.open = false;
+        let config = &options.config;
+
+        let uniform = if animation.frame_skip && strip.is_none() {
+            uniform_color(config)
+        } else {
+            None
+        };
+        let mut contents = match uniform {
+            Some(color) => {
+                skipped += 1;
+                log::debug!("Frame {} is a single color; skipping the render.", frame);
+                vec![color; config.width as usize * config.height as usize]
+            }
+            None => match &strip {
+                Some((strip, strip_config)) => resample(strip, strip_config, config),
+                None if animation.reuse_frames && config.algo != Algo::BarnsleyFern => {
+                    let samples = match previous.take() {
+                        Some((previous, previous_config)) => {
+                            let frame_samples = reuse(&previous, &previous_config, config);
+                            let psnr = frame_samples.psnr;
+                            if psnr >= animation.reuse_min_psnr {
+                                log::debug!("Frame {} reused samples at {:.1} dB.", frame, psnr);
+                                frame_samples
+                            } else {
+                                log::debug!(
+                                    "Reusing samples for frame {} gave {:.1} dB; iterating them.",
+                                    frame,
+                                    psnr
+                                );
+                                frame_samples.iterate_reused(config)
+                            }
+                        }
+                        None => ReusedFrame::iterated(crate::get_samples(config)),
+                    };
+                    let contents = samples
+                        .samples
+                        .iter()
+                        .map(|sample| colorize(*sample, config))
+                        .collect();
+                    previous = Some((samples, config.clone()));
+                    contents
+                }
+                None => render_image(config, options.seed())?,
+            },
+        };
+        crate::post::apply(
+            &mut contents,
+            config.width as usize,
+            config.height as usize,
+            &options.post,
+        );
+        crate::draw_overlays(
+            &options,
+            &mut crate::Image::new(&mut contents, config.width as usize, config.height as usize),
+        );
+        if let Some(cvd) = options.simulate_cvd {
+            crate::color::simulate_image(&mut contents, cvd);
+        }
+        let image = crate::ImageBuffer::from_contents(
+            contents,
+            config.width as usize,
+            config.height as usize,
+        )
+        .expect("the frame has the size of its config");
+        let data = crate::encode_image(&image, options.output.format, &options.output.encoder)?;
+        let path = crate::write_encoded(&options.output, &data)?;
+        log::debug!(
+            "Wrote frame {} of {} to {:?}.",
+            frame + 1,
+            animation.frames,
+            path
+        );
+    }
+    Ok(skipped)
+}
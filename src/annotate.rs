@@ -0,0 +1,193 @@
+//! Draw a caption with the parameters of the render on the image, so the location travels with
+//! the picture.
+
+use crate::{font, Algo, Config, Image, RGB};
+use std::cmp;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Position {
+    /// A strip along the top edge.
+    Top,
+    /// A strip along the bottom edge.
+    Bottom,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+impl Position {
+    pub const ALL: [Self; 6] = [
+        Self::Top,
+        Self::Bottom,
+        Self::TopLeft,
+        Self::TopRight,
+        Self::BottomLeft,
+        Self::BottomRight,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Top => "top",
+            Self::Bottom => "bottom",
+            Self::TopLeft => "top-left",
+            Self::TopRight => "top-right",
+            Self::BottomLeft => "bottom-left",
+            Self::BottomRight => "bottom-right",
+        }
+    }
+    fn is_strip(self) -> bool {
+        matches!(self, Self::Top | Self::Bottom)
+    }
+    fn is_top(self) -> bool {
+        matches!(self, Self::Top | Self::TopLeft | Self::TopRight)
+    }
+}
+impl Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+impl FromStr for Position {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|position| position.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown position {:?}", s))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnnotateOptions {
+    pub position: Position,
+    pub foreground: RGB,
+    pub background: RGB,
+    /// The caption is never taller than this fraction of the height of the image.
+    pub max_fraction: f64,
+}
+
+/// The parts of the caption of `config`, like `mandelbrot`, `center -0.6+0i`, `zoom 1` and
+/// `50 iterations`. The zoom is relative to the default scale. With [`Config::y_up`], the
+/// caption says so, as the center is mirrored otherwise.
+pub fn caption(config: &Config) -> Vec<String> {
+    let zoom = config.scale.im / Config::default().scale.im;
+    let zoom = if zoom >= 1e4 {
+        format!("{:.2e}", zoom)
+    } else {
+        // Remove trailing zeros.
+        let zoom = format!("{:.2}", zoom);
+        zoom.trim_end_matches('0').trim_end_matches('.').to_owned()
+    };
+    let mut parts = vec![match (&config.algo, config.julia) {
+        (Algo::Mandelbrot, None) => "mandelbrot".to_owned(),
+        (Algo::Mandelbrot, Some(c)) => format!("julia c={}{:+}i", c.re, c.im),
+        (Algo::BarnsleyFern, _) => "fern".to_owned(),
+    }];
+    parts.push(format!("center {}{:+}i", config.pos.re, config.pos.im));
+    if config.y_up && config.algo != Algo::BarnsleyFern {
+        parts.push("imaginary axis up".to_owned());
+    }
+    parts.push(format!("zoom {}", zoom));
+    parts.push(format!("{} iterations", config.iterations));
+    parts
+}
+
+/// Joins `parts` into lines no wider than `max_width` pixels at `scale`.
+///
+/// Returns [`None`] if a part doesn't fit on a line by itself.
+fn layout(parts: &[String], scale: usize, max_width: usize) -> Option<Vec<String>> {
+    let mut lines: Vec<String> = Vec::new();
+    for part in parts {
+        if font::text_width(part, scale) > max_width {
+            return None;
+        }
+        match lines.last_mut() {
+            Some(line) if font::text_width(&format!("{}  {}", line, part), scale) <= max_width => {
+                line.push_str("  ");
+                line.push_str(part);
+            }
+            _ => lines.push(part.clone()),
+        }
+    }
+    Some(lines)
+}
+
+/// The padding around the text and the space between lines, in pixels of the font.
+const PADDING: usize = 2;
+/// The height of the caption with `lines` at `scale`.
+fn box_height(lines: usize, scale: usize) -> usize {
+    (lines * (font::GLYPH_HEIGHT + PADDING) + PADDING) * scale
+}
+
+/// Draws the caption of `config` on `image`, which must have the size of `config`.
+///
+/// The text grows with the image, but shrinks (and wraps) to keep within
+/// [`AnnotateOptions::max_fraction`]. If it doesn't fit at the smallest size, a warning is
+/// logged and nothing is drawn.
+pub fn draw(config: &Config, image: &mut Image, options: &AnnotateOptions) {
+    let width = config.width as usize;
+    let height = config.height as usize;
+    let parts = caption(config);
+    let max_height = (height as f64 * options.max_fraction) as usize;
+
+    let mut scale = cmp::max(1, cmp::min(width, height) / 400);
+    let (lines, scale) = loop {
+        let fits = layout(&parts, scale, width.saturating_sub(PADDING * 2 * scale))
+            .filter(|lines| box_height(lines.len(), scale) <= max_height);
+        match fits {
+            Some(lines) => break (lines, scale),
+            None if scale > 1 => scale -= 1,
+            None => {
+                log::warn!(
+                    "The annotation doesn't fit in {}% of the image; skipping it.",
+                    options.max_fraction * 100.0
+                );
+                return;
+            }
+        }
+    };
+
+    let padding = PADDING * scale;
+    let line_height = (font::GLYPH_HEIGHT + PADDING) * scale;
+    let box_height = box_height(lines.len(), scale);
+    let box_width = if options.position.is_strip() {
+        width
+    } else {
+        lines
+            .iter()
+            .map(|line| font::text_width(line, scale))
+            .max()
+            .unwrap_or(0)
+            + padding * 2
+    };
+    let left = match options.position {
+        Position::TopRight | Position::BottomRight => width - box_width,
+        _ => 0,
+    };
+    let top = if options.position.is_top() {
+        0
+    } else {
+        height - box_height
+    };
+
+    for y in top..top + box_height {
+        for x in left..left + box_width {
+            if let Some(pixel) = image.pixel_mut(x, y) {
+                *pixel = options.background;
+            }
+        }
+    }
+    for (index, line) in lines.iter().enumerate() {
+        font::draw_text(
+            image,
+            line,
+            left + padding,
+            top + padding + index * line_height,
+            scale,
+            options.foreground,
+        );
+    }
+}
@@ -0,0 +1,123 @@
+//! Ambient occlusion of escape-time fractals, which darkens the valleys of the smoothed iterations
+//! to give the image depth.
+//!
+//! The iterations are treated as a heightfield, with the points which don't escape at the top.
+//! Every pixel marches a few short rays across it and is darkened by how much of the sky the
+//! higher pixels around it hide. Only the [`Sample`]s are used, so nothing is iterated again.
+
+use crate::{Config, Sample, RGB};
+use rayon::iter::{
+    IndexedParallelIterator, IntoParallelIterator, IntoParallelRefMutIterator, ParallelIterator,
+};
+use std::f64::consts::TAU;
+
+/// The number of rays of each pixel, evenly spread around it.
+pub const DIRECTIONS: usize = 8;
+/// The number of heights each ray samples, evenly spread up to the radius.
+pub const STEPS: usize = 4;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AoOptions {
+    /// How much a fully occluded pixel is darkened, from 0 (not at all) to 1 (black).
+    pub strength: f64,
+    /// The length of the rays in pixels. The highest point is this high, so larger radii also
+    /// give steeper slopes.
+    pub radius: f64,
+}
+
+/// The heights of `samples`, from 0 at the fewest iterations to `radius` at the most, on a log
+/// scale. Points which don't escape are at `radius`.
+pub fn heights(samples: &[Sample], radius: f64) -> Vec<f64> {
+    let height = |sample: &Sample| (1.0 + sample.iterations.max(0.0)).ln();
+    let (min, max) = samples.iter().filter(|sample| sample.escaped).fold(
+        (f64::INFINITY, f64::NEG_INFINITY),
+        |(min, max), sample| {
+            let height = height(sample);
+            (min.min(height), max.max(height))
+        },
+    );
+    let range = max - min;
+    samples
+        .iter()
+        .map(|sample| {
+            if !sample.escaped {
+                radius
+            } else if range > 0.0 {
+                (height(sample) - min) / range * radius
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+/// The occlusion of every pixel of the `width`×`height` `heights`, from 0 (open sky) to 1.
+///
+/// Each ray takes the steepest slope up to a higher pixel along it; the occlusion is the mean of
+/// the sines of those slopes. Rays stop at the edges of the image, as if it was flat outside.
+///
+/// ```
+/// # use fractal_renderer::ao::occlusion;
+/// // A flat plane with a pit in the middle.
+/// let mut heights = vec![4.0; 9 * 9];
+/// heights[4 * 9 + 4] = 0.0;
+/// let occlusion = occlusion(&heights, 9, 9, 4.0);
+/// assert!(occlusion[4 * 9 + 4] > 0.5);
+/// assert_eq!(occlusion[0], 0.0);
+/// ```
+pub fn occlusion(heights: &[f64], width: usize, height: usize, radius: f64) -> Vec<f64> {
+    let directions: Vec<(f64, f64)> = (0..DIRECTIONS)
+        .map(|i| {
+            let angle = i as f64 / DIRECTIONS as f64 * TAU;
+            (angle.cos(), angle.sin())
+        })
+        .collect();
+    (0..height)
+        .into_par_iter()
+        .flat_map_iter(|y| {
+            let directions = &directions;
+            (0..width).map(move |x| {
+                let here = heights[y * width + x];
+                let occluded: f64 = directions
+                    .iter()
+                    .map(|(dx, dy)| {
+                        let mut steepest = 0.0_f64;
+                        for step in 1..=STEPS {
+                            let distance = radius * step as f64 / STEPS as f64;
+                            let (sx, sy) = (
+                                (x as f64 + dx * distance).round(),
+                                (y as f64 + dy * distance).round(),
+                            );
+                            if sx < 0.0 || sy < 0.0 || sx >= width as f64 || sy >= height as f64 {
+                                break;
+                            }
+                            let rise = heights[sy as usize * width + sx as usize] - here;
+                            steepest = steepest.max(rise / distance);
+                        }
+                        steepest / (1.0 + steepest * steepest).sqrt()
+                    })
+                    .sum();
+                occluded / DIRECTIONS as f64
+            })
+        })
+        .collect()
+}
+
+/// Darkens `contents`, the colors of `samples`, by their occlusion.
+pub fn apply(contents: &mut [RGB], samples: &[Sample], config: &Config, options: &AoOptions) {
+    let heights = heights(samples, options.radius);
+    let occlusion = occlusion(
+        &heights,
+        config.width as usize,
+        config.height as usize,
+        options.radius,
+    );
+    contents
+        .par_iter_mut()
+        .zip(occlusion.into_par_iter())
+        .for_each(|(pixel, occlusion)| {
+            let factor = (1.0 - options.strength * occlusion).clamp(0.0, 1.0);
+            let channel = |value: u8| (value as f64 * factor).round() as u8;
+            *pixel = RGB::new(channel(pixel.r), channel(pixel.g), channel(pixel.b));
+        });
+}
@@ -0,0 +1,85 @@
+//! Draw the axes, gridlines and coordinate labels over the image of an escape-time fractal.
+//!
+//! The gridlines are at multiples of 1, 2 or 5 times a power of ten, chosen so they are roughly
+//! [`SPACING`] pixels apart.
+
+use crate::{font, mapping, Algo, Config, Image, Imaginary, RGB};
+use std::cmp;
+
+/// The preferred distance in pixels between gridlines.
+pub const SPACING: f64 = 150.0;
+
+/// The smallest of 1, 2 or 5 times a power of ten which is at least `min`.
+pub fn nice_step(min: f64) -> f64 {
+    let magnitude = 10_f64.powf(min.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|factor| factor * magnitude)
+        .find(|step| *step >= min)
+        .unwrap_or(magnitude * 10.0)
+}
+
+/// The multiples of `step` from `min` to `max`.
+fn multiples(min: f64, max: f64, step: f64) -> impl Iterator<Item = f64> {
+    let first = (min / step).ceil();
+    let last = (max / step).floor();
+    (0..)
+        .map(move |i| first + i as f64)
+        .take_while(move |k| *k <= last)
+        // Adding zero turns -0 into 0, which is then printed without a sign.
+        .map(move |k| k * step + 0.0)
+}
+
+/// Draws the axes, gridlines and labels on `image`. Does nothing for the fern.
+///
+/// `image` must have the size of `config`. Lines and labels grow with the size of the image.
+pub fn draw(config: &Config, image: &mut Image, color: RGB) {
+    if let Algo::BarnsleyFern = config.algo {
+        return;
+    }
+    let width = config.width as usize;
+    let height = config.height as usize;
+    let thickness = cmp::max(1, cmp::min(width, height) / 1000);
+    let scale = thickness * 2;
+    let margin = 2 * scale;
+
+    let viewport = config.viewport();
+    let step = Imaginary {
+        re: nice_step(viewport.width() / config.width as f64 * SPACING),
+        im: nice_step(viewport.height() / config.height as f64 * SPACING),
+    };
+    if !(step.re.is_finite() && step.re > 0.0 && step.im.is_finite() && step.im > 0.0) {
+        return;
+    }
+    let decimals = |step: f64| (-step.log10().floor()).max(0.0) as usize;
+
+    for re in multiples(viewport.min.re, viewport.max.re, step.re) {
+        let (x, _) = mapping::complex_to_pixel(config, Imaginary { re, im: 0.0 });
+        let x = x.max(0.0) as usize;
+        // The axes are thicker than the gridlines.
+        let lines = if re == 0.0 { thickness * 2 } else { thickness };
+        for y in 0..height {
+            for x in x..x + lines {
+                if let Some(pixel) = image.pixel_mut(x, y) {
+                    *pixel = color;
+                }
+            }
+        }
+        let label = format!("{:.*}", decimals(step.re), re);
+        font::draw_text(image, &label, x + margin, margin, scale, color);
+    }
+    for im in multiples(viewport.min.im, viewport.max.im, step.im) {
+        let (_, y) = mapping::complex_to_pixel(config, Imaginary { re: 0.0, im });
+        let y = y.max(0.0) as usize;
+        let lines = if im == 0.0 { thickness * 2 } else { thickness };
+        for y in y..y + lines {
+            for x in 0..width {
+                if let Some(pixel) = image.pixel_mut(x, y) {
+                    *pixel = color;
+                }
+            }
+        }
+        let label = format!("{:.*}i", decimals(step.im), im);
+        font::draw_text(image, &label, margin, y + margin, scale, color);
+    }
+}
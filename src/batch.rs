@@ -0,0 +1,401 @@
+//! Render many variations of a [`Config`] from a TOML manifest.
+//!
+//! A manifest has an optional `[base]` table and a list of `[[job]]` tables.
+//! Both contain partial [`Config`]s (the same keys as the serialized `Config`),
+//! which are applied on top of the config given on the command line.
+//! Colors, `julia` and the size can also be written like on the command line, see
+//! [`crate::parse`].
+//!
+//! ```toml
+//! [base]
+//! julia = "-0.8+0.156i"
+//! iterations = 500
+//! size = "1080p"
+//!
+//! [[job]]
+//! name = "dendrite"
+//! julia = { re = 0.0, im = 1.0 }
+//!
+//! [[job]]
+//! name = "rabbit"
+//! output = "renders/rabbit"
+//! julia = "-0.123+0.745i"
+//! primary_color = "#ffd700"
+//! exposure = 8
+//! ```
+//!
+//! Like `-a` on the command line, a table with `algo` renders a Julia set only if it also has
+//! `julia`. Manifests from before [`Config::julia`], with `algo = "julia"` and `julia_set`, still
+//! work.
+//!
+//! The outputs can be templates, like `output = "renders/{name}_{zoom:.0e}"`, see
+//! [`crate::template`]. Jobs without an output are named by the `--output` of the batch if it has
+//! fields, else by their name.
+//!
+//! [`Manifest::configs`] resolves the jobs in order, so the same format can serve as a list of
+//! keyframes.
+
+use crate::format::Format;
+use crate::parse::{self, ParseError};
+use crate::template::{Template, TemplateError, Values};
+use crate::{Config, Options, OutputOptions};
+use serde::Deserialize;
+use std::fmt::{self, Display};
+use std::path::Path;
+use std::{fs, io};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchOptions {
+    /// Path to the manifest.
+    pub manifest: String,
+    /// How many jobs to render at the same time.
+    pub parallel: usize,
+    /// Stop at the first failing job.
+    pub fail_fast: bool,
+}
+
+#[derive(Debug)]
+pub enum ManifestError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+}
+impl Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "failed to read manifest: {}", err),
+            Self::Parse(err) => write!(f, "failed to parse manifest: {}", err),
+        }
+    }
+}
+impl std::error::Error for ManifestError {}
+
+/// Why a job couldn't be resolved to a [`Config`], see [`Manifest::resolve`].
+#[derive(Debug)]
+pub enum ResolveError {
+    /// The string value of `key` isn't a valid shorthand.
+    Shorthand {
+        key: String,
+        err: ParseError,
+    },
+    Config(toml::de::Error),
+    /// The config can't be rendered.
+    Invalid(crate::ConfigError),
+}
+impl Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Shorthand { key, err } => write!(f, "{}: {}", key, err),
+            Self::Config(err) => write!(f, "{}", err),
+            Self::Invalid(err) => write!(f, "{}", err),
+        }
+    }
+}
+impl std::error::Error for ResolveError {}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Manifest {
+    #[serde(default)]
+    pub base: toml::value::Table,
+    #[serde(default, rename = "job")]
+    pub jobs: Vec<Job>,
+}
+#[derive(Debug, Clone, Deserialize)]
+pub struct Job {
+    pub name: String,
+    /// Output file name, which can be a template, see [`crate::template`]. Defaults to the
+    /// `--output` of the batch if it has fields, like `renders/{name}`, else to [`Self::name`].
+    /// The extension of `--format` is appended if it has no known extension.
+    pub output: Option<String>,
+    /// The keys of [`Config`] to change.
+    #[serde(flatten)]
+    pub overrides: toml::value::Table,
+}
+impl Job {
+    /// The template of the path to write the image to and its format, see
+    /// [`crate::format::output_path`].
+    pub fn output_path(&self, output: &OutputOptions) -> (String, Format) {
+        let has_fields = |template: &str| {
+            Template::parse(template).map_or(false, |template| template.fields().next().is_some())
+        };
+        match &self.output {
+            Some(path) => crate::format::output_path(path, output.format),
+            // Without fields, every job would overwrite the same file.
+            None if has_fields(&output.filename) => (output.filename.clone(), output.format),
+            None => {
+                let name = self.name.replace('{', "{{").replace('}', "}}");
+                crate::format::output_path(&name, output.format)
+            }
+        }
+    }
+    /// [`Self::output_path`] with the fields replaced by the values of `config`, the resolved
+    /// config of the job.
+    pub fn expanded_path(
+        &self,
+        options: &Options,
+        config: &Config,
+    ) -> Result<(String, Format), TemplateError> {
+        let (path, format) = self.output_path(&options.output);
+        let values = Values {
+            config,
+            name: Some(&self.name),
+            ..Values::from_options(options)
+        };
+        Ok((Template::parse(&path)?.render(&values)?, format))
+    }
+}
+impl Manifest {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ManifestError> {
+        let data = fs::read_to_string(path).map_err(ManifestError::Io)?;
+        toml::from_str(&data).map_err(ManifestError::Parse)
+    }
+    /// Apply [`Self::base`] and the overrides of `job` on top of `config`.
+    pub fn resolve(&self, config: &Config, job: &Job) -> Result<Config, ResolveError> {
+        apply_tables(config, &[&self.base, &job.overrides])
+    }
+    /// Resolve all jobs, in order.
+    pub fn configs<'a>(
+        &'a self,
+        config: &'a Config,
+    ) -> impl Iterator<Item = (&'a Job, Result<Config, ResolveError>)> + 'a {
+        self.jobs
+            .iter()
+            .map(move |job| (job, self.resolve(config, job)))
+    }
+}
+/// Applies the partial config `table` on top of `config`, with the same keys and shorthands as
+/// the tables of a manifest.
+///
+/// ```
+/// # use fractal_renderer::{batch::apply, Algo, Config, Imaginary};
+/// let table = toml::from_str("julia = \"-0.8+0.156i\"\nsize = \"720p\"").unwrap();
+/// let config = apply(&Config::new(Algo::Mandelbrot), &table).unwrap();
+/// assert_eq!(config.julia, Some(Imaginary { re: -0.8, im: 0.156 }));
+/// assert_eq!((config.width, config.height), (1280, 720));
+///
+/// let table = toml::from_str("algo = \"mandelbrot\"").unwrap();
+/// assert_eq!(apply(&config, &table).unwrap().julia, None);
+///
+/// let table = toml::from_str("iterations = 0").unwrap();
+/// assert!(apply(&Config::new(Algo::Mandelbrot), &table).is_err());
+/// ```
+pub fn apply(config: &Config, table: &toml::value::Table) -> Result<Config, ResolveError> {
+    apply_tables(config, &[table])
+}
+fn apply_tables(config: &Config, tables: &[&toml::value::Table]) -> Result<Config, ResolveError> {
+    let mut value = toml::Value::try_from(config).expect("a config is always serializable to TOML");
+    for table in tables {
+        let table = expand(table)?;
+        // Like `-a`, `algo` picks the fractal, which is a Julia set only with `julia`.
+        if let (toml::Value::Table(value), true) = (&mut value, table.contains_key("algo")) {
+            value.remove("julia");
+        }
+        merge(&mut value, &table);
+    }
+    let config: Config = value.try_into().map_err(ResolveError::Config)?;
+    config.validate().map_err(ResolveError::Invalid)?;
+    Ok(config)
+}
+/// Replaces the string shorthands in `table` with the values of the serialized [`Config`]:
+/// `primary_color` and `secondary_color` like `"#ff8800"`, `background` like `"#ff8800"` or
+/// `"checkerboard"`, `julia` like `"-0.8+0.156i"` and `size` like `"1080p"`, which sets
+/// `width` and `height`.
+///
+/// `algo = "julia"` and `julia_set` from before [`Config::julia`] are replaced by the Julia set of
+/// Mandelbrot and `julia`.
+fn expand(table: &toml::value::Table) -> Result<toml::value::Table, ResolveError> {
+    fn serialize(value: impl serde::Serialize) -> toml::Value {
+        toml::Value::try_from(value).expect("colors and complex numbers are serializable to TOML")
+    }
+    let mut expanded = table.clone();
+    for (key, value) in table {
+        let s = match value {
+            toml::Value::String(s) => s,
+            _ => continue,
+        };
+        let shorthand = |err| ResolveError::Shorthand {
+            key: key.clone(),
+            err,
+        };
+        match key.as_str() {
+            "primary_color" | "secondary_color" => {
+                let color = parse::color(s).map_err(shorthand)?;
+                expanded.insert(key.clone(), serialize(color));
+            }
+            "background" => {
+                let background = parse::background(s).map_err(shorthand)?;
+                expanded.insert(key.clone(), serialize(background));
+            }
+            "julia" | "julia_set" => {
+                let constant = parse::complex(s).map_err(shorthand)?;
+                expanded.insert(key.clone(), serialize(constant));
+            }
+            "size" => {
+                let (width, height) = parse::size(s).map_err(shorthand)?;
+                expanded.remove(key);
+                expanded.insert("width".into(), toml::Value::Integer(width.into()));
+                expanded.insert("height".into(), toml::Value::Integer(height.into()));
+            }
+            _ => {}
+        }
+    }
+    if let Some(constant) = expanded.remove("julia_set") {
+        expanded.entry("julia").or_insert(constant);
+    }
+    if expanded.get("algo").and_then(toml::Value::as_str) == Some("julia") {
+        expanded.insert("algo".into(), toml::Value::String("mandelbrot".into()));
+        expanded
+            .entry("julia")
+            .or_insert_with(|| serialize(crate::Imaginary::ZERO));
+    }
+    Ok(expanded)
+}
+/// Recursively overrides the values in `target` with those of `table`.
+fn merge(target: &mut toml::Value, table: &toml::value::Table) {
+    let target = if let toml::Value::Table(t) = target {
+        t
+    } else {
+        return;
+    };
+    for (key, value) in table {
+        match (target.get_mut(key), value) {
+            (Some(existing @ toml::Value::Table(_)), toml::Value::Table(value)) => {
+                merge(existing, value)
+            }
+            _ => {
+                target.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+struct JobReport {
+    name: String,
+    result: Result<std::time::Duration, String>,
+}
+
+/// Renders all jobs in the manifest of `batch`, on top of `options`.
+///
+/// Returns `false` if any job failed.
+pub fn run(options: &Options, batch: &BatchOptions) -> bool {
+    use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Instant;
+
+    let manifest = match Manifest::from_file(&batch.manifest) {
+        Ok(m) => m,
+        Err(err) => {
+            log::error!("{}", err);
+            return false;
+        }
+    };
+    let mut invalid = false;
+    for job in &manifest.jobs {
+        let (filename, format) = job.output_path(&options.output);
+        if let Err(err) = job.expanded_path(options, &options.config) {
+            log::error!(
+                "Job {:?} has an invalid output {:?}: {}",
+                job.name,
+                filename,
+                err
+            );
+            invalid = true;
+        } else if !format.is_supported() {
+            log::error!(
+                "Job {:?} can't write {:?}, as this binary was built without the {} feature.",
+                job.name,
+                filename,
+                format
+            );
+            invalid = true;
+        }
+    }
+    if invalid {
+        return false;
+    }
+    let aborted = AtomicBool::new(false);
+
+    let render = |job: &Job| -> JobReport {
+        let report = |result| JobReport {
+            name: job.name.clone(),
+            result,
+        };
+        if aborted.load(Ordering::SeqCst) {
+            return report(Err("skipped".into()));
+        }
+        let start = Instant::now();
+        let result = manifest
+            .resolve(&options.config, job)
+            .map_err(|err| format!("invalid job: {}", err))
+            .and_then(|config| {
+                let (filename, format) = job
+                    .expanded_path(options, &config)
+                    .map_err(|err| format!("invalid output: {}", err))?;
+                let options = Options {
+                    config,
+                    output: OutputOptions {
+                        filename,
+                        format,
+                        ..options.output.clone()
+                    },
+                    batch: None,
+                    ..options.clone()
+                };
+                // The options of the job, like those of a single render on the command line.
+                crate::validate(&options).map_err(|err| format!("invalid job: {}", err))?;
+                let image = match options.seed() {
+                    Some(seed) => crate::get_image_seeded(&options.config, seed),
+                    None => crate::get_image(&options.config),
+                };
+                crate::write_image(&options, image).map_err(|err| err.to_string())
+            })
+            .map(|_| start.elapsed());
+        if let Err(err) = &result {
+            log::error!("Job {:?} failed: {}", job.name, err);
+            if batch.fail_fast {
+                aborted.store(true, Ordering::SeqCst);
+            }
+        }
+        report(result)
+    };
+
+    let reports: Vec<JobReport> = if batch.parallel > 1 {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(batch.parallel)
+            .build()
+            .expect("failed to start batch thread pool");
+        pool.install(|| manifest.jobs.par_iter().map(render).collect())
+    } else {
+        manifest.jobs.iter().map(render).collect()
+    };
+
+    let name_width = reports
+        .iter()
+        .map(|r| r.name.len())
+        .max()
+        .unwrap_or(0)
+        .max(3);
+    log::info!(
+        "{:<width$}  {:>10}  result",
+        "job",
+        "time",
+        width = name_width
+    );
+    for report in &reports {
+        match &report.result {
+            Ok(time) => log::info!(
+                "{:<width$}  {:>9.2}s  ok",
+                report.name,
+                time.as_secs_f64(),
+                width = name_width
+            ),
+            Err(err) => log::info!(
+                "{:<width$}  {:>10}  {}",
+                report.name,
+                "-",
+                err,
+                width = name_width
+            ),
+        }
+    }
+    reports.iter().all(|r| r.result.is_ok())
+}
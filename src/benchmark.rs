@@ -0,0 +1,110 @@
+//! Repeatedly render a [`Config`] to measure performance.
+
+use crate::render::{self, RenderError};
+use crate::{Algo, Config};
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkOptions {
+    /// Number of renders.
+    pub runs: u32,
+    /// Print the result as JSON instead of a table.
+    pub json: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct BenchmarkResult {
+    /// Time of each run, sorted.
+    pub times: Vec<Duration>,
+    pub pixels: u64,
+    /// For escape-time fractals, this is the upper bound of iterations (pixels × max iterations).
+    /// For the fern, it's the number of points.
+    pub iterations: u64,
+}
+impl BenchmarkResult {
+    pub fn min(&self) -> Duration {
+        self.times[0]
+    }
+    pub fn median(&self) -> Duration {
+        let len = self.times.len();
+        if len % 2 == 0 {
+            (self.times[len / 2 - 1] + self.times[len / 2]) / 2
+        } else {
+            self.times[len / 2]
+        }
+    }
+    pub fn mean(&self) -> Duration {
+        self.times.iter().sum::<Duration>() / self.times.len() as u32
+    }
+    /// Megapixels per second, using the median time.
+    pub fn megapixels_per_second(&self) -> f64 {
+        self.pixels as f64 / 1_000_000.0 / self.median().as_secs_f64()
+    }
+    /// Iterations per second, using the median time. See [`Self::iterations`].
+    pub fn iterations_per_second(&self) -> f64 {
+        self.iterations as f64 / self.median().as_secs_f64()
+    }
+
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"runs\":{},\"min\":{},\"median\":{},\"mean\":{},\"pixels\":{},\"megapixels_per_second\":{},\"iterations_per_second\":{}}}",
+            self.times.len(),
+            self.min().as_secs_f64(),
+            self.median().as_secs_f64(),
+            self.mean().as_secs_f64(),
+            self.pixels,
+            self.megapixels_per_second(),
+            self.iterations_per_second(),
+        )
+    }
+    pub fn to_table(&self) -> String {
+        format!(
+            "runs          {}\n\
+             min           {:.2?}\n\
+             median        {:.2?}\n\
+             mean          {:.2?}\n\
+             megapixels/s  {:.2}\n\
+             iterations/s  {:.3e}",
+            self.times.len(),
+            self.min(),
+            self.median(),
+            self.mean(),
+            self.megapixels_per_second(),
+            self.iterations_per_second(),
+        )
+    }
+}
+
+/// Renders `config` `runs` times with [`render::render`], like a normal render, but without
+/// encoding.
+///
+/// # Panics
+///
+/// Panics if `runs` is 0.
+pub fn run(config: &Config, runs: u32) -> Result<BenchmarkResult, RenderError> {
+    assert!(runs > 0, "a benchmark needs at least one run");
+    let mut times = (0..runs)
+        .map(|run| {
+            let stats = render::render(config)?.stats;
+            log::debug!(
+                "Run {} took {:.2?} on the {}.",
+                run + 1,
+                stats.duration,
+                stats.used_backend
+            );
+            Ok(stats.duration)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    times.sort_unstable();
+
+    let pixels = config.width as u64 * config.height as u64;
+    let iterations = match config.algo {
+        Algo::Mandelbrot => pixels * config.iterations as u64,
+        Algo::BarnsleyFern => config.iterations as u64,
+    };
+    Ok(BenchmarkResult {
+        times,
+        pixels,
+        iterations,
+    })
+}
@@ -0,0 +1,476 @@
+//! Render the Buddhabrot: the density of the orbits of points of the Mandelbrot set.
+//!
+//! Random constants `c` are taken from [`SAMPLE_REGION`] and every point of their orbit within
+//! the image is counted. The counts are kept in one `u32` buffer per channel and normalized
+//! before being turned into colors.
+//!
+//! - [`Mode::Normal`] counts the orbits of constants which escape.
+//! - [`Mode::Anti`] counts the orbits of constants which don't escape.
+//! - [`Mode::Nebula`] counts the escaping orbits three times, with the iterations of
+//!   [`BuddhabrotOptions::nebula_iterations`], in the red, green and blue channel.
+//!
+//! The constants come from [`BuddhabrotOptions::sampler`]; the low-discrepancy sequences give
+//! less noise for the same [`BuddhabrotOptions::samples`]. With
+//! [`BuddhabrotOptions::importance`], they are drawn more often where the orbits which cross the
+//! image start, see [`Importance`]. This helps the most when zoomed in, where few orbits do.
+//!
+//! Every thread has its own buffers, so the memory usage is
+//! `channels × 4 bytes × pixels × (threads + 1)`, see [`buffer_bytes`].
+
+use crate::sampler::{self, Sampler};
+use crate::{mapping, ComplexRect, Config, Imaginary, RGB};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use std::fmt::{self, Display};
+use std::mem::size_of;
+use std::str::FromStr;
+
+/// All orbits which escape start in this rectangle.
+pub const SAMPLE_REGION: ComplexRect = ComplexRect {
+    min: Imaginary { re: -2.0, im: -2.0 },
+    max: Imaginary { re: 2.0, im: 2.0 },
+};
+/// Buffers of at least this many bytes are warned about.
+pub const LARGE_BUFFERS: u64 = 4 << 30;
+/// The number of parts the seeded render is split in, to sample them in parallel.
+const SEEDED_PARTS: u64 = 16;
+/// The cells along each side of [`SAMPLE_REGION`] of the map of [`Importance`].
+pub const IMPORTANCE_CELLS: usize = 256;
+/// The constants of the cells which add the most to the image are drawn `2^IMPORTANCE_LEVELS`
+/// times as often as those which add the least, see [`Importance`].
+pub const IMPORTANCE_LEVELS: u32 = 6;
+/// The most iterations of the map of [`Importance`].
+const IMPORTANCE_ITERATIONS: u32 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Normal,
+    Anti,
+    Nebula,
+}
+impl Mode {
+    pub const ALL: [Self; 3] = [Self::Normal, Self::Anti, Self::Nebula];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::Anti => "anti",
+            Self::Nebula => "nebula",
+        }
+    }
+    /// The number of hit buffers.
+    pub fn channels(self) -> usize {
+        match self {
+            Self::Normal | Self::Anti => 1,
+            Self::Nebula => 3,
+        }
+    }
+}
+impl Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+impl FromStr for Mode {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|mode| mode.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown Buddhabrot mode {:?}", s))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuddhabrotOptions {
+    pub mode: Mode,
+    /// The number of random constants per pixel of the image.
+    pub samples: u32,
+    /// The iterations of the red, green and blue channel of [`Mode::Nebula`].
+    /// The other modes use [`Config::iterations`].
+    pub nebula_iterations: [u32; 3],
+    /// Where the constants come from.
+    pub sampler: sampler::Kind,
+    /// Draw the constants whose orbits cross the image more often, see [`Importance`].
+    pub importance: bool,
+}
+
+/// Parses `R,G,B`, the iterations of each channel.
+pub fn parse_nebula_iterations(s: &str) -> Result<[u32; 3], String> {
+    let values = s
+        .split(',')
+        .map(crate::parse::positive_integer)
+        .collect::<Result<Vec<_>, _>>()?;
+    match values[..] {
+        [r, g, b] => Ok([r, g, b]),
+        _ => Err("expected 3 comma separated iterations: red,green,blue".into()),
+    }
+}
+
+/// The bytes of the hit buffers when rendering `config`, or [`None`] if they're too large to be
+/// addressed on this platform.
+pub fn buffer_bytes(config: &Config, options: &BuddhabrotOptions) -> Option<u64> {
+    let bytes = (config.width as usize)
+        .checked_mul(config.height as usize)?
+        .checked_mul(options.mode.channels() * size_of::<u32>())
+        .filter(|bytes| *bytes <= isize::MAX as usize)?;
+    (bytes as u64).checked_mul(rayon::current_num_threads() as u64 + 1)
+}
+
+/// Whether `c` is in the main cardioid or the period-2 bulb, which never escape.
+fn in_main_bulbs(c: Imaginary) -> bool {
+    let im2 = c.im * c.im;
+    let q = (c.re - 0.25) * (c.re - 0.25) + im2;
+    q * (q + (c.re - 0.25)) <= 0.25 * im2 || (c.re + 1.0) * (c.re + 1.0) + im2 <= 0.0625
+}
+
+/// A coarse map of how much the orbits of the constants in each cell of [`SAMPLE_REGION`] add to
+/// the image, to draw the constants from.
+///
+/// Every cell has a level from 0 to [`IMPORTANCE_LEVELS`]; constants are drawn `2^level` times
+/// as often from it. To keep the density unbiased, their hits count `2^(IMPORTANCE_LEVELS -
+/// level)` times, which are whole numbers, so the counts stay integers, see [`render`].
+///
+/// ```
+/// # use fractal_renderer::{Algo, Config, buddhabrot::*};
+/// let mut config = Config::new(Algo::Mandelbrot);
+/// config.iterations = 100;
+/// let options = BuddhabrotOptions {
+///     mode: Mode::Normal,
+///     samples: 1,
+///     nebula_iterations: [1; 3],
+///     sampler: Default::default(),
+///     importance: true,
+/// };
+/// let importance = Importance::new(&config, &options);
+/// // Far outside the set, every constant escapes at once, so they are drawn the least often.
+/// let (c, weight) = importance.warp([0.0, 0.0]);
+/// assert_eq!((c.re, c.im), (SAMPLE_REGION.min.re, SAMPLE_REGION.min.im));
+/// assert_eq!(weight, 64);
+/// // The warped points cover the whole region.
+/// let (c, _) = importance.warp([0.999_999, 0.999_999]);
+/// assert!(c.re > 1.99 && c.im > 1.99);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Importance {
+    /// The level of each cell, row by row.
+    levels: Vec<u32>,
+    /// The cumulative weight of the rows up to and including each, divided by the total.
+    rows: Vec<f64>,
+    /// The cumulative weight of the cells in each row up to and including each, divided by the
+    /// weight of the row.
+    columns: Vec<f64>,
+}
+impl Importance {
+    /// Estimates the levels of the cells for rendering `config` with `options`, by iterating the
+    /// corners of the cells.
+    ///
+    /// A cell gets the level of the corner of it or its neighbours with the most counted points
+    /// in the image, as `log2` of the count, so the filaments between the corners aren't missed.
+    pub fn new(config: &Config, options: &BuddhabrotOptions) -> Self {
+        let cells = IMPORTANCE_CELLS;
+        let iterations = iterations(config, options).min(IMPORTANCE_ITERATIONS);
+        let corners: Vec<usize> = (0..=cells)
+            .into_par_iter()
+            .flat_map_iter(|y| {
+                let mut orbit = Vec::new();
+                (0..=cells)
+                    .map(move |x| {
+                        let c = Imaginary {
+                            re: SAMPLE_REGION.min.re
+                                + x as f64 / cells as f64 * SAMPLE_REGION.width(),
+                            im: SAMPLE_REGION.min.im
+                                + y as f64 / cells as f64 * SAMPLE_REGION.height(),
+                        };
+                        let escaped = iterate(c, iterations, config.limit, &mut orbit);
+                        if escaped == (options.mode == Mode::Anti) {
+                            return 0;
+                        }
+                        orbit
+                            .iter()
+                            .filter(|&&z| pixel(config, z).is_some())
+                            .count()
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        let corners = &corners;
+        let levels: Vec<u32> = (0..cells * cells)
+            .map(|i| {
+                let (x, y) = (i % cells, i / cells);
+                let most = (y.saturating_sub(1)..(y + 3).min(cells + 1))
+                    .flat_map(|y| {
+                        (x.saturating_sub(1)..(x + 3).min(cells + 1))
+                            .map(move |x| corners[y * (cells + 1) + x])
+                    })
+                    .max()
+                    .unwrap_or(0);
+                (usize::BITS - most.leading_zeros()).min(IMPORTANCE_LEVELS)
+            })
+            .collect();
+
+        let weight = |level: u32| (1_u64 << level) as f64;
+        let mut columns = Vec::with_capacity(cells * cells);
+        let mut row_weights = Vec::with_capacity(cells);
+        for row in levels.chunks(cells) {
+            let total: f64 = row.iter().map(|&level| weight(level)).sum();
+            let mut sum = 0.0;
+            columns.extend(row.iter().map(|&level| {
+                sum += weight(level);
+                sum / total
+            }));
+            row_weights.push(total);
+        }
+        let total: f64 = row_weights.iter().sum();
+        let mut sum = 0.0;
+        let rows = row_weights
+            .iter()
+            .map(|weight| {
+                sum += weight;
+                sum / total
+            })
+            .collect();
+        Self {
+            levels,
+            rows,
+            columns,
+        }
+    }
+    /// The share of the constants drawn from the cells of the highest level.
+    pub fn highest_share(&self) -> f64 {
+        let weight = |level: u32| (1_u64 << level) as f64;
+        let total: f64 = self.levels.iter().map(|&level| weight(level)).sum();
+        let highest = self
+            .levels
+            .iter()
+            .filter(|&&level| level == IMPORTANCE_LEVELS)
+            .count() as f64;
+        highest * weight(IMPORTANCE_LEVELS) / total
+    }
+    /// Maps the point `[x, y]` in the unit square to a constant in [`SAMPLE_REGION`], and the
+    /// weight of its hits.
+    ///
+    /// `y` picks the row and `x` the cell in it, following their weights. What's left of them is
+    /// the position in the cell, so evenly spread points stay evenly spread within the cells.
+    pub fn warp(&self, [x, y]: [f64; 2]) -> (Imaginary, u32) {
+        let cells = IMPORTANCE_CELLS;
+        let (row, y) = invert(&self.rows, y);
+        let (column, x) = invert(&self.columns[row * cells..(row + 1) * cells], x);
+        let c = Imaginary {
+            re: SAMPLE_REGION.min.re + (column as f64 + x) / cells as f64 * SAMPLE_REGION.width(),
+            im: SAMPLE_REGION.min.im + (row as f64 + y) / cells as f64 * SAMPLE_REGION.height(),
+        };
+        let weight = 1 << (IMPORTANCE_LEVELS - self.levels[row * cells + column]);
+        (c, weight)
+    }
+}
+/// The index of the bucket of the cumulative distribution `cumulative` which `v` falls in, and
+/// the position of `v` in it, in `0..1`.
+fn invert(cumulative: &[f64], v: f64) -> (usize, f64) {
+    let index = cumulative
+        .partition_point(|&threshold| threshold <= v)
+        .min(cumulative.len() - 1);
+    let low = if index == 0 {
+        0.0
+    } else {
+        cumulative[index - 1]
+    };
+    let position = (v - low) / (cumulative[index] - low);
+    (index, position.clamp(0.0, 1.0 - f64::EPSILON))
+}
+
+/// Iterates `c` at most `iterations` times into `orbit`, returning whether it escaped.
+fn iterate(c: Imaginary, iterations: u32, limit: f64, orbit: &mut Vec<Imaginary>) -> bool {
+    let squared = limit * limit;
+    orbit.clear();
+    let mut z = Imaginary::ZERO;
+    for _ in 0..iterations {
+        z = z.square() + c;
+        let dist = z.squared_distance();
+        if dist > squared || dist.is_nan() {
+            return true;
+        }
+        orbit.push(z);
+    }
+    false
+}
+
+/// The index of the pixel of `z`, if it's within the image.
+fn pixel(config: &Config, z: Imaginary) -> Option<usize> {
+    let width = config.width as usize;
+    let height = config.height as usize;
+    let (x, y) = mapping::complex_to_pixel(config, z);
+    // Also skips NaN.
+    if !(x >= 0.0 && y >= 0.0) {
+        return None;
+    }
+    let (x, y) = (x as usize, y as usize);
+    if x < width && y < height {
+        Some(y * width + x)
+    } else {
+        None
+    }
+}
+/// Adds `weight` for the points of `orbit` within the image to `hits`.
+fn accumulate(config: &Config, orbit: &[Imaginary], weight: u32, hits: &mut [u32]) {
+    for &z in orbit {
+        if let Some(pixel) = pixel(config, z) {
+            hits[pixel] = hits[pixel].saturating_add(weight);
+        }
+    }
+}
+
+/// The most iterations of any channel.
+fn iterations(config: &Config, options: &BuddhabrotOptions) -> u32 {
+    match options.mode {
+        Mode::Normal | Mode::Anti => config.iterations,
+        Mode::Nebula => options.nebula_iterations.iter().copied().max().unwrap_or(0),
+    }
+}
+
+/// Takes `samples` constants from `sampler`, warped by `importance` if any, and adds their orbits
+/// to `buffers`, one per channel.
+fn sample(
+    config: &Config,
+    options: &BuddhabrotOptions,
+    samples: u64,
+    sampler: &mut dyn Sampler,
+    importance: Option<&Importance>,
+    buffers: &mut [Vec<u32>],
+) {
+    let iterations = iterations(config, options);
+    let mut orbit = Vec::new();
+    for _ in 0..samples {
+        let point = sampler.next_point();
+        let (c, weight) = match importance {
+            Some(importance) => importance.warp(point),
+            None => (
+                Imaginary {
+                    re: point[0] * SAMPLE_REGION.width() + SAMPLE_REGION.min.re,
+                    im: point[1] * SAMPLE_REGION.height() + SAMPLE_REGION.min.im,
+                },
+                1,
+            ),
+        };
+        // Skip the constants which are known not to escape, unless they are counted.
+        if options.mode != Mode::Anti && in_main_bulbs(c) {
+            continue;
+        }
+        let escaped = iterate(c, iterations, config.limit, &mut orbit);
+        match options.mode {
+            Mode::Normal if escaped => accumulate(config, &orbit, weight, &mut buffers[0]),
+            Mode::Anti if !escaped => accumulate(config, &orbit, weight, &mut buffers[0]),
+            // The orbit escaped after its length + 1 iterations.
+            Mode::Nebula if escaped => {
+                for (channel, &max) in options.nebula_iterations.iter().enumerate() {
+                    if orbit.len() < max as usize {
+                        accumulate(config, &orbit, weight, &mut buffers[channel]);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Maps the counts of `hits` to `0..=255`, relative to the largest count.
+///
+/// The square root brings out the faint orbits.
+fn normalize(hits: &[u32]) -> Vec<u8> {
+    let max = hits.iter().copied().max().unwrap_or(0);
+    if max == 0 {
+        return vec![0; hits.len()];
+    }
+    let max = max as f64;
+    hits.iter()
+        .map(|&hits| ((hits as f64 / max).sqrt() * 255.0).round() as u8)
+        .collect()
+}
+
+/// Renders the Buddhabrot of `config` with `options`.
+///
+/// With a `seed`, the image is the same for every number of threads.
+pub fn render(config: &Config, options: &BuddhabrotOptions, seed: Option<u64>) -> Vec<RGB> {
+    let pixels = config.width as usize * config.height as usize;
+    let channels = options.mode.channels();
+    let samples = pixels as u64 * options.samples as u64;
+    let parts = match seed {
+        Some(_) => SEEDED_PARTS,
+        None => rayon::current_num_threads() as u64,
+    };
+    let importance = if options.importance {
+        let importance = Importance::new(config, options);
+        log::debug!(
+            "Drawing {:.1}% of the points from the cells which add the most to the image.",
+            importance.highest_share() * 100.0
+        );
+        Some(importance)
+    } else {
+        None
+    };
+
+    // The counts are integers, so the sum is the same in any order, and the image doesn't depend
+    // on how rayon splits the parts.
+    let buffers = (0..parts)
+        .into_par_iter()
+        .fold(
+            || vec![vec![0_u32; pixels]; channels],
+            |mut buffers, part| {
+                // The first parts take the remainder.
+                let samples = samples / parts + (part < samples % parts) as u64;
+                let mut sampler = options.sampler.part(seed, part, parts);
+                sample(
+                    config,
+                    options,
+                    samples,
+                    &mut *sampler,
+                    importance.as_ref(),
+                    &mut buffers,
+                );
+                buffers
+            },
+        )
+        .reduce(
+            || vec![vec![0_u32; pixels]; channels],
+            |mut a, b| {
+                for (a, b) in a.iter_mut().zip(&b) {
+                    for (a, b) in a.iter_mut().zip(b) {
+                        *a = a.saturating_add(*b);
+                    }
+                }
+                a
+            },
+        );
+
+    let channels: Vec<_> = buffers.iter().map(|hits| normalize(hits)).collect();
+    let glow = |i: usize| match options.mode {
+        Mode::Normal | Mode::Anti => [channels[0][i]; 3],
+        Mode::Nebula => [channels[0][i], channels[1][i], channels[2][i]],
+    };
+    (0..pixels)
+        .map(|i| {
+            let (x, y) = (i as u32 % config.width, i as u32 / config.width);
+            lighten(config.background.color(x, y, RGB::new(0, 0, 0)), glow(i))
+        })
+        .collect()
+}
+
+/// `background` lightened by the `glow` of the orbits, which is the glow itself on black.
+///
+/// ```
+/// # use fractal_renderer::{buddhabrot::lighten, RGB};
+/// assert_eq!(lighten(RGB::new(0, 0, 0), [10, 128, 255]), RGB::new(10, 128, 255));
+/// assert_eq!(lighten(RGB::new(0, 64, 128), [0, 0, 0]), RGB::new(0, 64, 128));
+/// assert_eq!(lighten(RGB::new(0, 64, 128), [255, 255, 255]), RGB::new(255, 255, 255));
+/// ```
+pub fn lighten(background: RGB, [r, g, b]: [u8; 3]) -> RGB {
+    // Screen blending.
+    let channel = |background: u8, glow: u8| {
+        255 - ((255 - background as u32) * (255 - glow as u32) + 127) / 255
+    };
+    RGB::new(
+        channel(background.r, r) as u8,
+        channel(background.g, g) as u8,
+        channel(background.b, b) as u8,
+    )
+}
@@ -0,0 +1,305 @@
+//! Camera paths of animations through keyframes, see `--keyframes`.
+//!
+//! The frames are spread evenly over the segments between the keyframes. Along each segment, the
+//! center moves with an [`Interpolator`] and the scale changes by the same factor every frame, so
+//! the zoom looks steady at any depth. [`Keyframe::ease_in`] and [`Keyframe::ease_out`] slow the
+//! camera down around a keyframe.
+//!
+//! [`Interpolation::CatmullRom`] curves through the keyframes, so the camera doesn't turn
+//! corners at them like with [`Interpolation::Linear`]. [`plot`] draws the path over an overview,
+//! to check it before rendering the frames.
+
+use crate::{Config, Imaginary, Orientation, RGB};
+use serde::Deserialize;
+use std::fmt::{self, Display};
+use std::fs;
+use std::str::FromStr;
+
+/// The length of the longer side of [`plot`].
+pub const PLOT_SIZE: u32 = 800;
+/// The space around the frames in [`plot`], relative to their extent.
+const PLOT_MARGIN: f64 = 0.1;
+const PATH_COLOR: RGB = RGB::new(255, 255, 255);
+const KEYFRAME_COLOR: RGB = RGB::new(255, 64, 64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Keyframe {
+    /// The real part of the center, like `-x`.
+    pub x: f64,
+    /// The imaginary part of the center, like `-y`.
+    pub y: f64,
+    /// Like `--scale`.
+    pub scale: f64,
+    /// How much the camera slows down when arriving at the keyframe, from 0 to 1, where it stops.
+    #[serde(default)]
+    pub ease_in: f64,
+    /// How much the camera slows down when leaving the keyframe, from 0 to 1.
+    #[serde(default)]
+    pub ease_out: f64,
+}
+impl Keyframe {
+    fn pos(&self) -> Imaginary {
+        Imaginary {
+            re: self.x,
+            im: self.y,
+        }
+    }
+}
+
+/// Moves the camera between keyframes.
+pub trait Interpolator {
+    /// The point between `points[segment]` and `points[segment + 1]` at `t`, from 0 to 1.
+    fn interpolate(&self, points: &[Imaginary], segment: usize, t: f64) -> Imaginary;
+}
+/// Straight lines between the keyframes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Linear;
+impl Interpolator for Linear {
+    fn interpolate(&self, points: &[Imaginary], segment: usize, t: f64) -> Imaginary {
+        let (a, b) = (points[segment], points[segment + 1]);
+        Imaginary {
+            re: a.re + (b.re - a.re) * t,
+            im: a.im + (b.im - a.im) * t,
+        }
+    }
+}
+/// A Catmull-Rom spline through the keyframes. The tangent at each keyframe points from the one
+/// before it to the one after it, and the ends continue straight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CatmullRom;
+impl Interpolator for CatmullRom {
+    fn interpolate(&self, points: &[Imaginary], segment: usize, t: f64) -> Imaginary {
+        let at = |i: usize| points[i.min(points.len() - 1)];
+        let (p0, p1, p2, p3) = (
+            at(segment.saturating_sub(1)),
+            at(segment),
+            at(segment + 1),
+            at(segment + 2),
+        );
+        let (t2, t3) = (t * t, t * t * t);
+        let axis = |p0: f64, p1: f64, p2: f64, p3: f64| {
+            0.5 * (2.0 * p1
+                + (p2 - p0) * t
+                + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+                + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+        };
+        Imaginary {
+            re: axis(p0.re, p1.re, p2.re, p3.re),
+            im: axis(p0.im, p1.im, p2.im, p3.im),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    Linear,
+    CatmullRom,
+}
+impl Interpolation {
+    pub const ALL: [Self; 2] = [Self::Linear, Self::CatmullRom];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Linear => "linear",
+            Self::CatmullRom => "catmull-rom",
+        }
+    }
+    pub fn interpolator(self) -> &'static dyn Interpolator {
+        match self {
+            Self::Linear => &Linear,
+            Self::CatmullRom => &CatmullRom,
+        }
+    }
+}
+impl Default for Interpolation {
+    fn default() -> Self {
+        Self::CatmullRom
+    }
+}
+impl Display for Interpolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+impl FromStr for Interpolation {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|interpolation| interpolation.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown interpolation {:?}", s))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraPath {
+    /// At least 2, see [`load`].
+    pub keyframes: Vec<Keyframe>,
+    pub interpolation: Interpolation,
+}
+
+/// Reads the keyframes of a JSON file: an array of [`Keyframe`]s, like
+/// `[{"x": -0.75, "y": 0, "scale": 1, "ease_out": 0.5}, {"x": -0.7436, "y": 0.1318, "scale": 1e4}]`.
+pub fn load(path: &str) -> Result<Vec<Keyframe>, String> {
+    let data = fs::read_to_string(path).map_err(|err| format!("{:?}: {}", path, err))?;
+    parse(&data).map_err(|err| format!("{:?}: {}", path, err))
+}
+/// Parses the keyframes of [`load`].
+///
+/// ```
+/// # use fractal_renderer::camera::parse;
+/// let keyframes = parse(r#"[{"x": -0.75, "y": 0, "scale": 1}, {"x": 0, "y": 1, "scale": 2, "ease_in": 1}]"#).unwrap();
+/// assert_eq!(keyframes[1].ease_in, 1.0);
+/// assert_eq!(keyframes[1].ease_out, 0.0);
+///
+/// assert!(parse(r#"[{"x": 0, "y": 0, "scale": 1}]"#).is_err());
+/// assert!(parse(r#"[{"x": 0, "y": 0, "scale": 1}, {"x": 0, "y": 0, "scale": 0}]"#).is_err());
+/// ```
+pub fn parse(data: &str) -> Result<Vec<Keyframe>, String> {
+    let keyframes: Vec<Keyframe> = serde_json::from_str(data).map_err(|err| err.to_string())?;
+    if keyframes.len() < 2 {
+        return Err("a camera path needs at least 2 keyframes".into());
+    }
+    for (i, keyframe) in keyframes.iter().enumerate() {
+        if !(keyframe.x.is_finite() && keyframe.y.is_finite()) {
+            return Err(format!("keyframe {} isn't at a finite point", i));
+        }
+        if !(keyframe.scale.is_finite() && keyframe.scale > 0.0) {
+            return Err(format!("the scale of keyframe {} isn't positive", i));
+        }
+        if !((0.0..=1.0).contains(&keyframe.ease_in) && (0.0..=1.0).contains(&keyframe.ease_out)) {
+            return Err(format!("the easing of keyframe {} isn't in [0, 1]", i));
+        }
+    }
+    Ok(keyframes)
+}
+
+/// The progress along a segment at `t`, slowed down at the start by `ease_out` of the keyframe it
+/// leaves and at the end by `ease_in` of the keyframe it arrives at. Without easing, it's `t`.
+fn ease(t: f64, ease_out: f64, ease_in: f64) -> f64 {
+    // A cubic from 0 to 1 with these slopes at the ends, which only rises for slopes up to 3.
+    let (start, end) = (1.0 - ease_out, 1.0 - ease_in);
+    (start + end - 2.0) * t * t * t + (3.0 - 2.0 * start - end) * t * t + start * t
+}
+
+/// The config at `progress`, from 0 at the first keyframe to 1 at the last, with the center
+/// moved by `interpolator`. The center and scale of `config` are replaced; the ratio of the
+/// scales of the axes is kept.
+///
+/// ```
+/// # use fractal_renderer::camera::{config_at, parse, CatmullRom, Linear};
+/// # use fractal_renderer::{Algo, Config};
+/// let keyframes = parse(
+///     r#"[{"x": 0, "y": 0, "scale": 1}, {"x": 1, "y": 0, "scale": 100}, {"x": 1, "y": 1, "scale": 100}]"#,
+/// )
+/// .unwrap();
+/// let config = Config::new(Algo::Mandelbrot);
+///
+/// let halfway = config_at(&config, &keyframes, &Linear, 0.25);
+/// assert!((halfway.pos.re - 0.5).abs() < 1e-12 && halfway.pos.im == 0.0);
+/// // Halfway in log-space.
+/// assert!((halfway.scale.im - 10.0).abs() < 1e-9);
+///
+/// // The spline passes through the keyframes, but overshoots the straight line to turn the
+/// // corner smoothly.
+/// let keyframe = config_at(&config, &keyframes, &CatmullRom, 0.5);
+/// assert!((keyframe.pos.re - 1.0).abs() < 1e-12 && keyframe.pos.im.abs() < 1e-12);
+/// let curved = config_at(&config, &keyframes, &CatmullRom, 0.375);
+/// assert!(curved.pos.im < 0.0);
+/// ```
+pub fn config_at(
+    config: &Config,
+    keyframes: &[Keyframe],
+    interpolator: &dyn Interpolator,
+    progress: f64,
+) -> Config {
+    let segments = keyframes.len() - 1;
+    let position = progress.clamp(0.0, 1.0) * segments as f64;
+    let segment = (position as usize).min(segments - 1);
+    let (from, to) = (keyframes[segment], keyframes[segment + 1]);
+    let t = ease(position - segment as f64, from.ease_out, to.ease_in);
+
+    let points: Vec<Imaginary> = keyframes.iter().map(Keyframe::pos).collect();
+    let scale = (from.scale.ln() + (to.scale.ln() - from.scale.ln()) * t).exp();
+    let mut config = config.clone();
+    config.pos = interpolator.interpolate(&points, segment, t);
+    config.pos_low = Imaginary::ZERO;
+    config.scale = config.scale * (scale / config.scale.im);
+    config
+}
+
+/// An overview of every frame of `configs`, at most [`PLOT_SIZE`] pixels on each side, with the
+/// path of their centers and the views of `keyframes` drawn over it.
+///
+/// Returns the image and its config.
+pub fn plot(configs: &[Config], keyframes: &[Config]) -> (Vec<RGB>, Config) {
+    let mut bounds = configs[0].viewport();
+    for config in configs {
+        let viewport = config.viewport();
+        bounds.min.re = bounds.min.re.min(viewport.min.re);
+        bounds.min.im = bounds.min.im.min(viewport.min.im);
+        bounds.max.re = bounds.max.re.max(viewport.max.re);
+        bounds.max.im = bounds.max.im.max(viewport.max.im);
+    }
+    let (width, height) = (
+        bounds.width() * (1.0 + 2.0 * PLOT_MARGIN),
+        bounds.height() * (1.0 + 2.0 * PLOT_MARGIN),
+    );
+    let size = |side: f64| {
+        (PLOT_SIZE as f64 * side / width.max(height))
+            .round()
+            .max(1.0) as u32
+    };
+    let first = &configs[0];
+    let overview = Config {
+        width: size(width),
+        height: size(height),
+        pos: Imaginary {
+            re: (bounds.min.re + bounds.max.re) / 2.0,
+            im: (bounds.min.im + bounds.max.im) / 2.0,
+        },
+        pos_low: Imaginary::ZERO,
+        scale: Imaginary {
+            re: 1.0 / height,
+            im: 1.0 / height,
+        },
+        orientation: Orientation::default(),
+        exp_map: false,
+        ..first.clone()
+    };
+
+    let mut contents = crate::get_image(&overview);
+    let mut image = crate::Image::new(
+        &mut contents,
+        overview.width as usize,
+        overview.height as usize,
+    );
+    let pixel = |z: Imaginary| crate::mapping::complex_to_pixel(&overview, z);
+    for keyframe in keyframes {
+        let viewport = keyframe.viewport();
+        let corners = [
+            viewport.min,
+            Imaginary {
+                re: viewport.max.re,
+                im: viewport.min.im,
+            },
+            viewport.max,
+            Imaginary {
+                re: viewport.min.re,
+                im: viewport.max.im,
+            },
+        ];
+        for (i, corner) in corners.iter().enumerate() {
+            image.draw_line(pixel(*corner), pixel(corners[(i + 1) % 4]), KEYFRAME_COLOR);
+        }
+    }
+    for pair in configs.windows(2) {
+        image.draw_line(pixel(pair[0].pos), pixel(pair[1].pos), PATH_COLOR);
+    }
+    for keyframe in keyframes {
+        image.fill_circle(pixel(keyframe.pos), 3.0, KEYFRAME_COLOR);
+    }
+    (contents, overview)
+}
@@ -0,0 +1,286 @@
+//! Periodically save the progress of long renders, so they can be resumed.
+//!
+//! Escape-time fractals are rendered in bands of rows, and the finished rows are saved.
+//! The fern is plotted in the parts of a seeded render, see [`crate::get_image_seeded`], and saves
+//! the number of parts plotted and their accumulation buffer. With the same seed, it's the same
+//! image as without checkpoints.
+//!
+//! # Format
+//!
+//! All integers are little endian.
+//!
+//! | bytes                | content                                                     |
+//! |----------------------|-------------------------------------------------------------|
+//! | 8                    | magic `FRCKPT01`                                            |
+//! | 8                    | [`config_hash`]                                             |
+//! | 4 + 4                | width, height                                               |
+//! | 4                    | finished rows, or plotted parts of the fern                  |
+//! | 3 × pixels           | finished rows, or the colors of the fern once a part is plotted |
+//! | 4 × pixels           | the hit counts of the fern with [`Config::fern_normalize`]   |
+
+use crate::{ifs, Algo, Config, RGB};
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use std::fmt::{self, Display};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const MAGIC: &[u8; 8] = b"FRCKPT02";
+const HEADER_LEN: usize = 8 + 8 + 4 + 4 + 4;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckpointOptions {
+    /// Where to save the progress.
+    pub path: PathBuf,
+    /// Minimum time between saves.
+    pub interval: Duration,
+    /// Continue from the checkpoint at [`Self::path`].
+    pub resume: bool,
+}
+
+#[derive(Debug)]
+pub enum CheckpointError {
+    Io(io::Error),
+    /// The checkpoint was made with another config.
+    ConfigMismatch,
+    /// The checkpoint isn't valid.
+    Corrupt,
+}
+impl Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "checkpoint IO failed: {}", err),
+            Self::ConfigMismatch => write!(
+                f,
+                "the checkpoint was made with a different configuration; \
+                 use the same arguments as the original render"
+            ),
+            Self::Corrupt => write!(f, "the checkpoint file is corrupt"),
+        }
+    }
+}
+impl std::error::Error for CheckpointError {}
+impl From<io::Error> for CheckpointError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// A stable hash of all the fields of `config`, using FNV-1a.
+pub fn config_hash(config: &Config) -> u64 {
+    crate::config_to_toml(config)
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+        })
+}
+
+struct Checkpoint {
+    progress: u32,
+    contents: Vec<RGB>,
+    /// Only of the fern with [`Config::fern_normalize`].
+    hits: Vec<u32>,
+}
+impl Checkpoint {
+    fn new(config: &Config) -> Self {
+        let pixels = config.width as usize * config.height as usize;
+        let (contents, hits) = match config.algo {
+            Algo::Mandelbrot => (Vec::with_capacity(pixels), Vec::new()),
+            Algo::BarnsleyFern if config.fern_normalize => (Vec::new(), vec![0; pixels]),
+            // The colors of the first part replace them.
+            Algo::BarnsleyFern => (Vec::new(), Vec::new()),
+        };
+        Self {
+            progress: 0,
+            contents,
+            hits,
+        }
+    }
+}
+
+fn tmp_path(path: &Path) -> PathBuf {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    PathBuf::from(tmp)
+}
+fn write(path: &Path, config: &Config, checkpoint: &Checkpoint) -> io::Result<()> {
+    let tmp = tmp_path(path);
+
+    let mut data =
+        Vec::with_capacity(HEADER_LEN + checkpoint.contents.len() * 3 + checkpoint.hits.len() * 4);
+    data.extend_from_slice(MAGIC);
+    data.extend_from_slice(&config_hash(config).to_le_bytes());
+    data.extend_from_slice(&config.width.to_le_bytes());
+    data.extend_from_slice(&config.height.to_le_bytes());
+    data.extend_from_slice(&checkpoint.progress.to_le_bytes());
+    for pixel in &checkpoint.contents {
+        data.extend_from_slice(&[pixel.r, pixel.g, pixel.b]);
+    }
+    for hits in &checkpoint.hits {
+        data.extend_from_slice(&hits.to_le_bytes());
+    }
+
+    let mut file = fs::File::create(&tmp)?;
+    file.write_all(&data)?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(&tmp, path)
+}
+fn read(path: &Path, config: &Config) -> Result<Checkpoint, CheckpointError> {
+    let mut data = Vec::new();
+    fs::File::open(path)?.read_to_end(&mut data)?;
+    if data.len() < HEADER_LEN || &data[..8] != MAGIC {
+        return Err(CheckpointError::Corrupt);
+    }
+    let u32_at = |i: usize| u32::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]);
+    let mut hash = [0; 8];
+    hash.copy_from_slice(&data[8..16]);
+    if u64::from_le_bytes(hash) != config_hash(config)
+        || u32_at(16) != config.width
+        || u32_at(20) != config.height
+    {
+        return Err(CheckpointError::ConfigMismatch);
+    }
+    let progress = u32_at(24);
+    let pixels = config.width as usize * config.height as usize;
+    let (end, colors, hits) = match config.algo {
+        Algo::Mandelbrot => (config.height, progress as usize * config.width as usize, 0),
+        Algo::BarnsleyFern if config.fern_normalize => (crate::SEEDED_FERN_PARTS, 0, pixels),
+        Algo::BarnsleyFern => (
+            crate::SEEDED_FERN_PARTS,
+            if progress == 0 { 0 } else { pixels },
+            0,
+        ),
+    };
+    let body = &data[HEADER_LEN..];
+    if progress > end || body.len() != colors * 3 + hits * 4 {
+        return Err(CheckpointError::Corrupt);
+    }
+    let (color_data, hit_data) = body.split_at(colors * 3);
+    let contents = color_data
+        .chunks_exact(3)
+        .map(|p| RGB {
+            r: p[0],
+            g: p[1],
+            b: p[2],
+        })
+        .collect();
+    let hits = hit_data
+        .chunks_exact(4)
+        .map(|h| u32::from_le_bytes([h[0], h[1], h[2], h[3]]))
+        .collect();
+    Ok(Checkpoint {
+        progress,
+        contents,
+        hits,
+    })
+}
+
+/// Renders `config` like [`crate::get_image`], or [`crate::get_image_seeded`] with `seed`, saving
+/// the progress according to `options`.
+///
+/// The checkpoint is kept when the render is done, so it can still be resumed if writing the
+/// image fails. [`remove`] it once the image is written.
+pub fn render(
+    config: &Config,
+    options: &CheckpointOptions,
+    seed: Option<u64>,
+) -> Result<Vec<RGB>, CheckpointError> {
+    let mut checkpoint = if options.resume {
+        let checkpoint = read(&options.path, config)?;
+        log::info!("Resuming from {:?}.", options.path);
+        checkpoint
+    } else {
+        Checkpoint::new(config)
+    };
+    let mut last_save = Instant::now();
+    let mut save = |checkpoint: &Checkpoint| -> io::Result<()> {
+        if last_save.elapsed() >= options.interval {
+            write(&options.path, config, checkpoint)?;
+            log::debug!("Saved checkpoint to {:?}.", options.path);
+            last_save = Instant::now();
+        }
+        Ok(())
+    };
+
+    match config.algo {
+        Algo::Mandelbrot => {
+            let band = (rayon::current_num_threads() as u32 * 4).max(1);
+            while checkpoint.progress < config.height {
+                let end = (checkpoint.progress + band).min(config.height);
+                let rows = crate::get_rows(config, checkpoint.progress..end);
+                checkpoint.contents.extend_from_slice(&rows);
+                checkpoint.progress = end;
+                save(&checkpoint)?;
+            }
+        }
+        Algo::BarnsleyFern => {
+            let parts = crate::SEEDED_FERN_PARTS;
+            // Without a seed, the parts left are seeded differently every run.
+            let seed = seed.unwrap_or_else(|| SmallRng::from_entropy().gen());
+            let seeds = crate::part_seeds(seed, parts);
+            let part = Config {
+                iterations: config.iterations / parts,
+                ..config.clone()
+            };
+            // Framed once, for all the parts.
+            let fern = ifs::Ifs::barnsley();
+            let points =
+                |config: &Config, rng: &mut SmallRng, plot: &mut dyn FnMut(usize, usize)| {
+                    crate::ifs_points(config, &fern, rng, plot)
+                };
+            let rng = |index: u32| SmallRng::seed_from_u64(seeds[index as usize]);
+            let batch = (rayon::current_num_threads() as u32).max(1);
+            while checkpoint.progress < parts {
+                let end = (checkpoint.progress + batch).min(parts);
+                let indices = checkpoint.progress..end;
+                // Combined in the order of the parts, like the seeded render.
+                if config.fern_normalize {
+                    let plotted: Vec<_> = indices
+                        .into_par_iter()
+                        .map(|index| crate::plot_part_hits(&part, &mut rng(index), &points))
+                        .collect();
+                    for hits in plotted {
+                        checkpoint.hits =
+                            crate::combine_hits(mem::take(&mut checkpoint.hits), hits);
+                    }
+                } else {
+                    let plotted: Vec<_> = indices
+                        .into_par_iter()
+                        .map(|index| crate::plot_part_colors(&part, &mut rng(index), &points))
+                        .collect();
+                    for colors in plotted {
+                        let contents = mem::take(&mut checkpoint.contents);
+                        checkpoint.contents = crate::combine_colors(&part, contents, colors);
+                    }
+                }
+                checkpoint.progress = end;
+                save(&checkpoint)?;
+            }
+        }
+    }
+    // A final save, so resuming after a failed write doesn't render anything again.
+    write(&options.path, config, &checkpoint)?;
+    Ok(match config.algo {
+        Algo::BarnsleyFern if config.fern_normalize => {
+            crate::fern_density(config, &checkpoint.hits)
+        }
+        _ => checkpoint.contents,
+    })
+}
+
+/// Removes the checkpoint of `options`, after the image it's of is written.
+pub fn remove(options: &CheckpointOptions) -> io::Result<()> {
+    // The temporary file is left if we were killed while saving.
+    for path in [options.path.clone(), tmp_path(&options.path)] {
+        match fs::remove_file(path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+            _ => {}
+        }
+    }
+    Ok(())
+}
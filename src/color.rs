@@ -0,0 +1,288 @@
+//! Simulation of color vision deficiencies, to check that renders and palettes read correctly for
+//! color-blind viewers, see [`simulate`] and [`lint`].
+//!
+//! Colors are converted from sRGB to linear RGB and on to the responses of the long, medium and
+//! short wavelength cones (LMS). The response of the missing cone is replaced by a mix of the two
+//! others, which keeps white and the grays, see [`Cvd::lms_projection`], and the result is
+//! converted back. This is the method of Viénot, Brettel & Mollon (1999), with the matrices
+//! popularized by Fidaner, Lin & Ozguven's daltonize. It's accurate for protanopia and
+//! deuteranopia, but only approximates tritanopia.
+
+use crate::palette::Palette;
+use crate::RGB;
+use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+/// The smallest difference of adjacent palette stops, in CIE76 ΔE, which reads as a change of
+/// color at a glance.
+pub const MIN_DELTA_E: f64 = 10.0;
+
+/// Linear RGB to LMS.
+///
+/// ```
+/// # use fractal_renderer::color::{LMS_TO_RGB, RGB_TO_LMS};
+/// // The published matrices are each other's inverse.
+/// for row in 0..3 {
+///     for column in 0..3 {
+///         let product: f64 = (0..3).map(|i| RGB_TO_LMS[row][i] * LMS_TO_RGB[i][column]).sum();
+///         let identity = if row == column { 1.0 } else { 0.0 };
+///         assert!((product - identity).abs() < 1e-5, "{} {}", row, column);
+///     }
+/// }
+/// ```
+pub const RGB_TO_LMS: [[f64; 3]; 3] = [
+    [17.8824, 43.5161, 4.11935],
+    [3.45565, 27.1554, 3.86714],
+    [0.0299566, 0.184309, 1.46709],
+];
+/// LMS to linear RGB, the inverse of [`RGB_TO_LMS`].
+pub const LMS_TO_RGB: [[f64; 3]; 3] = [
+    [0.0809444479, -0.130504409, 0.116721066],
+    [-0.0102485335, 0.0540193266, -0.113614708],
+    [-0.000365296938, -0.00412161469, 0.693511405],
+];
+/// Linear RGB to CIE XYZ, with the D65 white point of sRGB.
+const RGB_TO_XYZ: [[f64; 3]; 3] = [
+    [0.4124564, 0.3575761, 0.1804375],
+    [0.2126729, 0.7151522, 0.0721750],
+    [0.0193339, 0.1191920, 0.9503041],
+];
+const D65: [f64; 3] = [0.95047, 1.0, 1.08883];
+
+/// A color vision deficiency, where one kind of cone is missing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cvd {
+    /// No long wavelength (red) cones.
+    Protanopia,
+    /// No medium wavelength (green) cones.
+    Deuteranopia,
+    /// No short wavelength (blue) cones.
+    Tritanopia,
+}
+impl Cvd {
+    pub const ALL: [Self; 3] = [Self::Protanopia, Self::Deuteranopia, Self::Tritanopia];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Protanopia => "protanopia",
+            Self::Deuteranopia => "deuteranopia",
+            Self::Tritanopia => "tritanopia",
+        }
+    }
+    /// The name shown in the GUI.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Protanopia => "Protanopia",
+            Self::Deuteranopia => "Deuteranopia",
+            Self::Tritanopia => "Tritanopia",
+        }
+    }
+    /// Replaces the response of the missing cone in LMS by a mix of the two others.
+    ///
+    /// ```
+    /// # use fractal_renderer::color::{Cvd, RGB_TO_LMS};
+    /// // White looks the same to everyone.
+    /// let white: Vec<f64> = RGB_TO_LMS.iter().map(|row| row.iter().sum()).collect();
+    /// for cvd in Cvd::ALL {
+    ///     let projection = cvd.lms_projection();
+    ///     for (row, expected) in projection.iter().zip(&white) {
+    ///         let seen: f64 = row.iter().zip(&white).map(|(a, b)| a * b).sum();
+    ///         assert!((seen - expected).abs() < 1e-3, "{}", cvd);
+    ///     }
+    /// }
+    /// ```
+    pub fn lms_projection(self) -> [[f64; 3]; 3] {
+        match self {
+            Self::Protanopia => [[0.0, 2.02344, -2.52581], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            Self::Deuteranopia => [[1.0, 0.0, 0.0], [0.494207, 0.0, 1.24827], [0.0, 0.0, 1.0]],
+            Self::Tritanopia => [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [-0.395913, 0.801109, 0.0]],
+        }
+    }
+    /// The simulation as one matrix in linear RGB.
+    ///
+    /// ```
+    /// # use fractal_renderer::color::Cvd;
+    /// // The published values of Viénot, Brettel & Mollon.
+    /// let protanopia = [
+    ///     [0.11238, 0.88762, 0.0],
+    ///     [0.11238, 0.88762, 0.0],
+    ///     [0.00401, -0.00401, 1.0],
+    /// ];
+    /// let deuteranopia = [
+    ///     [0.29275, 0.70725, 0.0],
+    ///     [0.29275, 0.70725, 0.0],
+    ///     [-0.02234, 0.02234, 1.0],
+    /// ];
+    /// for (cvd, expected) in [(Cvd::Protanopia, protanopia), (Cvd::Deuteranopia, deuteranopia)] {
+    ///     let matrix = cvd.matrix();
+    ///     for (row, expected) in matrix.iter().zip(&expected) {
+    ///         for (value, expected) in row.iter().zip(expected) {
+    ///             assert!((value - expected).abs() < 1e-4, "{}", cvd);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn matrix(self) -> [[f64; 3]; 3] {
+        multiply(&LMS_TO_RGB, &multiply(&self.lms_projection(), &RGB_TO_LMS))
+    }
+}
+impl Display for Cvd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+impl FromStr for Cvd {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|cvd| cvd.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown color vision deficiency {:?}", s))
+    }
+}
+
+fn multiply(a: &[[f64; 3]; 3], b: &[[f64; 3]; 3]) -> [[f64; 3]; 3] {
+    let mut product = [[0.0; 3]; 3];
+    for (row, a) in product.iter_mut().zip(a) {
+        for (column, value) in row.iter_mut().enumerate() {
+            *value = (0..3).map(|i| a[i] * b[i][column]).sum();
+        }
+    }
+    product
+}
+fn transform(matrix: &[[f64; 3]; 3], v: [f64; 3]) -> [f64; 3] {
+    let row = |row: &[f64; 3]| row[0] * v[0] + row[1] * v[1] + row[2] * v[2];
+    [row(&matrix[0]), row(&matrix[1]), row(&matrix[2])]
+}
+
+/// The linear intensity, from 0 to 1, of a sRGB channel.
+pub fn srgb_to_linear(channel: u8) -> f64 {
+    let c = channel as f64 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+/// The sRGB channel of a linear intensity, clamped to `0..=1`.
+pub fn linear_to_srgb(value: f64) -> u8 {
+    let v = value.clamp(0.0, 1.0);
+    let c = if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    };
+    (c * 255.0).round() as u8
+}
+fn linear(color: RGB) -> [f64; 3] {
+    [
+        srgb_to_linear(color.r),
+        srgb_to_linear(color.g),
+        srgb_to_linear(color.b),
+    ]
+}
+
+/// How `color` looks with `cvd`.
+///
+/// ```
+/// # use fractal_renderer::{color::{delta_e, simulate, Cvd}, RGB};
+/// for cvd in Cvd::ALL {
+///     // Grays stay the same.
+///     for gray in [0, 50, 128, 255] {
+///         let gray = RGB::new(gray, gray, gray);
+///         assert!(delta_e(simulate(gray, cvd), gray) < 1.0, "{}", cvd);
+///     }
+///     // Simulating again changes nothing.
+///     let seen = simulate(RGB::new(200, 80, 30), cvd);
+///     assert!(delta_e(simulate(seen, cvd), seen) < 1.0, "{}", cvd);
+/// }
+/// // This red and green are confused without green cones, but not without blue cones.
+/// let (red, green) = (RGB::new(200, 60, 40), RGB::new(110, 140, 40));
+/// assert!(delta_e(red, green) > 40.0);
+/// let seen = |cvd| delta_e(simulate(red, cvd), simulate(green, cvd));
+/// assert!(seen(Cvd::Deuteranopia) < 10.0);
+/// assert!(seen(Cvd::Tritanopia) > 40.0);
+/// ```
+pub fn simulate(color: RGB, cvd: Cvd) -> RGB {
+    simulate_with(&cvd.matrix(), color)
+}
+fn simulate_with(matrix: &[[f64; 3]; 3], color: RGB) -> RGB {
+    let [r, g, b] = transform(matrix, linear(color));
+    RGB::new(linear_to_srgb(r), linear_to_srgb(g), linear_to_srgb(b))
+}
+/// Simulates `cvd` on every pixel of `contents`, see [`simulate`].
+pub fn simulate_image(contents: &mut [RGB], cvd: Cvd) {
+    let matrix = cvd.matrix();
+    contents
+        .par_iter_mut()
+        .for_each(|pixel| *pixel = simulate_with(&matrix, *pixel));
+}
+
+/// The CIE L\*a\*b\* of `color`.
+fn lab(color: RGB) -> [f64; 3] {
+    let xyz = transform(&RGB_TO_XYZ, linear(color));
+    let f = |t: f64| {
+        if t > 216.0 / 24389.0 {
+            t.cbrt()
+        } else {
+            (24389.0 / 27.0 * t + 16.0) / 116.0
+        }
+    };
+    let [x, y, z] = [f(xyz[0] / D65[0]), f(xyz[1] / D65[1]), f(xyz[2] / D65[2])];
+    [116.0 * y - 16.0, 500.0 * (x - y), 200.0 * (y - z)]
+}
+/// The CIE76 difference of `a` and `b`: the distance in L\*a\*b\*, where about 2.3 is just
+/// noticeable.
+///
+/// ```
+/// # use fractal_renderer::{color::delta_e, RGB};
+/// assert!((delta_e(RGB::new(0, 0, 0), RGB::new(255, 255, 255)) - 100.0).abs() < 0.01);
+/// ```
+pub fn delta_e(a: RGB, b: RGB) -> f64 {
+    let (a, b) = (lab(a), lab(b));
+    (0..3).map(|i| (a[i] - b[i]).powi(2)).sum::<f64>().sqrt()
+}
+
+/// Adjacent stops of a palette which are told apart, but not with a [`Cvd`], see [`lint`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Confusion {
+    /// The index of the first stop. The other is the next.
+    pub stop: usize,
+    /// The difference of the stops, see [`delta_e`].
+    pub delta_e: f64,
+    /// The difference of the stops with the deficiency.
+    pub simulated_delta_e: f64,
+}
+
+/// The adjacent stops of `palette` which differ by at least [`MIN_DELTA_E`], but less with
+/// `cvd`.
+///
+/// ```
+/// # use fractal_renderer::{color::{lint, Cvd}, palette};
+/// let traffic_light = palette::parse("c83c28,6e8c28,ffffff").unwrap();
+/// let confused = lint(&traffic_light, Cvd::Deuteranopia);
+/// assert_eq!(confused.len(), 1);
+/// assert_eq!(confused[0].stop, 0);
+/// assert!(lint(&traffic_light, Cvd::Tritanopia).is_empty());
+/// ```
+pub fn lint(palette: &Palette, cvd: Cvd) -> Vec<Confusion> {
+    let matrix = cvd.matrix();
+    palette
+        .stops()
+        .windows(2)
+        .enumerate()
+        .filter_map(|(stop, pair)| {
+            let (a, b) = (pair[0].color, pair[1].color);
+            let delta_e = delta_e(a, b);
+            let simulated_delta_e =
+                self::delta_e(simulate_with(&matrix, a), simulate_with(&matrix, b));
+            (delta_e >= MIN_DELTA_E && simulated_delta_e < MIN_DELTA_E).then(|| Confusion {
+                stop,
+                delta_e,
+                simulated_delta_e,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,104 @@
+//! Renders which are the same bytes for every number of threads, see `--deterministic`.
+//!
+//! Escape-time fractals always are: every pixel is computed on its own, and the rows are
+//! collected in order. The fern, IFS and Buddhabrot plot random points, so with
+//! `--deterministic` (and `--checksum`) they use [`SEED`](crate::digest::SEED) and split the
+//! points in a fixed number of parts. Each part is seeded by its index, not by the thread which
+//! plots it, and the parts are combined with [`tree_reduce`], in the same order for every number
+//! of threads. The counts of the Buddhabrot are whole numbers, which add up the same in any
+//! order. The exposure of the whole image, [`auto_exposure`](crate::render::auto_exposure), sums
+//! the brightness of the rows with [`tree_reduce`] too.
+//!
+//! These are still nondeterministic:
+//! - the GPU backend, as the driver decides the precision and order of the operations,
+//! - `--time-limit`, which stops at the last quality finished in time,
+//! - the GUI and `--serve`, which plot the fern unseeded.
+//!
+//! ```
+//! # use fractal_renderer::buddhabrot::{self, BuddhabrotOptions, Mode};
+//! # use fractal_renderer::render::{auto_exposure, METER_TARGET};
+//! # use fractal_renderer::{digest::SEED, get_image_seeded, get_samples, Algo, Config};
+//! let pool = |threads| {
+//!     rayon::ThreadPoolBuilder::new()
+//!         .num_threads(threads)
+//!         .build()
+//!         .unwrap()
+//! };
+//! let render = |config: &Config, threads| pool(threads).install(|| get_image_seeded(config, SEED));
+//!
+//! let mut fern = Config::new(Algo::BarnsleyFern);
+//! fern.width = 120;
+//! fern.height = 160;
+//! fern.iterations = 100_000;
+//! for normalize in [false, true] {
+//!     fern.fern_normalize = normalize;
+//!     assert_eq!(render(&fern, 1), render(&fern, 8));
+//! }
+//!
+//! let mut mandelbrot = Config::new(Algo::Mandelbrot);
+//! mandelbrot.width = 96;
+//! mandelbrot.height = 64;
+//! assert_eq!(render(&mandelbrot, 1), render(&mandelbrot, 8));
+//!
+//! // Exposed by the mean brightness of the whole image, on the thread pool which renders it.
+//! let auto_exposed = |threads| {
+//!     pool(threads).install(|| {
+//!         let mut config = mandelbrot.clone();
+//!         let exposure = auto_exposure(&config, &get_samples(&config), METER_TARGET);
+//!         config.exposure = exposure.unwrap();
+//!         (config.exposure.to_bits(), get_image_seeded(&config, SEED))
+//!     })
+//! };
+//! assert_eq!(auto_exposed(1), auto_exposed(8));
+//!
+//! mandelbrot.iterations = 200;
+//! let options = BuddhabrotOptions {
+//!     mode: Mode::Nebula,
+//!     samples: 4,
+//!     nebula_iterations: [200, 100, 50],
+//!     sampler: Default::default(),
+//!     importance: true,
+//! };
+//! let buddhabrot = |threads| {
+//!     pool(threads).install(|| buddhabrot::render(&mandelbrot, &options, Some(SEED)))
+//! };
+//! assert_eq!(buddhabrot(1), buddhabrot(8));
+//! ```
+
+use std::ops::Range;
+
+/// Computes `part` of every index in `parts`, in parallel, and combines them pairwise: the
+/// first half with the second half, each combined the same way. The result is the same for every
+/// number of threads, even if `combine` isn't associative, like adding floats.
+///
+/// # Panics
+///
+/// If `parts` is empty.
+///
+/// ```
+/// # use fractal_renderer::deterministic::tree_reduce;
+/// let sum = |threads| {
+///     let pool = rayon::ThreadPoolBuilder::new()
+///         .num_threads(threads)
+///         .build()
+///         .unwrap();
+///     pool.install(|| tree_reduce(0..1000, &|i| 1.0 / (i as f64 + 1.0), &|a, b| a + b))
+/// };
+/// assert_eq!(sum(1).to_bits(), sum(8).to_bits());
+/// ```
+pub fn tree_reduce<T: Send>(
+    parts: Range<u64>,
+    part: &(impl Fn(u64) -> T + Sync),
+    combine: &(impl Fn(T, T) -> T + Sync),
+) -> T {
+    assert!(!parts.is_empty(), "there has to be a part to reduce");
+    if parts.end - parts.start == 1 {
+        return part(parts.start);
+    }
+    let middle = parts.start + (parts.end - parts.start) / 2;
+    let (a, b) = rayon::join(
+        || tree_reduce(parts.start..middle, part, combine),
+        || tree_reduce(middle..parts.end, part, combine),
+    );
+    combine(a, b)
+}
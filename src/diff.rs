@@ -0,0 +1,285 @@
+//! Compare two images, to verify that optimizations don't change the output.
+//!
+//! PPM images can always be read. PNG images need the `png` feature.
+
+use crate::format::Format;
+use crate::{ImageBuffer, Options, RGB};
+use std::fmt::{self, Display};
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompareOptions {
+    /// The image compared against.
+    pub reference: String,
+    /// The image to compare, or [`None`] to compare a fresh render of the config.
+    pub image: Option<String>,
+    /// Channels which differ by at most this much are equal.
+    pub tolerance: u8,
+    /// The percentage of differing pixels above which the comparison fails.
+    pub threshold: f64,
+    /// Where to write the heat-map of the differences.
+    pub diff_image: Option<String>,
+    /// The factor the differences are multiplied by in the heat-map.
+    pub amplify: f64,
+}
+
+#[derive(Debug)]
+pub enum DiffError {
+    Io(PathBuf, io::Error),
+    /// The image can't be read, with the reason.
+    Decode(PathBuf, String),
+    /// The images have the sizes `(width, height)`.
+    SizeMismatch((usize, usize), (usize, usize)),
+}
+impl Display for DiffError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "{:?}: {}", path, err),
+            Self::Decode(path, reason) => write!(f, "failed to read {:?}: {}", path, reason),
+            Self::SizeMismatch(a, b) => write!(
+                f,
+                "the images have different sizes, {}×{} and {}×{}",
+                a.0, a.1, b.0, b.1
+            ),
+        }
+    }
+}
+impl std::error::Error for DiffError {}
+
+/// Checks that the image at `path` can be read by this binary, based on its extension.
+pub fn readable(path: &str) -> Result<(), String> {
+    match Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .and_then(Format::from_extension)
+    {
+        Some(Format::Ppm) => Ok(()),
+        Some(Format::Png) if Format::Png.is_supported() => Ok(()),
+        Some(Format::Png) => Err(format!(
+            "can't read {:?}, as this binary was built without the png feature",
+            path
+        )),
+        _ => Err(format!(
+            "can't read {:?}; only PNG and PPM are supported",
+            path
+        )),
+    }
+}
+
+/// Reads a PNG or PPM image.
+pub fn load(path: impl AsRef<Path>) -> Result<ImageBuffer, DiffError> {
+    let path = path.as_ref();
+    let data = fs::read(path).map_err(|err| DiffError::Io(path.to_owned(), err))?;
+    decode(&data).map_err(|reason| DiffError::Decode(path.to_owned(), reason))
+}
+
+/// Decodes a PNG or PPM image, or returns why it can't be.
+///
+/// ```
+/// # use fractal_renderer::{diff::decode, ImageBuffer, RGB};
+/// let image = decode(b"P6\n2 1\n255\n\xff\x00\x00\x00\x00\xff").unwrap();
+/// assert_eq!(image.contents(), [RGB::new(255, 0, 0), RGB::new(0, 0, 255)]);
+/// assert!(decode(b"\x00\x00\x00\x1cftypavif").is_err());
+/// ```
+pub fn decode(data: &[u8]) -> Result<ImageBuffer, String> {
+    if data.starts_with(b"P6") {
+        return parse_ppm(data).ok_or_else(|| "invalid PPM".to_owned());
+    }
+    #[cfg(feature = "png")]
+    if data.starts_with(b"\x89PNG") {
+        let image = image::load_from_memory_with_format(data, image::ImageFormat::Png)
+            .map_err(|err| err.to_string())?
+            .to_rgb8();
+        let (width, height) = image.dimensions();
+        let contents = image
+            .pixels()
+            .map(|pixel| RGB::new(pixel[0], pixel[1], pixel[2]))
+            .collect();
+        return ImageBuffer::from_contents(contents, width as usize, height as usize)
+            .ok_or_else(|| "the image is too large".to_owned());
+    }
+    Err("only PNG and binary PPM images are supported".to_owned())
+}
+
+/// Parses a binary PPM with a maximum value of 255.
+fn parse_ppm(data: &[u8]) -> Option<ImageBuffer> {
+    let mut rest = data.strip_prefix(b"P6")?;
+    let mut header = [0_usize; 3];
+    for value in &mut header {
+        // Skip whitespace and comments.
+        loop {
+            match rest.first()? {
+                b'#' => {
+                    let end = rest.iter().position(|&b| b == b'\n')?;
+                    rest = &rest[end..];
+                }
+                b if b.is_ascii_whitespace() => rest = &rest[1..],
+                _ => break,
+            }
+        }
+        let len = rest.iter().take_while(|b| b.is_ascii_digit()).count();
+        *value = std::str::from_utf8(&rest[..len]).ok()?.parse().ok()?;
+        rest = &rest[len..];
+    }
+    let [width, height, max] = header;
+    if max != 255 || !rest.first()?.is_ascii_whitespace() {
+        return None;
+    }
+    let pixels = rest[1..].get(..width.checked_mul(height)?.checked_mul(3)?)?;
+    let contents = pixels
+        .chunks_exact(3)
+        .map(|pixel| RGB::new(pixel[0], pixel[1], pixel[2]))
+        .collect();
+    ImageBuffer::from_contents(contents, width, height)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffReport {
+    /// The largest difference of each channel.
+    pub max: [u8; 3],
+    /// The mean difference of each channel.
+    pub mean: [f64; 3],
+    /// The number of pixels with a channel differing by more than the tolerance.
+    pub differing: usize,
+    pub pixels: usize,
+}
+impl DiffReport {
+    pub fn differing_percent(&self) -> f64 {
+        if self.pixels == 0 {
+            return 0.0;
+        }
+        self.differing as f64 / self.pixels as f64 * 100.0
+    }
+}
+impl Display for DiffReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "max difference    r {}  g {}  b {}\n\
+             mean difference   r {:.4}  g {:.4}  b {:.4}\n\
+             differing pixels  {} of {} ({:.4}%)",
+            self.max[0],
+            self.max[1],
+            self.max[2],
+            self.mean[0],
+            self.mean[1],
+            self.mean[2],
+            self.differing,
+            self.pixels,
+            self.differing_percent(),
+        )
+    }
+}
+
+fn channels(pixel: RGB) -> [u8; 3] {
+    [pixel.r, pixel.g, pixel.b]
+}
+fn check_size(a: &ImageBuffer, b: &ImageBuffer) -> Result<(), DiffError> {
+    if a.width() != b.width() || a.height() != b.height() {
+        return Err(DiffError::SizeMismatch(
+            (a.width(), a.height()),
+            (b.width(), b.height()),
+        ));
+    }
+    Ok(())
+}
+
+/// Compares `a` and `b`. Pixels differ if any channel differs by more than `tolerance`.
+pub fn compare(a: &ImageBuffer, b: &ImageBuffer, tolerance: u8) -> Result<DiffReport, DiffError> {
+    check_size(a, b)?;
+    let mut max = [0; 3];
+    let mut sum = [0_u64; 3];
+    let mut differing = 0;
+    for (a, b) in a.contents().iter().zip(b.contents()) {
+        let (a, b) = (channels(*a), channels(*b));
+        let mut differs = false;
+        for channel in 0..3 {
+            let difference = (a[channel] as i16 - b[channel] as i16).unsigned_abs() as u8;
+            max[channel] = max[channel].max(difference);
+            sum[channel] += difference as u64;
+            differs |= difference > tolerance;
+        }
+        differing += differs as usize;
+    }
+    let pixels = a.contents().len();
+    let mean = |channel: usize| {
+        if pixels == 0 {
+            0.0
+        } else {
+            sum[channel] as f64 / pixels as f64
+        }
+    };
+    Ok(DiffReport {
+        max,
+        mean: [mean(0), mean(1), mean(2)],
+        differing,
+        pixels,
+    })
+}
+
+/// The color of `heat` in `0..=765`, from black through red and yellow to white, which spans the
+/// three ramps.
+pub fn ramp(heat: u16) -> RGB {
+    let ramp = |start: u16| heat.saturating_sub(start).min(255) as u8;
+    RGB::new(ramp(0), ramp(255), ramp(510))
+}
+
+/// A heat-map of the differences between `a` and `b`, from black (equal) through red and yellow
+/// to white. The largest difference of the channels is multiplied by `amplify`.
+pub fn heat_map(a: &ImageBuffer, b: &ImageBuffer, amplify: f64) -> Result<ImageBuffer, DiffError> {
+    check_size(a, b)?;
+    let contents = a
+        .contents()
+        .iter()
+        .zip(b.contents())
+        .map(|(a, b)| {
+            let (a, b) = (channels(*a), channels(*b));
+            let difference = (0..3)
+                .map(|channel| (a[channel] as i16 - b[channel] as i16).unsigned_abs())
+                .max()
+                .unwrap_or(0);
+            ramp((difference as f64 * amplify * 3.0).min(765.0) as u16)
+        })
+        .collect();
+    Ok(ImageBuffer::from_contents(contents, a.width(), a.height())
+        .expect("the size is the same as the inputs"))
+}
+
+/// Compares the images of `compare`, logging the report, and writes the heat-map if requested.
+///
+/// Returns whether the images are within [`CompareOptions::threshold`].
+pub fn run(options: &Options, compare: &CompareOptions) -> Result<bool, DiffError> {
+    let reference = load(&compare.reference)?;
+    let image = match &compare.image {
+        Some(path) => load(path)?,
+        None => {
+            let config = &options.config;
+            let mut contents = crate::get_image(config);
+            crate::draw_overlays(
+                options,
+                &mut crate::Image::new(
+                    &mut contents,
+                    config.width as usize,
+                    config.height as usize,
+                ),
+            );
+            ImageBuffer::from_contents(contents, config.width as usize, config.height as usize)
+                .expect("the rendered image has the size of the config")
+        }
+    };
+    let report = self::compare(&reference, &image, compare.tolerance)?;
+    println!("{}", report);
+
+    if let Some(path) = &compare.diff_image {
+        let heat_map = heat_map(&reference, &image, compare.amplify)?;
+        let mut options = options.clone();
+        options.output.set_path(path, Format::Png);
+        options.config.width = heat_map.width() as u32;
+        options.config.height = heat_map.height() as u32;
+        crate::write_image(&options, heat_map.into_contents())
+            .map_err(|err| DiffError::Io(options.output.filename.clone().into(), err))?;
+    }
+
+    Ok(report.differing_percent() <= compare.threshold)
+}
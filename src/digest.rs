@@ -0,0 +1,45 @@
+//! Stable digests of renders, to pin the output across refactors without storing images.
+//!
+//! The digest is the 64-bit FNV-1a hash of the width, height and RGB bytes of the image, as 16
+//! hexadecimal digits. The fern and the Buddhabrot are rendered with the fixed [`SEED`].
+//!
+//! A digest is only stable for the same version of this crate and identical floating point
+//! semantics. Different CPUs, compilers or target features (e.g. fused multiply-add) can round
+//! differently and change some pixels.
+
+use crate::{Config, RGB};
+
+/// The seed of the random numbers of the fern.
+pub const SEED: u64 = 0;
+
+/// The digest of `contents`, an image of `width` × `height`.
+pub fn digest(contents: &[RGB], width: u32, height: u32) -> String {
+    let hash = width
+        .to_le_bytes()
+        .iter()
+        .chain(&height.to_le_bytes())
+        .copied()
+        .chain(
+            contents
+                .iter()
+                .flat_map(|pixel| [pixel.r, pixel.g, pixel.b]),
+        )
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+        });
+    format!("{:016x}", hash)
+}
+
+/// Renders `config` (without overlays) and returns its digest.
+pub fn render_digest(config: &Config) -> String {
+    let contents = crate::get_image_seeded(config, SEED);
+    digest(&contents, config.width, config.height)
+}
+
+/// Checks that `s` is a digest, as returned by [`digest`].
+pub fn parse_digest(s: &str) -> Result<String, String> {
+    if s.len() != 16 || !s.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("expected 16 hexadecimal digits, got {:?}", s));
+    }
+    Ok(s.to_ascii_lowercase())
+}
@@ -0,0 +1,133 @@
+//! Finding points near the edge of an escape-time fractal, where it's most detailed.
+//!
+//! A point is [near the boundary](near_boundary) when it escapes, but only after a good part of
+//! the iterations, see [`ESCAPE_BAND`]. Points drawn from a [`Sampler`] are kept only when they
+//! are, which biases [`explore`](crate::explore) towards the edge and gives the constants of
+//! [`random_julia`], as the Julia sets of constants near the edge of the Mandelbrot set are the
+//! interesting ones.
+
+use crate::buddhabrot::SAMPLE_REGION;
+use crate::sampler::{self, Sampler};
+use crate::{format, grid, sample_point, Algo, Config, Imaginary, Options};
+use std::io;
+use std::ops::Range;
+
+/// The iterations after which points near the boundary escape, as fractions of the iterations
+/// of the config.
+pub const ESCAPE_BAND: Range<f64> = 0.05..1.0;
+/// The iterations of the Mandelbrot set the constants of [`random_julia`] are checked with.
+pub const RANDOM_JULIA_ITERATIONS: u32 = 256;
+/// How many points [`random_julia`] draws for each constant before giving up.
+const RANDOM_JULIA_ATTEMPTS: usize = 1000;
+/// The constants of [`random_julia`] are rounded to this, so they print short.
+const RANDOM_JULIA_PRECISION: f64 = 1e6;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RandomJuliaOptions {
+    /// How many constants to find.
+    pub count: usize,
+    /// The same seed finds the same constants.
+    pub seed: u64,
+    /// Where to write a contact sheet of the Julia sets of the constants.
+    pub sheet: Option<String>,
+}
+
+/// Whether `point` of the fractal of `config` is near the boundary, see [`ESCAPE_BAND`].
+///
+/// ```
+/// # use fractal_renderer::{discovery::near_boundary, Algo, Config, Imaginary};
+/// let mut config = Config::new(Algo::Mandelbrot);
+/// config.iterations = 256;
+/// // Inside, far outside and just outside the Mandelbrot set.
+/// assert!(!near_boundary(&config, Imaginary { re: -0.1, im: 0.1 }));
+/// assert!(!near_boundary(&config, Imaginary { re: 1.5, im: 1.5 }));
+/// assert!(near_boundary(&config, Imaginary { re: -0.75, im: 0.1 }));
+/// ```
+pub fn near_boundary(config: &Config, point: Imaginary) -> bool {
+    let sample = sample_point(config, point);
+    let iterations = config.iterations as f64;
+    sample.escaped
+        && sample.iterations >= ESCAPE_BAND.start * iterations
+        && sample.iterations < ESCAPE_BAND.end * iterations
+}
+
+/// Draws points from `sampler` until one is [near the boundary](near_boundary) of `config`,
+/// with `to_complex` giving the point of the fractal each is at. Gives up after `attempts`.
+pub fn next_near_boundary(
+    config: &Config,
+    sampler: &mut dyn Sampler,
+    attempts: usize,
+    to_complex: impl Fn([f64; 2]) -> Imaginary,
+) -> Option<[f64; 2]> {
+    (0..attempts)
+        .map(|_| sampler.next_point())
+        .find(|&point| near_boundary(config, to_complex(point)))
+}
+
+/// `count` Julia constants near the boundary of the Mandelbrot set, found with
+/// [`RANDOM_JULIA_ITERATIONS`]. Fewer if they're hard to find, which they aren't.
+///
+/// The same `seed` gives the same constants, and they print exactly with [`format_constant`].
+///
+/// ```
+/// # use fractal_renderer::{discovery::{format_constant, random_julia}, parse};
+/// let constants = random_julia(12, 7);
+/// assert_eq!(constants.len(), 12);
+/// assert_eq!(constants, random_julia(12, 7));
+/// assert_ne!(constants, random_julia(12, 8));
+/// for constant in constants {
+///     assert_eq!(parse::complex(&format_constant(constant)), Ok(constant));
+/// }
+/// ```
+pub fn random_julia(count: usize, seed: u64) -> Vec<Imaginary> {
+    let config = Config {
+        iterations: RANDOM_JULIA_ITERATIONS,
+        ..Config::new(Algo::Mandelbrot)
+    };
+    let round = |v: f64| (v * RANDOM_JULIA_PRECISION).round() / RANDOM_JULIA_PRECISION;
+    let to_complex = |[x, y]: [f64; 2]| Imaginary {
+        re: round(SAMPLE_REGION.min.re + x * SAMPLE_REGION.width()),
+        im: round(SAMPLE_REGION.min.im + y * SAMPLE_REGION.height()),
+    };
+    let mut sampler = sampler::Kind::Pseudorandom.part(Some(seed), 0, 1);
+    let mut constants = Vec::with_capacity(count);
+    while constants.len() < count {
+        match next_near_boundary(&config, &mut *sampler, RANDOM_JULIA_ATTEMPTS, to_complex) {
+            Some(point) => constants.push(to_complex(point)),
+            None => {
+                log::warn!(
+                    "Only found {} of {} constants near the boundary.",
+                    constants.len(),
+                    count
+                );
+                break;
+            }
+        }
+    }
+    constants
+}
+
+/// `constant` like `-0.8+0.156i`, which [`parse::complex`](crate::parse::complex) parses to
+/// exactly the same.
+pub fn format_constant(constant: Imaginary) -> String {
+    format!("{}{:+}i", constant.re, constant.im)
+}
+
+/// Prints the constants of [`random_julia`] to stdout, one per line, and renders the contact
+/// sheet of [`RandomJuliaOptions::sheet`] with the view of `options`.
+pub fn run(options: &Options, random: &RandomJuliaOptions) -> io::Result<()> {
+    log::info!("Finding Julia constants with --seed {}.", random.seed);
+    let constants = random_julia(random.count, random.seed);
+    for constant in &constants {
+        println!("{}", format_constant(*constant));
+    }
+    if let Some(path) = &random.sheet {
+        let columns = (constants.len() as f64).sqrt().ceil() as usize;
+        let contents = grid::render_constants(&options.config, columns, &constants);
+        let mut options = options.clone();
+        options.output.set_path(path, format::Format::Png);
+        let written = crate::write_image(&options, contents)?;
+        log::info!("Wrote the contact sheet to {:?}.", written);
+    }
+    Ok(())
+}
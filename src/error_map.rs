@@ -0,0 +1,117 @@
+//! Estimate where the precision of a render is too low, by comparing a sparse grid of its
+//! samples to double-double precision.
+//!
+//! [`Precision::F64`] is the fast path; deep zooms need [`Precision::DoubleDouble`], see
+//! [`Config::uses_double_double`]. The error map shows where the fast path loses iterations, so
+//! it's clear whether a zoom level still tolerates it.
+
+use crate::{diff, Config, Precision, RGB};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use std::fmt::{self, Display};
+
+/// Every `SPACING`th pixel along each axis is compared.
+pub const SPACING: usize = 8;
+
+/// The discrepancies of the samples of a grid.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorMap {
+    pub columns: usize,
+    pub rows: usize,
+    /// The difference in iterations between the render and the reference, row by row.
+    pub discrepancies: Vec<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ErrorStats {
+    /// The largest discrepancy, in iterations.
+    pub max: f64,
+    pub mean: f64,
+    /// The percentage of the samples which differ by more than 1 iteration.
+    pub above_one: f64,
+}
+impl Display for ErrorStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "max {:.2} iterations, mean {:.3}, {:.2}% of the samples off by more than 1 iteration",
+            self.max, self.mean, self.above_one
+        )
+    }
+}
+
+impl ErrorMap {
+    pub fn stats(&self) -> ErrorStats {
+        let count = self.discrepancies.len().max(1) as f64;
+        ErrorStats {
+            max: self.discrepancies.iter().copied().fold(0.0, f64::max),
+            mean: self.discrepancies.iter().sum::<f64>() / count,
+            above_one: self
+                .discrepancies
+                .iter()
+                .filter(|&&discrepancy| discrepancy > 1.0)
+                .count() as f64
+                / count
+                * 100.0,
+        }
+    }
+    /// A heat map of the discrepancies the size of `config`, where each sample covers the
+    /// [`SPACING`]×[`SPACING`] pixels around it.
+    ///
+    /// It's black where the render is exact, red at 1 iteration off, yellow at 3 and white at 7
+    /// or more.
+    pub fn heat_map(&self, config: &Config) -> Vec<RGB> {
+        let (width, height) = (config.width as usize, config.height as usize);
+        let mut contents = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let row = (y / SPACING).min(self.rows - 1) * self.columns;
+            for x in 0..width {
+                let discrepancy = self.discrepancies[row + (x / SPACING).min(self.columns - 1)];
+                let heat = (255.0 * (1.0 + discrepancy).log2()).min(765.0);
+                contents.push(diff::ramp(heat as u16));
+            }
+        }
+        contents
+    }
+}
+
+/// Samples the pixel in the middle of every [`SPACING`]×[`SPACING`] block of `config` and a
+/// double-double reference of it.
+///
+/// If `config` already uses double-double precision, every discrepancy is 0.
+///
+/// ```
+/// # use fractal_renderer::{error_map::estimate, Algo, Config, Precision};
+/// let mut config = Config::new(Algo::Mandelbrot);
+/// config.width = 64;
+/// config.height = 30;
+/// config.precision = Precision::F64;
+/// let map = estimate(&config);
+/// assert_eq!((map.columns, map.rows), (8, 4));
+/// // Far from the limits of `f64`.
+/// assert!(map.stats().max < 1.0);
+/// ```
+pub fn estimate(config: &Config) -> ErrorMap {
+    let mut reference = config.clone();
+    reference.precision = Precision::DoubleDouble;
+    let (width, height) = (config.width as usize, config.height as usize);
+    let columns = (width + SPACING - 1) / SPACING;
+    let rows = (height + SPACING - 1) / SPACING;
+    let center = |cell: usize, size: usize| ((cell * SPACING + SPACING / 2).min(size - 1)) as f64;
+    let discrepancies = (0..rows)
+        .into_par_iter()
+        .flat_map_iter(|row| {
+            let reference = &reference;
+            (0..columns).map(move |column| {
+                let (x, y) = (center(column, width), center(row, height));
+                let sample = crate::sample(config, x, y);
+                let precise = crate::sample(reference, x, y);
+                (sample.iterations - precise.iterations).abs()
+            })
+        })
+        .collect();
+    ErrorMap {
+        columns,
+        rows,
+        discrepancies,
+    }
+}
@@ -0,0 +1,347 @@
+//! Search for interesting views of an escape-time fractal, see [`search`].
+//!
+//! Starting at the view of the config, each level zooms [`ZOOM_STEP`] times further into the
+//! best views of the previous one. Candidates are spread over those views with a seeded
+//! [`sampler`](crate::sampler), preferring points near the boundary of the set (see
+//! [`discovery`](crate::discovery)), and each is scored with a small render, see [`score`].
+
+use crate::format;
+use crate::render::auto_iterations;
+use crate::{
+    discovery, mapping, sampler, template, Config, Imaginary, Options, OutputOptions, Sample,
+};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use std::cmp::Ordering;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// The longer side of the renders the candidates are scored by.
+pub const SCORE_SIZE: u32 = 64;
+/// How many times each level zooms in.
+pub const ZOOM_STEP: f64 = 4.0;
+/// The number of levels, so the deepest views are [`ZOOM_STEP`]^`LEVELS` times zoomed in.
+pub const LEVELS: usize = 10;
+/// How many of the best views of a level the next level zooms into.
+pub const BEAM_WIDTH: usize = 4;
+/// How many points each candidate tries to find one near the boundary of the set with, see
+/// [`discovery`].
+const BOUNDARY_ATTEMPTS: usize = 16;
+/// The number of bins of the histogram [`Score::entropy`] is the entropy of.
+const ENTROPY_BINS: usize = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExploreOptions {
+    /// How many views to render.
+    pub count: usize,
+    /// The most candidates to score, over all levels.
+    pub max_candidates: usize,
+    /// The same seed finds the same views.
+    pub seed: u64,
+    /// Where the renders and `explore.json` are written.
+    pub directory: String,
+}
+
+/// How interesting a small render looks, see [`score`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Score {
+    /// The entropy of the histogram of the iterations, from 0 if all pixels look the same to
+    /// 1 if they're spread evenly over all bins.
+    pub entropy: f64,
+    /// The fraction of the pixels next to a pixel which is on the other side of the edge of
+    /// the set.
+    pub boundary: f64,
+}
+impl Score {
+    pub fn value(&self) -> f64 {
+        self.entropy + self.boundary
+    }
+}
+
+/// Scores the `samples` of a render `width` wide with `iterations`.
+///
+/// ```
+/// # use fractal_renderer::{explore::score, Imaginary, Sample};
+/// let sample = |escaped, iterations| Sample {
+///     escaped,
+///     iterations,
+///     final_z: Imaginary::ZERO,
+///     min_orbit_radius: 0.0,
+/// };
+/// // All inside: nothing to see.
+/// let inside = vec![sample(false, 100.0); 16];
+/// assert_eq!(score(&inside, 4, 100).value(), 0.0);
+///
+/// // The left half escapes, in varying iterations.
+/// let edge: Vec<_> = (0..16)
+///     .map(|i| match i % 4 {
+///         0 | 1 => sample(true, (1 + i * 6) as f64),
+///         _ => sample(false, 100.0),
+///     })
+///     .collect();
+/// let edge = score(&edge, 4, 100);
+/// assert_eq!(edge.boundary, 0.5);
+/// assert!(edge.entropy > 0.4);
+/// ```
+pub fn score(samples: &[Sample], width: usize, iterations: u32) -> Score {
+    if samples.is_empty() || width == 0 {
+        return Score::default();
+    }
+    let height = samples.len() / width;
+
+    // The last bin is the inside.
+    let mut histogram = [0_u32; ENTROPY_BINS + 1];
+    let max = (1.0 + iterations as f64).ln().max(f64::MIN_POSITIVE);
+    for sample in samples {
+        let bin = if sample.escaped {
+            let position = (1.0 + sample.iterations).ln() / max;
+            ((position * ENTROPY_BINS as f64) as usize).min(ENTROPY_BINS - 1)
+        } else {
+            ENTROPY_BINS
+        };
+        histogram[bin] += 1;
+    }
+    let total = samples.len() as f64;
+    let entropy: f64 = histogram
+        .iter()
+        .filter(|count| **count > 0)
+        .map(|count| {
+            let p = *count as f64 / total;
+            -p * p.log2()
+        })
+        .sum();
+
+    let escaped = |x: usize, y: usize| samples[y * width + x].escaped;
+    let mut boundary = 0;
+    for y in 0..height {
+        for x in 0..width {
+            let here = escaped(x, y);
+            let neighbours = [
+                (x > 0).then(|| (x - 1, y)),
+                (x + 1 < width).then(|| (x + 1, y)),
+                (y > 0).then(|| (x, y - 1)),
+                (y + 1 < height).then(|| (x, y + 1)),
+            ];
+            if neighbours
+                .iter()
+                .flatten()
+                .any(|&(x, y)| escaped(x, y) != here)
+            {
+                boundary += 1;
+            }
+        }
+    }
+
+    Score {
+        entropy: entropy / ((ENTROPY_BINS + 1) as f64).log2(),
+        boundary: boundary as f64 / total,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    /// The view, at the size of the config the search started from.
+    pub config: Config,
+    pub score: Score,
+}
+
+/// The candidate zoomed [`ZOOM_STEP`] times into `parent`, at the point (`x`, `y`) in `0..1`
+/// of it's image.
+fn zoomed(parent: &Config, [x, y]: [f64; 2]) -> Config {
+    let mut config = parent.clone();
+    config.pos =
+        mapping::pixel_to_complex(parent, x * parent.width as f64, y * parent.height as f64);
+    config.pos_low = Imaginary::ZERO;
+    config.scale = parent.scale * ZOOM_STEP;
+    config.iterations = parent.iterations.max(auto_iterations(&config));
+    config
+}
+/// Best first.
+fn by_score(a: &Candidate, b: &Candidate) -> Ordering {
+    b.score
+        .value()
+        .partial_cmp(&a.score.value())
+        .unwrap_or(Ordering::Equal)
+}
+fn score_config(config: &Config) -> Score {
+    let mut small = config.clone();
+    let longer = config.width.max(config.height).max(1) as f64;
+    let shrink = SCORE_SIZE as f64 / longer;
+    small.width = ((config.width as f64 * shrink).round() as u32).max(1);
+    small.height = ((config.height as f64 * shrink).round() as u32).max(1);
+    score(
+        &crate::get_samples(&small),
+        small.width as usize,
+        small.iterations,
+    )
+}
+/// Whether `a` and `b` show mostly the same, being within a [`ZOOM_STEP`] of each other's zoom
+/// and having the center in each other's view.
+fn overlaps(a: &Config, b: &Config) -> bool {
+    let (zoom_a, zoom_b) = (template::zoom(a), template::zoom(b));
+    if zoom_a.max(zoom_b) / zoom_a.min(zoom_b) >= ZOOM_STEP {
+        return false;
+    }
+    // The view is 4 / zoom high.
+    let half_height = 2.0 / zoom_a.max(zoom_b);
+    let distance = (a.pos.re - b.pos.re).hypot(a.pos.im - b.pos.im);
+    distance < half_height
+}
+
+/// Finds the [`ExploreOptions::count`] most interesting views within the view of `config`,
+/// best first. At most [`ExploreOptions::max_candidates`] are scored.
+///
+/// The views don't overlap and are the same for the same [`ExploreOptions::seed`].
+///
+/// ```
+/// # use fractal_renderer::{explore::{search, ExploreOptions}, template::zoom, Algo, Config};
+/// let mut config = Config::new(Algo::Mandelbrot);
+/// config.width = 64;
+/// config.height = 48;
+/// let options = ExploreOptions {
+///     count: 3,
+///     max_candidates: 30,
+///     seed: 7,
+///     directory: "explore".into(),
+/// };
+/// let views = search(&config, &options);
+/// assert_eq!(views.len(), 3);
+/// assert!(views.iter().all(|view| zoom(&view.config) > zoom(&config)));
+/// assert!(views[0].score.value() >= views[2].score.value());
+/// assert_eq!(views, search(&config, &options));
+/// ```
+pub fn search(config: &Config, options: &ExploreOptions) -> Vec<Candidate> {
+    let per_level = (options.max_candidates / LEVELS).max(1);
+    let levels = LEVELS.min(options.max_candidates);
+
+    let mut scored: Vec<Candidate> = Vec::with_capacity(per_level * levels);
+    let mut parents = vec![config.clone()];
+    for level in 0..levels {
+        let mut sampler = sampler::Kind::Halton.part(Some(options.seed ^ level as u64), 0, 1);
+        let configs: Vec<Config> = (0..per_level)
+            .map(|i| {
+                let parent = &parents[i % parents.len()];
+                let to_complex = |[x, y]: [f64; 2]| {
+                    mapping::pixel_to_complex(
+                        parent,
+                        x * parent.width as f64,
+                        y * parent.height as f64,
+                    )
+                };
+                let point = discovery::next_near_boundary(
+                    parent,
+                    &mut *sampler,
+                    BOUNDARY_ATTEMPTS,
+                    to_complex,
+                );
+                zoomed(parent, point.unwrap_or_else(|| sampler.next_point()))
+            })
+            .collect();
+        let mut candidates: Vec<Candidate> = configs
+            .into_par_iter()
+            .map(|config| Candidate {
+                score: score_config(&config),
+                config,
+            })
+            .collect();
+        candidates.sort_by(by_score);
+        log::debug!(
+            "Level {} of {}: the best of {} candidates scored {:.3}.",
+            level + 1,
+            levels,
+            candidates.len(),
+            candidates.first().map_or(0.0, |best| best.score.value())
+        );
+        parents = candidates
+            .iter()
+            .take(BEAM_WIDTH)
+            .map(|candidate| candidate.config.clone())
+            .collect();
+        scored.extend(candidates);
+    }
+
+    // Stable, so ties keep the order they were found in.
+    scored.sort_by(by_score);
+    let mut views: Vec<Candidate> = Vec::with_capacity(options.count);
+    for candidate in scored {
+        if views.len() == options.count {
+            break;
+        }
+        if !views
+            .iter()
+            .any(|view| overlaps(&view.config, &candidate.config))
+        {
+            views.push(candidate);
+        }
+    }
+    views
+}
+
+/// Searches the view of `options.config` and writes the views found to
+/// [`ExploreOptions::directory`], named by their rank, with `explore.json` listing their
+/// coordinates and scores.
+pub fn run(options: &Options, explore: &ExploreOptions) -> io::Result<()> {
+    log::info!("Exploring with --seed {}.", explore.seed);
+    let views = search(&options.config, explore);
+    if views.len() < explore.count {
+        log::warn!(
+            "Only found {} views which don't overlap; allow more with --explore-candidates.",
+            views.len()
+        );
+    }
+    std::fs::create_dir_all(&explore.directory)?;
+
+    let digits = views.len().to_string().len().max(2);
+    let mut listed = Vec::with_capacity(views.len());
+    for (rank, view) in views.iter().enumerate() {
+        let name = format!("{:0digits$}", rank + 1, digits = digits);
+        let path = Path::new(&explore.directory).join(&name);
+        let (filename, format) =
+            format::output_path(&path.to_string_lossy(), options.output.format);
+        let options = Options {
+            config: view.config.clone(),
+            output: OutputOptions {
+                filename,
+                format,
+                ..options.output.clone()
+            },
+            ..options.clone()
+        };
+        let image = crate::get_image(&options.config);
+        let written = crate::write_image(&options, image)?;
+        log::debug!(
+            "Wrote view {} of {}, scoring {:.3}, to {:?}.",
+            rank + 1,
+            views.len(),
+            view.score.value(),
+            written
+        );
+        let file = Path::new(&written).file_name().map_or_else(
+            || written.clone(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+        listed.push(serde_json::json!({
+            "file": file,
+            "score": view.score.value(),
+            "entropy": view.score.entropy,
+            "boundary": view.score.boundary,
+            "center": view.config.pos,
+            "zoom": template::zoom(&view.config),
+            "iterations": view.config.iterations,
+        }));
+    }
+
+    // The centers are mirrored without it.
+    let json = serde_json::json!({
+        "seed": explore.seed,
+        "y_up": options.config.y_up,
+        "views": listed,
+    });
+    let path = Path::new(&explore.directory).join("explore.json");
+    let written = format::write_atomically(
+        &path.to_string_lossy(),
+        options.output.on_conflict,
+        |file| writeln!(file, "{:#}", json),
+    )?;
+    log::info!("Listed the views in {:?}.", written);
+    Ok(())
+}
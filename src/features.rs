@@ -0,0 +1,117 @@
+//! The cargo features this binary was built with, so missing capabilities are reported at
+//! runtime instead of the code paths silently being absent.
+//!
+//! Every feature compiles on its own and together with every other; `cargo run -p xtask`
+//! builds all those combinations.
+//!
+//! The GPU renderer isn't a feature of this tree, it lives on the `gpu` branch. Whether a
+//! [`Backend`](crate::Backend) is available is checked by [`render::backend`](crate::render::backend).
+
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    /// Writing AVIF images.
+    Avif,
+    /// Reading and writing PNG images.
+    Png,
+    /// The GUI, see `--gui`.
+    Gui,
+    /// Conversions to and from the types of the `image` crate.
+    ImageInterop,
+    /// Knowing the available memory, see [`resources`](crate::resources).
+    Sysinfo,
+    /// Serving tiles over HTTP, see `--serve`.
+    Serve,
+    /// Rendering again when the config changes, see `--watch`.
+    Watch,
+    /// The C API and its header.
+    Ffi,
+}
+impl Feature {
+    pub const ALL: [Self; 8] = [
+        Self::Avif,
+        Self::Png,
+        Self::Gui,
+        Self::ImageInterop,
+        Self::Sysinfo,
+        Self::Serve,
+        Self::Watch,
+        Self::Ffi,
+    ];
+
+    /// The name of the cargo feature.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Avif => "avif",
+            Self::Png => "png",
+            Self::Gui => "gui",
+            Self::ImageInterop => "image-interop",
+            Self::Sysinfo => "sysinfo",
+            Self::Serve => "serve",
+            Self::Watch => "watch",
+            Self::Ffi => "ffi",
+        }
+    }
+    /// Whether this binary was built with the feature.
+    pub fn is_enabled(self) -> bool {
+        match self {
+            Self::Avif => cfg!(feature = "avif"),
+            Self::Png => cfg!(feature = "png"),
+            Self::Gui => cfg!(feature = "gui"),
+            Self::ImageInterop => cfg!(feature = "image-interop"),
+            Self::Sysinfo => cfg!(feature = "sysinfo"),
+            Self::Serve => cfg!(feature = "serve"),
+            Self::Watch => cfg!(feature = "watch"),
+            Self::Ffi => cfg!(feature = "ffi"),
+        }
+    }
+    /// Checks that the feature is enabled, else returns why `what` isn't available.
+    ///
+    /// ```
+    /// # use fractal_renderer::features::Feature;
+    /// let serve = Feature::Serve.require("serving tiles");
+    /// if Feature::Serve.is_enabled() {
+    ///     assert!(serve.is_ok());
+    /// } else {
+    ///     assert_eq!(
+    ///         serve.unwrap_err(),
+    ///         "serving tiles isn't available, as this binary was built without the serve feature"
+    ///     );
+    /// }
+    /// ```
+    pub fn require(self, what: &str) -> Result<(), String> {
+        if self.is_enabled() {
+            Ok(())
+        } else {
+            Err(format!(
+                "{} isn't available, as this binary was built without the {} feature",
+                what, self
+            ))
+        }
+    }
+}
+impl Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+impl FromStr for Feature {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|feature| feature.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown feature {:?}", s))
+    }
+}
+
+/// The features this binary was built with.
+pub fn enabled() -> impl Iterator<Item = Feature> {
+    Feature::ALL
+        .iter()
+        .copied()
+        .filter(|feature| feature.is_enabled())
+}
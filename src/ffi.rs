@@ -0,0 +1,303 @@
+//! C bindings, enabled by the `ffi` feature.
+//!
+//! The header, `include/fractal_renderer.h`, is checked in. cbindgen generates it to `OUT_DIR`
+//! when building, and the `ffi_header` test fails if the checked in one is out of date; run it
+//! with `UPDATE_FFI_HEADER=1` to update it.
+//! See `examples/ffi/render.c` for usage.
+//!
+//! Functions returning `int32_t` return [`FR_OK`] or one of the negative error codes, after
+//! which [`fr_last_error_message`] describes the error. Panics never cross the boundary.
+
+use crate::{Algo, Config, Imaginary, RGB};
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+pub const FR_OK: i32 = 0;
+/// A pointer argument was null.
+pub const FR_NULL_POINTER: i32 = -1;
+/// An argument was out of range.
+pub const FR_INVALID_ARGUMENT: i32 = -2;
+/// The output buffer is smaller than `width * height * 3` bytes.
+pub const FR_BUFFER_TOO_SMALL: i32 = -3;
+/// The renderer panicked. This is a bug.
+pub const FR_PANIC: i32 = -4;
+
+pub const FR_ALGO_MANDELBROT: u32 = 0;
+/// [`FR_ALGO_MANDELBROT`] in Julia mode, with the constant 0 until [`fr_config_set_julia`].
+pub const FR_ALGO_JULIA: u32 = 1;
+pub const FR_ALGO_BARNSLEY_FERN: u32 = 2;
+
+/// An opaque configuration, created by [`fr_config_new`] and freed by [`fr_config_free`].
+pub struct FrConfig(Config);
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = RefCell::new(None);
+}
+
+fn set_last_error(message: String) {
+    // Interior nul bytes can't be represented.
+    let message = CString::new(message.replace('\0', " ")).expect("nul bytes were removed");
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Runs `f`, converting errors and panics to error codes.
+fn guard(f: impl FnOnce() -> Result<(), (i32, String)>) -> i32 {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => FR_OK,
+        Ok(Err((code, message))) => {
+            set_last_error(message);
+            code
+        }
+        Err(_) => {
+            set_last_error("the renderer panicked".into());
+            FR_PANIC
+        }
+    }
+}
+
+/// Runs `f` on the config behind `config`.
+///
+/// # Safety
+///
+/// `config` must be null or a pointer returned by [`fr_config_new`] which isn't freed.
+unsafe fn with_config(
+    config: *mut FrConfig,
+    f: impl FnOnce(&mut Config) -> Result<(), (i32, String)>,
+) -> i32 {
+    guard(|| match config.as_mut() {
+        Some(config) => f(&mut config.0),
+        None => Err((FR_NULL_POINTER, "the config is null".into())),
+    })
+}
+
+/// An error if any of `values` is infinite or NaN.
+fn finite(what: &str, values: &[f64]) -> Result<(), (i32, String)> {
+    if values.iter().all(|value| value.is_finite()) {
+        Ok(())
+    } else {
+        Err((FR_INVALID_ARGUMENT, format!("{} must be finite", what)))
+    }
+}
+
+/// The message of the last error on this thread, or null if there hasn't been one.
+///
+/// The string is valid until the next call on this thread.
+#[no_mangle]
+pub extern "C" fn fr_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Creates a config with the defaults of `algo`, one of the `FR_ALGO_*` constants.
+///
+/// Returns null if `algo` is invalid.
+#[no_mangle]
+pub extern "C" fn fr_config_new(algo: u32) -> *mut FrConfig {
+    let mut config = ptr::null_mut();
+    guard(|| {
+        let new = match algo {
+            FR_ALGO_MANDELBROT => Config::new(Algo::Mandelbrot),
+            FR_ALGO_JULIA => Config::new_julia(Algo::Mandelbrot, Imaginary::ZERO),
+            FR_ALGO_BARNSLEY_FERN => Config::new(Algo::BarnsleyFern),
+            _ => return Err((FR_INVALID_ARGUMENT, format!("unknown algorithm {}", algo))),
+        };
+        config = Box::into_raw(Box::new(FrConfig(new)));
+        Ok(())
+    });
+    config
+}
+
+/// Frees a config created by [`fr_config_new`]. Does nothing if `config` is null.
+///
+/// # Safety
+///
+/// `config` must not be used after this.
+#[no_mangle]
+pub unsafe extern "C" fn fr_config_free(config: *mut FrConfig) {
+    if !config.is_null() {
+        drop(Box::from_raw(config));
+    }
+}
+
+/// # Safety
+///
+/// `config` must be null or a valid config.
+#[no_mangle]
+pub unsafe extern "C" fn fr_config_set_size(config: *mut FrConfig, width: u32, height: u32) -> i32 {
+    with_config(config, |config| {
+        if width == 0 || height == 0 {
+            return Err((FR_INVALID_ARGUMENT, "the size can't be zero".into()));
+        }
+        config.width = width;
+        config.height = height;
+        Ok(())
+    })
+}
+
+/// Sets the maximum number of iterations, at least 1.
+///
+/// # Safety
+///
+/// `config` must be null or a valid config.
+#[no_mangle]
+pub unsafe extern "C" fn fr_config_set_iterations(config: *mut FrConfig, iterations: u32) -> i32 {
+    with_config(config, |config| {
+        if iterations == 0 {
+            return Err((FR_INVALID_ARGUMENT, "the iterations can't be zero".into()));
+        }
+        config.iterations = iterations;
+        Ok(())
+    })
+}
+
+/// Sets the center of the image.
+///
+/// # Safety
+///
+/// `config` must be null or a valid config.
+#[no_mangle]
+pub unsafe extern "C" fn fr_config_set_position(config: *mut FrConfig, re: f64, im: f64) -> i32 {
+    with_config(config, |config| {
+        finite("the position", &[re, im])?;
+        config.pos = Imaginary { re, im };
+        Ok(())
+    })
+}
+
+/// # Safety
+///
+/// `config` must be null or a valid config.
+#[no_mangle]
+pub unsafe extern "C" fn fr_config_set_scale(config: *mut FrConfig, re: f64, im: f64) -> i32 {
+    with_config(config, |config| {
+        if !(re > 0.0 && im > 0.0) {
+            return Err((FR_INVALID_ARGUMENT, "the scale must be positive".into()));
+        }
+        finite("the scale", &[re, im])?;
+        config.scale = Imaginary { re, im };
+        Ok(())
+    })
+}
+
+/// Sets the escape radius and the radius of points colored as inside.
+///
+/// # Safety
+///
+/// `config` must be null or a valid config.
+#[no_mangle]
+pub unsafe extern "C" fn fr_config_set_limits(
+    config: *mut FrConfig,
+    limit: f64,
+    stable_limit: f64,
+) -> i32 {
+    with_config(config, |config| {
+        finite("the limits", &[limit, stable_limit])?;
+        if stable_limit > limit {
+            return Err((
+                FR_INVALID_ARGUMENT,
+                "the stable limit can't be larger than the limit".into(),
+            ));
+        }
+        config.limit = limit;
+        config.stable_limit = stable_limit;
+        Ok(())
+    })
+}
+
+/// # Safety
+///
+/// `config` must be null or a valid config.
+#[no_mangle]
+pub unsafe extern "C" fn fr_config_set_exposure(config: *mut FrConfig, exposure: f64) -> i32 {
+    with_config(config, |config| {
+        finite("the exposure", &[exposure])?;
+        config.exposure = exposure;
+        Ok(())
+    })
+}
+
+/// Sets the constant of the Julia set, which renders the Julia set of the algorithm.
+///
+/// # Safety
+///
+/// `config` must be null or a valid config.
+#[no_mangle]
+pub unsafe extern "C" fn fr_config_set_julia(config: *mut FrConfig, re: f64, im: f64) -> i32 {
+    with_config(config, |config| {
+        finite("the Julia constant", &[re, im])?;
+        config.julia = Some(Imaginary { re, im });
+        Ok(())
+    })
+}
+
+/// Sets the primary and secondary colors, as `0xRRGGBB`.
+///
+/// # Safety
+///
+/// `config` must be null or a valid config.
+#[no_mangle]
+pub unsafe extern "C" fn fr_config_set_colors(
+    config: *mut FrConfig,
+    primary: u32,
+    secondary: u32,
+) -> i32 {
+    fn rgb(color: u32) -> RGB {
+        RGB::new((color >> 16) as u8, (color >> 8) as u8, color as u8)
+    }
+    with_config(config, |config| {
+        config.primary_color = rgb(primary);
+        config.secondary_color = rgb(secondary);
+        Ok(())
+    })
+}
+
+/// Renders `config` into `out`, as `width * height` RGB8 pixels in rows from the top left.
+///
+/// Fails with [`FR_INVALID_ARGUMENT`] if the config can't be rendered.
+///
+/// # Safety
+///
+/// `config` must be null or a valid config and `out` must be null or valid for writes of
+/// `out_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn fr_render(config: *const FrConfig, out: *mut u8, out_len: usize) -> i32 {
+    guard(|| {
+        let config = match config.as_ref() {
+            Some(config) => &config.0,
+            None => return Err((FR_NULL_POINTER, "the config is null".into())),
+        };
+        config
+            .validate()
+            .map_err(|err| (FR_INVALID_ARGUMENT, err.to_string()))?;
+        if out.is_null() {
+            return Err((FR_NULL_POINTER, "the output buffer is null".into()));
+        }
+        let len = crate::resources::image_bytes(config).ok_or_else(|| {
+            (
+                FR_INVALID_ARGUMENT,
+                "the image is too large to be addressed".to_owned(),
+            )
+        })?;
+        if out_len < len {
+            return Err((
+                FR_BUFFER_TOO_SMALL,
+                format!(
+                    "the image needs {} bytes, but the buffer is {}",
+                    len, out_len
+                ),
+            ));
+        }
+        let contents = crate::get_image(config);
+        let out = std::slice::from_raw_parts_mut(out, len);
+        for (pixel, out) in contents.iter().zip(out.chunks_exact_mut(3)) {
+            out.copy_from_slice(&[pixel.r, pixel.g, pixel.b]);
+        }
+        Ok(())
+    })
+}
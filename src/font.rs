@@ -0,0 +1,98 @@
+//! A tiny bitmap font, to draw text on images without any font files.
+//!
+//! Glyphs are 3×5 pixels. Letters are case insensitive.
+
+use crate::{Image, RGB};
+
+/// The width of a glyph.
+pub const GLYPH_WIDTH: usize = 3;
+/// The height of a glyph.
+pub const GLYPH_HEIGHT: usize = 5;
+/// The horizontal distance between the starts of two characters.
+pub const ADVANCE: usize = GLYPH_WIDTH + 1;
+
+/// The rows from the top, where the highest of the three bits is the left pixel.
+fn glyph(c: char) -> Option<[u8; 5]> {
+    Some(match c.to_ascii_lowercase() {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'a' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'b' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'c' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'd' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'e' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'f' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'g' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'h' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'i' => [0b010, 0b000, 0b010, 0b010, 0b010],
+        'j' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'k' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'l' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'm' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'n' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'o' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'p' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'r' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        's' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        't' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'u' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'v' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'w' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'x' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+        '×' => [0b000, 0b101, 0b010, 0b101, 0b000],
+        _ => return None,
+    })
+}
+
+/// The width in pixels of `text` drawn at `scale`.
+pub fn text_width(text: &str, scale: usize) -> usize {
+    match text.chars().count() {
+        0 => 0,
+        len => (len * ADVANCE - 1) * scale,
+    }
+}
+
+/// Draws `text` with its top left corner at (`x`, `y`), with every pixel of the font being
+/// `scale` pixels. Characters without a glyph are drawn as spaces.
+/// Parts outside of the image are clipped.
+pub fn draw_text(image: &mut Image, text: &str, x: usize, y: usize, scale: usize, color: RGB) {
+    for (index, c) in text.chars().enumerate() {
+        let glyph = match glyph(c) {
+            Some(glyph) => glyph,
+            None => continue,
+        };
+        let left = x + index * ADVANCE * scale;
+        for (row, bits) in glyph.iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if bits & (0b100 >> column) == 0 {
+                    continue;
+                }
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        let px = left + column * scale + dx;
+                        if let Some(pixel) = image.pixel_mut(px, y + row * scale + dy) {
+                            *pixel = color;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
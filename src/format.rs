@@ -0,0 +1,262 @@
+//! Image formats of the output, and how it's written.
+
+use crate::features::Feature;
+use std::fmt::{self, Display};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The output path which writes the image to stdout.
+pub const STDOUT: &str = "-";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Avif,
+    Png,
+    /// Binary PPM, which needs no encoder.
+    Ppm,
+}
+impl Format {
+    pub const ALL: [Self; 3] = [Self::Avif, Self::Png, Self::Ppm];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Avif => "avif",
+            Self::Png => "png",
+            Self::Ppm => "ppm",
+        }
+    }
+    /// The format with the (case insensitive) `extension`.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|format| format.extension().eq_ignore_ascii_case(extension))
+    }
+    /// The cargo feature with the encoder of the format, if it needs one.
+    pub fn feature(self) -> Option<Feature> {
+        match self {
+            Self::Avif => Some(Feature::Avif),
+            Self::Png => Some(Feature::Png),
+            Self::Ppm => None,
+        }
+    }
+    /// Whether this binary was built with the encoder of the format.
+    pub fn is_supported(self) -> bool {
+        self.feature().map_or(true, Feature::is_enabled)
+    }
+    /// The first format of [`Self::ALL`] this binary can write, the default of `--format`.
+    ///
+    /// ```
+    /// # use fractal_renderer::format::Format;
+    /// let preferred = Format::preferred();
+    /// assert!(preferred.is_supported());
+    /// if cfg!(feature = "avif") {
+    ///     assert_eq!(preferred, Format::Avif);
+    /// }
+    /// ```
+    pub fn preferred() -> Self {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|format| format.is_supported())
+            .unwrap_or(Self::Ppm)
+    }
+}
+impl Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.extension())
+    }
+}
+impl FromStr for Format {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_extension(s).ok_or_else(|| format!("unknown format {:?}", s))
+    }
+}
+
+/// How the encoders compress the image, see [`encode_image`](crate::encode_image). PNG and PPM
+/// are lossless and always the same.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EncoderSettings {
+    /// The speed of the AVIF encoder, from 1, the slowest and smallest, to 10.
+    pub avif_speed: u8,
+    /// The quality of AVIF images, from 0 to 100.
+    pub avif_quality: f32,
+}
+impl Default for EncoderSettings {
+    fn default() -> Self {
+        Self {
+            avif_speed: 8,
+            avif_quality: 100.0,
+        }
+    }
+}
+
+/// Resolves the path to write to and its format.
+///
+/// If `output` has a known extension, that format is used.
+/// Else, the extension of `format` is appended.
+/// [`STDOUT`] is kept as-is and uses `format`.
+///
+/// ```
+/// # use fractal_renderer::format::{output_path, Format, STDOUT};
+/// assert_eq!(output_path("out", Format::Avif), ("out.avif".into(), Format::Avif));
+/// assert_eq!(output_path("out", Format::Png), ("out.png".into(), Format::Png));
+/// // The extension wins over `format`, and is never added twice.
+/// assert_eq!(output_path("out.png", Format::Avif), ("out.png".into(), Format::Png));
+/// assert_eq!(output_path("OUT.PPM", Format::Avif), ("OUT.PPM".into(), Format::Ppm));
+/// assert_eq!(
+///     output_path("dir/out.avif", Format::Png),
+///     ("dir/out.avif".into(), Format::Avif)
+/// );
+/// // Unknown extensions are part of the name.
+/// assert_eq!(output_path("out.v2", Format::Png), ("out.v2.png".into(), Format::Png));
+/// assert_eq!(output_path("dir.png/out", Format::Ppm), ("dir.png/out.ppm".into(), Format::Ppm));
+/// assert_eq!(output_path(STDOUT, Format::Ppm), (STDOUT.into(), Format::Ppm));
+/// ```
+pub fn output_path(output: &str, format: Format) -> (String, Format) {
+    if output == STDOUT {
+        return (output.to_owned(), format);
+    }
+    match Path::new(output)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .and_then(Format::from_extension)
+    {
+        Some(format) => (output.to_owned(), format),
+        None => (format!("{}.{}", output, format.extension()), format),
+    }
+}
+
+/// What to do if the output file already exists, see [`write_atomically`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnConflict {
+    /// Fail, leaving the file untouched.
+    Error,
+    /// Write to the first free [`numbered`] path instead.
+    Rename,
+    Overwrite,
+}
+impl OnConflict {
+    pub const ALL: [Self; 3] = [Self::Error, Self::Rename, Self::Overwrite];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Rename => "rename",
+            Self::Overwrite => "overwrite",
+        }
+    }
+}
+impl Display for OnConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+impl FromStr for OnConflict {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|on_conflict| on_conflict.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown conflict handling {:?}", s))
+    }
+}
+
+/// `path` with `-n` appended to the file name, before the extension.
+///
+/// ```
+/// # use fractal_renderer::format::numbered;
+/// assert_eq!(numbered("renders/output.avif", 2), "renders/output-2.avif");
+/// assert_eq!(numbered("output", 3), "output-3");
+/// assert_eq!(numbered("renders.v2/output", 2), "renders.v2/output-2");
+/// ```
+pub fn numbered(path: &str, n: u32) -> String {
+    let name_start = path.rfind(|c| c == '/' || c == '\\').map_or(0, |i| i + 1);
+    match path[name_start..].rfind('.') {
+        Some(dot) if dot > 0 => {
+            let (stem, extension) = path.split_at(name_start + dot);
+            format!("{}-{}{}", stem, n, extension)
+        }
+        _ => format!("{}-{}", path, n),
+    }
+}
+
+/// Writes a file with `write`, so it's either written completely or not at all.
+///
+/// `write` writes to a temporary file next to `path`, which is renamed to `path` if it
+/// succeeds and removed if it fails. If `path` already exists, `on_conflict` decides what
+/// happens. Returns the path written to.
+///
+/// ```
+/// # use fractal_renderer::format::{numbered, write_atomically, OnConflict};
+/// # use std::io::Write;
+/// let dir = std::env::temp_dir().join(format!("fractal-renderer-{}", std::process::id()));
+/// std::fs::create_dir_all(&dir).unwrap();
+/// let path = dir.join("output.ppm").to_str().unwrap().to_owned();
+/// let write = |data: &'static [u8]| move |file: &mut std::fs::File| file.write_all(data);
+///
+/// assert_eq!(write_atomically(&path, OnConflict::Error, write(b"first")).unwrap(), path);
+/// let err = write_atomically(&path, OnConflict::Error, write(b"second")).unwrap_err();
+/// assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+/// let renamed = write_atomically(&path, OnConflict::Rename, write(b"second")).unwrap();
+/// assert_eq!(renamed, numbered(&path, 2));
+/// assert_eq!(std::fs::read(&path).unwrap(), b"first");
+///
+/// let failed = write_atomically(&path, OnConflict::Overwrite, |file| {
+///     file.write_all(b"half")?;
+///     Err(std::io::Error::new(std::io::ErrorKind::Other, "the encoder failed"))
+/// });
+/// assert!(failed.is_err());
+/// assert_eq!(std::fs::read(&path).unwrap(), b"first");
+/// // Only the two written files are left.
+/// assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+///
+/// write_atomically(&path, OnConflict::Overwrite, write(b"third")).unwrap();
+/// assert_eq!(std::fs::read(&path).unwrap(), b"third");
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+pub fn write_atomically(
+    path: &str,
+    on_conflict: OnConflict,
+    write: impl FnOnce(&mut fs::File) -> io::Result<()>,
+) -> io::Result<String> {
+    let destination = match on_conflict {
+        OnConflict::Error if Path::new(path).exists() => {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{:?} already exists", path),
+            ))
+        }
+        OnConflict::Rename if Path::new(path).exists() => (2..)
+            .map(|n| numbered(path, n))
+            .find(|path| !Path::new(path).exists())
+            .expect("there is a free number"),
+        _ => path.to_owned(),
+    };
+
+    // Unique within the process too, as the GUI writes screenshots on other threads.
+    static WRITES: AtomicUsize = AtomicUsize::new(0);
+    let tmp = format!(
+        "{}.{}-{}.tmp",
+        destination,
+        std::process::id(),
+        WRITES.fetch_add(1, Ordering::Relaxed)
+    );
+    let result = fs::File::create(&tmp).and_then(|mut file| {
+        write(&mut file)?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&tmp, &destination)
+    });
+    if let Err(err) = result {
+        // The file may not even have been created.
+        let _ = fs::remove_file(&tmp);
+        return Err(err);
+    }
+    Ok(destination)
+}
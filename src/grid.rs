@@ -0,0 +1,227 @@
+//! Contact sheets of many Julia sets, to find interesting constants.
+
+use crate::{font, ComplexRect, Config, Image, Imaginary, RGB};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JuliaGrid {
+    pub columns: u32,
+    pub rows: u32,
+    /// The Julia constants are sampled from the centers of `columns`×`rows` cells in this rectangle.
+    pub bounds: ComplexRect,
+    /// Write the constant of each cell in its corner, see [`label`].
+    pub labels: bool,
+}
+impl JuliaGrid {
+    /// The Julia constant of the cell at `column` and `row`.
+    ///
+    /// `row` 0 has the smallest imaginary part, as in the rendered fractals.
+    pub fn constant(&self, column: u32, row: u32) -> Imaginary {
+        Imaginary {
+            re: self.bounds.min.re
+                + (column as f64 + 0.5) / self.columns as f64 * self.bounds.width(),
+            im: self.bounds.min.im + (row as f64 + 0.5) / self.rows as f64 * self.bounds.height(),
+        }
+    }
+}
+
+/// A path through the Julia constants, to compare the Julia sets along it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sweep {
+    /// Counterclockwise around `center`, starting right of it.
+    Circle { center: Imaginary, radius: f64 },
+    /// From `from` to `to`.
+    Line { from: Imaginary, to: Imaginary },
+}
+impl Sweep {
+    /// `count` constants evenly spaced along the path. The line includes both ends, while the
+    /// circle doesn't come back to where it started.
+    ///
+    /// ```
+    /// # use fractal_renderer::{grid::Sweep, Imaginary};
+    /// let line = Sweep::Line {
+    ///     from: Imaginary { re: -1.0, im: 0.0 },
+    ///     to: Imaginary { re: 1.0, im: 0.5 },
+    /// };
+    /// let constants = line.constants(3);
+    /// assert_eq!(constants[1], Imaginary { re: 0.0, im: 0.25 });
+    /// assert_eq!(constants[2], Imaginary { re: 1.0, im: 0.5 });
+    ///
+    /// let circle = Sweep::Circle {
+    ///     center: Imaginary::ZERO,
+    ///     radius: 0.5,
+    /// };
+    /// let constants = circle.constants(4);
+    /// assert_eq!(constants[0], Imaginary { re: 0.5, im: 0.0 });
+    /// assert!((constants[1].im - 0.5).abs() < 1e-12);
+    /// assert!((constants[3].im + 0.5).abs() < 1e-12);
+    /// ```
+    pub fn constants(&self, count: usize) -> Vec<Imaginary> {
+        (0..count)
+            .map(|i| match *self {
+                Self::Circle { center, radius } => {
+                    let angle = i as f64 / count as f64 * std::f64::consts::TAU;
+                    Imaginary {
+                        re: center.re + radius * angle.cos(),
+                        im: center.im + radius * angle.sin(),
+                    }
+                }
+                Self::Line { from, to } => {
+                    let t = i as f64 / (count - 1).max(1) as f64;
+                    Imaginary {
+                        re: from.re + (to.re - from.re) * t,
+                        im: from.im + (to.im - from.im) * t,
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+/// A cell of a contact sheet, see [`cells`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub column: usize,
+    pub row: usize,
+    /// The top left corner of the cell in the sheet.
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// The `columns`×`rows` cells of a `width`×`height` contact sheet, row by row, separated by thin
+/// lines.
+pub fn cells(width: usize, height: usize, columns: usize, rows: usize) -> Vec<Cell> {
+    let separator = (width.min(height) / 250).max(1);
+    // Start and end (exclusive) of cell `i` out of `cells` along an axis of length `len`.
+    let span = |i: usize, cells: usize, len: usize| {
+        let start = i * (len + separator) / cells;
+        let end = (i + 1) * (len + separator) / cells - separator;
+        (start, end.max(start + 1))
+    };
+    (0..columns * rows)
+        .map(|i| {
+            let (column, row) = (i % columns, i / columns);
+            let (x0, x1) = span(column, columns, width);
+            let (y0, y1) = span(row, rows, height);
+            Cell {
+                column,
+                row,
+                x: x0,
+                y: y0,
+                width: x1 - x0,
+                height: y1 - y0,
+            }
+        })
+        .collect()
+}
+
+/// Renders `cells` in parallel with `render`, which returns the `cell.width`×`cell.height`
+/// contents of a cell, and composes them into a black `width`×`height` image.
+pub fn compose(
+    width: usize,
+    height: usize,
+    cells: &[Cell],
+    render: impl Fn(&Cell) -> Vec<RGB> + Sync,
+) -> Vec<RGB> {
+    let rendered: Vec<_> = cells.into_par_iter().map(&render).collect();
+
+    let mut contents = vec![RGB::new(0, 0, 0); width * height];
+    let mut image = Image::new(&mut contents, width, height);
+    for (cell, mut rendered) in cells.iter().zip(rendered) {
+        let rendered = Image::new(&mut rendered, cell.width, cell.height);
+        image.blit(&rendered, cell.x, cell.y);
+    }
+    contents
+}
+
+/// Renders the Julia sets of `constants` into a image of `config.width`×`config.height`, in
+/// `columns` and as many rows as they need, row by row.
+///
+/// `config` is used as a template for every cell; the cells are separated by thin black lines.
+pub fn render_constants(config: &Config, columns: usize, constants: &[Imaginary]) -> Vec<RGB> {
+    let width = config.width as usize;
+    let height = config.height as usize;
+    let columns = columns.max(1);
+    let rows = ((constants.len() + columns - 1) / columns).max(1);
+    let mut cells = cells(width, height, columns, rows);
+    cells.truncate(constants.len());
+    compose(width, height, &cells, |cell| {
+        let config = Config {
+            width: cell.width as u32,
+            height: cell.height as u32,
+            julia: Some(constants[cell.row * columns + cell.column]),
+            ..config.clone()
+        };
+        crate::get_image(&config)
+    })
+}
+
+/// The padding around the text of [`label`], in pixels of the font.
+const LABEL_PADDING: usize = 1;
+
+/// Writes `constant` in the bottom left corner of `cell` of `image`, white on black. The text
+/// grows with the cell; labels which don't fit in it are skipped.
+///
+/// ```
+/// # use fractal_renderer::{grid::{cells, label}, Image, Imaginary, RGB};
+/// let mut contents = vec![RGB::new(0, 0, 128); 200 * 100];
+/// let mut image = Image::new(&mut contents, 200, 100);
+/// let cells = cells(200, 100, 2, 1);
+/// label(&mut image, &cells[0], Imaginary { re: -0.8, im: 0.156 });
+/// let white = RGB::new(255, 255, 255);
+/// // The first cell is labeled at its bottom, the second isn't touched.
+/// assert!(contents[50 * 200..].iter().any(|pixel| *pixel == white));
+/// assert!(!contents[..50 * 200].contains(&white));
+/// assert!(contents.iter().skip(100).step_by(200).all(|pixel| *pixel == RGB::new(0, 0, 128)));
+/// ```
+pub fn label(image: &mut Image, cell: &Cell, constant: Imaginary) {
+    let text = format!("{:.3}{:+.3}i", constant.re, constant.im);
+    let scale = (cell.width.min(cell.height) / 150).max(1);
+    let padding = LABEL_PADDING * scale;
+    let width = font::text_width(&text, scale) + padding * 2;
+    let height = font::GLYPH_HEIGHT * scale + padding * 2;
+    if width > cell.width || height > cell.height {
+        return;
+    }
+    let top = cell.y + cell.height - height;
+    for y in top..top + height {
+        for x in cell.x..cell.x + width {
+            if let Some(pixel) = image.pixel_mut(x, y) {
+                *pixel = RGB::new(0, 0, 0);
+            }
+        }
+    }
+    let white = RGB::new(255, 255, 255);
+    font::draw_text(image, &text, cell.x + padding, top + padding, scale, white);
+}
+
+/// Renders the Julia sets of `grid` into a image of `config.width`×`config.height`, see
+/// [`render_constants`]. The constant of each cell is logged, and written in it with
+/// [`JuliaGrid::labels`].
+pub fn render(config: &Config, grid: &JuliaGrid) -> Vec<RGB> {
+    let (width, height) = (config.width as usize, config.height as usize);
+    let (columns, rows) = (grid.columns as usize, grid.rows as usize);
+    let cells = cells(width, height, columns, rows);
+    let constant = |cell: &Cell| grid.constant(cell.column as u32, cell.row as u32);
+    let constants: Vec<_> = cells.iter().map(constant).collect();
+    let mut contents = render_constants(config, columns, &constants);
+    if grid.labels {
+        let mut image = Image::new(&mut contents, width, height);
+        for cell in &cells {
+            label(&mut image, cell, constant(cell));
+        }
+    }
+    for cell in &cells {
+        let constant = constant(cell);
+        log::debug!(
+            "Cell at ({}, {}): {} + {}i",
+            cell.x,
+            cell.y,
+            constant.re,
+            constant.im
+        );
+    }
+    contents
+}
@@ -1,6 +1,16 @@
-use crate::{Algo, Config, Options};
+use crate::aa::{self, AaOptions};
+use crate::color::{self, Cvd};
+use crate::grid::Sweep;
+use crate::palette::{self, Palette};
+use crate::render;
+use crate::session::{self, SessionLog};
+use crate::view::{self, View};
+use crate::{
+    mapping, Algo, Config, Imaginary, InteriorMapping, Options, Rotation, SmoothMode, RGB,
+};
 use std::cmp;
-use std::sync::atomic::AtomicBool;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{mpsc, Arc, Mutex};
 
 use eframe::{egui, epi};
@@ -24,14 +34,202 @@ impl Ord for F32Ord {
     }
 }
 
+/// How long a toast is shown, in seconds. It fades out during the last [`TOAST_FADE`].
+const TOAST_DURATION: f64 = 1.0;
+const TOAST_FADE: f64 = 0.3;
+
+/// How long a view has to stay the same to be added to the session log, in seconds.
+const SESSION_SETTLE: f64 = 1.0;
+/// How long the window has to keep it's size before rendering at it, in seconds.
+const RESIZE_SETTLE: f64 = 0.3;
+/// How many pixels the size of the image on screen has to change by for match window mode to
+/// render at it, so the rounding of fractional sizes doesn't render again and again. The image
+/// is also shown at its own size when it's this close to fitting, see [`fit_to_pixels`].
+const RESIZE_THRESHOLD: f32 = 1.0;
+/// How much the pixels per point have to change to count as a new display, for example when the
+/// window is dragged to a screen with another scale factor.
+const SCALE_THRESHOLD: f32 = 0.01;
+/// How long a render has to be on screen before its export is previewed, in seconds, so moving
+/// around doesn't start an encode for every view.
+const PREVIEW_SETTLE: f64 = 0.5;
+/// The threshold of adaptive AA when it's turned on in the GUI, see [`aa::AaOptions`].
+const AA_THRESHOLD: u8 = 24;
+/// The height of the thumbnails of the session panel.
+const THUMBNAIL_HEIGHT: u32 = 36;
+/// Thumbnails are rendered this many times larger, then shrunk, so thin filaments stay visible.
+const THUMBNAIL_OVERSAMPLING: u32 = 4;
+/// The size of the thumbnails of the Julia sweep.
+const SWEEP_THUMBNAIL_SIZE: (u32, u32) = (160, 100);
+/// The paths the sweep starts with, when choosing between a circle and a line. The circle goes
+/// through many of the well known Julia sets.
+const DEFAULT_SWEEP_CIRCLE: Sweep = Sweep::Circle {
+    center: Imaginary::ZERO,
+    radius: 0.7885,
+};
+const DEFAULT_SWEEP_LINE: Sweep = Sweep::Line {
+    from: Imaginary {
+        re: -0.8,
+        im: 0.156,
+    },
+    to: Imaginary {
+        re: 0.285,
+        im: 0.01,
+    },
+};
+
+/// The range of [`Config::scale`] scrolling zooms within. Further out, the fractal is a dot, and
+/// further in, even double-double precision shows blocks.
+const SCALE_RANGE: (f64, f64) = (1e-3, crate::location::DOUBLE_DOUBLE_MAX_ZOOM);
+
+/// The color of widgets which cause a problem, like a slider which clips the image.
+const WARNING_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 170, 0);
+
+/// The sizes of the resolution menu.
+const RESOLUTION_PRESETS: [(u32, u32); 4] = [(1280, 720), (1920, 1080), (2560, 1440), (3840, 2160)];
+
+/// What a render shows: the options, the view and whether it shows the samples of adaptive AA.
+type Shown = (Options, View, bool);
+
+/// A finished tile of a render, see [`crate::get_samples_tiled`], drawn over the previous image
+/// until the render is done.
+struct Chunk {
+    /// The number of the render. Tiles of renders older than the image on screen are discarded.
+    generation: u64,
+    /// The size of the whole image.
+    size: [usize; 2],
+    tile: crate::grid::Cell,
+    pixels: Vec<egui::Color32>,
+}
+
+/// What a thumbnail shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum ThumbnailId {
+    /// The entry of the session log with this id.
+    Session(usize),
+    /// The Julia set of a constant of the sweep, see [`App::sweep_base`].
+    Sweep {
+        generation: u64,
+        /// The bits of the real and imaginary parts of the constant.
+        constant: (u64, u64),
+    },
+}
+
+/// A finished render.
+struct Rendered {
+    /// See [`Chunk::generation`].
+    generation: u64,
+    image: egui::ColorImage,
+    /// A copy of `image` for the texture, made on the render thread so the GUI doesn't stall
+    /// copying a large image.
+    upload: egui::ColorImage,
+    /// The iterations to suggest if it needs more, see [`render::needs_more_iterations`].
+    suggested_iterations: Option<u32>,
+    /// Only of [`View::Color`]; the other views don't depend on the exposure.
+    clipping: render::Clipping,
+    /// The samples and the config they're of, for the spot meter. [`None`] for the fern.
+    samples: Option<(Config, Arc<Vec<crate::Sample>>)>,
+    /// See [`App::shown`].
+    shown: Shown,
+}
+
+/// The size of an encoded image and the decoded image, if this binary can decode the format.
+type Encoded<I> = Result<(usize, Option<I>), String>;
+
+/// A render after encoding it in the format of the output and decoding it again, to see what the
+/// export loses.
+struct Preview {
+    /// The generation of the render, see [`Chunk::generation`].
+    generation: u64,
+    result: Encoded<egui::ColorImage>,
+}
+
 struct App {
     state: Options,
+    view: View,
+    /// Show how many samples adaptive AA took of each pixel instead of the render, see
+    /// [`aa::counts_image`].
+    show_aa_samples: bool,
+    /// Change the height with the width and the other way around, keeping the aspect ratio.
+    lock_aspect: bool,
+    /// Render at the size of the image on screen times [`Self::render_scale`], instead of the
+    /// size of the config.
+    match_window: bool,
+    render_scale: f32,
+    /// The size to render at in match window mode and the time it was last changed.
+    pending_size: Option<((u32, u32), f64)>,
+    /// The pixels per point of the display the window was last on, 0 before the first frame.
+    pixels_per_point: f32,
+    /// The message of the last shortcut and the time it was used.
+    toast: Option<(String, f64)>,
+    /// The index of the stop of the palette being edited.
+    selected_stop: Option<usize>,
+    /// The palette string or `.map`/`.ggr` file to import from or export to.
+    palette_source: String,
+    session: Option<SessionLog>,
+    show_session: bool,
+    /// The config which isn't logged yet and the time it was last changed.
+    pending_log: Option<(Config, f64)>,
+    /// The thumbnails of the session log and the sweep. The config sent to the worker has the
+    /// size of the thumbnail.
+    thumbnails: HashMap<ThumbnailId, egui::TextureHandle>,
+    requested_thumbnails: HashSet<ThumbnailId>,
+    thumbnail_channel: mpsc::Sender<(ThumbnailId, Config, epi::Frame)>,
+    rendered_thumbnails: mpsc::Receiver<(ThumbnailId, egui::ColorImage)>,
+    show_sweep: bool,
+    sweep: Sweep,
+    /// The number of thumbnails along [`Self::sweep`].
+    sweep_count: usize,
+    /// The generation of the sweep thumbnails and the config they're rendered with, but for the
+    /// constant. A new generation starts when the settings of the main view change, and the
+    /// thumbnails of older generations are dropped. Moving around the main view keeps them.
+    sweep_base: (u64, Config),
     gui_on: bool,
-    image: Arc<Mutex<Option<egui::ColorImage>>>,
+    /// The last finished render.
+    image: Arc<Mutex<Option<Rendered>>>,
+    chunks: mpsc::Receiver<Chunk>,
+    /// The generation of the last finished render on screen.
+    completed: u64,
+    /// The image in the texture, with the tiles of the render in progress.
+    displayed: Option<egui::ColorImage>,
+    /// What [`Self::displayed`] shows and the generation it's of. Until the next render is done,
+    /// the image is scaled and cropped to what it would look like, see [`approximation`].
+    shown: Option<(u64, Shown)>,
+    /// The generation of the last requested render and what it shows.
+    requested: (u64, Option<Shown>),
     texture: Option<(egui::TextureHandle, eframe::egui::Vec2)>,
+    /// Show the image as it looks with this deficiency. Only the texture is simulated, so it
+    /// doesn't change the exported images.
+    simulate_cvd: Option<Cvd>,
+    /// The deficiency the texture is simulated with.
+    uploaded_cvd: Option<Cvd>,
+    /// The iterations to suggest for the render on screen, if it has too few.
+    suggested_iterations: Option<u32>,
+    /// The clipping of the render on screen, to warn at the sliders which cause it.
+    clipping: render::Clipping,
+    /// The time [`Self::completed`] was put on screen.
+    completed_at: f64,
+    /// The samples of the render on screen, see [`Rendered::samples`].
+    samples: Option<(Config, Arc<Vec<crate::Sample>>)>,
+    /// Clicking the image sets the exposure, see [`render::metered_exposure`].
+    metering: bool,
+    /// The color around the image where it doesn't cover the window, and of what's not rendered
+    /// yet.
+    letterbox: egui::Color32,
+    /// Show the render as it's exported, see [`Preview`].
+    preview_export: bool,
+    preview_channel: mpsc::Sender<(u64, Options, egui::ColorImage, epi::Frame)>,
+    previews: mpsc::Receiver<Preview>,
+    /// The generation of the render to preview, or 0 for none. The encoder skips the requests and
+    /// drops the previews of other generations, which cancels them.
+    preview_wanted: Arc<AtomicU64>,
+    /// The last preview, as a texture.
+    preview: Option<(u64, Encoded<egui::TextureHandle>)>,
     working: Arc<AtomicBool>,
-    redraw_channel: mpsc::Sender<(Config, epi::Frame)>,
+    redraw_channel: mpsc::Sender<(Options, View, bool, Option<egui::Vec2>, epi::Frame)>,
     try_redraw: bool,
+    /// The last position of the pointer over the image, relative to it's size. Renders start
+    /// there, or at the center without one.
+    focus: Option<egui::Vec2>,
 }
 impl App {
     fn request_redraw(&mut self, frame: epi::Frame) {
@@ -42,56 +240,331 @@ impl App {
         self.try_redraw = false;
         self.working
             .store(true, std::sync::atomic::Ordering::SeqCst);
+        // The worker counts the generations the same way.
+        self.requested = (
+            self.requested.0 + 1,
+            Some((self.state.clone(), self.view, self.show_aa_samples)),
+        );
         self.redraw_channel
-            .send((self.state.config.clone(), frame))
+            .send((
+                self.state.clone(),
+                self.view,
+                self.show_aa_samples,
+                self.focus,
+                frame,
+            ))
             .unwrap();
     }
-    fn new(options: Options) -> Self {
-        let (redraw_channel, rx) = mpsc::channel::<(Config, epi::Frame)>();
+    fn new(mut options: Options) -> Self {
+        use_fern_density(&mut options.config);
+        let (redraw_channel, rx) =
+            mpsc::channel::<(Options, View, bool, Option<egui::Vec2>, epi::Frame)>();
 
         let image = Arc::new(Mutex::new(None));
         let image_handle = Arc::clone(&image);
+        let (chunk_tx, chunks) = mpsc::channel();
+        // Sent to from the threads of the render.
+        let chunk_tx = Mutex::new(chunk_tx);
         let working = Arc::new(AtomicBool::new(false));
         let working_handle = Arc::clone(&working);
         std::thread::spawn(move || {
             let thread_poll = rayon::ThreadPoolBuilder::new().build().unwrap();
 
-            while let Ok((config, frame)) = rx.recv() {
-                let contents = thread_poll.install(|| crate::get_image(&config));
+            // The samples of the last config, so changing the view or only the colors of the
+            // config recolors them, see `view::recolors`.
+            let mut cache: Option<(Config, Arc<Vec<crate::Sample>>)> = None;
+            let mut generation = 0;
 
-                #[allow(clippy::unsound_collection_transmute)]
-                let mut image_rgb_contents: Vec<u8> = unsafe { std::mem::transmute(contents) };
-                unsafe { image_rgb_contents.set_len(image_rgb_contents.len() * 3) };
-
-                let image_buffer: image::RgbImage =
-                    image::ImageBuffer::from_raw(config.width, config.height, image_rgb_contents)
-                        .unwrap();
-
-                let size = [image_buffer.width() as _, image_buffer.height() as _];
-                let image_buffer = image::DynamicImage::ImageRgb8(image_buffer);
-                let image_buffer = image_buffer.to_rgba8();
-                let pixels = image_buffer.as_flat_samples();
+            while let Ok((options, view, show_aa_samples, focus, frame)) = rx.recv() {
+                generation += 1;
+                let config = &options.config;
+                let mut needs_more_iterations = false;
+                let mut clipping = render::Clipping::default();
+                let mut rendered_samples = None;
+                let mut image_buffer = if options.boundary_preview {
+                    let contents =
+                        thread_poll.install(|| crate::get_julia_boundary_image(config, None));
+                    crate::ImageBuffer::from_contents(
+                        contents,
+                        config.width as usize,
+                        config.height as usize,
+                    )
+                    .expect("the plotted image has the size of the config")
+                } else if let Algo::BarnsleyFern = config.algo {
+                    match thread_poll.install(|| crate::render(config)) {
+                        Ok(output) => {
+                            clipping = output.stats.clipping;
+                            output.image
+                        }
+                        Err(err) => {
+                            log::error!("{}", err);
+                            working_handle.store(false, std::sync::atomic::Ordering::SeqCst);
+                            continue;
+                        }
+                    }
+                } else {
+                    let palette = options.palette.as_ref();
+                    let size = [config.width as usize, config.height as usize];
+                    // The auto-contrast of the iterations view is per tile until the render is done.
+                    let send_tile = |tile, samples: &[crate::Sample]| {
+                        let pixels = view::colorize(samples, config, view, palette)
+                            .iter()
+                            .map(|pixel| egui::Color32::from_rgb(pixel.r, pixel.g, pixel.b))
+                            .collect();
+                        let chunk = Chunk {
+                            generation,
+                            size,
+                            tile,
+                            pixels,
+                        };
+                        // The receiver is only gone when the app is closing.
+                        if chunk_tx.lock().unwrap().send(chunk).is_ok() {
+                            frame.request_repaint();
+                        }
+                    };
+                    // The tiles closest to where the user looks first.
+                    let focus = focus.unwrap_or_else(|| egui::Vec2::splat(0.5));
+                    let focus = (
+                        focus.x as f64 * config.width as f64,
+                        focus.y as f64 * config.height as f64,
+                    );
+                    let distance = |tile: &crate::grid::Cell| {
+                        let x = tile.x as f64 + tile.width as f64 / 2.0 - focus.0;
+                        let y = tile.y as f64 + tile.height as f64 / 2.0 - focus.1;
+                        x.hypot(y)
+                    };
+                    let samples =
+                        match cache.take() {
+                            Some((cached, samples)) if view::recolors(&cached, config) => samples,
+                            _ => Arc::new(thread_poll.install(|| {
+                                crate::get_samples_tiled_by(config, distance, send_tile)
+                            })),
+                        };
+                    let escapes: Vec<Option<f32>> = samples
+                        .iter()
+                        .map(|sample| sample.escaped.then(|| sample.iterations as f32))
+                        .collect();
+                    let starved = thread_poll.install(|| {
+                        render::starved_fraction(&escapes, config.width as usize, config.iterations)
+                    });
+                    needs_more_iterations = render::needs_more_iterations(starved);
+                    let mut contents =
+                        thread_poll.install(|| view::colorize(&samples, config, view, palette));
+                    if view == View::Color {
+                        // Like the stats of a render, without the inside.
+                        clipping = render::clipping(
+                            contents
+                                .iter()
+                                .zip(&escapes)
+                                .filter_map(|(color, escape)| escape.map(|_| color)),
+                        );
+                        if let Some(aa) = &options.aa {
+                            let antialiased = thread_poll.install(|| {
+                                aa::apply(
+                                    &mut contents,
+                                    config,
+                                    palette,
+                                    aa.threshold,
+                                    show_aa_samples,
+                                )
+                            });
+                            if let Some(counts) = antialiased.counts {
+                                contents = aa::counts_image(&counts);
+                            }
+                        }
+                    }
+                    rendered_samples = Some((config.clone(), Arc::clone(&samples)));
+                    cache = Some((config.clone(), samples));
+                    crate::ImageBuffer::from_contents(
+                        contents,
+                        config.width as usize,
+                        config.height as usize,
+                    )
+                    .expect("the rendered image has the size of the config")
+                };
+                crate::draw_overlays(&options, &mut image_buffer.as_image());
 
-                let color_image = egui::ColorImage::from_rgba_unmultiplied(size, pixels.as_slice());
+                // Convert straight to the pixels of the texture, which are always opaque.
+                let size = [image_buffer.width(), image_buffer.height()];
+                let pixels = image_buffer
+                    .contents()
+                    .iter()
+                    .map(|pixel| egui::Color32::from_rgb(pixel.r, pixel.g, pixel.b))
+                    .collect();
+                let color_image = egui::ColorImage { size, pixels };
                 {
                     let mut lock = image_handle.lock().unwrap();
-                    *lock = Some(color_image);
+                    *lock = Some(Rendered {
+                        generation,
+                        upload: color_image.clone(),
+                        image: color_image,
+                        suggested_iterations: needs_more_iterations
+                            .then(|| render::suggested_iterations(config)),
+                        clipping,
+                        samples: rendered_samples,
+                        shown: (options, view, show_aa_samples),
+                    });
                 }
                 working_handle.store(false, std::sync::atomic::Ordering::SeqCst);
                 frame.request_repaint();
             }
 
-            println!("Shutting rendering down.");
+            log::debug!("Shutting rendering down.");
+        });
+
+        let (thumbnail_channel, thumbnail_rx) =
+            mpsc::channel::<(ThumbnailId, Config, epi::Frame)>();
+        let (thumbnail_tx, rendered_thumbnails) = mpsc::channel();
+        std::thread::spawn(move || {
+            while let Ok((id, mut config, frame)) = thumbnail_rx.recv() {
+                let size = [config.width as usize, config.height as usize];
+                config.width *= THUMBNAIL_OVERSAMPLING;
+                config.height *= THUMBNAIL_OVERSAMPLING;
+                let image = crate::ImageBuffer::from_contents(
+                    crate::get_image(&config),
+                    config.width as usize,
+                    config.height as usize,
+                )
+                .expect("the render has the size of the config")
+                .resized(size[0], size[1], crate::resize::Filter::Lanczos3);
+                let pixels = image
+                    .contents()
+                    .iter()
+                    .map(|pixel| egui::Color32::from_rgb(pixel.r, pixel.g, pixel.b))
+                    .collect();
+                if thumbnail_tx
+                    .send((id, egui::ColorImage { size, pixels }))
+                    .is_err()
+                {
+                    break;
+                }
+                frame.request_repaint();
+            }
         });
 
+        let (preview_channel, preview_rx) =
+            mpsc::channel::<(u64, Options, egui::ColorImage, epi::Frame)>();
+        let (preview_tx, previews) = mpsc::channel();
+        let preview_wanted = Arc::new(AtomicU64::new(0));
+        let wanted = Arc::clone(&preview_wanted);
+        std::thread::spawn(move || {
+            while let Ok(mut request) = preview_rx.recv() {
+                // Only the latest request is encoded.
+                while let Ok(newer) = preview_rx.try_recv() {
+                    request = newer;
+                }
+                let (generation, options, image, frame) = request;
+                // An encode which already started can't be stopped, but its preview is dropped.
+                let cancelled = || wanted.load(Ordering::SeqCst) != generation;
+                if cancelled() {
+                    continue;
+                }
+                let contents = image
+                    .pixels
+                    .iter()
+                    .map(|pixel| RGB::new(pixel.r(), pixel.g(), pixel.b()))
+                    .collect();
+                let image =
+                    crate::ImageBuffer::from_contents(contents, image.size[0], image.size[1])
+                        .expect("the texture has the size of its pixels");
+                let start = std::time::Instant::now();
+                let result =
+                    crate::encode_image(&image, options.output.format, &options.output.encoder)
+                        .map(|data| {
+                            let decoded = crate::diff::decode(&data).ok().map(|image| {
+                                let pixels = image
+                                    .contents()
+                                    .iter()
+                                    .map(|pixel| egui::Color32::from_rgb(pixel.r, pixel.g, pixel.b))
+                                    .collect();
+                                egui::ColorImage {
+                                    size: [image.width(), image.height()],
+                                    pixels,
+                                }
+                            });
+                            (data.len(), decoded)
+                        })
+                        .map_err(|err| err.to_string());
+                if cancelled() {
+                    log::debug!("Dropped the cancelled preview of render {}.", generation);
+                    continue;
+                }
+                log::debug!(
+                    "Previewed the {} export in {:.2?}.",
+                    options.output.format,
+                    start.elapsed()
+                );
+                if preview_tx.send(Preview { generation, result }).is_err() {
+                    break;
+                }
+                frame.request_repaint();
+            }
+        });
+
+        let session = if options.session_log {
+            match SessionLog::open_today() {
+                Ok(session) => {
+                    log::info!("Logging the session to {:?}.", session.path());
+                    Some(session)
+                }
+                Err(err) => {
+                    log::error!("Failed to open the session log: {}", err);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let simulate_cvd = options.simulate_cvd;
         Self {
             state: options,
+            view: View::default(),
+            show_aa_samples: false,
+            lock_aspect: true,
+            match_window: false,
+            render_scale: 1.0,
+            pending_size: None,
+            pixels_per_point: 0.0,
+            toast: None,
+            selected_stop: None,
+            palette_source: String::new(),
+            session,
+            show_session: false,
+            pending_log: None,
+            thumbnails: HashMap::new(),
+            requested_thumbnails: HashSet::new(),
+            thumbnail_channel,
+            rendered_thumbnails,
+            show_sweep: false,
+            sweep: DEFAULT_SWEEP_CIRCLE,
+            sweep_count: 40,
+            sweep_base: (0, Config::new_julia(Algo::Mandelbrot, Imaginary::ZERO)),
             gui_on: true,
             image,
+            chunks,
+            completed: 0,
+            displayed: None,
+            shown: None,
+            requested: (0, None),
             texture: None,
+            simulate_cvd,
+            uploaded_cvd: None,
+            suggested_iterations: None,
+            clipping: render::Clipping::default(),
+            completed_at: 0.0,
+            samples: None,
+            metering: false,
+            letterbox: egui::Color32::BLACK,
+            preview_export: false,
+            preview_channel,
+            previews,
+            preview_wanted,
+            preview: None,
             working,
             redraw_channel,
             try_redraw: false,
+            focus: None,
         }
     }
 }
@@ -107,15 +580,144 @@ impl epi::App for App {
             ctx: &egui::Context,
             frame: &epi::Frame,
         ) -> Option<(egui::TextureHandle, egui::Vec2)> {
+            // The whole image of a finished render, whether all of the texture has to be replaced
+            // and the part of it the tiles since the last frame cover.
+            let mut upload = None;
+            let mut whole = false;
+            let mut dirty: Option<[usize; 4]> = None;
             let img = { app.image.lock().unwrap().take() };
-            if let Some(img) = img {
-                let size = img.size;
-                let handle = ctx.load_texture("main fractal", img);
-                app.texture = Some((handle, egui::Vec2::new(size[0] as _, size[1] as _)));
+            if let Some(rendered) = img {
+                app.completed = rendered.generation;
+                app.suggested_iterations = rendered.suggested_iterations;
+                app.clipping = rendered.clipping;
+                app.samples = rendered.samples;
+                app.completed_at = ctx.input().time;
+                app.displayed = Some(rendered.image);
+                app.shown = Some((rendered.generation, rendered.shown));
+                upload = Some(rendered.upload);
                 if app.try_redraw {
                     app.request_redraw(frame.clone());
                 }
             }
+            let letterbox = app.letterbox;
+            while let Ok(chunk) = app.chunks.try_recv() {
+                if chunk.generation <= app.completed {
+                    continue;
+                }
+                // The first tile of a render. Its tiles are drawn over the previous image as it
+                // looks in the new view, instead of over the part of the plane it used to show.
+                if matches!(app.shown, Some((generation, _)) if generation < chunk.generation) {
+                    if let (Some((_, shown)), (_, Some(target)), Some(displayed)) =
+                        (&app.shown, &app.requested, &mut app.displayed)
+                    {
+                        let (options, target_view, show_aa_samples) = target;
+                        if let Some(approximation) =
+                            approximation(shown, options, *target_view, *show_aa_samples)
+                        {
+                            *displayed = egui::ColorImage {
+                                size: chunk.size,
+                                pixels: view::approximate_image(
+                                    &displayed.pixels,
+                                    displayed.size,
+                                    &approximation,
+                                    chunk.size,
+                                    letterbox,
+                                ),
+                            };
+                            whole = true;
+                        }
+                    }
+                    app.shown = app
+                        .requested
+                        .1
+                        .clone()
+                        .map(|target| (chunk.generation, target));
+                }
+                // Draw over the previous image, unless the size changed.
+                let displayed = match &mut app.displayed {
+                    Some(displayed) if displayed.size == chunk.size => displayed,
+                    displayed => {
+                        whole = true;
+                        displayed.insert(egui::ColorImage {
+                            size: chunk.size,
+                            pixels: vec![letterbox; chunk.size[0] * chunk.size[1]],
+                        })
+                    }
+                };
+                let tile = chunk.tile;
+                for (y, row) in chunk.pixels.chunks(tile.width).enumerate() {
+                    let start = (tile.y + y) * chunk.size[0] + tile.x;
+                    displayed.pixels[start..start + tile.width].copy_from_slice(row);
+                }
+                let (right, bottom) = (tile.x + tile.width, tile.y + tile.height);
+                dirty = Some(match dirty {
+                    Some([x, y, x2, y2]) => {
+                        [x.min(tile.x), y.min(tile.y), x2.max(right), y2.max(bottom)]
+                    }
+                    None => [tile.x, tile.y, right, bottom],
+                });
+            }
+            if app.uploaded_cvd != app.simulate_cvd {
+                app.uploaded_cvd = app.simulate_cvd;
+                whole = true;
+            }
+            if let Some(img) = &app.displayed {
+                let start = std::time::Instant::now();
+                let size = egui::Vec2::new(img.size[0] as _, img.size[1] as _);
+                let cvd = app.simulate_cvd;
+                let for_texture = |image: egui::ColorImage| match cvd {
+                    Some(cvd) => simulated(&image, cvd),
+                    None => image,
+                };
+                let same_size =
+                    matches!(&app.texture, Some((_, texture_size)) if *texture_size == size);
+                // Tiles drawn over a finished render aren't in its copy.
+                let delta = if whole || (dirty.is_some() && (upload.is_some() || !same_size)) {
+                    Some(egui::epaint::ImageDelta::full(match cvd {
+                        Some(cvd) => simulated(img, cvd),
+                        None => img.clone(),
+                    }))
+                } else if let Some(upload) = upload {
+                    Some(egui::epaint::ImageDelta::full(for_texture(upload)))
+                } else {
+                    // Only the part of the texture the new tiles cover. egui keeps one update per
+                    // texture and frame, so the tiles are uploaded together.
+                    dirty.map(|[x, y, right, bottom]| {
+                        let pixels = img
+                            .pixels
+                            .chunks(img.size[0])
+                            .skip(y)
+                            .take(bottom - y)
+                            .flat_map(|row| &row[x..right])
+                            .copied()
+                            .collect();
+                        let region = egui::ColorImage {
+                            size: [right - x, bottom - y],
+                            pixels,
+                        };
+                        egui::epaint::ImageDelta::partial([x, y], for_texture(region))
+                    })
+                };
+                if let Some(delta) = delta {
+                    // Update the existing texture instead of allocating a new one for every render.
+                    match &mut app.texture {
+                        Some((handle, texture_size)) => {
+                            ctx.tex_manager().write().set(handle.id(), delta);
+                            *texture_size = size;
+                        }
+                        None => {
+                            let texture = ctx.load_texture("main fractal", delta.image);
+                            app.texture = Some((texture, size));
+                        }
+                    }
+                    log::debug!(
+                        "Uploaded the {}×{} texture in {:.2?}.",
+                        size.x,
+                        size.y,
+                        start.elapsed()
+                    );
+                }
+            }
             if let Some(texture) = &app.texture {
                 return Some(texture.clone());
             }
@@ -123,10 +725,111 @@ impl epi::App for App {
             None
         }
         let texture = texture(self, ctx, frame);
+        while let Ok(preview) = self.previews.try_recv() {
+            let result = preview.result.map(|(bytes, image)| {
+                (
+                    bytes,
+                    image.map(|image| ctx.load_texture("export preview", image)),
+                )
+            });
+            self.preview = Some((preview.generation, result));
+        }
+        if self.preview_export {
+            let previewed =
+                matches!(self.preview, Some((generation, _)) if generation == self.completed);
+            let waiting = !previewed
+                && self.preview_wanted.load(Ordering::SeqCst) != self.completed
+                && !self.working.load(Ordering::SeqCst);
+            if let (true, Some(image)) = (waiting, &self.displayed) {
+                if ctx.input().time - self.completed_at >= PREVIEW_SETTLE {
+                    self.preview_wanted.store(self.completed, Ordering::SeqCst);
+                    self.preview_channel
+                        .send((
+                            self.completed,
+                            self.state.clone(),
+                            image.clone(),
+                            frame.clone(),
+                        ))
+                        .unwrap();
+                } else {
+                    ctx.request_repaint();
+                }
+            }
+        } else {
+            self.preview_wanted.store(0, Ordering::SeqCst);
+        }
+        // The preview of the render on screen, if it's done.
+        let preview = self
+            .preview
+            .as_ref()
+            .filter(|(generation, _)| self.preview_export && *generation == self.completed)
+            .map(|(_, result)| result);
+        let preview_status = self.preview_export.then(|| match preview {
+            None => "Encoding…".to_owned(),
+            Some(Ok((bytes, image))) => format!(
+                "{} {}, {}",
+                crate::resources::format_bytes(*bytes as u64),
+                self.state.output.format,
+                if image.is_some() {
+                    "hold Space for the render"
+                } else {
+                    "can't be decoded to preview"
+                }
+            ),
+            Some(Err(err)) => err.clone(),
+        });
+        // Holding Space flips back to the render.
+        let flipped = !ctx.wants_keyboard_input() && ctx.input().key_down(egui::Key::Space);
+        let texture = match preview {
+            Some(Ok((_, Some(preview)))) if !flipped => {
+                texture.map(|(_, size)| (preview.clone(), size))
+            }
+            _ => texture,
+        };
+        while let Ok((id, image)) = self.rendered_thumbnails.try_recv() {
+            let name = match id {
+                ThumbnailId::Session(id) => format!("session {}", id),
+                ThumbnailId::Sweep {
+                    generation,
+                    constant,
+                } => {
+                    // Requested before the settings changed.
+                    if generation != self.sweep_base.0 {
+                        continue;
+                    }
+                    format!("sweep {:?}", constant)
+                }
+            };
+            let texture = ctx.load_texture(name, image);
+            self.thumbnails.insert(id, texture);
+        }
 
-        let previous_state = self.state.config.clone();
+        let previous_state = self.state.clone();
+        let previous_view = self.view;
+        let previous_show_aa_samples = self.show_aa_samples;
 
         let config = &mut self.state.config;
+        let grid = &mut self.state.grid;
+        let current_view = &mut self.view;
+        let adaptive_aa = &mut self.state.aa;
+        let show_aa_samples = &mut self.show_aa_samples;
+        let lock_aspect = &mut self.lock_aspect;
+        let match_window = &mut self.match_window;
+        let render_scale = &mut self.render_scale;
+        let palette = &mut self.state.palette;
+        let selected_stop = &mut self.selected_stop;
+        let palette_source = &mut self.palette_source;
+        let logging = self.session.is_some();
+        let show_session = &mut self.show_session;
+        let show_sweep = &mut self.show_sweep;
+        let preview_export = &mut self.preview_export;
+        let simulate_cvd = &mut self.simulate_cvd;
+        let clipping = self.clipping;
+        let metering = &mut self.metering;
+        let letterbox = &mut self.letterbox;
+        // While the constant is dragged, only its boundary is plotted, see
+        // `Options::boundary_preview`.
+        let mut dragging_constant = false;
 
         if ctx.input().key_down(egui::Key::M) {
             self.gui_on = !self.gui_on;
@@ -145,7 +848,6 @@ impl epi::App for App {
                                 egui::ComboBox::from_id_source("type")
                                     .selected_text(match config.algo {
                                         crate::Algo::Mandelbrot => "Mandelbrot",
-                                        crate::Algo::Julia => "Julia",
                                         crate::Algo::BarnsleyFern => "Fern",
                                     })
                                     .show_ui(ui, |ui| {
@@ -154,24 +856,67 @@ impl epi::App for App {
                                             Algo::Mandelbrot,
                                             "Mandelbrot",
                                         );
-                                        ui.selectable_value(&mut config.algo, Algo::Julia, "Julia");
                                         ui.selectable_value(
                                             &mut config.algo,
                                             Algo::BarnsleyFern,
                                             "Fern",
                                         );
                                     });
+                                if let Algo::Mandelbrot = config.algo {
+                                    let mut julia = config.julia.is_some();
+                                    ui.checkbox(&mut julia, "Julia");
+                                    if julia != config.julia.is_some() {
+                                        config.julia = julia.then(|| Imaginary::ZERO);
+                                    }
+                                }
                             }
                             // Resolution
                             {
-                                ui.add(
-                                    egui::DragValue::new(&mut config.width)
-                                        .clamp_range(16..=u32::MAX),
-                                );
-                                ui.add(
-                                    egui::DragValue::new(&mut config.height)
-                                        .clamp_range(16..=u32::MAX),
-                                );
+                                ui.checkbox(match_window, "Match window");
+                                if *match_window {
+                                    ui.add(
+                                        egui::Slider::new(render_scale, 0.25..=4.0)
+                                            .logarithmic(true)
+                                            .text("render scale"),
+                                    );
+                                } else {
+                                    let (width, height) = (config.width, config.height);
+                                    ui.add(
+                                        egui::DragValue::new(&mut config.width)
+                                            .clamp_range(16..=u32::MAX),
+                                    );
+                                    ui.add(
+                                        egui::DragValue::new(&mut config.height)
+                                            .clamp_range(16..=u32::MAX),
+                                    );
+                                    // The viewport is widened or narrowed when only one changes, so
+                                    // the fractal is never distorted.
+                                    if *lock_aspect {
+                                        let scaled = |value: u32, from: u32, to: u32| {
+                                            let value = value as f64 * to as f64 / from as f64;
+                                            cmp::max(value.round() as u32, 16)
+                                        };
+                                        if config.width != width {
+                                            config.height = scaled(height, width, config.width);
+                                        } else if config.height != height {
+                                            config.width = scaled(width, height, config.height);
+                                        }
+                                    }
+                                    ui.checkbox(lock_aspect, "Lock aspect");
+                                    egui::ComboBox::from_id_source("resolution")
+                                        .selected_text("Presets")
+                                        .show_ui(ui, |ui| {
+                                            for (width, height) in RESOLUTION_PRESETS {
+                                                let selected = config.width == width
+                                                    && config.height == height;
+                                                let label = format!("{}×{}", width, height);
+                                                if ui.selectable_label(selected, label).clicked() {
+                                                    config.width = width;
+                                                    config.height = height;
+                                                }
+                                            }
+                                        });
+                                }
                             }
 
                             // Iterations
@@ -179,56 +924,204 @@ impl epi::App for App {
                             {
                                 ui.add(egui::DragValue::new(&mut config.iterations));
                             }
+                            let defaults = calc::defaults(&config.algo);
+                            let reference = Config::new(config.algo.clone());
                             // Exposure
-                            if let Algo::Mandelbrot | Algo::Julia = config.algo {
+                            if let Algo::Mandelbrot = config.algo {
                                 ui.separator();
-                                ui.add(
-                                    egui::Slider::new(&mut config.exposure, 0.01..=50.0)
-                                        .logarithmic(true),
-                                );
+                                let (min, max) = defaults.exposure_range;
+                                let response = warned(ui, clipping_warning(clipping), |ui| {
+                                    ui.add(
+                                        egui::Slider::new(&mut config.exposure, min..=max)
+                                            .logarithmic(true)
+                                            .clamp_to_range(true),
+                                    )
+                                });
+                                reset_menu(response, &mut config.exposure, reference.exposure);
+                                let meter =
+                                    ui.selectable_label(*metering, "Meter")
+                                        .on_hover_text(format!(
+                                            "Click a point to expose it at {:.0}% brightness.",
+                                            render::METER_TARGET * 100.0
+                                        ));
+                                if meter.clicked() {
+                                    *metering = !*metering;
+                                }
+                                if config.inside {
+                                    let response = ui.add(
+                                        egui::Slider::new(&mut config.interior_exposure, min..=max)
+                                            .logarithmic(true)
+                                            .clamp_to_range(true)
+                                            .text("inside"),
+                                    );
+                                    reset_menu(
+                                        response,
+                                        &mut config.interior_exposure,
+                                        reference.interior_exposure,
+                                    );
+                                    let mut sqrt = config.interior_mapping == InteriorMapping::Sqrt;
+                                    ui.checkbox(&mut sqrt, "√ inside");
+                                    config.interior_mapping = if sqrt {
+                                        InteriorMapping::Sqrt
+                                    } else {
+                                        InteriorMapping::Linear
+                                    };
+                                }
                             }
                             // Color weight
                             if let Algo::BarnsleyFern = config.algo {
                                 ui.separator();
-                                ui.add(
-                                    egui::Slider::new(&mut config.color_weight, 0.0001..=10.0)
-                                        .logarithmic(true),
+                                let (min, max) = defaults.color_weight_range;
+                                let response = warned(ui, clipping_warning(clipping), |ui| {
+                                    ui.add(
+                                        egui::Slider::new(&mut config.color_weight, min..=max)
+                                            .logarithmic(true)
+                                            .clamp_to_range(true),
+                                    )
+                                });
+                                reset_menu(
+                                    response,
+                                    &mut config.color_weight,
+                                    calc::FERN_NORMALIZED_WEIGHT,
                                 );
                             }
+                            // View
+                            ui.separator();
+                            // The fern has no samples to show.
+                            ui.add_enabled_ui(config.algo != Algo::BarnsleyFern, |ui| {
+                                egui::ComboBox::from_id_source("view")
+                                    .selected_text(current_view.label())
+                                    .show_ui(ui, |ui| {
+                                        for view in View::ALL {
+                                            ui.selectable_value(current_view, view, view.label());
+                                        }
+                                    });
+                                let mut enabled = adaptive_aa.is_some();
+                                ui.checkbox(&mut enabled, "Adaptive AA")
+                                    .on_hover_text("Supersample the edges, see --adaptive-aa.");
+                                if enabled != adaptive_aa.is_some() {
+                                    *adaptive_aa = enabled.then(|| AaOptions {
+                                        threshold: AA_THRESHOLD,
+                                        debug: None,
+                                    });
+                                }
+                                if let Some(aa) = adaptive_aa {
+                                    ui.add(
+                                        egui::Slider::new(&mut aa.threshold, 0..=255)
+                                            .text("AA threshold"),
+                                    );
+                                    ui.checkbox(show_aa_samples, "Show samples").on_hover_text(
+                                        "Show how many samples each pixel took, from black for \
+                                         one to white for 16.",
+                                    );
+                                }
+                            });
+                            egui::ComboBox::from_id_source("cvd")
+                                .selected_text(simulate_cvd.map_or("Normal vision", Cvd::label))
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(simulate_cvd, None, "Normal vision");
+                                    for cvd in Cvd::ALL {
+                                        ui.selectable_value(simulate_cvd, Some(cvd), cvd.label());
+                                    }
+                                });
+                            ui.label("Letterbox").on_hover_text(
+                                "The color around the image and of what's not rendered yet.",
+                            );
+                            let mut rgb = [letterbox.r(), letterbox.g(), letterbox.b()];
+                            if egui::color_picker::color_edit_button_srgb(ui, &mut rgb).changed() {
+                                *letterbox = egui::Color32::from_rgb(rgb[0], rgb[1], rgb[2]);
+                            }
+                            // Orientation
+                            ui.separator();
+                            {
+                                let turned = config.orientation.rotation.is_quarter_turn();
+                                egui::ComboBox::from_id_source("rotation")
+                                    .selected_text(format!(
+                                        "{}°",
+                                        config.orientation.rotation.quarter_turns() * 90
+                                    ))
+                                    .show_ui(ui, |ui| {
+                                        for rotation in [
+                                            Rotation::None,
+                                            Rotation::Quarter,
+                                            Rotation::Half,
+                                            Rotation::ThreeQuarters,
+                                        ] {
+                                            let label =
+                                                format!("{}°", rotation.quarter_turns() * 90);
+                                            ui.selectable_value(
+                                                &mut config.orientation.rotation,
+                                                rotation,
+                                                label,
+                                            );
+                                        }
+                                    });
+                                // Turn the image instead of squeezing the fractal into it.
+                                if turned != config.orientation.rotation.is_quarter_turn()
+                                    && !*match_window
+                                {
+                                    std::mem::swap(&mut config.width, &mut config.height);
+                                }
+                                ui.checkbox(&mut config.orientation.flip_h, "Flip H");
+                                ui.checkbox(&mut config.orientation.flip_v, "Flip V");
+                            }
                             // Flags
                             ui.separator();
-                            if let Algo::Mandelbrot | Algo::Julia = config.algo {
+                            if let Algo::Mandelbrot = config.algo {
                                 ui.checkbox(&mut config.inside, "Coloured inside");
                                 ui.checkbox(&mut config.smooth, "Smoothed");
+                                ui.checkbox(&mut config.y_up, "Math orientation")
+                                    .on_hover_text(
+                                        "Put the positive imaginary axis up, like in mathematics.",
+                                    );
+                                egui::ComboBox::from_id_source("smooth_mode")
+                                    .selected_text(format!("{:?}", config.smooth_mode))
+                                    .show_ui(ui, |ui| {
+                                        for mode in [
+                                            SmoothMode::Classic,
+                                            SmoothMode::Normalized,
+                                            SmoothMode::Log,
+                                        ] {
+                                            let label = format!("{:?}", mode);
+                                            ui.selectable_value(
+                                                &mut config.smooth_mode,
+                                                mode,
+                                                label,
+                                            );
+                                        }
+                                    });
+                                // The labels can't be turned.
+                                let turned = config.orientation.rotation.is_quarter_turn();
+                                if turned {
+                                    *grid = false;
+                                }
+                                ui.add_enabled_ui(!turned, |ui| ui.checkbox(grid, "Grid"));
                             }
                             ui.separator();
                             // julia pos
-                            if let Algo::Julia = &mut config.algo {
-                                let mut value = egui::Vec2::new(
-                                    config.julia_set.re as f32,
-                                    config.julia_set.im as f32,
-                                );
+                            if let Some(julia) = &mut config.julia {
+                                let mut value = egui::Vec2::new(julia.re as f32, julia.im as f32);
 
                                 let frame = egui::containers::Frame::dark_canvas(ui.style())
                                     .margin(egui::Vec2::ZERO);
 
-                                frame.show(ui, |ui| {
-                                    let widget = vec2ui::PointSelect::new(
-                                        &mut value,
-                                        egui::Vec2::new(-1.5, -1.5)..=egui::Vec2::new(1.5, 1.5),
-                                        80.0,
-                                    );
-                                    ui.add(widget).changed()
-                                });
+                                dragging_constant = frame
+                                    .show(ui, |ui| {
+                                        let widget = vec2ui::PointSelect::new(
+                                            &mut value,
+                                            egui::Vec2::new(-1.5, -1.5)..=egui::Vec2::new(1.5, 1.5),
+                                            80.0,
+                                        );
+                                        ui.add(widget).dragged()
+                                    })
+                                    .inner;
 
-                                config.julia_set.re = value.x as f64;
-                                config.julia_set.im = value.y as f64;
+                                julia.re = value.x as f64;
+                                julia.im = value.y as f64;
                             }
                             // info
                             ui.label(format!("{:.3}", config.scale.re));
-                            if let Algo::Julia = config.algo {
-                                let mut value = config.julia_set;
-                                let initial_value = value;
+                            if let Some(julia) = &mut config.julia {
                                 ui.horizontal_wrapped(|ui| {
                                     ui.add(
                                         egui::DragValue::new(&mut config.pos.re).max_decimals(6),
@@ -236,48 +1129,428 @@ impl epi::App for App {
                                     ui.add(
                                         egui::DragValue::new(&mut config.pos.im).max_decimals(6),
                                     );
-                                    ui.label("i");
+                                    imaginary_label(ui, config.y_up);
                                     ui.end_row();
-                                    ui.add(egui::DragValue::new(&mut value.re).max_decimals(6));
-                                    ui.add(egui::DragValue::new(&mut value.im).max_decimals(6));
+                                    ui.add(egui::DragValue::new(&mut julia.re).max_decimals(6));
+                                    ui.add(egui::DragValue::new(&mut julia.im).max_decimals(6));
                                     ui.label("i");
-
-                                    if value != initial_value {
-                                        config.julia_set = value;
-                                        config.algo = Algo::Julia;
-                                    }
                                 });
                             } else {
                                 ui.add(egui::DragValue::new(&mut config.pos.re).max_decimals(6));
                                 ui.add(egui::DragValue::new(&mut config.pos.im).max_decimals(6));
-                                ui.label("i");
+                                imaginary_label(ui, config.y_up);
+                            }
+                            ui.separator();
+                            ui.checkbox(preview_export, "Preview export quality");
+                            if let Some(status) = &preview_status {
+                                ui.label(status.as_str());
+                            }
+                            if logging {
+                                ui.separator();
+                                ui.checkbox(show_session, "Session");
+                            }
+                            if let Algo::Mandelbrot = config.algo {
+                                ui.separator();
+                                ui.checkbox(show_sweep, "Julia sweep");
                             }
                         },
                     )
                 });
+                // Palette
+                if let Algo::Mandelbrot = config.algo {
+                    ui.horizontal(|ui| {
+                        let mut enabled = palette.is_some();
+                        ui.checkbox(&mut enabled, "Palette");
+                        if enabled != palette.is_some() {
+                            *palette = if enabled {
+                                Some(Palette::default())
+                            } else {
+                                None
+                            };
+                            *selected_stop = None;
+                        }
+                        let palette = match palette {
+                            Some(palette) => palette,
+                            None => return,
+                        };
+                        ui.add(paletteui::GradientEdit::new(
+                            palette,
+                            selected_stop,
+                            egui::Vec2::new(300.0, 20.0),
+                        ));
+                        if let Some(index) = *selected_stop {
+                            let color = palette.stops()[index].color;
+                            let mut color = [color.r, color.g, color.b];
+                            if egui::color_picker::color_edit_button_srgb(ui, &mut color).changed()
+                            {
+                                palette.set_color(index, RGB::new(color[0], color[1], color[2]));
+                            }
+                            if ui.button("Delete stop").clicked() && palette.remove(index) {
+                                *selected_stop = None;
+                            }
+                        }
+                        ui.separator();
+                        ui.add(
+                            egui::TextEdit::singleline(palette_source)
+                                .hint_text("palette, .map or .ggr")
+                                .desired_width(200.0),
+                        );
+                        if ui.button("Import").clicked() {
+                            match palette::parse(palette_source) {
+                                Ok(imported) => {
+                                    *palette = imported;
+                                    *selected_stop = None;
+                                }
+                                Err(err) => log::error!("Failed to import palette: {}", err),
+                            }
+                        }
+                        if ui.button("Export").clicked() {
+                            // To the file, if it's a palette file. Else as a palette string for
+                            // `--palette`, which is also copied.
+                            let is_file = std::path::Path::new(palette_source.as_str())
+                                .extension()
+                                .and_then(|extension| extension.to_str())
+                                .map_or(false, |extension| {
+                                    extension.eq_ignore_ascii_case("map")
+                                        || extension.eq_ignore_ascii_case("ggr")
+                                });
+                            if is_file {
+                                if let Err(err) = palette.save(palette_source.as_str()) {
+                                    log::error!("Failed to export palette: {}", err);
+                                }
+                            } else {
+                                *palette_source = palette.to_string();
+                                ui.output().copied_text = palette_source.clone();
+                            }
+                        }
+                        if let Some(cvd) = *simulate_cvd {
+                            let confused: Vec<String> = color::lint(palette, cvd)
+                                .iter()
+                                .map(|confusion| {
+                                    format!("{} and {}", confusion.stop + 1, confusion.stop + 2)
+                                })
+                                .collect();
+                            if !confused.is_empty() {
+                                ui.separator();
+                                ui.colored_label(
+                                    WARNING_COLOR,
+                                    format!("Stops {} look alike", confused.join(", ")),
+                                );
+                            }
+                        }
+                    });
+                }
+            });
+        }
+        // The config of the session log entry to go to.
+        let mut jump_to = None;
+        let session = self
+            .session
+            .as_ref()
+            .filter(|_| self.gui_on && self.show_session);
+        if let Some(session) = session {
+            let thumbnails = &self.thumbnails;
+            let requested = &mut self.requested_thumbnails;
+            let thumbnail_channel = &self.thumbnail_channel;
+            egui::SidePanel::right("session").show(ctx, |ui| {
+                ui.heading("Session");
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    let first_id = session.first_id();
+                    for (index, entry) in session.entries().iter().enumerate().rev() {
+                        let id = ThumbnailId::Session(first_id + index);
+                        ui.horizontal(|ui| {
+                            let clicked = match thumbnails.get(&id) {
+                                Some(texture) => {
+                                    let size = texture.size_vec2();
+                                    ui.add(egui::ImageButton::new(texture, size)).clicked()
+                                }
+                                None => {
+                                    // Rendered lazily, as the entries are scrolled to.
+                                    if requested.insert(id) {
+                                        thumbnail_channel
+                                            .send((
+                                                id,
+                                                session_thumbnail(&entry.config),
+                                                frame.clone(),
+                                            ))
+                                            .unwrap();
+                                    }
+                                    let size = egui::Vec2::splat(THUMBNAIL_HEIGHT as f32);
+                                    ui.allocate_exact_size(size, egui::Sense::hover());
+                                    false
+                                }
+                            };
+                            ui.vertical(|ui| {
+                                ui.label(format!("{} UTC", session::format_time(entry.time)));
+                                ui.label(format!(
+                                    "{:?}, scale {:.3}",
+                                    entry.config.algo, entry.config.scale.re
+                                ));
+                            });
+                            if clicked {
+                                jump_to = Some(entry.config.clone());
+                            }
+                        });
+                    }
+                });
             });
         }
+        // Julia sweep
+        let escape_time = matches!(self.state.config.algo, Algo::Mandelbrot);
+        if self.gui_on && self.show_sweep && escape_time {
+            let base = Config {
+                width: SWEEP_THUMBNAIL_SIZE.0,
+                height: SWEEP_THUMBNAIL_SIZE.1,
+                ..julia_view(&self.state.config, Imaginary::ZERO)
+            };
+            if base != self.sweep_base.1 {
+                self.sweep_base = (self.sweep_base.0 + 1, base);
+                self.thumbnails
+                    .retain(|id, _| matches!(id, ThumbnailId::Session(_)));
+                self.requested_thumbnails
+                    .retain(|id| matches!(id, ThumbnailId::Session(_)));
+            }
+            let (generation, base) = &self.sweep_base;
+            let sweep = &mut self.sweep;
+            let sweep_count = &mut self.sweep_count;
+            let thumbnails = &self.thumbnails;
+            let requested = &mut self.requested_thumbnails;
+            let thumbnail_channel = &self.thumbnail_channel;
+            let mut picked = None;
+            egui::Window::new("Julia sweep")
+                .open(&mut self.show_sweep)
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        let circle = matches!(sweep, Sweep::Circle { .. });
+                        if ui.radio(circle, "Circle").clicked() && !circle {
+                            *sweep = DEFAULT_SWEEP_CIRCLE;
+                        }
+                        if ui.radio(!circle, "Line").clicked() && circle {
+                            *sweep = DEFAULT_SWEEP_LINE;
+                        }
+                        ui.separator();
+                        let drag = |ui: &mut egui::Ui, value: &mut f64| {
+                            ui.add(egui::DragValue::new(value).speed(0.005).max_decimals(4));
+                        };
+                        match sweep {
+                            Sweep::Circle { center, radius } => {
+                                ui.label("Center");
+                                drag(ui, &mut center.re);
+                                drag(ui, &mut center.im);
+                                ui.label("i, radius");
+                                drag(ui, radius);
+                            }
+                            Sweep::Line { from, to } => {
+                                ui.label("From");
+                                drag(ui, &mut from.re);
+                                drag(ui, &mut from.im);
+                                ui.label("i to");
+                                drag(ui, &mut to.re);
+                                drag(ui, &mut to.im);
+                                ui.label("i");
+                            }
+                        }
+                        ui.separator();
+                        ui.add(egui::Slider::new(sweep_count, 30..=60).text("Thumbnails"));
+                    });
+                    let size = egui::Vec2::new(
+                        SWEEP_THUMBNAIL_SIZE.0 as f32,
+                        SWEEP_THUMBNAIL_SIZE.1 as f32,
+                    );
+                    egui::ScrollArea::horizontal().show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            for constant in sweep.constants(*sweep_count) {
+                                let id = ThumbnailId::Sweep {
+                                    generation: *generation,
+                                    constant: (constant.re.to_bits(), constant.im.to_bits()),
+                                };
+                                let response = match thumbnails.get(&id) {
+                                    Some(texture) => ui.add(egui::ImageButton::new(texture, size)),
+                                    None => {
+                                        // They stream in as the worker finishes them.
+                                        if requested.insert(id) {
+                                            let config = Config {
+                                                julia: Some(constant),
+                                                ..base.clone()
+                                            };
+                                            thumbnail_channel
+                                                .send((id, config, frame.clone()))
+                                                .unwrap();
+                                        }
+                                        ui.allocate_exact_size(size, egui::Sense::hover()).1
+                                    }
+                                };
+                                let response = response.on_hover_text(format!(
+                                    "{:.4} {:+.4}i",
+                                    constant.re, constant.im
+                                ));
+                                if response.clicked() {
+                                    picked = Some(constant);
+                                }
+                            }
+                        });
+                    });
+                });
+            if let Some(constant) = picked {
+                let config = &mut self.state.config;
+                if config.julia.is_some() {
+                    config.julia = Some(constant);
+                } else {
+                    jump_to = Some(julia_view(config, constant));
+                }
+            }
+        }
         // Render this after controls to give that space. (even if it was below this on screen)
+        // The position of a ctrl-click, relative to the size of the image.
+        let mut clicked = None;
+        // The position of a click with the spot meter, relative to the size of the image.
+        let mut metered = None;
+        let metering = self.metering;
+        let samples = &self.samples;
+        // Until the render of the current view is done, the image on screen is scaled to it.
+        let approximated = self.shown.as_ref().and_then(|(_, shown)| {
+            approximation(shown, &self.state, self.view, self.show_aa_samples)
+        });
+        let target_size = egui::Vec2::new(
+            self.state.config.width as f32,
+            self.state.config.height as f32,
+        );
+        // The position of the pointer over the image, relative to it's size.
+        let mut hovered = None;
+        // The space for the image, in points.
+        let mut display_size = egui::Vec2::ZERO;
         egui::CentralPanel::default()
-            .frame(egui::Frame::none().fill(egui::Color32::BLACK))
+            .frame(egui::Frame::none().fill(self.letterbox))
             .show(ctx, |ui| {
+                display_size = ui.available_size();
                 if let Some((texture, size)) = texture {
-                    let aspect_ratio = size.x / size.y;
-                    let available_size = ui.available_size();
-                    let mut space = available_size;
-                    space.y = cmp::min(F32Ord(available_size.y), F32Ord(space.x / aspect_ratio)).0;
-                    space.x = cmp::min(F32Ord(available_size.x), F32Ord(space.y * aspect_ratio)).0;
-                    let margin = egui::Vec2::new(
-                        (available_size.x - space.x) / 2.0,
-                        (available_size.y - space.y) / 2.0,
-                    );
+                    // Letterboxed to the new view, the image may only cover part of it.
+                    let size = if approximated.is_some() {
+                        target_size
+                    } else {
+                        size
+                    };
+                    let available = ui.available_rect_before_wrap();
+                    let placed = fit_to_pixels(size, available, ctx.pixels_per_point());
+                    let space = placed.size();
+                    let margin = placed.min - available.min;
                     egui::Frame::none().margin(margin).show(ui, |ui| {
-                        ui.image(&texture, space);
+                        let response = match approximated {
+                            Some(approximated) => {
+                                let (rect, response) =
+                                    ui.allocate_exact_size(space, egui::Sense::click());
+                                let relative = |[min, max]: [[f64; 2]; 2]| {
+                                    let point =
+                                        |[x, y]: [f64; 2]| egui::Pos2::new(x as f32, y as f32);
+                                    egui::Rect::from_min_max(point(min), point(max))
+                                };
+                                let uv = relative(approximated.uv);
+                                let placement = relative(approximated.placement);
+                                let placed = egui::Rect::from_min_max(
+                                    rect.min + placement.min.to_vec2() * rect.size(),
+                                    rect.min + placement.max.to_vec2() * rect.size(),
+                                );
+                                egui::Image::new(&texture, placed.size())
+                                    .uv(uv)
+                                    .paint_at(ui, placed);
+                                response
+                            }
+                            None => ui
+                                .add(egui::Image::new(&texture, space).sense(egui::Sense::click())),
+                        };
+                        let rect = response.rect;
+                        let relative = |pos: egui::Pos2| (pos - rect.min) / rect.size();
+                        hovered = response.hover_pos().map(relative);
+                        if metering {
+                            // Show what's metered, which also helps when working on the colors.
+                            let tooltip = match (hovered, samples) {
+                                (Some(hovered), Some((config, samples))) => {
+                                    ui.output().cursor_icon = egui::CursorIcon::Crosshair;
+                                    sample_at(config, samples, hovered).map(describe_sample)
+                                }
+                                _ => None,
+                            };
+                            let response = match tooltip {
+                                Some(tooltip) => response.on_hover_text(tooltip),
+                                None => response,
+                            };
+                            if response.clicked() {
+                                metered = response.interact_pointer_pos().map(relative);
+                            }
+                        } else if response.clicked() && ui.input().modifiers.ctrl {
+                            clicked = response.interact_pointer_pos().map(relative);
+                        }
                     });
                 }
             });
+        if hovered.is_some() {
+            self.focus = hovered;
+        }
+        if let Some(relative) = metered {
+            self.metering = false;
+            if let Some((config, samples)) = &self.samples {
+                let exposure = sample_at(config, samples, relative).and_then(|sample| {
+                    render::metered_exposure(config, sample, render::METER_TARGET)
+                });
+                let message = match exposure {
+                    Some(exposure) => {
+                        self.state.config.exposure = exposure;
+                        format!("Exposure {:.2}", exposure)
+                    }
+                    None => "Can't meter the inside".to_owned(),
+                };
+                self.toast = Some((message, ctx.input().time));
+            }
+        }
+        let pixels_per_point = ctx.pixels_per_point();
+        if (pixels_per_point - self.pixels_per_point).abs() > SCALE_THRESHOLD {
+            log::debug!("The display has {} pixels per point.", pixels_per_point);
+            self.pixels_per_point = pixels_per_point;
+            // Wait for the window to settle on the new display.
+            self.pending_size = None;
+            ctx.request_repaint();
+        }
+        // Match window
+        if self.match_window {
+            let scale = pixels_per_point * self.render_scale;
+            let config = &mut self.state.config;
+            let changed = |points: f32, pixels: u32| {
+                (points * scale - pixels as f32).abs() >= RESIZE_THRESHOLD
+            };
+            let pixels = |points: f32| cmp::max((points * scale).round() as u32, 16);
+            let size = (pixels(display_size.x), pixels(display_size.y));
+            let now = ctx.input().time;
+            // The size is at least 16 pixels, so a smaller window never matches.
+            let settled = size == (config.width, config.height)
+                || (!changed(display_size.x, config.width)
+                    && !changed(display_size.y, config.height));
+            if settled {
+                self.pending_size = None;
+            } else {
+                match self.pending_size {
+                    // Only render once the window stops changing size.
+                    Some((pending, since)) if pending == size => {
+                        if now - since >= RESIZE_SETTLE {
+                            config.width = size.0;
+                            config.height = size.1;
+                            self.pending_size = None;
+                        }
+                    }
+                    _ => self.pending_size = Some((size, now)),
+                }
+                ctx.request_repaint();
+            }
+        }
         // Input
         {
+            if let Some(relative) = clicked {
+                let config = &self.state.config;
+                self.state.orbit = Some(crate::mapping::pixel_to_complex(
+                    config,
+                    relative.x as f64 * config.width as f64,
+                    relative.y as f64 * config.height as f64,
+                ));
+            }
             let config = &mut self.state.config;
             // Movement
             #[allow(unused_braces, clippy::blocks_in_if_conditions)]
@@ -285,20 +1558,28 @@ impl epi::App for App {
                 let dt = { ctx.input().predicted_dt } as f64;
 
                 let scale_x = 1.0 / config.scale.re;
-                let scale_y = 1.0 / config.scale.im;
+                let scale_y = if config.y_up { -1.0 } else { 1.0 } / config.scale.im;
                 // move
+                let mut direction = (0.0, 0.0);
                 if { ctx.input().key_down(egui::Key::ArrowLeft) } {
-                    config.pos.re -= scale_x * dt * 0.5;
+                    direction.0 -= 1.0;
                 }
                 if { ctx.input().key_down(egui::Key::ArrowRight) } {
-                    config.pos.re += scale_y * dt * 0.5;
+                    direction.0 += 1.0;
                 }
                 if { ctx.input().key_down(egui::Key::ArrowUp) } {
-                    config.pos.im -= scale_x * dt * 0.5;
+                    direction.1 -= 1.0;
                 }
                 if { ctx.input().key_down(egui::Key::ArrowDown) } {
-                    config.pos.im += scale_y * dt * 0.5;
+                    direction.1 += 1.0;
                 }
+                // The arrows move along the screen, whichever way the image is turned.
+                let (x, y) = (config.width as f64, config.height as f64);
+                let (from_x, from_y) = mapping::unorient(config.orientation, x, y, 0.0, 0.0);
+                let (to_x, to_y) =
+                    mapping::unorient(config.orientation, x, y, direction.0, direction.1);
+                config.pos.re += (to_x - from_x) * scale_x * dt * 0.5;
+                config.pos.im += (to_y - from_y) * scale_y * dt * 0.5;
                 // scale
                 {
                     let delta = ctx.input().scroll_delta.y;
@@ -313,32 +1594,370 @@ impl epi::App for App {
                             } else {
                                 1.0 + (delta as f64 / 80.0)
                             };
+                        config.scale.re = config.scale.re.clamp(SCALE_RANGE.0, SCALE_RANGE.1);
+                        config.scale.im = config.scale.im.clamp(SCALE_RANGE.0, SCALE_RANGE.1);
                     }
                 }
+                // shortcuts
+                let typed: Vec<String> = ctx
+                    .input()
+                    .events
+                    .iter()
+                    .filter_map(|event| match event {
+                        egui::Event::Text(text) => Some(text.clone()),
+                        _ => None,
+                    })
+                    .collect();
+                let escape_time = matches!(config.algo, Algo::Mandelbrot);
+                for text in typed {
+                    let message = match text.as_str() {
+                        "+" if escape_time => {
+                            config.exposure *= 1.1;
+                            format!("Exposure {:.2}", config.exposure)
+                        }
+                        "-" if escape_time => {
+                            config.exposure /= 1.1;
+                            format!("Exposure {:.2}", config.exposure)
+                        }
+                        "[" => {
+                            config.iterations = cmp::max(config.iterations / 2, 1);
+                            format!("{} iterations", config.iterations)
+                        }
+                        "]" => {
+                            config.iterations = config.iterations.saturating_mul(2);
+                            format!("{} iterations", config.iterations)
+                        }
+                        "i" if escape_time => {
+                            config.inside = !config.inside;
+                            format!(
+                                "Coloured inside {}",
+                                if config.inside { "on" } else { "off" }
+                            )
+                        }
+                        _ => continue,
+                    };
+                    self.toast = Some((message, ctx.input().time));
+                }
                 // screenshot
-                #[cfg(feature = "avif")]
                 if { ctx.input().key_pressed(egui::Key::S) } {
                     let mut options = self.state.clone();
                     std::thread::spawn(move || {
                         options.config.width *= 2;
                         options.config.height *= 2;
-                        let image = crate::get_image(&options.config);
-                        crate::write_image(&options, image);
+                        let mut image = crate::get_image(&options.config);
+                        let config = &options.config;
+                        crate::draw_overlays(
+                            &options,
+                            &mut crate::Image::new(
+                                &mut image,
+                                config.width as usize,
+                                config.height as usize,
+                            ),
+                        );
+                        if let Some(cvd) = options.simulate_cvd {
+                            crate::color::simulate_image(&mut image, cvd);
+                        }
+                        let image = crate::ImageBuffer::from_contents(
+                            image,
+                            config.width as usize,
+                            config.height as usize,
+                        )
+                        .expect("the rendered image has the size of the config");
+                        let written = crate::encode_image(
+                            &image,
+                            options.output.format,
+                            &options.output.encoder,
+                        )
+                        .and_then(|data| crate::write_encoded(&options.output, &data));
+                        match written {
+                            Ok(path) => log::info!("Wrote screenshot to {:?}.", path),
+                            Err(err) => log::error!("Failed to write screenshot: {}", err),
+                        }
                     });
                 }
             }
         }
+        // Toast
+        {
+            let now = ctx.input().time;
+            if let Some((_, shown)) = self.toast {
+                if now - shown > TOAST_DURATION {
+                    self.toast = None;
+                }
+            }
+            if let Some((message, shown)) = &self.toast {
+                let left = TOAST_DURATION - (now - shown);
+                let alpha = ((left / TOAST_FADE).min(1.0) * 255.0) as u8;
+                egui::Area::new("toast")
+                    .anchor(egui::Align2::CENTER_BOTTOM, egui::Vec2::new(0.0, -20.0))
+                    .interactable(false)
+                    .show(ctx, |ui| {
+                        egui::Frame::none()
+                            .fill(egui::Color32::from_black_alpha(alpha / 4 * 3))
+                            .margin(egui::Vec2::new(8.0, 4.0))
+                            .show(ui, |ui| {
+                                ui.visuals_mut().override_text_color =
+                                    Some(egui::Color32::from_white_alpha(alpha));
+                                ui.label(message.as_str());
+                            });
+                    });
+                // Keep fading out without input.
+                ctx.request_repaint();
+            }
+        }
+        // Too few iterations
+        if let (true, Some(suggestion)) = (self.gui_on, self.suggested_iterations) {
+            let iterations = &mut self.state.config.iterations;
+            egui::Area::new("iterations")
+                .anchor(egui::Align2::RIGHT_BOTTOM, egui::Vec2::new(-8.0, -8.0))
+                .show(ctx, |ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Too few iterations for this view.");
+                            if ui.button(format!("Use {}", suggestion)).clicked() {
+                                *iterations = suggestion;
+                            }
+                        });
+                    });
+                });
+        }
         // Apply changes
+        self.state.boundary_preview = dragging_constant && self.state.config.julia.is_some();
         {
-            let config = &mut self.state.config;
-            if config != &previous_state {
-                if config.algo != previous_state.algo {
-                    let new_state = Config::new(config.algo.clone());
+            let jumped = jump_to.is_some();
+            if let Some(config) = jump_to {
+                self.state.config = config;
+            }
+            if self.state != previous_state
+                || self.view != previous_view
+                || self.show_aa_samples != previous_show_aa_samples
+            {
+                // The preview of the last render is no longer needed.
+                self.preview_wanted.store(0, Ordering::SeqCst);
+                let config = &mut self.state.config;
+                let switched_julia =
+                    config.julia.is_some() != previous_state.config.julia.is_some();
+                if (config.algo != previous_state.config.algo || switched_julia) && !jumped {
+                    let new_state = match config.julia {
+                        Some(c) if switched_julia => Config::new_julia(config.algo.clone(), c),
+                        _ => Config::new(config.algo.clone()),
+                    };
                     *config = new_state;
+                    use_fern_density(config);
                 }
                 self.request_redraw(frame.clone());
             }
         }
+        // Session log
+        if let Some(session) = &mut self.session {
+            let now = ctx.input().time;
+            let config = &self.state.config;
+            let changed = match &self.pending_log {
+                Some((pending, _)) => pending != config,
+                None => session.entries().last().map(|entry| &entry.config) != Some(config),
+            };
+            if changed {
+                self.pending_log = Some((config.clone(), now));
+            }
+            if let Some((pending, since)) = &self.pending_log {
+                // Only log views which are stayed on, not every step of scrolling.
+                if now - since >= SESSION_SETTLE {
+                    if let Err(err) = session.append(pending) {
+                        log::error!("Failed to log the session: {}", err);
+                    }
+                    self.pending_log = None;
+                    let first_id = session.first_id();
+                    self.thumbnails.retain(|id, _| match id {
+                        ThumbnailId::Session(id) => *id >= first_id,
+                        ThumbnailId::Sweep { .. } => true,
+                    });
+                } else {
+                    ctx.request_repaint();
+                }
+            }
+        }
+    }
+}
+
+/// How the image of `shown` looks with `options`, `view` and `show_aa_samples`, if they only
+/// move, zoom or resize it, see [`view::approximation`]. [`None`] if they're the same.
+fn approximation(
+    shown: &Shown,
+    options: &Options,
+    view: View,
+    show_aa_samples: bool,
+) -> Option<view::Approximation> {
+    let (shown_options, shown_view, shown_aa_samples) = shown;
+    let same_image = Options {
+        config: shown_options.config.clone(),
+        ..options.clone()
+    };
+    if *shown_view != view
+        || *shown_aa_samples != show_aa_samples
+        || same_image != *shown_options
+        || shown_options.config == options.config
+    {
+        return None;
+    }
+    view::approximation(&shown_options.config, &options.config)
+}
+
+/// Where the image of `size` pixels is shown in `available`, with `pixels_per_point`: centered
+/// and as large as fits, keeping its aspect ratio.
+///
+/// The math is in physical pixels, so the image starts on a pixel of the screen and, when it's
+/// within [`RESIZE_THRESHOLD`] of fitting, is shown at its own size. Every pixel of the image is
+/// then exactly one of the screen, like in match window mode.
+fn fit_to_pixels(size: egui::Vec2, available: egui::Rect, pixels_per_point: f32) -> egui::Rect {
+    let available_pixels = available.size() * pixels_per_point;
+    let aspect_ratio = size.x / size.y;
+    let mut space = available_pixels;
+    space.y = cmp::min(F32Ord(available_pixels.y), F32Ord(space.x / aspect_ratio)).0;
+    space.x = cmp::min(F32Ord(available_pixels.x), F32Ord(space.y * aspect_ratio)).0;
+    let native =
+        (space.x - size.x).abs() < RESIZE_THRESHOLD && (space.y - size.y).abs() < RESIZE_THRESHOLD;
+    let space = if native {
+        size
+    } else {
+        egui::Vec2::new(space.x.floor(), space.y.floor())
+    };
+    let min = |available_min: f32, available: f32, space: f32| {
+        (available_min * pixels_per_point + (available - space) / 2.0).round() / pixels_per_point
+    };
+    egui::Rect::from_min_size(
+        egui::Pos2::new(
+            min(available.min.x, available_pixels.x, space.x),
+            min(available.min.y, available_pixels.y, space.y),
+        ),
+        space / pixels_per_point,
+    )
+}
+/// The warning of a slider which clips the image, see [`render::Clipping`].
+fn clipping_warning(clipping: render::Clipping) -> Option<&'static str> {
+    if clipping.is_dark() {
+        Some("Almost all of the image is black. Right-click to reset.")
+    } else if clipping.is_bright() {
+        Some("Almost all of the image is white. Right-click to reset.")
+    } else {
+        None
+    }
+}
+/// Shows the widget of `add_contents` in amber, explaining `warning` on hover, if there's one.
+fn warned(
+    ui: &mut egui::Ui,
+    warning: Option<&str>,
+    add_contents: impl FnOnce(&mut egui::Ui) -> egui::Response,
+) -> egui::Response {
+    let warning = match warning {
+        Some(warning) => warning,
+        None => return add_contents(ui),
+    };
+    ui.scope(|ui| {
+        let visuals = ui.visuals_mut();
+        visuals.override_text_color = Some(WARNING_COLOR);
+        for widget in [
+            &mut visuals.widgets.inactive,
+            &mut visuals.widgets.hovered,
+            &mut visuals.widgets.active,
+        ] {
+            widget.bg_fill = WARNING_COLOR;
+        }
+        add_contents(ui).on_hover_text(warning)
+    })
+    .inner
+}
+/// Adds "Reset to default" to the context menu of `response`, which sets `value` to `default`.
+fn reset_menu<T>(response: egui::Response, value: &mut T, default: T) {
+    response.context_menu(|ui| {
+        if ui.button("Reset to default").clicked() {
+            *value = default;
+            ui.close_menu();
+        }
+    });
+}
+
+/// `config` at the size of the thumbnails of the session panel. Scaling both sides keeps the
+/// viewport.
+fn session_thumbnail(config: &Config) -> Config {
+    let width = THUMBNAIL_HEIGHT as f64 * config.width as f64 / config.height as f64;
+    let width = (width.round() as u32).clamp(1, THUMBNAIL_HEIGHT * 4);
+    Config {
+        width,
+        height: THUMBNAIL_HEIGHT,
+        ..config.clone()
+    }
+}
+
+/// The sample of `samples` of `config` at the point `relative` to the size of the image.
+fn sample_at(
+    config: &Config,
+    samples: &[crate::Sample],
+    relative: egui::Vec2,
+) -> Option<crate::Sample> {
+    let (width, height) = (config.width as usize, config.height as usize);
+    let x = (relative.x * width as f32) as usize;
+    let y = (relative.y * height as f32) as usize;
+    if x >= width || y >= height {
+        return None;
+    }
+    samples.get(y * width + x).copied()
+}
+/// The raw values of `sample`, shown by the spot meter.
+fn describe_sample(sample: crate::Sample) -> String {
+    format!(
+        "{} after {:.3} iterations\n|z| = {:.4e}\nClosest to 0: {:.4}",
+        if sample.escaped { "Escaped" } else { "Inside" },
+        sample.iterations,
+        sample.final_z.squared_distance().sqrt(),
+        sample.min_orbit_radius
+    )
+}
+/// `image` as it looks with `cvd`, see [`color::simulate_image`].
+fn simulated(image: &egui::ColorImage, cvd: Cvd) -> egui::ColorImage {
+    let mut pixels: Vec<RGB> = image
+        .pixels
+        .iter()
+        .map(|pixel| RGB::new(pixel.r(), pixel.g(), pixel.b()))
+        .collect();
+    color::simulate_image(&mut pixels, cvd);
+    egui::ColorImage {
+        size: image.size,
+        pixels: pixels
+            .iter()
+            .map(|pixel| egui::Color32::from_rgb(pixel.r, pixel.g, pixel.b))
+            .collect(),
+    }
+}
+
+/// The Julia set of `constant` with the settings of `config`, seen whole.
+fn julia_view(config: &Config, constant: Imaginary) -> Config {
+    let defaults = calc::defaults(&config.algo);
+    Config {
+        julia: Some(constant),
+        pos: defaults.julia_pos,
+        pos_low: Imaginary::ZERO,
+        scale: defaults.scale,
+        exp_map: false,
+        ..config.clone()
+    }
+}
+
+/// Labels the imaginary part of the center, with which way it grows, see [`Config::y_up`].
+fn imaginary_label(ui: &mut egui::Ui, y_up: bool) {
+    let (label, direction) = if y_up {
+        ("i ↑", "upwards, like in mathematics")
+    } else {
+        ("i ↓", "downwards")
+    };
+    ui.label(label)
+        .on_hover_text(format!("The imaginary part grows {}.", direction));
+}
+
+/// Makes the fern look the same when changing the iterations, without changing the weight.
+fn use_fern_density(config: &mut Config) {
+    if !config.fern_normalize {
+        config.fern_normalize = true;
+        config.color_weight = calc::FERN_NORMALIZED_WEIGHT;
     }
 }
 
@@ -423,3 +2042,114 @@ pub mod vec2ui {
         }
     }
 }
+
+/// A gradient bar to edit a [`Palette`].
+///
+/// Double-click to add a stop, drag a stop to move it and right-click it to delete it. Clicking
+/// a stop selects it, to change it's color.
+pub mod paletteui {
+    use super::*;
+    pub struct GradientEdit<'a> {
+        size: egui::Vec2,
+        /// How close to a stop, in points, the pointer has to be to grab it.
+        grab_radius: f32,
+        palette: &'a mut Palette,
+        selected: &'a mut Option<usize>,
+    }
+    impl<'a> GradientEdit<'a> {
+        pub fn new(
+            palette: &'a mut Palette,
+            selected: &'a mut Option<usize>,
+            size: egui::Vec2,
+        ) -> Self {
+            GradientEdit {
+                size,
+                grab_radius: 6.0,
+                palette,
+                selected,
+            }
+        }
+
+        fn position_to_x(rect: &egui::Rect, position: f64) -> f32 {
+            egui::remap_clamp(position as f32, 0.0..=1.0, rect.x_range())
+        }
+        fn x_to_position(rect: &egui::Rect, x: f32) -> f64 {
+            egui::remap_clamp(x, rect.x_range(), 0.0..=1.0) as f64
+        }
+        /// The index of the stop closest to `x`, if it's within [`Self::grab_radius`].
+        fn stop_at(&self, rect: &egui::Rect, x: f32) -> Option<usize> {
+            self.palette
+                .stops()
+                .iter()
+                .map(|stop| (Self::position_to_x(rect, stop.position) - x).abs())
+                .enumerate()
+                .filter(|(_, distance)| *distance <= self.grab_radius)
+                .min_by_key(|(_, distance)| F32Ord(*distance))
+                .map(|(index, _)| index)
+        }
+    }
+    fn color32(color: RGB) -> egui::Color32 {
+        egui::Color32::from_rgb(color.r, color.g, color.b)
+    }
+    impl egui::Widget for GradientEdit<'_> {
+        fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+            let (rect, mut response) =
+                ui.allocate_exact_size(self.size, egui::Sense::click_and_drag());
+
+            if let Some(pointer) = response.interact_pointer_pos() {
+                let position = Self::x_to_position(&rect, pointer.x);
+                let stop = self.stop_at(&rect, pointer.x);
+                if response.secondary_clicked() {
+                    if let Some(index) = stop {
+                        if self.palette.remove(index) {
+                            *self.selected = None;
+                            response.mark_changed();
+                        }
+                    }
+                } else if response.double_clicked() && stop.is_none() {
+                    let color = self.palette.color_at(position);
+                    *self.selected = Some(self.palette.insert(position, color));
+                    response.mark_changed();
+                } else if response.clicked() || response.drag_started() {
+                    *self.selected = stop;
+                } else if response.dragged() {
+                    if let Some(index) = *self.selected {
+                        *self.selected = Some(self.palette.set_position(index, position));
+                        response.mark_changed();
+                    }
+                }
+            }
+
+            let painter = ui.painter();
+            // One strip per point of width.
+            let strips = (rect.width() as usize).max(1);
+            for strip in 0..strips {
+                let left = rect.left() + strip as f32;
+                let position = Self::x_to_position(&rect, left + 0.5);
+                let strip = egui::Rect::from_min_max(
+                    egui::Pos2::new(left, rect.top()),
+                    egui::Pos2::new(left + 1.0, rect.bottom()),
+                );
+                painter.rect_filled(strip, 0.0, color32(self.palette.color_at(position)));
+            }
+            let stroke = ui.style().interact(&response).fg_stroke;
+            let selected_stroke = ui.style().visuals.widgets.active.fg_stroke;
+            for (index, stop) in self.palette.stops().iter().enumerate() {
+                let center =
+                    egui::Pos2::new(Self::position_to_x(&rect, stop.position), rect.bottom());
+                painter.circle(
+                    center,
+                    4.0,
+                    color32(stop.color),
+                    if *self.selected == Some(index) {
+                        selected_stroke
+                    } else {
+                        stroke
+                    },
+                );
+            }
+
+            response
+        }
+    }
+}
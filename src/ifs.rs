@@ -0,0 +1,284 @@
+//! Iterated function systems, of which the Barnsley fern is one, and mutations of them to explore.
+//!
+//! An [`Ifs`] is plotted like the fern, see [`crate::get_ifs_image`].
+
+use crate::grid;
+use crate::{Config, Imaginary, RGB};
+use rand::{Rng, SeedableRng};
+use std::fmt::{self, Display};
+
+/// The columns and rows of the contact sheet of [`mutation_sheet`].
+pub const SHEET_SIZE: usize = 3;
+
+/// The affine map `(x, y) → (a x + b y + e, c x + d y + f)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub e: f64,
+    pub f: f64,
+    /// How often this transform is picked, relative to the others.
+    pub weight: f64,
+}
+impl Transform {
+    #[inline(always)]
+    pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            self.a * x + self.b * y + self.e,
+            self.c * x + self.d * y + self.f,
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ifs {
+    transforms: Vec<Transform>,
+    /// The cumulative weight of the transforms up to and including each, divided by the total.
+    thresholds: Vec<f64>,
+    /// See [`Self::bounds`].
+    bounds: (Imaginary, Imaginary),
+}
+impl Ifs {
+    /// Returns an error if there are no transforms or the weights aren't positive.
+    pub fn new(transforms: Vec<Transform>) -> Result<Self, String> {
+        if transforms.is_empty() {
+            return Err("an IFS needs at least one transform".into());
+        }
+        if transforms
+            .iter()
+            .any(|transform| !(transform.weight >= 0.0 && transform.weight.is_finite()))
+        {
+            return Err("the weights of the transforms can't be negative".into());
+        }
+        let total: f64 = transforms.iter().map(|transform| transform.weight).sum();
+        if total <= 0.0 {
+            return Err("at least one transform needs a positive weight".into());
+        }
+        let mut sum = 0.0;
+        let thresholds = transforms
+            .iter()
+            .map(|transform| {
+                sum += transform.weight;
+                sum / total
+            })
+            .collect();
+        let mut ifs = Self {
+            transforms,
+            thresholds,
+            bounds: (Imaginary::ZERO, Imaginary::ZERO),
+        };
+        ifs.bounds = ifs.estimate_bounds();
+        Ok(ifs)
+    }
+    /// The Barnsley fern, see <https://en.wikipedia.org/wiki/Barnsley_fern#Python>.
+    pub fn barnsley() -> Self {
+        let transform = |a, b, c, d, e, f, weight| Transform {
+            a,
+            b,
+            c,
+            d,
+            e,
+            f,
+            weight,
+        };
+        Self::new(vec![
+            transform(0.00, 0.00, 0.00, 0.16, 0.00, 0.00, 0.01),
+            transform(0.85, 0.04, -0.04, 0.85, 0.00, 1.60, 0.85),
+            transform(0.20, -0.26, 0.23, 0.22, 0.00, 1.60, 0.07),
+            transform(-0.15, 0.28, 0.26, 0.24, 0.00, 0.44, 0.07),
+        ])
+        .expect("the fern is a valid IFS")
+    }
+    pub fn transforms(&self) -> &[Transform] {
+        &self.transforms
+    }
+
+    /// Applies the transform chosen by `r` in `0..1`, following the weights.
+    #[inline(always)]
+    pub fn step(&self, x: f64, y: f64, r: f64) -> (f64, f64) {
+        let index = self
+            .thresholds
+            .iter()
+            .position(|threshold| r < *threshold)
+            .unwrap_or(self.transforms.len() - 1);
+        self.transforms[index].apply(x, y)
+    }
+    /// The bounding box of the attractor, as `(min, max)`.
+    ///
+    /// It's estimated once, when the IFS is made, by iterating a fixed sequence of points. Every
+    /// plot of the IFS frames it the same, without paying for the estimate again.
+    pub fn bounds(&self) -> (Imaginary, Imaginary) {
+        self.bounds
+    }
+    fn estimate_bounds(&self) -> (Imaginary, Imaginary) {
+        let mut rng = rand::rngs::SmallRng::seed_from_u64(0);
+        let (mut x, mut y) = (0.0, 0.0);
+        let mut min = Imaginary { re: x, im: y };
+        let mut max = min;
+        for _ in 0..50_000 {
+            let (next_x, next_y) = self.step(x, y, rng.gen());
+            x = next_x;
+            y = next_y;
+            min.re = min.re.min(x);
+            min.im = min.im.min(y);
+            max.re = max.re.max(x);
+            max.im = max.im.max(y);
+        }
+        (min, max)
+    }
+
+    /// Multiplies every coefficient and weight by a random factor in `1 ± amount`.
+    ///
+    /// ```
+    /// # use fractal_renderer::ifs::Ifs;
+    /// # use rand::SeedableRng;
+    /// let fern = Ifs::barnsley();
+    /// let mut rng = rand::rngs::SmallRng::seed_from_u64(1);
+    /// let mutant = fern.mutate(0.05, &mut rng);
+    /// for (fern, mutant) in fern.transforms().iter().zip(mutant.transforms()) {
+    ///     assert!((mutant.d - fern.d).abs() <= fern.d.abs() * 0.05);
+    ///     assert!((mutant.weight - fern.weight).abs() <= fern.weight * 0.05);
+    /// }
+    /// // The same seed gives the same mutant.
+    /// let mut rng = rand::rngs::SmallRng::seed_from_u64(1);
+    /// assert_eq!(fern.mutate(0.05, &mut rng), mutant);
+    /// ```
+    pub fn mutate(&self, amount: f64, rng: &mut impl Rng) -> Self {
+        let mut vary = |value: f64| value * (1.0 + (rng.gen::<f64>() * 2.0 - 1.0) * amount);
+        let transforms = self
+            .transforms
+            .iter()
+            .map(|transform| Transform {
+                a: vary(transform.a),
+                b: vary(transform.b),
+                c: vary(transform.c),
+                d: vary(transform.d),
+                e: vary(transform.e),
+                f: vary(transform.f),
+                weight: vary(transform.weight),
+            })
+            .collect();
+        Self::new(transforms).expect("the weights stay positive if `amount` is below 1")
+    }
+}
+impl Default for Ifs {
+    fn default() -> Self {
+        Self::barnsley()
+    }
+}
+/// The format of [`parse`]: `a,b,c,d,e,f,weight` for each transform, separated by `;`.
+impl Display for Ifs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, t) in self.transforms.iter().enumerate() {
+            if i > 0 {
+                write!(f, ";")?;
+            }
+            write!(
+                f,
+                "{},{},{},{},{},{},{}",
+                t.a, t.b, t.c, t.d, t.e, t.f, t.weight
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses `a,b,c,d,e,f,weight` for each transform, separated by `;`.
+///
+/// ```
+/// # use fractal_renderer::ifs::{parse, Ifs};
+/// let fern = Ifs::barnsley();
+/// assert_eq!(parse(&fern.to_string()), Ok(fern));
+/// assert!(parse("0.5,0,0,0.5,0,0").is_err());
+/// ```
+pub fn parse(s: &str) -> Result<Ifs, String> {
+    let transforms = s
+        .split(';')
+        .map(|transform| {
+            let values = transform
+                .split(',')
+                .map(|value| {
+                    value
+                        .trim()
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|value| value.is_finite())
+                        .ok_or_else(|| format!("{:?} is not a number", value.trim()))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            match values[..] {
+                [a, b, c, d, e, f, weight] => Ok(Transform {
+                    a,
+                    b,
+                    c,
+                    d,
+                    e,
+                    f,
+                    weight,
+                }),
+                _ => Err(format!(
+                    "expected a,b,c,d,e,f,weight for each transform, got {:?}",
+                    transform
+                )),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ifs::new(transforms)
+}
+
+/// Parses the amount of [`Mutation::amount`], which is between 0 and 1.
+pub fn parse_amount(s: &str) -> Result<f64, String> {
+    match s.trim().parse::<f64>() {
+        Ok(amount) if (0.0..1.0).contains(&amount) => Ok(amount),
+        _ => Err(format!(
+            "{:?} is not a fraction between 0 and 1, like 0.05 for 5%",
+            s
+        )),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mutation {
+    /// The largest relative change of each coefficient, see [`Ifs::mutate`].
+    pub amount: f64,
+    /// The seed of the mutations, so a sheet can be rendered again.
+    pub seed: u64,
+}
+impl Mutation {
+    /// The mutants of `ifs` on the sheet, row by row.
+    pub fn mutants(&self, ifs: &Ifs) -> Vec<Ifs> {
+        let mut rng = rand::rngs::SmallRng::seed_from_u64(self.seed);
+        (0..SHEET_SIZE * SHEET_SIZE)
+            .map(|_| ifs.mutate(self.amount, &mut rng))
+            .collect()
+    }
+}
+
+/// Renders a contact sheet of the [`SHEET_SIZE`]×[`SHEET_SIZE`] mutants of `ifs`, each framed to
+/// fit its cell, and logs the coefficients of each.
+///
+/// `seed` is used for plotting, like [`crate::get_ifs_image`].
+pub fn mutation_sheet(
+    config: &Config,
+    ifs: &Ifs,
+    mutation: &Mutation,
+    seed: Option<u64>,
+) -> Vec<RGB> {
+    let mutants = mutation.mutants(ifs);
+    let (width, height) = (config.width as usize, config.height as usize);
+    let cells = grid::cells(width, height, SHEET_SIZE, SHEET_SIZE);
+    let contents = grid::compose(width, height, &cells, |cell| {
+        let config = Config {
+            width: cell.width as u32,
+            height: cell.height as u32,
+            ..config.clone()
+        };
+        crate::get_ifs_image(&config, &mutants[cell.row * SHEET_SIZE + cell.column], seed)
+    });
+    for (cell, mutant) in cells.iter().zip(&mutants) {
+        log::debug!("Cell at ({}, {}): --ifs '{}'", cell.x, cell.y, mutant);
+    }
+    contents
+}
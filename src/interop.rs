@@ -0,0 +1,44 @@
+//! Conversions to and from the [`image`] crate.
+//!
+//! The buffers are reused when possible, as [`RGB`] has the same layout as three bytes.
+
+use crate::{ImageBuffer, RGB};
+use std::fmt::{self, Display};
+
+impl From<ImageBuffer> for image::RgbImage {
+    fn from(buffer: ImageBuffer) -> Self {
+        let (width, height) = (buffer.width() as u32, buffer.height() as u32);
+        let bytes: Vec<u8> = bytemuck::cast_vec(buffer.into_contents());
+        image::RgbImage::from_raw(width, height, bytes)
+            .expect("an ImageBuffer always has width × height pixels")
+    }
+}
+
+/// The [`image::RgbImage`] has more pixels than can be addressed.
+#[derive(Debug)]
+pub struct TooLarge;
+impl Display for TooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the image is too large to be addressed")
+    }
+}
+impl std::error::Error for TooLarge {}
+
+impl TryFrom<image::RgbImage> for ImageBuffer {
+    type Error = TooLarge;
+    fn try_from(image: image::RgbImage) -> Result<Self, Self::Error> {
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let len = width
+            .checked_mul(height)
+            .and_then(|pixels| pixels.checked_mul(3))
+            .ok_or(TooLarge)?;
+        let mut bytes = image.into_raw();
+        bytes.truncate(len);
+        // The capacity must also be a multiple of 3 to reuse the allocation.
+        let contents: Vec<RGB> = match bytemuck::allocation::try_cast_vec(bytes) {
+            Ok(contents) => contents,
+            Err((_, bytes)) => bytemuck::cast_slice(&bytes).to_vec(),
+        };
+        ImageBuffer::from_contents(contents, width, height).ok_or(TooLarge)
+    }
+}
@@ -0,0 +1,329 @@
+//! Export of the samples of escape-time fractals, see `--export-iterations`, and continuing a
+//! render with more iterations from them, see [`deepen`] and `--deepen`.
+//!
+//! Points which escaped don't change with more iterations, so only the others are iterated
+//! again, from the last point of their orbit. Deep zooms with many iterations often have few of
+//! those, which makes deepening much faster than rendering again.
+//!
+//! # Format
+//!
+//! The samples are written as a NumPy `.npy` array of `height` rows of `width` records, which
+//! can be loaded with `numpy.load`. The fields of the records are the version of the format, and
+//! [`read`] only accepts these. Plain arrays of iterations can't be deepened, as the orbits can't
+//! be continued without their last point.
+//!
+//! | field              | type   | content                                                    |
+//! |--------------------|--------|------------------------------------------------------------|
+//! | `iterations`       | `<f8`  | [`Sample::iterations`], or the limit for points inside     |
+//! | `escaped`          | `\|b1` | [`Sample::escaped`]                                        |
+//! | `final_re`         | `<f8`  | the real part of [`Sample::final_z`]                       |
+//! | `final_im`         | `<f8`  | the imaginary part of [`Sample::final_z`]                  |
+//! | `min_orbit_radius` | `<f8`  | [`Sample::min_orbit_radius`]                               |
+//!
+//! The view isn't stored, so deepen with the same arguments as the original render, but more
+//! iterations.
+
+use crate::{format, Config, Imaginary, Sample};
+use rayon::iter::{IndexedParallelIterator, ParallelIterator};
+use rayon::slice::ParallelSliceMut;
+use std::fmt::{self, Display};
+use std::fs;
+use std::io::{self, Read, Write};
+
+const MAGIC: &[u8; 6] = b"\x93NUMPY";
+/// The fields of the records, as NumPy writes them.
+const FIELDS: &str = "[('iterations', '<f8'), ('escaped', '|b1'), ('final_re', '<f8'), \
+                      ('final_im', '<f8'), ('min_orbit_radius', '<f8')]";
+const RECORD_LEN: usize = 8 + 1 + 8 + 8 + 8;
+
+#[derive(Debug)]
+pub enum ExportError {
+    Io(io::Error),
+    /// The file isn't a `.npy` array of samples.
+    Corrupt,
+    /// The array has other fields, like plain iterations without the last points of the
+    /// orbits.
+    Fields(String),
+    /// The export is `width`×`height`, which differs from the config.
+    Size {
+        width: usize,
+        height: usize,
+    },
+    /// The export has at least as many iterations as the config.
+    Iterations(u32),
+    /// The export can't be deepened with double-double precision, as the last points are
+    /// rounded to `f64`.
+    DoubleDouble,
+}
+impl Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "failed to read the iterations: {}", err),
+            Self::Corrupt => write!(f, "the file isn't an export of --export-iterations"),
+            Self::Fields(fields) => write!(
+                f,
+                "the array has the fields {}, not those of --export-iterations with the last \
+                 points of the orbits",
+                fields
+            ),
+            Self::Size { width, height } => {
+                write!(
+                    f,
+                    "the export is {}×{}, not the size of the render",
+                    width, height
+                )
+            }
+            Self::Iterations(iterations) => write!(
+                f,
+                "the export already has {} iterations; deepen with more",
+                iterations
+            ),
+            Self::DoubleDouble => write!(
+                f,
+                "renders in double-double precision can't be deepened, \
+                 as the last points are exported in f64 precision"
+            ),
+        }
+    }
+}
+impl std::error::Error for ExportError {}
+impl From<io::Error> for ExportError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// The samples of an export, see [`read`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Export {
+    pub width: usize,
+    pub height: usize,
+    pub samples: Vec<Sample>,
+    /// The iterations the points inside were iterated for, or [`None`] if every point escaped.
+    pub iterations: Option<u32>,
+}
+
+/// Writes the `samples` of `config` in the [format](self#format).
+///
+/// ```
+/// # use fractal_renderer::{get_samples, iterations, Algo, Config, Imaginary};
+/// let mut config = Config::new_julia(Algo::Mandelbrot, Imaginary::ZERO);
+/// config.width = 30;
+/// config.height = 20;
+/// let samples = get_samples(&config);
+/// let mut data = Vec::new();
+/// iterations::write(&mut data, &samples, &config).unwrap();
+/// assert_eq!(&data[..6], b"\x93NUMPY");
+///
+/// let export = iterations::read(&data[..]).unwrap();
+/// assert_eq!((export.width, export.height), (30, 20));
+/// assert_eq!(export.iterations, Some(config.iterations));
+/// for (exported, sample) in export.samples.iter().zip(&samples) {
+///     assert_eq!(exported.final_z, sample.final_z);
+///     if sample.escaped {
+///         assert_eq!(exported, sample);
+///     }
+/// }
+/// ```
+pub fn write(mut writer: impl Write, samples: &[Sample], config: &Config) -> io::Result<()> {
+    let mut header = format!(
+        "{{'descr': {}, 'fortran_order': False, 'shape': ({}, {}), }}",
+        FIELDS, config.height, config.width
+    );
+    // The data starts at a multiple of 64 bytes, after the magic, version and length.
+    let unpadded = MAGIC.len() + 2 + 2 + header.len() + 1;
+    header.extend(std::iter::repeat(' ').take((64 - unpadded % 64) % 64));
+    header.push('\n');
+
+    let mut data = Vec::with_capacity(MAGIC.len() + 4 + header.len() + samples.len() * RECORD_LEN);
+    data.extend_from_slice(MAGIC);
+    data.extend_from_slice(&[1, 0]);
+    data.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    data.extend_from_slice(header.as_bytes());
+    for sample in samples {
+        let iterations = if sample.escaped {
+            sample.iterations
+        } else {
+            config.iterations as f64
+        };
+        data.extend_from_slice(&iterations.to_le_bytes());
+        data.push(sample.escaped as u8);
+        data.extend_from_slice(&sample.final_z.re.to_le_bytes());
+        data.extend_from_slice(&sample.final_z.im.to_le_bytes());
+        data.extend_from_slice(&sample.min_orbit_radius.to_le_bytes());
+    }
+    writer.write_all(&data)
+}
+/// Writes the `samples` of `config` to `path`, see [`write`] and [`format::write_atomically`].
+pub fn save(
+    path: &str,
+    on_conflict: format::OnConflict,
+    samples: &[Sample],
+    config: &Config,
+) -> io::Result<String> {
+    format::write_atomically(path, on_conflict, |file| write(file, samples, config))
+}
+
+/// Reads an export written by [`write`].
+pub fn read(mut reader: impl Read) -> Result<Export, ExportError> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    if data.len() < MAGIC.len() + 4 || &data[..MAGIC.len()] != MAGIC {
+        return Err(ExportError::Corrupt);
+    }
+    // Version 2.0 and 3.0 of `.npy` have a longer length.
+    let (header_start, header_len) = match data[MAGIC.len()] {
+        1 => (10, u16::from_le_bytes([data[8], data[9]]) as usize),
+        2 | 3 if data.len() >= 12 => (
+            12,
+            u32::from_le_bytes([data[8], data[9], data[10], data[11]]) as usize,
+        ),
+        _ => return Err(ExportError::Corrupt),
+    };
+    let header = data
+        .get(header_start..header_start + header_len)
+        .and_then(|header| std::str::from_utf8(header).ok())
+        .ok_or(ExportError::Corrupt)?;
+    // NumPy writes the keys in this order.
+    let value = |key: &str, end: &str| {
+        let start = header.find(key)? + key.len();
+        let len = header[start..].find(end)?;
+        Some(&header[start..start + len])
+    };
+    match value("'descr': ", ", 'fortran_order'") {
+        Some(fields) if fields == FIELDS => {}
+        Some(fields) => return Err(ExportError::Fields(fields.to_owned())),
+        None => return Err(ExportError::Corrupt),
+    }
+    if value("'fortran_order': ", ",") != Some("False") {
+        return Err(ExportError::Corrupt);
+    }
+    let shape: Vec<usize> = value("'shape': (", ")")
+        .ok_or(ExportError::Corrupt)?
+        .split(',')
+        .map(str::trim)
+        .filter(|side| !side.is_empty())
+        .map(|side| side.parse().map_err(|_| ExportError::Corrupt))
+        .collect::<Result<_, _>>()?;
+    let (height, width) = match shape[..] {
+        [height, width] => (height, width),
+        _ => return Err(ExportError::Corrupt),
+    };
+
+    let records = &data[header_start + header_len..];
+    if records.len() != width * height * RECORD_LEN {
+        return Err(ExportError::Corrupt);
+    }
+    let f64_at = |record: &[u8], i: usize| {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(&record[i..i + 8]);
+        f64::from_le_bytes(bytes)
+    };
+    let mut iterations = None;
+    let samples = records
+        .chunks_exact(RECORD_LEN)
+        .map(|record| {
+            let sample = Sample {
+                iterations: f64_at(record, 0),
+                escaped: record[8] != 0,
+                final_z: Imaginary {
+                    re: f64_at(record, 9),
+                    im: f64_at(record, 17),
+                },
+                min_orbit_radius: f64_at(record, 25),
+            };
+            if !sample.escaped {
+                iterations = Some(sample.iterations as u32);
+            }
+            sample
+        })
+        .collect();
+    Ok(Export {
+        width,
+        height,
+        samples,
+        iterations,
+    })
+}
+/// Reads the export at `path`, see [`read`].
+pub fn load(path: &str) -> Result<Export, ExportError> {
+    read(io::BufReader::new(fs::File::open(path)?))
+}
+
+/// The samples of `config`, continued from `export` of the same view with fewer iterations.
+///
+/// The points which didn't escape are iterated from the last point of their orbit, and those
+/// which escaped at the limit of the export from the start, as their smoothed iterations were
+/// clamped to it. The others are kept.
+///
+/// ```
+/// # use fractal_renderer::{get_samples, iterations, Algo, Config};
+/// let mut config = Config::new(Algo::Mandelbrot);
+/// config.width = 60;
+/// config.height = 40;
+/// config.iterations = 30;
+/// let mut data = Vec::new();
+/// iterations::write(&mut data, &get_samples(&config), &config).unwrap();
+/// let export = iterations::read(&data[..]).unwrap();
+///
+/// config.iterations = 400;
+/// let deepened = iterations::deepen(&config, &export).unwrap();
+/// assert_eq!(deepened.samples, get_samples(&config));
+/// assert!(deepened.iterated < 60 * 40 / 2);
+///
+/// config.iterations = 20;
+/// assert!(iterations::deepen(&config, &export).is_err());
+/// ```
+pub fn deepen(config: &Config, export: &Export) -> Result<Deepened, ExportError> {
+    let (width, height) = (config.width as usize, config.height as usize);
+    if (export.width, export.height) != (width, height) {
+        return Err(ExportError::Size {
+            width: export.width,
+            height: export.height,
+        });
+    }
+    if config.uses_double_double() {
+        return Err(ExportError::DoubleDouble);
+    }
+    let previous = match export.iterations {
+        Some(iterations) if iterations >= config.iterations => {
+            return Err(ExportError::Iterations(iterations))
+        }
+        Some(iterations) => iterations,
+        // Every point escaped, so none change.
+        None => {
+            return Ok(Deepened {
+                samples: export.samples.clone(),
+                iterated: 0,
+            })
+        }
+    };
+    let mut samples = export.samples.clone();
+    let iterated = samples
+        .par_chunks_mut(width)
+        .enumerate()
+        .map(|(y, row)| {
+            let mut iterated = 0;
+            for (x, sample) in row.iter_mut().enumerate() {
+                let (x, y) = (x as f64, y as f64);
+                if !sample.escaped {
+                    *sample = calc::continue_sample(config, x, y, *sample, previous);
+                } else if sample.iterations >= previous as f64 {
+                    *sample = crate::sample(config, x, y);
+                } else {
+                    continue;
+                }
+                iterated += 1;
+            }
+            iterated
+        })
+        .sum();
+    Ok(Deepened { samples, iterated })
+}
+/// The result of [`deepen`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deepened {
+    pub samples: Vec<Sample>,
+    /// The number of samples which were iterated.
+    pub iterated: usize,
+}
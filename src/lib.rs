@@ -1,13 +1,119 @@
-pub use calc::{get_recursive_pixel, Algo, Config, Imaginary, RGB};
+pub use calc::{
+    colorize, dd, defaults, get_recursive_pixel, mapping, resolved, sample, sample_point, Algo,
+    AlgoDefaults, Backend, Background, Bailout, ComplexRect, Config, ConfigError, Imaginary,
+    InteriorMapping, Orientation, Precision, Rotation, Sample, SmoothMode, CHECKERBOARD_SQUARE,
+    RGB,
+};
+pub use render::{render, RenderError, RenderOutput, RenderStats};
+
+use features::Feature;
+use format::EncoderSettings;
+use std::cmp;
+use std::fmt::{self, Display};
 use std::io::Write;
+use std::ops::Range;
+use std::str::FromStr;
+use std::time::Instant;
 
-use clap::{Arg, ArgGroup};
+use clap::{Arg, ArgGroup, ArgMatches};
 use rand::{Rng, SeedableRng};
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use rayon::iter::{IntoParallelIterator, ParallelBridge, ParallelIterator};
 
+#[path = "aa.rs"]
+pub mod aa;
+#[path = "animation.rs"]
+pub mod animation;
+#[path = "annotate.rs"]
+pub mod annotate;
+#[path = "ao.rs"]
+pub mod ao;
+#[path = "axes.rs"]
+pub mod axes;
+#[path = "batch.rs"]
+pub mod batch;
+#[path = "benchmark.rs"]
+pub mod benchmark;
+#[path = "buddhabrot.rs"]
+pub mod buddhabrot;
+#[path = "camera.rs"]
+pub mod camera;
+#[path = "checkpoint.rs"]
+pub mod checkpoint;
+#[path = "color.rs"]
+pub mod color;
+#[path = "deterministic.rs"]
+pub mod deterministic;
+#[path = "diff.rs"]
+pub mod diff;
+#[path = "digest.rs"]
+pub mod digest;
+#[path = "discovery.rs"]
+pub mod discovery;
+#[path = "error_map.rs"]
+pub mod error_map;
+#[path = "explore.rs"]
+pub mod explore;
+#[path = "features.rs"]
+pub mod features;
+#[cfg(feature = "ffi")]
+#[path = "ffi.rs"]
+pub mod ffi;
+#[path = "font.rs"]
+pub mod font;
+#[path = "format.rs"]
+pub mod format;
+#[path = "grid.rs"]
+pub mod grid;
 #[cfg(feature = "gui")]
 #[path = "gui.rs"]
 pub mod gui;
+#[path = "ifs.rs"]
+pub mod ifs;
+#[cfg(feature = "image-interop")]
+#[path = "interop.rs"]
+pub mod interop;
+#[path = "iterations.rs"]
+pub mod iterations;
+#[path = "location.rs"]
+pub mod location;
+#[path = "mask.rs"]
+pub mod mask;
+#[path = "orbit.rs"]
+pub mod orbit;
+#[path = "palette.rs"]
+pub mod palette;
+#[path = "parse.rs"]
+pub mod parse;
+#[path = "post.rs"]
+pub mod post;
+#[path = "refine.rs"]
+pub mod refine;
+#[path = "render.rs"]
+pub mod render;
+#[path = "resize.rs"]
+pub mod resize;
+#[path = "resources.rs"]
+pub mod resources;
+#[path = "sampler.rs"]
+pub mod sampler;
+#[path = "serve.rs"]
+pub mod serve;
+#[path = "session.rs"]
+pub mod session;
+#[path = "slice.rs"]
+pub mod slice;
+#[path = "template.rs"]
+pub mod template;
+#[path = "timing.rs"]
+pub mod timing;
+#[path = "verify.rs"]
+pub mod verify;
+#[path = "view.rs"]
+pub mod view;
+#[path = "wallpaper.rs"]
+pub mod wallpaper;
+#[path = "watch.rs"]
+pub mod watch;
 
 #[cfg(feature = "avif")]
 pub const fn transmute_rgb_slice(me: &[RGB]) -> &[ravif::RGB8] {
@@ -19,34 +125,447 @@ pub fn rgb_convert(rgb: RGB) -> ravif::RGB8 {
     ravif::RGB8::new(rgb.r, rgb.g, rgb.b)
 }
 
-fn parse_hex_rgb(s: &str) -> RGB {
-    let (p1, p2) = s.split_at(2);
-    let (p2, p3) = p2.split_at(2);
-    let r = u8::from_str_radix(p1, 16).expect("failed to parse hex color");
-    let g = u8::from_str_radix(p2, 16).expect("failed to parse hex color");
-    let b = u8::from_str_radix(p3, 16).expect("failed to parse hex color");
-    RGB::new(r, g, b)
+/// What's lost when rounding the number `s` to `value`, see [`Config::pos_low`].
+fn rounding_error(s: &str, value: f64) -> f64 {
+    s.parse::<dd::DoubleDouble>()
+        .map(|precise| (precise - value.into()).to_f64())
+        .unwrap_or(0.0)
 }
 
-pub fn get_options() -> Options {
-    let app = clap::App::new("fractal-renderer")
-        .about("Set `-d` for a more traditional look.")
+/// The subcommands of the command line. Each is a shorthand for the flag of its mode, like
+/// `serve ADDRESS` for `--serve ADDRESS`, which takes only the arguments which apply, so the
+/// others are unexpected. Without a subcommand, it's [`Command::Render`], which accepts every
+/// argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Render,
+    Animate,
+    Batch,
+    Serve,
+    Compare,
+    /// `--dry-run`, also listing the [features](features) of this binary.
+    Info,
+}
+impl Command {
+    pub const ALL: [Self; 6] = [
+        Self::Render,
+        Self::Animate,
+        Self::Batch,
+        Self::Serve,
+        Self::Compare,
+        Self::Info,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Render => "render",
+            Self::Animate => "animate",
+            Self::Batch => "batch",
+            Self::Serve => "serve",
+            Self::Compare => "compare",
+            Self::Info => "info",
+        }
+    }
+    fn about(self) -> &'static str {
+        match self {
+            Self::Render => "Render an image. The same as without a subcommand.",
+            Self::Animate => {
+                "Render FRAMES frames zooming from `--scale` to `--zoom-to`. \
+                 The same as `--animate FRAMES`."
+            }
+            Self::Batch => "Render all jobs in a TOML manifest. The same as `--batch MANIFEST`.",
+            Self::Serve => "Serve tiles for slippy maps. The same as `--serve ADDRESS`.",
+            Self::Compare => {
+                "Compare two PNG or PPM images. The same as `--compare REFERENCE IMAGE`."
+            }
+            Self::Info => {
+                "Print the effective configuration, the estimated resource usage and the \
+                 features of this binary, without rendering. Like `--dry-run`. \
+                 `info random-julia` instead prints Julia constants worth rendering."
+            }
+        }
+    }
+    /// The argument the subcommand sets, which it takes as the first positional arguments.
+    fn mode(self) -> Option<&'static str> {
+        match self {
+            Self::Render | Self::Info => None,
+            Self::Animate => Some("animate"),
+            Self::Batch => Some("batch"),
+            Self::Serve => Some("serve"),
+            Self::Compare => Some("compare"),
+        }
+    }
+    /// The arguments which only apply to this subcommand.
+    fn own_args(self) -> &'static [&'static str] {
+        match self {
+            Self::Render | Self::Info => &[],
+            Self::Animate => &[
+                "animate",
+                "zoom_to",
+                "keyframes",
+                "interpolation",
+                "plot_path",
+                "no_frame_skip",
+                "reuse_frames",
+                "reuse_min_psnr",
+            ],
+            Self::Batch => &["batch", "batch_parallel", "fail_fast"],
+            Self::Serve => &["serve", "serve_extent", "serve_cache", "serve_max_requests"],
+            Self::Compare => &[
+                "compare",
+                "compare_tolerance",
+                "compare_threshold",
+                "diff_image",
+                "diff_amplify",
+            ],
+        }
+    }
+    /// Whether the subcommand takes the argument or group `id`.
+    fn takes(self, id: &str) -> bool {
+        match self {
+            Self::Render => true,
+            Self::Compare => self.own_args().contains(&id) || GENERAL_ARGS.contains(&id),
+            _ => {
+                self.own_args().contains(&id)
+                    || GENERAL_ARGS.contains(&id)
+                    || SHARED_ARGS.contains(&id)
+            }
+        }
+    }
+    /// Whether the help of the subcommand shows the argument `id`. [`Command::Render`] takes
+    /// the arguments of the other subcommands too, but doesn't list them.
+    fn shows(self, id: &str) -> bool {
+        self.takes(id)
+            && (self != Self::Render
+                || Self::ALL
+                    .iter()
+                    .all(|command| !command.own_args().contains(&id)))
+    }
+    fn app(self) -> clap::App<'static> {
+        let mut app = with_args(clap::App::new(self.name()).about(self.about()), Some(self));
+        let hidden: Vec<&'static str> = app
+            .get_arguments()
+            .map(|arg| arg.get_name())
+            .filter(|id| !self.shows(id))
+            .collect();
+        for id in hidden {
+            app = app.mut_arg(id, |arg| arg.hide(true));
+        }
+        if self.mode().is_some() && self.takes("width") {
+            app = app
+                .mut_arg("width", |arg| arg.index(2))
+                .mut_arg("height", |arg| arg.index(3));
+        }
+        if self == Self::Info {
+            app = app.subcommand(random_julia_app());
+        }
+        app
+    }
+}
+impl Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+impl FromStr for Command {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|command| command.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown command {:?}", s))
+    }
+}
+/// The arguments every [`Command`] takes.
+const GENERAL_ARGS: &[&str] = &["help", "version", "verbose", "quiet", "threads", "force"];
+/// The arguments and groups of the image taken by the [`Command`]s which render: its size, view,
+/// colors and output.
+const SHARED_ARGS: &[&str] = &[
+    "width",
+    "height",
+    "width_flag",
+    "height_flag",
+    "width_group",
+    "height_group",
+    "aspect",
+    "size",
+    "iterations",
+    "limit",
+    "bailout",
+    "bailout_ellipse",
+    "stable_limit",
+    "pos_x",
+    "pos_y",
+    "scale_x",
+    "scale_y",
+    "scale_individual",
+    "scale",
+    "region",
+    "import_location",
+    "region_fit_height",
+    "precision",
+    "backend",
+    "exp_map",
+    "rotate",
+    "flip_h",
+    "flip_v",
+    "y_up",
+    "pixel_centers",
+    "exposure",
+    "primary_color",
+    "secondary_color",
+    "background",
+    "palette",
+    "post",
+    "simulate_cvd",
+    "disable_inside",
+    "interior_exposure",
+    "interior_mapping",
+    "unsmooth",
+    "smooth_mode",
+    "filename",
+    "on_conflict",
+    "overwrite",
+    "format",
+    "algo",
+    "julia_re",
+    "julia_im",
+    "julia_constant",
+    "color_weight",
+    "fern_normalize",
+    "config_file",
+];
+/// The modes which don't render the config to the output, like `--gui` and `--batch`, so most
+/// arguments of the output can't be used with them.
+const MODES: &[&str] = &[
+    "gui",
+    "batch",
+    "benchmark",
+    "slice",
+    "merge",
+    "serve",
+    "compare",
+    "reference",
+];
+/// The ways of rendering other than one pass over the pixels of the image, which the arguments
+/// that work on those passes can't be used with.
+const STAGED_RENDERS: &[&str] = &[
+    "julia_grid",
+    "buddhabrot",
+    "animate",
+    "checkpoint",
+    "resume",
+    "time_limit",
+];
+/// [`MODES`] except `allowed`, and `others`: the arguments an argument conflicts with.
+fn modes_but(allowed: &str, others: &[&'static str]) -> Vec<&'static str> {
+    MODES
+        .iter()
+        .copied()
+        .filter(|mode| *mode != allowed)
+        .chain(others.iter().copied())
+        .collect()
+}
+/// The subcommand of `matches`, from the app of [`build_cli`], and the matches of its
+/// arguments.
+///
+/// ```
+/// # use fractal_renderer::{build_cli, command, options_from_matches, Command};
+/// let options = |args: &[&str]| {
+///     let matches = build_cli().get_matches_from(["fractal-renderer"].iter().chain(args));
+///     assert_eq!(command(&matches).0, args[0].parse().unwrap_or(Command::Render));
+///     options_from_matches(&matches).unwrap()
+/// };
+/// let legacy = options(&["--batch", "jobs.toml", "-i", "80"]);
+/// assert_eq!(legacy, options(&["batch", "jobs.toml", "-i", "80"]));
+/// assert_eq!(legacy, options(&["render", "--batch", "jobs.toml", "-i", "80"]));
+///
+/// assert_eq!(
+///     options(&["400", "300", "-i", "80"]),
+///     options(&["render", "400", "300", "-i", "80"])
+/// );
+/// assert!(options(&["info"]).dry_run);
+/// ```
+pub fn command(matches: &ArgMatches) -> (Command, &ArgMatches) {
+    match matches.subcommand() {
+        Some((name, matches)) => (
+            name.parse()
+                .expect("the subcommands of the app are commands"),
+            matches,
+        ),
+        None => (Command::Render, matches),
+    }
+}
+/// The matches of a [`Command`], where the arguments it doesn't take read as not given.
+struct Matches<'a> {
+    command: Command,
+    matches: &'a ArgMatches,
+    /// The matches of no arguments at all, for the arguments `command` doesn't take. [`None`] if
+    /// it takes every argument.
+    defaults: Option<ArgMatches>,
+}
+impl<'a> Matches<'a> {
+    /// The matches of the [`Command`] of `matches`, from the app of [`build_cli`].
+    fn new(matches: &'a ArgMatches) -> Self {
+        let (command, matches) = command(matches);
+        let defaults = (command != Command::Render).then(|| {
+            build_cli()
+                .try_get_matches_from(["fractal-renderer"])
+                .expect("no arguments are valid")
+        });
+        Self {
+            command,
+            matches,
+            defaults,
+        }
+    }
+    /// Matches which have every argument they're asked about, like those of `info random-julia`.
+    fn all(matches: &'a ArgMatches) -> Self {
+        Self {
+            command: Command::Render,
+            matches,
+            defaults: None,
+        }
+    }
+    fn of(&self, id: &str) -> &ArgMatches {
+        match &self.defaults {
+            Some(defaults) if !self.command.takes(id) => defaults,
+            _ => self.matches,
+        }
+    }
+    fn value_of(&self, id: &str) -> Option<&str> {
+        self.of(id).value_of(id)
+    }
+    fn values_of(&self, id: &str) -> Option<clap::Values<'_>> {
+        self.of(id).values_of(id)
+    }
+    fn is_present(&self, id: &str) -> bool {
+        self.of(id).is_present(id)
+    }
+    fn occurrences_of(&self, id: &str) -> u64 {
+        self.of(id).occurrences_of(id)
+    }
+    fn subcommand(&self) -> Option<(&str, &'a ArgMatches)> {
+        self.matches.subcommand()
+    }
+}
+
+/// `info random-julia`, see [`discovery::random_julia`]. The view arguments of `info` before it
+/// apply to the contact sheet.
+fn random_julia_app() -> clap::App<'static> {
+    clap::App::new("random-julia")
+        .about("Print Julia constants near the edge of the Mandelbrot set, where the Julia sets are the most detailed, like `-0.8+0.156i`.")
+        .arg(
+            Arg::new("count")
+            .long("count")
+            .takes_value(true)
+            .value_name("N")
+            .default_value("12")
+            .help("The number of constants.")
+        )
+        .arg(
+            Arg::new("seed")
+            .long("seed")
+            .takes_value(true)
+            .value_name("N")
+            .help("The same seed finds the same constants. Without one, a random seed is used and logged.")
+        )
+        .arg(
+            Arg::new("sheet")
+            .long("sheet")
+            .takes_value(true)
+            .value_name("FILE")
+            .help("Also render a contact sheet of the Julia sets to FILE, with the size and colors of `info`.")
+        )
+}
+
+/// The command line interface, see [`options_from_matches`] and [`Command`].
+pub fn build_cli() -> clap::App<'static> {
+    let app = with_args(
+        clap::App::new("fractal-renderer")
+            .about("Set `-d` for a more traditional look.")
+            .args_conflicts_with_subcommands(true),
+        None,
+    );
+    Command::ALL
+        .iter()
+        .fold(app, |app, command| app.subcommand(command.app()))
+}
+/// The argument `command` sets: the first positional argument of its subcommand `mode`, else
+/// the flag of the same name.
+fn mode_arg(mode: Option<Command>, command: Command) -> Arg<'static> {
+    let id = command
+        .mode()
+        .expect("only commands with a mode have an argument");
+    let arg = Arg::new(id);
+    if mode == Some(command) {
+        arg.index(1).required(true)
+    } else {
+        arg.long(id)
+    }
+}
+/// Adds the arguments `mode` takes to `app`, the app of the subcommand `mode` or of the bare
+/// invocation, which takes every argument.
+fn with_args(app: clap::App<'static>, mode: Option<Command>) -> clap::App<'static> {
+    let takes = |id: &str| mode.map_or(true, |mode| mode.takes(id));
+    // clap requires the conflicts and requirements of an argument to be arguments of the app.
+    let known = |ids: &[&'static str]| -> Vec<&'static str> {
+        ids.iter().copied().filter(|id| takes(id)).collect()
+    };
+    let args = clap::App::new("arguments")
         .arg(
             Arg::new("width")
-                .help("Easily handles 100MP images.")
+                .help("Easily handles 100MP images. Same as `--width`.")
+                .validator(parse::side)
                 .default_value("750"),
         )
         .arg(
             Arg::new("height")
-                .help("Easily handles 100MP images.")
+                .help("Easily handles 100MP images. Same as `--height`.")
+                .validator(parse::side)
                 .default_value("500"),
         )
+        .arg(
+            Arg::new("width_flag")
+                .long("width")
+                .short('W')
+                .takes_value(true)
+                .value_name("PIXELS")
+                .validator(parse::side)
+                .help("The width, instead of the first positional argument."),
+        )
+        .arg(
+            Arg::new("height_flag")
+                .long("height")
+                .short('H')
+                .takes_value(true)
+                .value_name("PIXELS")
+                .validator(parse::side)
+                .help("The height, instead of the second positional argument."),
+        )
+        .arg(
+            Arg::new("aspect")
+                .long("aspect")
+                .takes_value(true)
+                .validator(parse::aspect)
+                .help("Compute the height from the width, or the width from the height, and this aspect ratio, e.g. `16:9`, `3:2`, `1:1` or `golden`."),
+        )
+        .arg(
+            Arg::new("size")
+                .long("size")
+                .takes_value(true)
+                .validator(parse::size)
+                .conflicts_with_all(&["width_group", "height_group", "aspect"])
+                .help("Set the width and height to `720p`, `1080p`, `1440p`, `4k`, `8k` or `WxH`."),
+        )
         .arg(
             Arg::new("iterations")
                 .long("iterations")
                 .short('i')
+                .visible_alias("points")
                 .takes_value(true)
-                .help("Limit of iterations. Default is 50 for Mandelbrot & Julia and 10_000_000 for Fern.")
+                .help("Limit of iterations, or the number of points the fern plots. Default is 50 for Mandelbrot & Julia and 10_000_000 for Fern. Below 100_000 points, the fern is almost blank.")
         )
         .arg(
             Arg::new("limit")
@@ -56,41 +575,149 @@ pub fn get_options() -> Options {
                 .takes_value(true)
                 .default_value("65536"),
         )
+        .arg(
+            Arg::new("bailout")
+                .long("bailout")
+                .takes_value(true)
+                .possible_values(["circle", "square", "real", "imag", "ellipse"])
+                .default_value("circle")
+                .conflicts_with_all(&known(&["buddhabrot"]))
+                .help("The shape points escape from: `|z|`, `max(|re|, |im|)`, `|re|` or `|im|` larger than `--limit`, or an ellipse stretched like `--scale-x` and `--scale-y`, so the colors stay round. Only circles and ellipses are smoothed."),
+        )
+        .arg(
+            Arg::new("bailout_ellipse")
+                .long("bailout-ellipse")
+                .takes_value(true)
+                .value_name("a,b")
+                .validator(parse::semi_axes)
+                .conflicts_with_all(&known(&["buddhabrot"]))
+                .help("Escape from the ellipse `(re / a)² + (im / b)² > limit²` instead. Implies `--bailout ellipse`."),
+        )
         .arg(
             Arg::new("stable_limit")
                 .long("stable-limit")
-                .help("The limit of points considered inside the fractal. Only applicable to Mandelbrot & Julia.")
+                .help("The radius of points considered inside the fractal. Can't be larger than `--limit`. Only applicable to Mandelbrot & Julia.")
                 .default_value("2"),
         )
+        .arg(
+            Arg::new("legacy_stable_limit")
+                .long("legacy-stable-limit")
+                .help("Treat `--stable-limit` as the squared radius, like previous versions did. This will be removed in the next release."),
+        )
         .arg(
             Arg::new("pos_x")
                 .short('x')
                 .takes_value(true)
-                .default_value_if("algo", Some("julia"), Some("0"))
-                .default_value("-0.6")
                 .allow_hyphen_values(true),
         )
         .arg(
             Arg::new("pos_y")
                 .short('y')
                 .takes_value(true)
-                .default_value("0")
                 .allow_hyphen_values(true),
         )
         .arg(Arg::new("scale_y").long("scale-y").takes_value(true))
         .arg(Arg::new("scale_x").long("scale-x").takes_value(true))
-        .group(
-            ArgGroup::new("scale_individual")
-                .arg("scale_x")
-                .arg("scale_y"),
-        )
         .arg(
             Arg::new("scale")
                 .conflicts_with("scale_individual")
                 .long("scale")
                 .short('s')
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("region")
+                .long("region")
+                .takes_value(true)
+                .allow_hyphen_values(true)
+                .validator(parse::region)
+                .conflicts_with_all(&["pos_x", "pos_y", "scale", "scale_individual"])
+                .help("Render exactly the rectangle `re_min,im_min,re_max,im_max` of the complex plane."),
+        )
+        .arg(
+            Arg::new("import_location")
+                .long("import-location")
+                .takes_value(true)
+                .value_name("FILE")
+                .conflicts_with_all(&["pos_x", "pos_y", "scale", "scale_individual", "region"])
+                .help("Use the position, zoom and iterations of a Kalles Fraktaler location file (`.kfr`)."),
+        )
+        .arg(
+            Arg::new("region_fit_height")
+                .long("region-fit-height")
+                .requires("region")
+                .help("Change the height so the region isn't stretched."),
+        )
+        .arg(
+            Arg::new("precision")
+                .long("precision")
+                .takes_value(true)
+                .possible_values(["auto", "f64", "dd"])
+                .default_value("auto")
+                .help("Iterate with f64 or the 5-10 times slower double-double (`dd`), which is good to zooms of 1e28. `auto` uses double-double when f64 isn't precise enough."),
+        )
+        .arg(
+            Arg::new("backend")
+                .long("backend")
+                .takes_value(true)
+                .possible_values(["auto", "cpu", "gpu"])
+                .default_value("auto")
+                .help("Where to render. `auto` uses the GPU if this binary has a GPU renderer, else the CPU."),
+        )
+        .arg(
+            Arg::new("exp_map")
+                .long("exp-map")
+                .conflicts_with_all(&known(&["region", "region_fit_height", "julia_grid", "serve", "buddhabrot", "grid"]))
+                .help("Render a strip in log-polar coordinates around the center, which covers all zooms from `--scale` to `--scale` × e^(2π × height / width).")
+                .long_help("Render a strip in log-polar coordinates around the center, which covers all zooms from `--scale` to `--scale` × e^(2π × height / width). The angle goes around the width and the distance to the center shrinks downwards. See the `mapping` module for how to unroll it.\n\nWith --animate, the frames are resampled from one strip covering the whole zoom instead of being rendered one by one, which is much faster for many frames."),
+        )
+        .arg(
+            Arg::new("rotate")
+                .long("rotate")
                 .takes_value(true)
-                .default_value("0.4"),
+                .possible_values(["0", "90", "180", "270"])
+                .default_value("0")
+                .conflicts_with_all(&known(&["serve"]))
+                .help("Turn the image clockwise by this many degrees. The width and height are those of the turned image.")
+                .long_help("Turn the image clockwise by this many degrees. The width and height are those of the turned image.\n\nThis changes which points the pixels show, so nothing is resampled: a 90° turn of a 600×400 image is a 400×600 image with exactly the same pixels, moved."),
+        )
+        .arg(
+            Arg::new("flip_h")
+                .long("flip-h")
+                .conflicts_with_all(&known(&["serve"]))
+                .help("Mirror the image left to right, after --rotate."),
+        )
+        .arg(
+            Arg::new("flip_v")
+                .long("flip-v")
+                .conflicts_with_all(&known(&["serve"]))
+                .help("Mirror the image top to bottom, after --rotate."),
+        )
+        .arg(
+            Arg::new("y_up")
+                .long("y-up")
+                .conflicts_with_all(&known(&["serve"]))
+                .help("Put the positive imaginary axis up, like in mathematics and most other programs, instead of down. Coordinates from elsewhere are mirrored without it.")
+                .long_help("Put the positive imaginary axis up, like in mathematics and most other programs, instead of down. Coordinates from elsewhere are mirrored without it.\n\nUnlike --flip-v, this changes which way the coordinates go, so `-y 0.1` is above the center instead of below it. The orbit, the grid and the GUI follow it too."),
+        )
+        .arg(
+            Arg::new("pixel_centers")
+                .long("pixel-centers")
+                .help("Sample the center of every pixel instead of its top left corner. Recommended: renders of the same view at different sizes then line up, so a small render is a downscale of a large one.")
+                .long_help("Sample the center of every pixel instead of its top left corner. Recommended: renders of the same view at different sizes then line up, so a small render is a downscale of a large one, e.g. to check thumbnails against full renders.\n\nThe corners are the default, so existing renders and checksums stay the same. The key in configs is `pixel_centers`."),
+        )
+        .arg(
+            Arg::new("verbose")
+                .long("verbose")
+                .short('v')
+                .conflicts_with("quiet")
+                .help("Print timings of each phase and the effective configuration."),
+        )
+        .arg(
+            Arg::new("quiet")
+                .long("quiet")
+                .short('q')
+                .help("Only print errors."),
         )
         .arg(
             Arg::new("exposure")
@@ -99,32 +726,133 @@ pub fn get_options() -> Options {
                 .takes_value(true)
                 .default_value("5"),
         )
-        .arg(Arg::new("primary_color").long("primary-color").takes_value(true).help("The main color of output."))
-        .arg(Arg::new("secondary_color").long("secondary-color").takes_value(true).help("The secondary color of output. Defaults to orange for Mandelbrot and Julia. Acts as the background color for the Fern."))
+        .arg(Arg::new("primary_color").long("primary-color").takes_value(true).validator(parse::color).help("The main color of output."))
+        .arg(Arg::new("secondary_color").long("secondary-color").takes_value(true).validator(parse::color).help("The secondary color of output. Defaults to orange for Mandelbrot and Julia. Acts as the background color for the Fern."))
+        .arg(
+            Arg::new("background")
+                .long("background")
+                .takes_value(true)
+                .value_name("BACKGROUND")
+                .validator(parse::background)
+                .default_value("auto")
+                .help("What the fern and the Buddhabrot are plotted on: auto, a color like ff8800, checkerboard or transparent. Auto is the secondary color for the fern and black for the Buddhabrot.")
+                .long_help("What the fern and the Buddhabrot are plotted on: auto, a color like ff8800, checkerboard or transparent. Auto is the secondary color for the fern and black for the Buddhabrot.\n\nThe Mandelbrot and Julia sets color every pixel, so they have no background. Transparent needs images with an alpha channel, which aren't supported yet; checkerboard previews it. The key in configs is `background`, like `\"checkerboard\"` or `{ solid = { r = 0, g = 0, b = 64 } }`."),
+        )
+        .arg(
+            Arg::new("palette")
+            .long("palette")
+            .takes_value(true)
+            .value_name("PALETTE")
+            .validator(palette::parse)
+            .conflicts_with_all(&known(&modes_but("gui", STAGED_RENDERS)))
+            .help("Color the outside with a gradient instead of the primary color: `POSITION:COLOR,…`, evenly spread `COLOR,…`, or a `.map` or `.ggr` file.")
+        )
+        .arg(
+            Arg::new("post")
+            .long("post")
+            .takes_value(true)
+            .value_name("OPS")
+            .validator(post::parse)
+            .conflicts_with_all(&known(MODES))
+            .help("Post-process the image with `OP:VALUE,…`, applied in order: `blur:SIGMA` in pixels, `sharpen:AMOUNT` and `bloom:STRENGTH`, e.g. `bloom:0.3,sharpen:0.5`.")
+        )
+        .arg(
+            Arg::new("simulate_cvd")
+            .long("simulate-cvd")
+            .takes_value(true)
+            .value_name("KIND")
+            .possible_values(color::Cvd::ALL.iter().map(|cvd| cvd.name()))
+            .conflicts_with_all(&known(&modes_but("gui", &[])))
+            .help("Preview how the image looks without red (`protanopia`), green (`deuteranopia`) or blue (`tritanopia`) cones, after the post-processing and overlays, and warn about stops of `--palette` which look alike. In the GUI, only screenshots are simulated, and it's where the simulation of the view starts.")
+        )
         .arg(
             Arg::new("disable_inside")
                 .long("disable-inside")
                 .short('d')
                 .help("Makes the inside of fractals black."),
         )
+        .arg(
+            Arg::new("interior_exposure")
+                .long("interior-exposure")
+                .takes_value(true)
+                .default_value("1")
+                .conflicts_with("disable_inside")
+                .help("The multiplier of the color of the inside."),
+        )
+        .arg(
+            Arg::new("interior_mapping")
+                .long("interior-mapping")
+                .takes_value(true)
+                .possible_values(["linear", "sqrt"])
+                .default_value("linear")
+                .conflicts_with("disable_inside")
+                .help("Brighten the inside by the squared distance of the last point of the orbit (`linear`), which darkens everything inside radius 1, or by the distance (`sqrt`)."),
+        )
         .arg(
             Arg::new("unsmooth")
                 .long("unsmooth")
                 .short('u')
                 .help("Don't smooth the aliasing of the borders."),
         )
+        .arg(
+            Arg::new("smooth_mode")
+                .long("smooth-mode")
+                .takes_value(true)
+                .possible_values(["classic", "normalized", "log"])
+                .default_value("classic")
+                .help("How the iterations are smoothed and mapped to colors. `classic` shifts the colors with larger `--limit`s, `normalized` corrects that, and `log` also maps the iterations through ln(1 + iterations), which brightens images where most points escape fast, like most Julia sets."),
+        )
         .arg(
             Arg::new("filename")
                 .long("output")
                 .short('o')
                 .takes_value(true)
-                .default_value("output"),
+                .default_value("output")
+                .help("The output file. The format is inferred from the extension; if there is none, the extension of `--format` is appended. `-` writes to stdout. Can contain fields like `{algo}_{zoom:.0e}_{frame:04}`, see `--help-template`."),
+        )
+        .arg(
+            Arg::new("on_conflict")
+                .long("on-conflict")
+                .takes_value(true)
+                .value_name("ACTION")
+                .possible_values(["error", "rename", "overwrite"])
+                .default_value("rename")
+                .help("What to do if the output file already exists. `rename` writes to `output-2.avif` and so on instead. The file is written to a temporary file first, so it's never left half written."),
+        )
+        .arg(
+            Arg::new("overwrite")
+                .long("overwrite")
+                .conflicts_with("on_conflict")
+                .help("Replace existing output files. Short for `--on-conflict overwrite`."),
+        )
+        .arg(
+            Arg::new("help_template")
+                .long("help-template")
+                .help("Print the fields of `--output` and exit."),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .takes_value(true)
+                .possible_values(["avif", "png", "ppm"])
+                .default_value(format::Format::preferred().extension())
+                .help(
+                    "The format of the output, if the output file has no known extension. \
+                     Defaults to the first of AVIF, PNG and PPM this binary can write.",
+                ),
         )
         .arg(
             Arg::new("open")
                 .long("open")
                 .help("Open the image after generation."),
         )
+        .arg(
+            Arg::new("open_with")
+                .long("open-with")
+                .takes_value(true)
+                .value_name("PROGRAM")
+                .help("Open the image with PROGRAM instead of the default viewer. Implies `--open`."),
+        )
         .arg(
             Arg::new("algo")
                 .long("algorithm")
@@ -133,236 +861,2546 @@ pub fn get_options() -> Options {
                 .default_value("mandelbrot")
                 .possible_value("mandelbrot")
                 .possible_value("fern")
-                .possible_value("julia").requires_if("julia", "julia_re").requires_if("julia", "julia_im"),
+                .possible_value("julia")
+                .long_help("The algorithm to use. `julia` is the Julia set of Mandelbrot, with the constant of `--julia-real` and `--julia-imaginary`; `-a mandelbrot --julia` is the same."),
         )
         .arg(
             Arg::new("julia_re")
             .long("julia-real")
-            .help("Real part of start point for Julia set.")
+            .help("Real part of start point for Julia set, with `-a julia`.")
             .takes_value(true)
-            .allow_hyphen_values(true),
+            .allow_hyphen_values(true)
+            .requires("julia_im")
+            .conflicts_with("julia_constant"),
         )
         .arg(
             Arg::new("julia_im")
             .long("julia-imaginary")
-            .help("Imaginary part of start point for Julia set.")
+            .help("Imaginary part of start point for Julia set, with `-a julia`.")
+            .takes_value(true)
+            .allow_hyphen_values(true)
+            .requires("julia_re")
+            .conflicts_with("julia_constant"),
+        )
+        .arg(
+            Arg::new("julia_constant")
+            .long("julia")
+            .takes_value(true)
+            .value_name("A+Bi")
+            .allow_hyphen_values(true)
+            .validator(parse::complex)
+            .help("Render the Julia set of the constant, e.g. `-0.8+0.156i`, with the formula of `-a`.")
+            .long_help("Render the Julia set of the constant, e.g. `-0.8+0.156i`, with the formula of `-a`: the pixel is the start of the orbit, and the constant is added every iteration. The view is centered on the origin. The fern has no Julia set.")
+        )
+        .arg(
+            Arg::new("boundary_preview")
+            .long("boundary-preview")
+            .conflicts_with_all(&[MODES, STAGED_RENDERS, &["explore", "watch", "palette", "ao_strength", "adaptive_aa", "error_map", "timing_map", "export_iterations", "deepen", "plot_orbit", "ifs", "ifs_mutate"]].concat())
+            .help("Plot only the boundary of the Julia set, by inverse iteration. Much faster than the full render, to preview constants.")
+            .long_help("Plot only the boundary of the Julia set, by inverse iteration, like the fern. This is orders of magnitude faster than the full render, to preview constants before rendering them, but parts of the boundary are faint. `--color-weight` and `--fern-normalize` color it like the fern. Needs `-a julia` or `--julia`.")
+        )
+        .arg(
+            Arg::new("julia_grid")
+            .long("julia-grid")
+            .takes_value(true)
+            .value_name("NxM")
+            .validator(parse::dimensions)
+            .help("Render a grid of N×M Julia sets, with constants evenly spread over `--julia-grid-bounds`.")
+        )
+        .arg(
+            Arg::new("wallpaper")
+            .long("wallpaper")
+            .takes_value(true)
+            .number_of_values(2)
+            .value_names(&["MODE", "NxM"])
+            .conflicts_with_all(&modes_but("reference", &["animate", "annotate"]))
+            .help("Compose the output of N×M copies of the render, which is rendered once at a fraction of the size. `tile` puts the copies side by side, `mirror` flips every other one, so the edges match.")
+        )
+        .arg(
+            Arg::new("julia_grid_bounds")
+            .long("julia-grid-bounds")
+            .takes_value(true)
+            .allow_hyphen_values(true)
+            .requires("julia_grid")
+            .validator(parse::region)
+            .default_value("-2,-1.25,0.5,1.25")
+            .help("The rectangle `re_min,im_min,re_max,im_max` of Julia constants to sample.")
+        )
+        .arg(
+            Arg::new("julia_grid_labels")
+            .long("julia-grid-labels")
+            .requires("julia_grid")
+            .help("Write the constant of each cell in its bottom left corner.")
+        )
+        .arg(
+            mode_arg(mode, Command::Animate)
+            .takes_value(true)
+            .value_name("FRAMES")
+            .validator(parse::positive_integer)
+            .conflicts_with_all(&known(&[MODES, &["julia_grid", "buddhabrot", "checkpoint", "resume", "checksum", "expect_checksum"]].concat()))
+            .help("Render FRAMES frames zooming from `--scale` to `--zoom-to`, or along `--keyframes`, to `<output>-0000.<ext>` and onwards, unless `--output` has a `{frame}` field.")
+        )
+        .arg(
+            Arg::new("zoom_to")
+            .long("zoom-to")
+            .takes_value(true)
+            .value_name("SCALE")
+            .validator(|v| match v.parse::<f64>() {
+                Ok(v) if v.is_finite() && v > 0.0 => Ok(()),
+                _ => Err(format!("{:?} isn't a positive scale", v)),
+            })
+            .requires("animate")
+            .help("The scale of the last frame of the animation.")
+        )
+        .arg(
+            Arg::new("keyframes")
+            .long("keyframes")
+            .takes_value(true)
+            .value_name("FILE")
+            .requires("animate")
+            .conflicts_with_all(&["zoom_to", "exp_map", "reuse_frames"])
+            .help("Move the camera through the keyframes of a JSON file instead of zooming towards the center: `[{\"x\": -0.75, \"y\": 0, \"scale\": 1, \"ease_out\": 0.5}, …]`. The scale changes steadily between keyframes, and `ease_in` and `ease_out`, from 0 to 1, slow the camera down around a keyframe.")
+        )
+        .arg(
+            Arg::new("interpolation")
+            .long("interpolation")
+            .takes_value(true)
+            .possible_values(camera::Interpolation::ALL.iter().map(|interpolation| interpolation.name()))
+            .default_value("catmull-rom")
+            .requires("keyframes")
+            .help("How the center moves between keyframes: along a curve through them, or in straight lines which turn at them.")
+        )
+        .arg(
+            Arg::new("plot_path")
+            .long("plot-path")
+            .takes_value(true)
+            .value_name("FILE")
+            .requires("keyframes")
+            .help("Instead of rendering the frames, write an overview of the animation with the path of the center and the views of the keyframes drawn over it, to check the path. Defaults to PNG if FILE has no known extension.")
+        )
+        .arg(
+            Arg::new("no_frame_skip")
+            .long("no-frame-skip")
+            .requires("animate")
+            .help("Render every frame, even if sparse probes find it's a single color.")
+        )
+        .arg(
+            Arg::new("reuse_frames")
+            .long("reuse-frames")
+            .requires("animate")
+            .conflicts_with("exp_map")
+            .help("Reuse the iterations of the previous frame for the pixels which are the same points, iterating only the edges and uncertain pixels again.")
+            .long_help("Reuse the iterations of the previous frame for the pixels which are the same points, iterating only the edges and uncertain pixels again.\n\nA few reused pixels are iterated anyway, and frames whose estimated PSNR is below --reuse-min-psnr are rendered fully.")
+        )
+        .arg(
+            Arg::new("reuse_min_psnr")
+            .long("reuse-min-psnr")
+            .takes_value(true)
+            .value_name("DB")
+            .default_value("40")
+            .requires("reuse_frames")
+            .help("The estimated PSNR a frame with reused iterations must reach, or it's rendered fully.")
+        )
+        .arg(
+            Arg::new("buddhabrot")
+            .long("buddhabrot")
+            .conflicts_with_all(&[MODES, &["julia_grid", "checkpoint", "resume"]].concat())
+            .help("Render the density of the orbits of the Mandelbrot set, the Buddhabrot, in white on black.")
+        )
+        .arg(
+            Arg::new("buddhabrot_mode")
+            .long("buddhabrot-mode")
+            .takes_value(true)
+            .possible_values(buddhabrot::Mode::ALL.iter().map(|mode| mode.name()))
+            .requires("buddhabrot")
+            .default_value("normal")
+            .help("Count the orbits which escape (`normal`), which don't (`anti`), or the escaping orbits with the iterations of `--nebula-iterations` in the red, green and blue channel (`nebula`).")
+        )
+        .arg(
+            Arg::new("buddhabrot_samples")
+            .long("buddhabrot-samples")
+            .takes_value(true)
+            .value_name("N")
+            .validator(parse::positive_integer)
+            .requires("buddhabrot")
+            .default_value("20")
+            .help("The number of random points per pixel. More make the image less noisy.")
+        )
+        .arg(
+            Arg::new("nebula_iterations")
+            .long("nebula-iterations")
+            .takes_value(true)
+            .value_name("R,G,B")
+            .validator(buddhabrot::parse_nebula_iterations)
+            .requires("buddhabrot")
+            .default_value("500,5000,50000")
+            .help("The iterations of the red, green and blue channel of the nebula mode.")
+            .long_help("The iterations of the red, green and blue channel of the nebula mode. The nebula keeps three buffers of 4 bytes per pixel for every thread.")
+        )
+        .arg(
+            Arg::new("sampler")
+            .long("sampler")
+            .takes_value(true)
+            .possible_values(sampler::Kind::ALL.iter().map(|kind| kind.name()))
+            .requires("buddhabrot")
+            .default_value("pseudorandom")
+            .help("Where the points of the Buddhabrot come from. The low-discrepancy sequences `sobol` and `halton` give less noise for the same number of samples.")
+        )
+        .arg(
+            Arg::new("importance")
+            .long("importance")
+            .requires("buddhabrot")
+            .help("Draw the points of the Buddhabrot whose orbits cross the image more often, which gives much less noise for the same number of samples when zoomed in.")
+            .long_help("Draw the points of the Buddhabrot whose orbits cross the image more often, which gives much less noise for the same number of samples when zoomed in. A coarse map of where those orbits start is rendered first. The hits of the other points count more, so the density doesn't change.")
+        )
+        .arg(
+            Arg::new("plot_orbit")
+            .long("plot-orbit")
+            .takes_value(true)
+            .value_name("RE,IM")
+            .allow_hyphen_values(true)
+            .validator(parse::point)
+            .conflicts_with_all(&["julia_grid", "batch", "slice", "merge", "serve"])
+            .help("Draw the orbit of the point over the image. For Julia sets, the point is the start, else it's the constant.")
+        )
+        .arg(
+            Arg::new("orbit_color")
+            .long("orbit-color")
+            .takes_value(true)
+            .validator(parse::color)
+            .default_value("ffffff")
+            .help("The color of the orbit.")
+        )
+        .arg(
+            Arg::new("grid")
+            .long("grid")
+            .conflicts_with_all(&["julia_grid", "batch", "slice", "merge", "serve"])
+            .help("Draw the axes and labelled gridlines over the image.")
+        )
+        .arg(
+            Arg::new("grid_color")
+            .long("grid-color")
+            .takes_value(true)
+            .validator(parse::color)
+            .default_value("ffffff")
+            .help("The color of the axes, gridlines and labels.")
+        )
+        .arg(
+            Arg::new("annotate")
+            .long("annotate")
+            .conflicts_with_all(&["julia_grid", "batch", "slice", "merge", "serve"])
+            .help("Caption the image with the algorithm, center, zoom and iterations.")
+        )
+        .arg(
+            Arg::new("annotate_position")
+            .long("annotate-position")
+            .takes_value(true)
+            .possible_values(annotate::Position::ALL.iter().map(|position| position.name()))
+            .default_value("bottom")
+            .help("Where to put the caption. `top` and `bottom` span the width of the image.")
+        )
+        .arg(
+            Arg::new("annotate_foreground")
+            .long("annotate-foreground")
             .takes_value(true)
-            .allow_hyphen_values(true),
+            .validator(parse::color)
+            .default_value("ffffff")
+            .help("The color of the text of the caption.")
+        )
+        .arg(
+            Arg::new("annotate_background")
+            .long("annotate-background")
+            .takes_value(true)
+            .validator(parse::color)
+            .default_value("000000")
+            .help("The background color of the caption.")
+        )
+        .arg(
+            Arg::new("annotate_max_fraction")
+            .long("annotate-max-fraction")
+            .takes_value(true)
+            .value_name("FRACTION")
+            .validator(|v| match v.parse::<f64>() {
+                Ok(v) if v > 0.0 && v <= 1.0 => Ok(()),
+                _ => Err(format!("{:?} isn't a fraction in (0, 1]", v)),
+            })
+            .default_value("0.1")
+            .help("The caption is made smaller to not be taller than this fraction of the image.")
         )
         .arg(
             Arg::new("color_weight")
             .long("color-weight")
             .short('w')
             .help("How much 'opacity' each hit on the Fern has. Increase to get a darker fern.").default_value("0.01")
+            .long_help("How much 'opacity' each hit on the Fern has. Increase to get a darker fern.\n\nWith --fern-normalize, this is `k` in `1 - exp(-k × hits / max hits)` and defaults to 64.")
         )
         .arg(
-            Arg::new("gui")
-            .long("gui")
-            .short('g')
-            .help("Start the GUI. Requires the `gui` cargo feature.")
-            .long_help("Use `s` to take a 2x screenshot. `m` hides the menybar. Use the arrow keys and scroll to move around the image.")
-        );
-
-    let matches = app.get_matches();
-
-    let width = matches.value_of_t("width").unwrap();
-    let height = matches.value_of_t("height").unwrap();
-    let iterations = matches.value_of_t("iterations").ok();
-    let pos = Imaginary {
-        re: matches.value_of_t("pos_x").unwrap(),
-        im: matches.value_of_t("pos_y").unwrap(),
-    };
-    let scale = Imaginary {
-        re: matches
-            .value_of_t("scale_x")
-            .or_else(|_| matches.value_of_t("scale"))
-            .unwrap(),
-        im: matches
-            .value_of_t("scale_y")
-            .or_else(|_| matches.value_of_t("scale"))
-            .unwrap(),
-    };
-    let limit = matches.value_of_t("limit").unwrap();
-    let stable_limit = matches.value_of_t("stable_limit").unwrap();
-    let exposure: f64 = matches.value_of_t("exposure").unwrap();
-    let primary_color = matches.value_of("primary_color").map(parse_hex_rgb);
-    let secondary_color = matches.value_of("secondary_color").map(parse_hex_rgb);
-    let inside_disabled = matches.is_present("disable_inside");
-    let unsmooth = matches.is_present("unsmooth");
-    let filename = matches
-        .value_of("filename")
-        .map(|f| format!("{}.avif", f))
-        .unwrap();
-    let open = matches.is_present("open");
-    let algo = matches.value_of_t("algo").unwrap();
-    let mut julia_set = Imaginary::ZERO;
-    if let Algo::Julia = &algo {
-        julia_set.re = matches.value_of_t("julia_re").unwrap();
-        julia_set.im = matches.value_of_t("julia_im").unwrap();
-    }
-    let color_weight = matches.value_of_t("color_weight").unwrap();
-    let gui = matches.is_present("gui");
-    if gui && cfg!(not(feature = "gui")) {
-        eprintln!("The gui feature isn't enabled! Remove the GUI argument.");
-    }
-
-    let reference = Config::new(algo.clone());
-    let config = Config {
-        width,
-        height,
-        iterations: iterations.unwrap_or(reference.iterations),
-        limit,
-        stable_limit,
-        pos,
-        scale,
-        exposure,
-        inside: !inside_disabled,
-        smooth: !unsmooth,
-        primary_color: primary_color.unwrap_or(reference.primary_color),
-        secondary_color: secondary_color.unwrap_or(reference.secondary_color),
-        color_weight,
-        julia_set,
-        algo,
+            Arg::new("fern_normalize")
+            .long("fern-normalize")
+            .help("Color the fern by the density of the hits, so it looks the same for any number of iterations.")
+        )
+        .arg(
+            Arg::new("ao_strength")
+            .long("ao-strength")
+            .takes_value(true)
+            .value_name("STRENGTH")
+            .validator(|v| match v.parse::<f64>() {
+                Ok(v) if (0.0..=1.0).contains(&v) => Ok(()),
+                _ => Err(format!("{:?} isn't a strength in [0, 1]", v)),
+            })
+            .conflicts_with_all(&[MODES, STAGED_RENDERS].concat())
+            .help("Darken the valleys of the iterations with ambient occlusion, which gives the image depth. STRENGTH is how dark the deepest valleys get, from 0 to 1, e.g. 0.6. The points which don't escape are the highest.")
+        )
+        .arg(
+            Arg::new("ao_radius")
+            .long("ao-radius")
+            .takes_value(true)
+            .value_name("PIXELS")
+            .validator(|v| match v.parse::<f64>() {
+                Ok(v) if v.is_finite() && v > 0.0 => Ok(()),
+                _ => Err(format!("{:?} isn't a positive radius", v)),
+            })
+            .requires("ao_strength")
+            .default_value("8")
+            .help("How far around each pixel the ambient occlusion looks. Larger radii give wider and steeper shadows.")
+        )
+        .arg(
+            Arg::new("adaptive_aa")
+            .long("adaptive-aa")
+            .takes_value(true)
+            .value_name("THRESHOLD")
+            .validator(|v| v.parse::<u8>().map(|_| ()).map_err(|_| format!("{:?} isn't a threshold from 0 to 255", v)))
+            .conflicts_with_all(&[MODES, STAGED_RENDERS].concat())
+            .help("Supersample the pixels which differ from a neighbour by more than THRESHOLD in a channel, from 0 to 255, e.g. 24: first with 2×2 samples, then 4×4 if those still differ by more.")
+        )
+        .arg(
+            Arg::new("aa_debug")
+            .long("aa-debug")
+            .takes_value(true)
+            .value_name("FILE")
+            .requires("adaptive_aa")
+            .help("Also write a grayscale map of how many samples `--adaptive-aa` took of each pixel, from black for one to white for 16, to tune the threshold. Defaults to PNG if FILE has no known extension.")
+        )
+        .arg(
+            Arg::new("error_map")
+            .long("error-map")
+            .takes_value(true)
+            .value_name("FILE")
+            .conflicts_with_all(&[MODES, &["julia_grid", "buddhabrot", "animate"]].concat())
+            .help("Also write a heat-map of where the precision is too low, from comparing every 8th pixel to double-double precision, and print how far off they are. Defaults to PNG if FILE has no known extension.")
+        )
+        .arg(
+            Arg::new("timing_map")
+            .long("timing-map")
+            .takes_value(true)
+            .value_name("FILE")
+            .conflicts_with_all(&[MODES, STAGED_RENDERS, &["deepen", "ifs", "ifs_mutate"]].concat())
+            .help("Also write a heat-map of how long each tile of 64×64 pixels took to render, relative to the slowest, and print the 10 slowest tiles. Defaults to PNG if FILE has no known extension.")
+        )
+        .arg(
+            Arg::new("ifs")
+            .long("ifs")
+            .takes_value(true)
+            .value_name("COEFFICIENTS")
+            .allow_hyphen_values(true)
+            .validator(ifs::parse)
+            .conflicts_with_all(&[MODES, STAGED_RENDERS].concat())
+            .help("Plot this iterated function system instead of the Barnsley fern: `a,b,c,d,e,f,weight` for each transform `(x, y) → (ax + by + e, cx + dy + f)`, separated by `;`. Needs `-a fern`.")
+        )
+        .arg(
+            Arg::new("ifs_mutate")
+            .long("ifs-mutate")
+            .takes_value(true)
+            .value_name("AMOUNT")
+            .validator(ifs::parse_amount)
+            .conflicts_with_all(&[MODES, STAGED_RENDERS].concat())
+            .help("Render a 3×3 contact sheet of mutants of the fern (or `--ifs`), with every coefficient changed by up to ± AMOUNT, e.g. 0.05 for 5%. The coefficients of each mutant are printed, to render it with `--ifs`. Needs `-a fern`.")
+        )
+        .arg(
+            Arg::new("ifs_seed")
+            .long("ifs-seed")
+            .takes_value(true)
+            .value_name("N")
+            .requires("ifs_mutate")
+            .default_value("0")
+            .help("The seed of the mutations of `--ifs-mutate`. The same seed gives the same mutants.")
+        )
+        .arg(
+            Arg::new("config_file")
+            .long("config")
+            .takes_value(true)
+            .value_name("FILE")
+            .help("Apply the partial config in a TOML file on top of the options, with the keys of the output of `--dry-run` and the shorthands of `--batch` jobs.")
+        )
+        .arg(
+            Arg::new("watch")
+            .long("watch")
+            .requires("config_file")
+            .conflicts_with_all(&modes_but("reference", &["julia_grid", "buddhabrot", "animate", "checkpoint", "time_limit", "explore", "dry_run", "verify", "ifs", "ifs_mutate", "ao_strength", "wallpaper", "checksum", "expect_checksum", "error_map"]))
+            .help("Render again whenever the file of `--config` changes, overwriting the output, until Ctrl-C is pressed. Requires the `watch` cargo feature.")
+        )
+        .arg(
+            mode_arg(mode, Command::Batch)
+            .takes_value(true)
+            .value_name("MANIFEST")
+            .help("Render all jobs in a TOML manifest. Options given on the command line are used as the base of each job.")
+        )
+        .arg(
+            Arg::new("batch_parallel")
+            .long("batch-parallel")
+            .takes_value(true)
+            .value_name("N")
+            .requires("batch")
+            .validator(parse::positive_integer)
+            .default_value("1")
+            .help("Number of batch jobs to render at once.")
+        )
+        .arg(
+            Arg::new("fail_fast")
+            .long("fail-fast")
+            .requires("batch")
+            .help("Stop rendering the batch when a job fails.")
+        )
+        .arg(
+            Arg::new("explore")
+            .long("explore")
+            .takes_value(true)
+            .value_name("N")
+            .conflicts_with_all(&[MODES, STAGED_RENDERS, &["wallpaper"]].concat())
+            .help("Search the view for the N most interesting views and render them to the directory `--output`, with `explore.json` listing their coordinates.")
+        )
+        .arg(
+            Arg::new("explore_candidates")
+            .long("explore-candidates")
+            .takes_value(true)
+            .value_name("N")
+            .requires("explore")
+            .default_value("400")
+            .help("The most views `--explore` scores with a small render before choosing the best.")
+        )
+        .arg(
+            Arg::new("seed")
+            .long("seed")
+            .takes_value(true)
+            .value_name("N")
+            .requires("explore")
+            .help("The seed of `--explore`. The same seed finds the same views. Without one, a random seed is used and logged.")
+        )
+        .arg(
+            Arg::new("benchmark")
+            .long("benchmark")
+            .takes_value(true)
+            .value_name("N")
+            .min_values(0)
+            .require_equals(true)
+            .default_missing_value("5")
+            .help("Render N times without writing the image and report the timings. N defaults to 5.")
+        )
+        .arg(
+            Arg::new("benchmark_json")
+            .long("benchmark-json")
+            .requires("benchmark")
+            .help("Print the benchmark result as JSON.")
+        )
+        .arg(
+            Arg::new("checkpoint")
+            .long("checkpoint")
+            .takes_value(true)
+            .value_name("FILE")
+            .conflicts_with("resume")
+            .help("Periodically save the progress to FILE. It's removed once the image is written.")
+        )
+        .arg(
+            Arg::new("checkpoint_interval")
+            .long("checkpoint-interval")
+            .takes_value(true)
+            .value_name("DURATION")
+            .validator(parse::duration)
+            .default_value("60s")
+            .help("Time between saves of the checkpoint, e.g. `30s` or `5m`.")
+        )
+        .arg(
+            Arg::new("resume")
+            .long("resume")
+            .takes_value(true)
+            .value_name("FILE")
+            .help("Continue the render saved in the checkpoint FILE. Use the same arguments as the original render.")
+        )
+        .arg(
+            Arg::new("export_iterations")
+            .long("export-iterations")
+            .takes_value(true)
+            .value_name("FILE")
+            .conflicts_with_all(&[MODES, STAGED_RENDERS, &["explore", "watch", "ifs", "ifs_mutate"]].concat())
+            .help("Also write the iterations and the last point of the orbit of every pixel to FILE, as a NumPy `.npy` array, which `--deepen` can continue.")
+        )
+        .arg(
+            Arg::new("export_mask")
+            .long("export-mask")
+            .takes_value(true)
+            .value_name("FILE")
+            .conflicts_with_all(&[MODES, STAGED_RENDERS, &["explore", "watch", "ifs", "ifs_mutate", "boundary_preview"]].concat())
+            .help("Also write a grayscale PNG mask of the inside to FILE, white where it's set and black elsewhere, from the same render. See `--export-mask-mode`.")
+        )
+        .arg(
+            Arg::new("export_mask_mode")
+            .long("export-mask-mode")
+            .takes_value(true)
+            .possible_values(mask::MaskMode::ALL.iter().map(|mode| mode.name()))
+            .requires("export_mask")
+            .default_value("interior")
+            .help("Mask the points which don't escape (`interior`), those which do (`exterior`), or the pixels with a neighbour on the other side (`boundary`), an edge matte.")
+        )
+        .arg(
+            Arg::new("deepen")
+            .long("deepen")
+            .takes_value(true)
+            .value_name("FILE")
+            .conflicts_with_all(&[MODES, STAGED_RENDERS, &["explore", "watch", "ifs", "ifs_mutate"]].concat())
+            .help("Continue the render exported to FILE with `--export-iterations` with more iterations, only iterating the pixels which didn't escape. Use the same arguments as the original render, but more `--iterations`.")
+        )
+        .arg(
+            Arg::new("slice")
+            .long("slice")
+            .takes_value(true)
+            .value_name("i/n")
+            .validator(slice::Slice::parse)
+            .conflicts_with_all(&["checkpoint", "resume", "time_limit", "julia_grid", "buddhabrot"])
+            .help("Only render the i:th of n horizontal bands, to `<output>.part<i>of<n>.raw`. Merge the parts with `--merge`.")
+        )
+        .arg(
+            Arg::new("merge")
+            .long("merge")
+            .takes_value(true)
+            .value_name("FILE")
+            .min_values(2)
+            .conflicts_with("slice")
+            .help("Merge the parts given after the output file (`--merge output.avif output.part*.raw`) and encode the image.")
+        )
+        .arg(
+            mode_arg(mode, Command::Serve)
+            .takes_value(true)
+            .value_name("ADDRESS")
+            .conflicts_with_all(&known(&modes_but("serve", &[])))
+            .help("Serve tiles at `http://ADDRESS/tile/{z}/{x}/{y}.png`, for slippy maps like Leaflet. Requires the `serve` cargo feature.")
+        )
+        .arg(
+            Arg::new("serve_extent")
+            .long("serve-extent")
+            .takes_value(true)
+            .allow_hyphen_values(true)
+            .validator(parse::region)
+            .default_value("-2.6,-2,1.4,2")
+            .help("The rectangle `re_min,im_min,re_max,im_max` covered by the tile at zoom 0.")
+        )
+        .arg(
+            Arg::new("serve_cache")
+            .long("serve-cache")
+            .takes_value(true)
+            .value_name("TILES")
+            .validator(|v| v.parse::<usize>().map_err(|err| err.to_string()))
+            .default_value("1024")
+            .help("The number of tiles to keep in memory.")
+        )
+        .arg(
+            Arg::new("serve_max_requests")
+            .long("serve-max-requests")
+            .takes_value(true)
+            .value_name("N")
+            .validator(|v| v.parse::<usize>().map_err(|err| err.to_string()))
+            .default_value("64")
+            .help("Respond with 503 when N tiles are being rendered.")
+        )
+        .arg(
+            mode_arg(mode, Command::Compare)
+            .takes_value(true)
+            .number_of_values(2)
+            .value_names(&["REFERENCE", "IMAGE"])
+            .validator(diff::readable)
+            .conflicts_with_all(&known(&modes_but("compare", &["julia_grid"])))
+            .help("Compare two PNG or PPM images and print the differences. Exits with 1 if they exceed `--compare-threshold`.")
+        )
+        .arg(
+            Arg::new("reference")
+            .long("reference")
+            .takes_value(true)
+            .validator(diff::readable)
+            .conflicts_with_all(&modes_but("reference", &["julia_grid"]))
+            .help("Render and compare the image to this reference, instead of writing it. Like `--compare`.")
+        )
+        .arg(
+            Arg::new("compare_tolerance")
+            .long("compare-tolerance")
+            .takes_value(true)
+            .validator(|v| v.parse::<u8>().map_err(|err| err.to_string()))
+            .default_value("0")
+            .help("Pixels differ if a channel differs by more than this.")
+        )
+        .arg(
+            Arg::new("compare_threshold")
+            .long("compare-threshold")
+            .takes_value(true)
+            .value_name("PERCENT")
+            .validator(|v| match v.parse::<f64>() {
+                Ok(v) if v >= 0.0 => Ok(()),
+                _ => Err(format!("{:?} isn't a non-negative percentage", v)),
+            })
+            .default_value("0")
+            .help("The percentage of differing pixels allowed.")
+        )
+        .arg(
+            Arg::new("diff_image")
+            .long("diff-image")
+            .takes_value(true)
+            .value_name("FILE")
+            .help("Write a heat-map of the differences when comparing. Defaults to PNG if FILE has no known extension.")
+        )
+        .arg(
+            Arg::new("diff_amplify")
+            .long("diff-amplify")
+            .takes_value(true)
+            .value_name("FACTOR")
+            .validator(|v| match v.parse::<f64>() {
+                Ok(v) if v > 0.0 => Ok(()),
+                _ => Err(format!("{:?} isn't a positive factor", v)),
+            })
+            .default_value("10")
+            .help("Multiply the differences in the heat-map by this.")
+        )
+        .arg(
+            Arg::new("checksum")
+            .long("checksum")
+            .conflicts_with_all(MODES)
+            .help("Print a digest of the rendered image (before overlays). The fern and Buddhabrot use a fixed seed.")
+            .long_help("Print a digest of the rendered image (before overlays). The fern and Buddhabrot use a fixed seed. The digest is only stable for the same version and identical floating point semantics of the hardware.")
+        )
+        .arg(
+            Arg::new("expect_checksum")
+            .long("expect-checksum")
+            .takes_value(true)
+            .value_name("HEX")
+            .validator(digest::parse_digest)
+            .conflicts_with_all(MODES)
+            .help("Exit with 1 if the digest of `--checksum` isn't HEX. Implies `--checksum`.")
+        )
+        .arg(
+            Arg::new("deterministic")
+            .long("deterministic")
+            .conflicts_with_all(&["gui", "serve", "time_limit"])
+            .help("Render the same image for every number of threads. The fern and Buddhabrot use a fixed seed, like with `--checksum`.")
+            .long_help("Render the same image for every number of threads. The fern, IFS and Buddhabrot use a fixed seed, like with `--checksum`, and split their points in a fixed number of parts, combined in the same order. The GPU backend, `--time-limit`, the GUI and `--serve` remain nondeterministic.")
+        )
+        .arg(
+            Arg::new("threads")
+            .long("threads")
+            .takes_value(true)
+            .value_name("N")
+            .validator(|v| match v.parse::<usize>() {
+                Ok(v) if v > 0 => Ok(()),
+                _ => Err(format!("{:?} isn't a positive number of threads", v)),
+            })
+            .help("Render with N threads. Defaults to the number of CPUs, or RAYON_NUM_THREADS.")
+        )
+        .arg(
+            Arg::new("dry_run")
+            .long("dry-run")
+            .help("Print the effective configuration as TOML and the estimated resource usage, without rendering.")
+        )
+        .arg(
+            Arg::new("time_limit")
+            .long("time-limit")
+            .takes_value(true)
+            .value_name("DURATION")
+            .validator(parse::duration)
+            .conflicts_with_all(&[MODES, &["julia_grid", "buddhabrot", "checkpoint", "resume", "palette", "animate", "checksum", "expect_checksum"]].concat())
+            .help("Render the best image possible in DURATION, e.g. `30s`. The render starts with fewer iterations and a lower resolution and refines them, then supersamples, and writes the best quality which finished.")
+        )
+        .arg(
+            Arg::new("verify")
+            .long("verify")
+            .conflicts_with_all(&[MODES, &["animate", "dry_run"]].concat())
+            .help("Render a fixed scene on every backend of this binary, compare them to the CPU f64 render and print the results. Fails if any backend differs. Run this first if the output of a backend looks wrong.")
+        )
+        .arg(
+            Arg::new("force")
+            .long("force")
+            .help("Render even if the estimated memory usage is more than the available memory, and without warning about very many iterations.")
+        )
+        .arg(
+            Arg::new("gui")
+            .long("gui")
+            .short('g')
+            .help("Start the GUI. Requires the `gui` cargo feature.")
+            .long_help("Use `s` to take a 2x screenshot. `m` hides the menybar. Use the arrow keys and scroll to move around the image. Ctrl-click to plot the orbit of a point. `+` and `-` change the exposure by 10%, `[` and `]` halve and double the iterations and `i` toggles the coloured inside.")
+        )
+        .arg(
+            Arg::new("session_log")
+            .long("session-log")
+            .requires("gui")
+            .help("Append every view of the GUI to the session log of today, in `~/.local/share/fractal-renderer/`. The log keeps the last 1000 views.")
+        )
+        .arg(
+            Arg::new("replay_session")
+            .long("replay-session")
+            .takes_value(true)
+            .value_name("FILE")
+            .conflicts_with_all(&["import_location", "region"])
+            .help("Use the config of an entry of a session log. Other options and the size, if given, still apply.")
+        )
+        .arg(
+            Arg::new("replay_index")
+            .long("index")
+            .takes_value(true)
+            .value_name("N")
+            .requires("replay_session")
+            .help("The entry of `--replay-session` to use, starting at 0. Defaults to the last.")
+        );
+    // clap adds `help` and `version` to every app.
+    let mut app = args
+        .get_arguments()
+        .filter(|arg| !["help", "version"].contains(&arg.get_name()) && takes(arg.get_name()))
+        .fold(app, |app, arg| app.arg(arg.clone()));
+    // The groups are all of the size and the view.
+    if takes("width") {
+        app = app
+            .group(ArgGroup::new("width_group").args(&["width", "width_flag"]))
+            .group(ArgGroup::new("height_group").args(&["height", "height_flag"]))
+            .group(
+                ArgGroup::new("scale_individual")
+                    .arg("scale_x")
+                    .arg("scale_y")
+                    .multiple(true),
+            );
+    }
+    app
+}
+
+/// Parses the command line and exits with a usage error if it's invalid.
+pub fn get_options() -> Options {
+    let mut app = build_cli();
+    let all_matches = app.get_matches_mut();
+    let matches = Matches::new(&all_matches);
+    // Set it now, so messages from parsing the options respect it.
+    log::set_max_level(log_level(&matches));
+    // And this, so the estimates of the memory usage count the threads.
+    if let Some(threads) = matches.value_of("threads") {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads.parse().expect("the validator checks the number"))
+            .build_global()
+            .expect("nothing uses the thread pool before parsing the options");
+    }
+    if matches.is_present("help_template") {
+        print!("{}", template::help());
+        std::process::exit(0);
+    }
+    match options_from_matches(&all_matches) {
+        Ok(options) => options,
+        Err(err) => app.error(err.kind(), err).exit(),
+    }
+}
+
+#[derive(Debug)]
+pub enum OptionsError {
+    /// The value of `flag` is invalid, because of `reason`.
+    Value { flag: String, reason: String },
+    /// A value without a default wasn't given.
+    Missing(String),
+    /// The options can't be used together.
+    Conflict(String),
+    /// The options are valid, but this binary can't do what they ask for.
+    Unsupported(String),
+}
+impl OptionsError {
+    /// The kind of the error for clap's message.
+    pub fn kind(&self) -> clap::ErrorKind {
+        match self {
+            Self::Value { .. } | Self::Unsupported(_) => clap::ErrorKind::InvalidValue,
+            Self::Missing(_) => clap::ErrorKind::MissingRequiredArgument,
+            Self::Conflict(_) => clap::ErrorKind::ArgumentConflict,
+        }
+    }
+}
+impl Display for OptionsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Value { flag, reason } => write!(f, "{}: {}", flag, reason),
+            Self::Missing(flag) => write!(f, "{} is required", flag),
+            Self::Conflict(message) | Self::Unsupported(message) => f.write_str(message),
+        }
+    }
+}
+impl std::error::Error for OptionsError {}
+
+/// How the argument `id` is written on the command line, like `--limit`, `-x` or `<width>`.
+fn flag_name(id: &str) -> String {
+    let cli = build_cli();
+    let arg = cli.get_arguments().find(|arg| arg.get_name() == id);
+    match arg.map(|arg| (arg.get_long(), arg.get_short())) {
+        Some((Some(long), _)) => format!("--{}", long),
+        Some((None, Some(short))) => format!("-{}", short),
+        Some((None, None)) => format!("<{}>", id),
+        None => id.to_owned(),
+    }
+}
+/// The value of `id` parsed with `parse`. `id` must be given or have a default value.
+fn parse_value<T, E: Display>(
+    matches: &Matches,
+    id: &str,
+    parse: impl FnOnce(&str) -> Result<T, E>,
+) -> Result<T, OptionsError> {
+    let value = matches
+        .value_of(id)
+        .ok_or_else(|| OptionsError::Missing(flag_name(id)))?;
+    parse(value).map_err(|err| OptionsError::Value {
+        flag: flag_name(id),
+        reason: err.to_string(),
+    })
+}
+/// [`parse_value`], or [`None`] if `id` isn't given.
+fn parse_optional<T, E: Display>(
+    matches: &Matches,
+    id: &str,
+    parse: impl FnOnce(&str) -> Result<T, E>,
+) -> Result<Option<T>, OptionsError> {
+    if matches.value_of(id).is_none() {
+        return Ok(None);
+    }
+    parse_value(matches, id, parse).map(Some)
+}
+/// Parses `s` with [`FromStr`], describing the error.
+fn from_str<T: FromStr>(s: &str) -> Result<T, String>
+where
+    T::Err: Display,
+{
+    s.trim()
+        .parse()
+        .map_err(|err| format!("{:?} isn't valid ({})", s, err))
+}
+/// The value of `id` parsed with [`FromStr`]. `id` must be given or have a default value.
+fn value<T: FromStr>(matches: &Matches, id: &str) -> Result<T, OptionsError>
+where
+    T::Err: Display,
+{
+    parse_value(matches, id, from_str)
+}
+
+fn log_level(matches: &Matches) -> log::LevelFilter {
+    if matches.is_present("quiet") {
+        log::LevelFilter::Error
+    } else if matches.is_present("verbose") {
+        log::LevelFilter::Debug
+    } else {
+        log::LevelFilter::Info
+    }
+}
+
+/// The [`Options`] of `matches`, from the app of [`build_cli`], with or without a [`Command`].
+///
+/// The options are checked with [`validate`]. Warnings about options without an effect are
+/// logged.
+pub fn options_from_matches(matches: &ArgMatches) -> Result<Options, OptionsError> {
+    let matches = &Matches::new(matches);
+    let command = matches.command;
+    // The flags and the positional arguments are in a group, so only one of them is given.
+    let side = |flag, positional| match parse_optional(matches, flag, parse::side)? {
+        Some(side) => Ok(side),
+        None => parse_value(matches, positional, parse::side),
+    };
+    let mut width = side("width_flag", "width")?;
+    let mut height = side("height_flag", "height")?;
+    if let Some((w, h)) = parse_optional(matches, "size", parse::size)? {
+        width = w;
+        height = h;
+        log::debug!("Using the size {}×{}.", width, height);
+    }
+    if let Some(aspect) = parse_optional(matches, "aspect", parse::aspect)? {
+        let given =
+            |flag, positional| matches.occurrences_of(positional) > 0 || matches.is_present(flag);
+        match (given("width_flag", "width"), given("height_flag", "height")) {
+            (true, true) => {
+                return Err(OptionsError::Conflict(
+                    "both width and height are given, so the aspect ratio can't be applied; \
+                     remove one of them or `--aspect`"
+                        .into(),
+                ))
+            }
+            // Derive the width from the height only if the height is the one given. Sides under
+            // `MIN_SIDE` are rejected by `validate`.
+            (false, true) => width = (height as f64 * aspect).round() as u32,
+            _ => height = (width as f64 / aspect).round() as u32,
+        }
+        log::debug!("Using the size {}×{}.", width, height);
+    }
+    let iterations = parse_optional(matches, "iterations", from_str)?;
+    let limit = value(matches, "limit")?;
+    let mut bailout: Bailout = value(matches, "bailout")?;
+    // An ellipse without axes is stretched like the view, once it's known.
+    let mut stretch_bailout = matches!(bailout, Bailout::Ellipse { .. });
+    if let Some((a, b)) = parse_optional(matches, "bailout_ellipse", parse::semi_axes)? {
+        if matches.occurrences_of("bailout") > 0 && !stretch_bailout {
+            return Err(OptionsError::Conflict(
+                "--bailout-ellipse escapes from an ellipse, but another shape was given with \
+                 --bailout"
+                    .into(),
+            ));
+        }
+        bailout = Bailout::Ellipse { a, b };
+        stretch_bailout = false;
+    }
+    let mut stable_limit: f64 = value(matches, "stable_limit")?;
+    if matches.is_present("legacy_stable_limit") {
+        log::warn!("--legacy-stable-limit is deprecated and will be removed in the next release.");
+        stable_limit = stable_limit.sqrt();
+    }
+    let exposure: f64 = value(matches, "exposure")?;
+    let interior_exposure: f64 = value(matches, "interior_exposure")?;
+    let primary_color = parse_optional(matches, "primary_color", parse::color)?;
+    let secondary_color = parse_optional(matches, "secondary_color", parse::color)?;
+    let inside_disabled = matches.is_present("disable_inside");
+    let unsmooth = matches.is_present("unsmooth");
+    let requested_format = value(matches, "format")?;
+    let (filename, format) = parse_value(matches, "filename", |output| {
+        template::Template::parse(output).map(|_| format::output_path(output, requested_format))
+    })?;
+    if matches.occurrences_of("format") > 0 && format != requested_format {
+        log::warn!(
+            "The output {:?} has the extension of {}, ignoring --format {}.",
+            filename,
+            format,
+            requested_format
+        );
+    }
+    let open_with = matches.value_of("open_with").map(str::to_owned);
+    let open = matches.is_present("open") || open_with.is_some();
+    let on_conflict = if matches.is_present("overwrite") {
+        format::OnConflict::Overwrite
+    } else {
+        value(matches, "on_conflict")?
+    };
+    // `-a julia` is from before Julia mode, see `Config::julia`.
+    let legacy_julia = matches.value_of("algo") == Some("julia");
+    let algo: Algo = if legacy_julia {
+        Algo::Mandelbrot
+    } else {
+        value(matches, "algo")?
+    };
+    let julia = match parse_optional(matches, "julia_constant", parse::complex)? {
+        Some(constant) => Some(constant),
+        None if legacy_julia => Some(Imaginary {
+            re: parse_optional(matches, "julia_re", from_str)?.unwrap_or(0.0),
+            im: parse_optional(matches, "julia_im", from_str)?.unwrap_or(0.0),
+        }),
+        None => None,
+    };
+    if julia.is_some() && algo == Algo::BarnsleyFern {
+        return Err(OptionsError::Conflict(
+            "--julia renders a Julia set, which the fern doesn't have".into(),
+        ));
+    }
+    let defaults = calc::defaults(&algo);
+    // The cells of the grid are Julia sets, so it's centered like them.
+    let center = if julia.is_some() || matches.is_present("julia_grid") {
+        defaults.julia_pos
+    } else {
+        defaults.pos
+    };
+    let coordinate = |id, default| -> Result<(f64, f64), OptionsError> {
+        Ok(parse_optional(matches, id, |v| {
+            let coordinate: f64 = from_str(v)?;
+            Ok::<_, String>((coordinate, rounding_error(v, coordinate)))
+        })?
+        .unwrap_or((default, 0.0)))
+    };
+    let (re, re_low) = coordinate("pos_x", center.re)?;
+    let (im, im_low) = coordinate("pos_y", center.im)?;
+    let pos = Imaginary { re, im };
+    let pos_low = Imaginary {
+        re: re_low,
+        im: im_low,
+    };
+    let scale = parse_optional(matches, "scale", from_str)?;
+    let scale = Imaginary {
+        re: parse_optional(matches, "scale_x", from_str)?
+            .or(scale)
+            .unwrap_or(defaults.scale.re),
+        im: parse_optional(matches, "scale_y", from_str)?
+            .or(scale)
+            .unwrap_or(defaults.scale.im),
+    };
+    let fern_normalize = matches.is_present("fern_normalize");
+    let color_weight = if fern_normalize && matches.occurrences_of("color_weight") == 0 {
+        calc::FERN_NORMALIZED_WEIGHT
+    } else {
+        value(matches, "color_weight")?
+    };
+    let palette = parse_optional(matches, "palette", palette::parse)?;
+    let post = parse_optional(matches, "post", post::parse)?.unwrap_or_default();
+    let simulate_cvd = parse_optional(matches, "simulate_cvd", from_str)?;
+    let orbit = parse_optional(matches, "plot_orbit", parse::point)?;
+    let orbit_color = parse_value(matches, "orbit_color", parse::color)?;
+    let grid = matches.is_present("grid");
+    let grid_color = parse_value(matches, "grid_color", parse::color)?;
+    let annotate = if matches.is_present("annotate") {
+        Some(annotate::AnnotateOptions {
+            position: value(matches, "annotate_position")?,
+            foreground: parse_value(matches, "annotate_foreground", parse::color)?,
+            background: parse_value(matches, "annotate_background", parse::color)?,
+            max_fraction: value(matches, "annotate_max_fraction")?,
+        })
+    } else {
+        None
+    };
+    let exp_map = matches.is_present("exp_map");
+    let animation = match parse_optional(matches, "animate", from_str)? {
+        Some(frames) => {
+            let path = match parse_optional(matches, "keyframes", camera::load)? {
+                Some(keyframes) => Some(camera::CameraPath {
+                    keyframes,
+                    interpolation: value(matches, "interpolation")?,
+                }),
+                None => None,
+            };
+            let zoom_to = match &path {
+                Some(path) => path.keyframes[path.keyframes.len() - 1].scale,
+                None => value(matches, "zoom_to")?,
+            };
+            Some(animation::AnimationOptions {
+                frames,
+                zoom_to,
+                path,
+                plot_path: matches.value_of("plot_path").map(str::to_owned),
+                frame_skip: !matches.is_present("no_frame_skip"),
+                exp_map,
+                reuse_frames: matches.is_present("reuse_frames"),
+                reuse_min_psnr: value(matches, "reuse_min_psnr")?,
+            })
+        }
+        None => None,
+    };
+    let buddhabrot = if matches.is_present("buddhabrot") {
+        Some(buddhabrot::BuddhabrotOptions {
+            mode: value(matches, "buddhabrot_mode")?,
+            samples: value(matches, "buddhabrot_samples")?,
+            nebula_iterations: parse_value(
+                matches,
+                "nebula_iterations",
+                buddhabrot::parse_nebula_iterations,
+            )?,
+            sampler: value(matches, "sampler")?,
+            importance: matches.is_present("importance"),
+        })
+    } else {
+        None
+    };
+    let gui = matches.is_present("gui");
+    // Only `info` has the subcommand.
+    let random_julia = match matches.subcommand() {
+        Some(("random-julia", matches)) => {
+            let matches = &Matches::all(matches);
+            Some(discovery::RandomJuliaOptions {
+                count: value(matches, "count")?,
+                seed: parse_optional(matches, "seed", from_str)?.unwrap_or_else(rand::random),
+                sheet: matches.value_of("sheet").map(str::to_owned),
+            })
+        }
+        _ => None,
+    };
+    let dry_run =
+        matches.is_present("dry_run") || (command == Command::Info && random_julia.is_none());
+    let serve = match matches.value_of("serve") {
+        Some(address) => Some(serve::ServeOptions {
+            address: address.to_owned(),
+            extent: parse_value(matches, "serve_extent", parse::region)?,
+            cache_size: value(matches, "serve_cache")?,
+            max_requests: value(matches, "serve_max_requests")?,
+        }),
+        None => None,
+    };
+    let images = matches
+        .values_of("compare")
+        .map(|mut images| {
+            let reference = images.next().map(str::to_owned);
+            (reference, images.next().map(str::to_owned))
+        })
+        .or_else(|| {
+            matches
+                .value_of("reference")
+                .map(|reference| (Some(reference.to_owned()), None))
+        });
+    let compare = match images {
+        Some((reference, image)) => Some(diff::CompareOptions {
+            reference: reference.ok_or_else(|| OptionsError::Missing(flag_name("compare")))?,
+            image,
+            tolerance: value(matches, "compare_tolerance")?,
+            threshold: value(matches, "compare_threshold")?,
+            diff_image: matches.value_of("diff_image").map(str::to_owned),
+            amplify: value(matches, "diff_amplify")?,
+        }),
+        None => None,
+    };
+    let expect_checksum = parse_optional(matches, "expect_checksum", digest::parse_digest)?;
+    let checksum = matches.is_present("checksum") || expect_checksum.is_some();
+    let julia_grid = match parse_optional(matches, "julia_grid", parse::dimensions)? {
+        Some((columns, rows)) => Some(grid::JuliaGrid {
+            columns,
+            rows,
+            bounds: parse_value(matches, "julia_grid_bounds", parse::region)?,
+            labels: matches.is_present("julia_grid_labels"),
+        }),
+        None => None,
+    };
+    let ao = match parse_optional(matches, "ao_strength", from_str)? {
+        Some(strength) => Some(ao::AoOptions {
+            strength,
+            radius: value(matches, "ao_radius")?,
+        }),
+        None => None,
+    };
+    let export_mask = match matches.value_of("export_mask") {
+        Some(path) => Some(mask::MaskOptions {
+            path: path.to_owned(),
+            mode: value(matches, "export_mask_mode")?,
+        }),
+        None => None,
+    };
+    let aa = parse_optional(matches, "adaptive_aa", from_str)?.map(|threshold| aa::AaOptions {
+        threshold,
+        debug: matches.value_of("aa_debug").map(str::to_owned),
+    });
+    let ifs_mutation = match parse_optional(matches, "ifs_mutate", ifs::parse_amount)? {
+        Some(amount) => Some(ifs::Mutation {
+            amount,
+            seed: value(matches, "ifs_seed")?,
+        }),
+        None => None,
+    };
+    let benchmark =
+        parse_optional(matches, "benchmark", from_str)?.map(|runs| benchmark::BenchmarkOptions {
+            runs,
+            json: matches.is_present("benchmark_json"),
+        });
+    let checkpoint = matches
+        .value_of("checkpoint")
+        .map(|path| (path, false))
+        .or_else(|| matches.value_of("resume").map(|path| (path, true)));
+    let checkpoint = match checkpoint {
+        Some((path, resume)) => Some(checkpoint::CheckpointOptions {
+            path: path.into(),
+            interval: parse_value(matches, "checkpoint_interval", parse::duration)?,
+            resume,
+        }),
+        None => None,
+    };
+    let export_iterations = matches.value_of("export_iterations").map(str::to_owned);
+    let deepen = matches.value_of("deepen").map(str::to_owned);
+    let slice = parse_optional(matches, "slice", slice::Slice::parse)?;
+    let merge: Option<Vec<String>> = matches
+        .values_of("merge")
+        .map(|files| files.map(str::to_owned).collect());
+    let batch = match matches.value_of("batch") {
+        Some(manifest) => Some(batch::BatchOptions {
+            manifest: manifest.to_owned(),
+            parallel: value(matches, "batch_parallel")?,
+            fail_fast: matches.is_present("fail_fast"),
+        }),
+        None => None,
+    };
+    let explore = match matches.value_of("explore") {
+        Some(_) => {
+            let positive = |s: &str| match from_str(s)? {
+                0 => Err("must be at least 1".to_owned()),
+                n => Ok(n),
+            };
+            Some(explore::ExploreOptions {
+                count: parse_value(matches, "explore", positive)?,
+                max_candidates: parse_value(matches, "explore_candidates", positive)?,
+                seed: parse_optional(matches, "seed", from_str)?.unwrap_or_else(rand::random),
+                directory: matches.value_of("filename").unwrap_or_default().to_owned(),
+            })
+        }
+        None => None,
+    };
+
+    let orientation = Orientation {
+        rotation: value(matches, "rotate")?,
+        flip_h: matches.is_present("flip_h"),
+        flip_v: matches.is_present("flip_v"),
+    };
+    let region = parse_optional(matches, "region", parse::region)?;
+    if let Some(region) = &region {
+        if matches.is_present("region_fit_height") {
+            // The width of a turned image covers the height of the region.
+            let ratio = if orientation.rotation.is_quarter_turn() {
+                region.width() / region.height()
+            } else {
+                region.height() / region.width()
+            };
+            height = (width as f64 * ratio).round() as u32;
+        }
+    }
+    log::debug!(
+        "The aspect ratio is {:.4} ({}×{}).",
+        width as f64 / height as f64,
+        width,
+        height
+    );
+
+    let mut config = Config {
+        width,
+        height,
+        iterations: iterations.unwrap_or(defaults.iterations),
+        limit,
+        bailout,
+        stable_limit,
+        pos,
+        pos_low,
+        scale,
+        exposure,
+        interior_exposure,
+        interior_mapping: value(matches, "interior_mapping")?,
+        inside: !inside_disabled,
+        smooth: !unsmooth,
+        smooth_mode: value(matches, "smooth_mode")?,
+        precision: value(matches, "precision")?,
+        backend: value(matches, "backend")?,
+        primary_color: primary_color.unwrap_or(defaults.primary_color),
+        secondary_color: secondary_color.unwrap_or(defaults.secondary_color),
+        color_weight,
+        fern_normalize,
+        // The frames of animations are resampled from a strip, but aren't strips themselves.
+        exp_map: exp_map && animation.is_none(),
+        orientation,
+        y_up: matches.is_present("y_up"),
+        pixel_centers: matches.is_present("pixel_centers"),
+        background: parse_value(matches, "background", parse::background)?,
+        julia,
+        algo,
+    };
+    if let Some(region) = region {
+        config.set_viewport(region);
+    }
+    if let Some(path) = matches.value_of("import_location") {
+        let mut location = std::fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|data| location::Location::parse_kfr(&data).map_err(|err| err.to_string()))
+            .map_err(|err| OptionsError::Value {
+                flag: flag_name("import_location"),
+                reason: format!("failed to import location {:?}: {}", path, err),
+            })?;
+        if iterations.is_some() {
+            location.iterations = None;
+        }
+        location.apply(&mut config);
+    }
+    if stretch_bailout {
+        config.bailout = Bailout::stretched(config.scale);
+    }
+    if let Some(path) = matches.value_of("replay_session") {
+        let index = parse_optional(matches, "replay_index", from_str)?;
+        let replayed = session::replay(path, index).map_err(|err| OptionsError::Value {
+            flag: flag_name("replay_session"),
+            reason: format!("failed to replay session {:?}: {}", path, err),
+        })?;
+        let sized = [
+            "width",
+            "height",
+            "width_flag",
+            "height_flag",
+            "size",
+            "aspect",
+        ]
+        .iter()
+        .any(|name| matches.occurrences_of(name) > 0);
+        config = Config {
+            width: if sized { width } else { replayed.width },
+            height: if sized { height } else { replayed.height },
+            ..replayed
+        };
+    }
+
+    let watch = match matches.value_of("config_file") {
+        Some(path) => {
+            let base = config.clone();
+            config = watch::load(path, &base).map_err(|err| OptionsError::Value {
+                flag: flag_name("config_file"),
+                reason: format!("{:?}: {}", path, err),
+            })?;
+            matches.is_present("watch").then(|| watch::WatchOptions {
+                path: path.to_owned(),
+                base,
+            })
+        }
+        None => None,
+    };
+
+    let wallpaper = match matches.values_of("wallpaper") {
+        Some(mut values) => {
+            let invalid = |reason: String| OptionsError::Value {
+                flag: flag_name("wallpaper"),
+                reason,
+            };
+            let mode = from_str(values.next().unwrap_or_default()).map_err(invalid)?;
+            let (columns, rows) = parse::dimensions(values.next().unwrap_or_default())
+                .map_err(|err| invalid(err.to_string()))?;
+            let wallpaper = wallpaper::WallpaperOptions {
+                mode,
+                columns,
+                rows,
+                width: config.width,
+                height: config.height,
+            };
+            let (width, height) = wallpaper.copy_size();
+            log::debug!(
+                "Rendering a {}×{} copy for the {}×{} wallpaper.",
+                width,
+                height,
+                config.width,
+                config.height
+            );
+            config.width = width;
+            config.height = height;
+            Some(wallpaper)
+        }
+        None => None,
+    };
+
+    let options = Options {
+        config,
+        output: OutputOptions {
+            filename,
+            format,
+            open,
+            open_with,
+            on_conflict,
+            encoder: EncoderSettings::default(),
+        },
+        gui,
+        session_log: matches.is_present("session_log"),
+        log_level: log_level(matches),
+        batch,
+        explore,
+        random_julia,
+        watch,
+        julia_grid,
+        boundary_preview: matches.is_present("boundary_preview"),
+        benchmark,
+        checkpoint,
+        export_iterations,
+        export_mask,
+        deepen,
+        slice,
+        merge,
+        serve,
+        animation,
+        buddhabrot,
+        palette,
+        post,
+        simulate_cvd,
+        orbit,
+        orbit_color,
+        grid,
+        grid_color,
+        annotate,
+        compare,
+        checksum,
+        expect_checksum,
+        deterministic: matches.is_present("deterministic"),
+        dry_run,
+        ao,
+        aa,
+        ifs: parse_optional(matches, "ifs", ifs::parse)?,
+        ifs_mutation,
+        time_limit: parse_optional(matches, "time_limit", parse::duration)?,
+        verify: matches.is_present("verify"),
+        error_map: matches.value_of("error_map").map(str::to_owned),
+        timing_map: matches.value_of("timing_map").map(str::to_owned),
+        force: matches.is_present("force"),
+        wallpaper,
+    };
+    validate(&options)?;
+
+    let escape_time_only = [
+        ("exposure", "--exposure"),
+        ("limit", "--limit"),
+        ("bailout", "--bailout"),
+        ("bailout_ellipse", "--bailout-ellipse"),
+        ("stable_limit", "--stable-limit"),
+        ("legacy_stable_limit", "--legacy-stable-limit"),
+        ("disable_inside", "--disable-inside"),
+        ("interior_exposure", "--interior-exposure"),
+        ("interior_mapping", "--interior-mapping"),
+        ("unsmooth", "--unsmooth"),
+        ("smooth_mode", "--smooth-mode"),
+        ("reuse_frames", "--reuse-frames"),
+        ("y_up", "--y-up"),
+        ("pixel_centers", "--pixel-centers"),
+    ];
+    let julia_only = [
+        ("julia_re", "--julia-real"),
+        ("julia_im", "--julia-imaginary"),
+    ];
+    let fern_only = [
+        ("color_weight", "--color-weight"),
+        ("fern_normalize", "--fern-normalize"),
+    ];
+    let config = &options.config;
+    let unused: &[_] = match config.algo {
+        Algo::Mandelbrot if config.julia.is_some() => &fern_only,
+        Algo::Mandelbrot => &[&fern_only[..], &julia_only].concat(),
+        Algo::BarnsleyFern => &[&escape_time_only[..], &julia_only].concat(),
+    };
+    for (arg, flag) in unused {
+        if matches.occurrences_of(arg) > 0 {
+            log::warn!(
+                "{} has no effect with the {:?} algorithm.",
+                flag,
+                config.algo
+            );
+        }
+    }
+    let plotted = config.algo == Algo::BarnsleyFern
+        || options.ifs.is_some()
+        || options.buddhabrot.is_some()
+        || options.boundary_preview;
+    if matches.occurrences_of("background") > 0 && !plotted {
+        log::warn!(
+            "--background has no effect with the {:?} algorithm, which colors every pixel.",
+            config.algo
+        );
+    }
+    if !config.bailout.is_smoothed() && config.smooth && config.algo != Algo::BarnsleyFern {
+        log::warn!(
+            "The iterations aren't smoothed with the {} bailout; use --unsmooth to silence this.",
+            matches.value_of("bailout").unwrap_or_default()
+        );
+    }
+    match render::iterations_warning(config) {
+        Some(render::IterationsWarning::TooMany { .. }) if options.force => {}
+        Some(warning) => log::warn!("Unusual --iterations: {}.", warning),
+        None => {}
+    }
+    if let (Some(palette), Some(cvd)) = (&options.palette, options.simulate_cvd) {
+        for confusion in color::lint(palette, cvd) {
+            log::warn!(
+                "Stops {} and {} of the palette look alike with {} (ΔE {:.1}, {:.1} without).",
+                confusion.stop + 1,
+                confusion.stop + 2,
+                cvd,
+                confusion.simulated_delta_e,
+                confusion.delta_e
+            );
+        }
+    }
+
+    Ok(options)
+}
+
+/// Checks that the options can be used together and that this binary supports them.
+///
+/// ```
+/// # use fractal_renderer::{build_cli, options_from_matches, OptionsError};
+/// let parse = |args: &[&str]| {
+///     let matches = build_cli().get_matches_from(["fractal-renderer"].iter().chain(args));
+///     options_from_matches(&matches)
+/// };
+/// assert!(parse(&["--scale", "2"]).is_ok());
+/// for args in [&["--scale", "0"][..], &["--scale-y=-1"], &["--scale-x", "inf"]] {
+///     let err = parse(args).unwrap_err();
+///     assert!(matches!(err, OptionsError::Value { flag, .. } if flag == "--scale"));
+/// }
+/// let err = parse(&["-i", "0"]).unwrap_err();
+/// assert!(matches!(err, OptionsError::Value { flag, .. } if flag == "--iterations"));
+/// ```
+pub fn validate(options: &Options) -> Result<(), OptionsError> {
+    let config = &options.config;
+    if let Err(err) = config.validate() {
+        let flag = match err {
+            ConfigError::Scale(_) => "scale",
+            ConfigError::NoIterations => "iterations",
+        };
+        return Err(OptionsError::Value {
+            flag: flag_name(flag),
+            reason: err.to_string(),
+        });
+    }
+    if config.stable_limit > config.limit {
+        return Err(OptionsError::Conflict(format!(
+            "the stable limit ({}) can't be larger than the escape limit ({})",
+            config.stable_limit, config.limit
+        )));
+    }
+
+    if config.width < parse::MIN_SIDE || config.height < parse::MIN_SIDE {
+        return Err(OptionsError::Conflict(format!(
+            "the size is {}×{}, but both the width and height must be at least {} pixels",
+            config.width,
+            config.height,
+            parse::MIN_SIDE
+        )));
+    }
+
+    if resources::image_bytes(config).is_none() {
+        return Err(OptionsError::Unsupported(format!(
+            "a {}×{} image is too large to be addressed on this platform",
+            config.width, config.height
+        )));
+    }
+
+    let larger_side = config.width.max(config.height);
+    for op in &options.post {
+        if let post::PostOp::Blur(sigma) = *op {
+            if sigma > larger_side as f32 {
+                return Err(OptionsError::Value {
+                    flag: flag_name("post"),
+                    reason: format!(
+                        "a blur of {} pixels is larger than the image, {} pixels",
+                        sigma, larger_side
+                    ),
+                });
+            }
+        }
+    }
+
+    if config.background == Background::Transparent {
+        return Err(OptionsError::Unsupported(
+            "a transparent background needs images with an alpha channel, which aren't supported \
+             yet; --background checkerboard previews it"
+                .into(),
+        ));
+    }
+
+    if let Err(err) = render::backend(config) {
+        return Err(OptionsError::Unsupported(err.to_string()));
+    }
+    if (options.export_iterations.is_some() || options.deepen.is_some())
+        && config.algo == Algo::BarnsleyFern
+    {
+        return Err(OptionsError::Conflict(
+            "the fern has no iterations to export or deepen".into(),
+        ));
+    }
+    if options.deepen.is_some() && config.uses_double_double() {
+        return Err(OptionsError::Value {
+            flag: flag_name("deepen"),
+            reason: iterations::ExportError::DoubleDouble.to_string(),
+        });
+    }
+    if options.deterministic && config.backend == Backend::Gpu {
+        return Err(OptionsError::Conflict(
+            "the GPU backend isn't deterministic; use --backend cpu with --deterministic".into(),
+        ));
+    }
+
+    if options.serve.is_some() {
+        Feature::Serve
+            .require("serving tiles")
+            .map_err(OptionsError::Unsupported)?;
+    }
+    if options.watch.is_some() {
+        Feature::Watch
+            .require("watching the config")
+            .map_err(OptionsError::Unsupported)?;
+    }
+    if options.gui {
+        Feature::Gui
+            .require("the GUI")
+            .map_err(OptionsError::Unsupported)?;
+    }
+    // Batch jobs can have their own formats, which are checked before rendering the batch.
+    let writes_image = !options.gui
+        && !options.dry_run
+        && options.benchmark.is_none()
+        && options.slice.is_none()
+        && options.batch.is_none()
+        && options.serve.is_none()
+        && options.compare.is_none();
+    let output_format = match &options.merge {
+        Some(files) => format::output_path(&files[0], options.output.format).1,
+        None => options.output.format,
     };
+    if writes_image && !output_format.is_supported() {
+        return Err(OptionsError::Unsupported(format!(
+            "can't write {} files, as this binary was built without the {} feature",
+            output_format, output_format
+        )));
+    }
 
-    Options {
-        config,
-        filename,
-        open,
-        gui,
+    // Batch jobs and tiles have sizes of their own.
+    let renders = !options.gui
+        && !options.dry_run
+        && options.batch.is_none()
+        && options.serve.is_none()
+        && options.merge.is_none();
+    if renders && !options.force {
+        let needed = resources::peak_memory(options);
+        match resources::available_memory() {
+            Some(available) if needed > available => {
+                return Err(OptionsError::Unsupported(format!(
+                    "the render needs about {} of memory, but only {} is available; \
+                     render it in parts with --slice or use --force to try anyway",
+                    resources::format_bytes(needed),
+                    resources::format_bytes(available)
+                )))
+            }
+            _ => {}
+        }
+    }
+
+    if options.grid && config.orientation.rotation.is_quarter_turn() {
+        return Err(OptionsError::Unsupported(
+            "the labels of --grid can't be turned; use --rotate 0 or 180".into(),
+        ));
+    }
+
+    if options.boundary_preview && config.julia.is_none() {
+        return Err(OptionsError::Conflict(
+            "--boundary-preview plots the boundary of a Julia set; use it with -a julia or --julia"
+                .into(),
+        ));
+    }
+    if config.algo != Algo::BarnsleyFern {
+        if options.ifs.is_some() {
+            return Err(OptionsError::Conflict(
+                "--ifs replaces the fern; use it with -a fern".into(),
+            ));
+        }
+        if options.ifs_mutation.is_some() {
+            return Err(OptionsError::Conflict(
+                "--ifs-mutate mutates the fern; use it with -a fern".into(),
+            ));
+        }
+    }
+    if let Algo::BarnsleyFern = config.algo {
+        if options.orbit.is_some() {
+            return Err(OptionsError::Conflict(
+                "the fern has no orbits; --plot-orbit needs an escape-time fractal".into(),
+            ));
+        }
+        if options.grid {
+            return Err(OptionsError::Conflict(
+                "the fern isn't in the complex plane; --grid needs an escape-time fractal".into(),
+            ));
+        }
+        if options.palette.is_some() {
+            return Err(OptionsError::Conflict(
+                "the fern has no iterations to color; --palette needs an escape-time fractal"
+                    .into(),
+            ));
+        }
+        if options.ao.is_some() {
+            return Err(OptionsError::Conflict(
+                "the fern has no iterations to shade; --ao-strength needs an escape-time fractal"
+                    .into(),
+            ));
+        }
+        if options.aa.is_some() {
+            return Err(OptionsError::Conflict(
+                "the fern is plotted, not sampled; --adaptive-aa needs an escape-time fractal"
+                    .into(),
+            ));
+        }
+        if options.time_limit.is_some() {
+            return Err(OptionsError::Conflict(
+                "the fern is plotted in one pass; --time-limit needs an escape-time fractal".into(),
+            ));
+        }
+        if options.error_map.is_some() {
+            return Err(OptionsError::Conflict(
+                "the fern has no iterations to compare; --error-map needs an escape-time fractal"
+                    .into(),
+            ));
+        }
+        if options.export_mask.is_some() {
+            return Err(OptionsError::Conflict(
+                "the fern has no inside to mask; --export-mask needs an escape-time fractal".into(),
+            ));
+        }
+        if options.timing_map.is_some() {
+            return Err(OptionsError::Conflict(
+                "the fern is plotted, not rendered in tiles; --timing-map needs an escape-time \
+                 fractal"
+                    .into(),
+            ));
+        }
+        if options.explore.is_some() {
+            return Err(OptionsError::Conflict(
+                "the fern has no iterations to score; --explore needs an escape-time fractal"
+                    .into(),
+            ));
+        }
+        let animated_exp_map = options
+            .animation
+            .as_ref()
+            .map_or(false, |animation| animation.exp_map);
+        if config.exp_map || animated_exp_map {
+            return Err(OptionsError::Conflict(
+                "the fern isn't in the complex plane; --exp-map needs an escape-time fractal"
+                    .into(),
+            ));
+        }
+    }
+
+    if options.explore.is_some() && options.output.filename == format::STDOUT {
+        return Err(OptionsError::Unsupported(
+            "the views of --explore are written to a directory; --output can't be stdout".into(),
+        ));
+    }
+    if options.animation.is_some() && options.output.filename == format::STDOUT {
+        return Err(OptionsError::Unsupported(
+            "the frames of an animation are written to files; --output can't be stdout".into(),
+        ));
+    }
+
+    if let Ok(output) = template::Template::parse(&options.output.filename) {
+        let known = [
+            (
+                template::Field::Frame,
+                options.animation.is_some(),
+                "--animate",
+            ),
+            (template::Field::Name, options.batch.is_some(), "--batch"),
+            (
+                template::Field::Seed,
+                options.ifs_mutation.is_some(),
+                "--ifs-mutate",
+            ),
+        ];
+        for (field, known, flag) in known {
+            if output.uses(field) && !known {
+                return Err(OptionsError::Conflict(format!(
+                    "{} in --output only has a value with {}",
+                    field, flag
+                )));
+            }
+        }
+    }
+
+    if let Some(buddhabrot) = &options.buddhabrot {
+        if config.algo != Algo::Mandelbrot {
+            return Err(OptionsError::Conflict(
+                "the Buddhabrot is made of the orbits of the Mandelbrot set; \
+                 --buddhabrot needs the mandelbrot algorithm"
+                    .into(),
+            ));
+        }
+        match buddhabrot::buffer_bytes(config, buddhabrot) {
+            None => {
+                return Err(OptionsError::Unsupported(format!(
+                    "the Buddhabrot buffers of a {}×{} image are too large to be addressed \
+                     on this platform",
+                    config.width, config.height
+                )))
+            }
+            Some(bytes) if bytes >= buddhabrot::LARGE_BUFFERS => log::warn!(
+                "The Buddhabrot buffers use {}; set RAYON_NUM_THREADS to use fewer threads and \
+                 less memory.",
+                resources::format_bytes(bytes)
+            ),
+            Some(bytes) => log::debug!(
+                "The Buddhabrot buffers use {}.",
+                resources::format_bytes(bytes)
+            ),
+        }
+    }
+
+    if let Some(path) = options.aa.as_ref().and_then(|aa| aa.debug.as_ref()) {
+        let format = format::output_path(path, format::Format::Png).1;
+        if !format.is_supported() {
+            return Err(OptionsError::Unsupported(format!(
+                "can't write the {} map of the samples, as this binary was built without the {} \
+                 feature",
+                format, format
+            )));
+        }
+    }
+    if let Some(mask) = &options.export_mask {
+        let format = format::output_path(&mask.path, format::Format::Png).1;
+        if format != format::Format::Png {
+            return Err(OptionsError::Value {
+                flag: flag_name("export_mask"),
+                reason: format!("masks are written as png, not {}", format),
+            });
+        }
+        if !format.is_supported() {
+            return Err(OptionsError::Unsupported(
+                "can't write the mask, as this binary was built without the png feature".into(),
+            ));
+        }
+    }
+    if let Some(path) = &options.timing_map {
+        let format = format::output_path(path, format::Format::Png).1;
+        if !format.is_supported() {
+            return Err(OptionsError::Unsupported(format!(
+                "can't write the {} timing map, as this binary was built without the {} feature",
+                format, format
+            )));
+        }
+    }
+    if let Some(path) = options
+        .compare
+        .as_ref()
+        .and_then(|compare| compare.diff_image.as_ref())
+    {
+        let format = format::output_path(path, format::Format::Png).1;
+        if !format.is_supported() {
+            return Err(OptionsError::Unsupported(format!(
+                "can't write the {} heat-map, as this binary was built without the {} feature",
+                format, format
+            )));
+        }
     }
+    Ok(())
 }
 
+/// Where and how the image is written, see [`write_image`].
 #[derive(Debug, Clone, PartialEq)]
-pub struct Options {
-    pub config: Config,
-
+pub struct OutputOptions {
+    /// The resolved output path, see [`format::output_path`].
     pub filename: String,
+    pub format: format::Format,
+    /// Open the image after it's written.
     pub open: bool,
+    /// The program to open the image with, instead of the default viewer.
+    pub open_with: Option<String>,
+    /// What to do if [`Self::filename`] already exists.
+    pub on_conflict: format::OnConflict,
+    pub encoder: EncoderSettings,
+}
+impl OutputOptions {
+    /// Writes to `path` instead, in the format of its extension or `default`, see
+    /// [`format::output_path`].
+    pub fn set_path(&mut self, path: &str, default: format::Format) {
+        let (filename, format) = format::output_path(path, default);
+        self.filename = filename;
+        self.format = format;
+    }
+    /// [`Self::filename`] with its fields replaced by `values`, see [`template`].
+    pub fn expanded(&self, values: &template::Values) -> Result<String, template::TemplateError> {
+        template::Template::parse(&self.filename)?.render(values)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    /// What to render. Everything else is about what to do with it.
+    pub config: Config,
+    pub output: OutputOptions,
     pub gui: bool,
+    /// Log the views of the GUI, see [`session`].
+    pub session_log: bool,
+    /// Only used by the binary, as a library shouldn't set up logging.
+    pub log_level: log::LevelFilter,
+    pub batch: Option<batch::BatchOptions>,
+    /// Render the most interesting views within the view, see [`explore`].
+    pub explore: Option<explore::ExploreOptions>,
+    /// Print Julia constants near the boundary of the Mandelbrot set, see `info random-julia`.
+    pub random_julia: Option<discovery::RandomJuliaOptions>,
+    /// Render again whenever the file of `--config` changes, see [`watch::run`].
+    pub watch: Option<watch::WatchOptions>,
+    pub julia_grid: Option<grid::JuliaGrid>,
+    /// Plot only the boundary of the Julia set, see [`get_julia_boundary_image`]. The GUI sets
+    /// it while the constant is dragged.
+    pub boundary_preview: bool,
+    pub benchmark: Option<benchmark::BenchmarkOptions>,
+    pub checkpoint: Option<checkpoint::CheckpointOptions>,
+    /// Also write the samples to this file, see [`iterations::write`].
+    pub export_iterations: Option<String>,
+    /// Also write a mask of the inside, see [`mask`].
+    pub export_mask: Option<mask::MaskOptions>,
+    /// Continue the render exported to this file, see [`iterations::deepen`].
+    pub deepen: Option<String>,
+    pub slice: Option<slice::Slice>,
+    /// The output file followed by the parts.
+    pub merge: Option<Vec<String>>,
+    pub serve: Option<serve::ServeOptions>,
+    /// Render a zoom animation instead of one image.
+    pub animation: Option<animation::AnimationOptions>,
+    /// Render the Buddhabrot instead of [`Config::algo`].
+    pub buddhabrot: Option<buddhabrot::BuddhabrotOptions>,
+    /// Color the escaping points with this instead of [`Config::primary_color`].
+    pub palette: Option<palette::Palette>,
+    /// The post-processing applied to the render, before the overlays, see [`post`].
+    pub post: Vec<post::PostOp>,
+    /// Show the image as it looks with this deficiency, after the post-processing and overlays,
+    /// see [`color::simulate_image`].
+    pub simulate_cvd: Option<color::Cvd>,
+    /// The point whose orbit is drawn over the image, see [`orbit::draw`].
+    pub orbit: Option<Imaginary>,
+    pub orbit_color: RGB,
+    /// Draw the axes and gridlines, see [`axes::draw`].
+    pub grid: bool,
+    pub grid_color: RGB,
+    /// Caption the image, see [`annotate::draw`].
+    pub annotate: Option<annotate::AnnotateOptions>,
+    pub compare: Option<diff::CompareOptions>,
+    /// Print the digest of the render, see [`digest`].
+    pub checksum: bool,
+    /// Fail if the digest isn't this.
+    pub expect_checksum: Option<String>,
+    /// Render the same image for every number of threads, see [`deterministic`].
+    pub deterministic: bool,
+    /// Print the config and estimates instead of rendering.
+    pub dry_run: bool,
+    /// Darken the image by its ambient occlusion, see [`ao`].
+    pub ao: Option<ao::AoOptions>,
+    /// Supersample the edges, see [`aa`].
+    pub aa: Option<aa::AaOptions>,
+    /// Plot this instead of the fern, see [`get_ifs_image`].
+    pub ifs: Option<ifs::Ifs>,
+    /// Render a contact sheet of mutants of [`Self::ifs`] or the fern, see [`ifs::mutation_sheet`].
+    pub ifs_mutation: Option<ifs::Mutation>,
+    /// Render the best quality possible in this time, see [`refine::render`].
+    pub time_limit: Option<std::time::Duration>,
+    /// Compare the backends instead of rendering, see [`verify::verify_backends`].
+    pub verify: bool,
+    /// Also write the heat-map of [`error_map::estimate`] to this file.
+    pub error_map: Option<String>,
+    /// Also write the heat-map of [`timing::heat_map`] to this file.
+    pub timing_map: Option<String>,
+    /// Render even if it's estimated to not fit in memory, see [`resources::peak_memory`].
+    pub force: bool,
+    /// Compose the output of copies of the render, which is the size of one, see [`wallpaper`].
+    pub wallpaper: Option<wallpaper::WallpaperOptions>,
+}
+impl Options {
+    /// The seed of the fern, IFS and Buddhabrot, if they have to be the same every time, for
+    /// [`Self::checksum`] and [`Self::deterministic`].
+    pub fn seed(&self) -> Option<u64> {
+        (self.checksum || self.deterministic).then(|| digest::SEED)
+    }
 }
 
-#[cfg(feature = "avif")]
-pub fn image_to_data(image: Image, image_config: &ravif::Config, options: &Options) -> Vec<u8> {
-    println!("Starting encode.");
-    let (data, _) = ravif::encode_rgb(image.into(), image_config).expect("encoding failed");
-    println!("Finished encode. Writing file {:?}.", options.filename);
-    data
+/// Serializes `config` as TOML, with the same keys as the batch manifests use.
+pub fn config_to_toml(config: &Config) -> String {
+    toml::Value::try_from(config)
+        .expect("a config is always serializable to TOML")
+        .to_string()
+}
+
+/// Renders the rows in `rows` of an escape-time fractal.
+///
+/// Returns an empty vec for the fern.
+pub fn get_rows(config: &Config, rows: Range<u32>) -> Vec<RGB> {
+    if let Algo::BarnsleyFern = config.algo {
+        return Vec::new();
+    }
+    let resolved = resolved::ResolvedConfig::new(config);
+    rows
+        // Only one parallell iter, else, it'd be less efficient.
+        .into_par_iter()
+        .map(|y| {
+            let mut row = Vec::with_capacity(config.width as usize);
+            for x in 0..config.width {
+                row.push(resolved.pixel(x, y))
+            }
+            row
+        })
+        .flatten()
+        .collect()
+}
+
+/// The [`Sample`]s of every pixel of an escape-time fractal, row by row. They can be colored with
+/// [`colorize`] or [`view::colorize`] without iterating them again.
+///
+/// Returns an empty vec for the fern.
+pub fn get_samples(config: &Config) -> Vec<Sample> {
+    if let Algo::BarnsleyFern = config.algo {
+        return Vec::new();
+    }
+    let resolved = resolved::ResolvedConfig::new(config);
+    (0..config.height)
+        .into_par_iter()
+        .map(|y| {
+            (0..config.width)
+                .map(|x| resolved.sample(x as f64, y as f64))
+                .collect::<Vec<_>>()
+        })
+        .flatten()
+        .collect()
+}
+
+/// The side of the tiles of [`get_samples_tiled`].
+pub const TILE_SIZE: usize = 64;
+
+/// Like [`get_samples`], but calls `on_tile` with every tile of at most
+/// [`TILE_SIZE`]×[`TILE_SIZE`] pixels and its samples, row by row, as soon as it's finished.
+/// The tiles finish in any order.
+///
+/// ```
+/// # use fractal_renderer::{get_samples, get_samples_tiled, Algo, Config};
+/// # use std::sync::Mutex;
+/// let mut config = Config::new(Algo::Mandelbrot);
+/// config.width = 100;
+/// config.height = 70;
+/// let covered = Mutex::new(0);
+/// let samples = get_samples_tiled(&config, |tile, samples| {
+///     assert_eq!(tile.width * tile.height, samples.len());
+///     *covered.lock().unwrap() += samples.len();
+/// });
+/// assert_eq!(covered.into_inner().unwrap(), 100 * 70);
+/// assert_eq!(samples, get_samples(&config));
+/// ```
+pub fn get_samples_tiled(
+    config: &Config,
+    on_tile: impl Fn(grid::Cell, &[Sample]) + Sync,
+) -> Vec<Sample> {
+    if let Algo::BarnsleyFern = config.algo {
+        return Vec::new();
+    }
+    let tiles = tiles(config);
+    let rendered: Vec<(grid::Cell, Vec<Sample>)> = tiles
+        .into_par_iter()
+        .map(|tile| {
+            let samples = tile_samples(config, tile);
+            on_tile(tile, &samples);
+            (tile, samples)
+        })
+        .collect();
+    assemble_tiles(config, &rendered)
+}
+/// Like [`get_samples_tiled`], but starts with the tiles of the lowest `priority`, like the
+/// distance from where the user looks, so they're finished first. Tiles of the same priority
+/// start row by row.
+///
+/// ```
+/// # use fractal_renderer::{get_samples, get_samples_tiled_by, grid::Cell, Algo, Config, Imaginary};
+/// # use std::sync::Mutex;
+/// let mut config = Config::new_julia(Algo::Mandelbrot, Imaginary::ZERO);
+/// config.width = 300;
+/// config.height = 200;
+/// let from_center = |tile: &Cell| {
+///     let x = tile.x as f64 + tile.width as f64 / 2.0 - 150.0;
+///     let y = tile.y as f64 + tile.height as f64 / 2.0 - 100.0;
+///     x.hypot(y)
+/// };
+/// let backwards = |tile: &Cell| -((tile.row * 10 + tile.column) as f64);
+/// let priorities: [&(dyn Fn(&Cell) -> f64 + Sync); 3] = [&from_center, &backwards, &|_| 0.0];
+/// for priority in priorities {
+///     let rendered = Mutex::new(Vec::new());
+///     let samples = get_samples_tiled_by(&config, priority, |tile, _| {
+///         rendered.lock().unwrap().push((tile.row, tile.column));
+///     });
+///     let mut rendered = rendered.into_inner().unwrap();
+///     rendered.sort_unstable();
+///     // 5×4 tiles, each rendered once.
+///     let all: Vec<_> = (0..4).flat_map(|row| (0..5).map(move |column| (row, column))).collect();
+///     assert_eq!(rendered, all);
+///     assert_eq!(samples, get_samples(&config));
+/// }
+/// ```
+pub fn get_samples_tiled_by(
+    config: &Config,
+    priority: impl Fn(&grid::Cell) -> f64,
+    on_tile: impl Fn(grid::Cell, &[Sample]) + Sync,
+) -> Vec<Sample> {
+    if let Algo::BarnsleyFern = config.algo {
+        return Vec::new();
+    }
+    let mut queue: Vec<(f64, grid::Cell)> = tiles(config)
+        .into_iter()
+        .map(|tile| (priority(&tile), tile))
+        .collect();
+    // Stable, so ties stay row by row.
+    queue.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(cmp::Ordering::Equal));
+    // The threads take the next tile of the queue when they're done with one.
+    let mut rendered: Vec<(grid::Cell, Vec<Sample>)> = queue
+        .into_iter()
+        .map(|(_, tile)| tile)
+        .par_bridge()
+        .map(|tile| {
+            let samples = tile_samples(config, tile);
+            on_tile(tile, &samples);
+            (tile, samples)
+        })
+        .collect();
+    rendered.sort_unstable_by_key(|(tile, _)| (tile.row, tile.column));
+    assemble_tiles(config, &rendered)
+}
+/// Like [`get_samples_tiled`], but also returns when each tile was rendered, row by row, see
+/// [`timing`].
+pub fn get_samples_timed(config: &Config) -> (Vec<Sample>, Vec<timing::TileTiming>) {
+    let timings = std::sync::Mutex::new(Vec::new());
+    let rendered: Vec<(grid::Cell, Vec<Sample>)> = tiles(config)
+        .into_par_iter()
+        .map(|tile| {
+            let start = std::time::Instant::now();
+            let samples = tile_samples(config, tile);
+            let end = std::time::Instant::now();
+            timings
+                .lock()
+                .unwrap()
+                .push(timing::TileTiming { tile, start, end });
+            (tile, samples)
+        })
+        .collect();
+    let mut timings = timings.into_inner().unwrap();
+    timings.sort_unstable_by_key(|timing| (timing.tile.row, timing.tile.column));
+    (assemble_tiles(config, &rendered), timings)
+}
+
+/// The tiles of [`get_samples_tiled`], row by row.
+fn tiles(config: &Config) -> Vec<grid::Cell> {
+    let (width, height) = (config.width as usize, config.height as usize);
+    let columns = (width + TILE_SIZE - 1) / TILE_SIZE;
+    let rows = (height + TILE_SIZE - 1) / TILE_SIZE;
+    (0..columns * rows)
+        .map(|i| {
+            let (column, row) = (i % columns, i / columns);
+            let (x, y) = (column * TILE_SIZE, row * TILE_SIZE);
+            grid::Cell {
+                column,
+                row,
+                x,
+                y,
+                width: TILE_SIZE.min(width - x),
+                height: TILE_SIZE.min(height - y),
+            }
+        })
+        .collect()
+}
+fn tile_samples(config: &Config, tile: grid::Cell) -> Vec<Sample> {
+    let resolved = resolved::ResolvedConfig::new(config);
+    let resolved = &resolved;
+    (tile.y..tile.y + tile.height)
+        .flat_map(|y| {
+            (tile.x..tile.x + tile.width).map(move |x| resolved.sample(x as f64, y as f64))
+        })
+        .collect()
+}
+/// The samples of the image, row by row, from the samples of its tiles, which are row by row.
+fn assemble_tiles(config: &Config, tiles: &[(grid::Cell, Vec<Sample>)]) -> Vec<Sample> {
+    let (width, height) = (config.width as usize, config.height as usize);
+    let columns = (width + TILE_SIZE - 1) / TILE_SIZE;
+    let mut samples = Vec::with_capacity(width * height);
+    for row in tiles.chunks(columns) {
+        for y in 0..row[0].0.height {
+            for (tile, tile_samples) in row {
+                samples.extend_from_slice(&tile_samples[y * tile.width..(y + 1) * tile.width]);
+            }
+        }
+    }
+    samples
 }
 
 pub fn get_image(config: &Config) -> Vec<RGB> {
+    render_contents(config, None)
+}
+/// Like [`get_image`], but the fern is always the same for the same `seed`, independent of the
+/// number of threads.
+pub fn get_image_seeded(config: &Config, seed: u64) -> Vec<RGB> {
+    render_contents(config, Some(seed))
+}
+/// The number of parts the seeded fern is split in, to plot them in parallel.
+pub(crate) const SEEDED_FERN_PARTS: u32 = 16;
+fn render_contents(config: &Config, seed: Option<u64>) -> Vec<RGB> {
     match config.algo {
-        Algo::Mandelbrot | Algo::Julia => {
-            let image: Vec<_> = (0..config.height)
-                // Only one parallell iter, else, it'd be less efficient.
-                .into_par_iter()
-                .map(|y| {
-                    let mut row = Vec::with_capacity(config.width as usize);
-                    for x in 0..config.width {
-                        row.push(get_recursive_pixel(config, x, y))
-                    }
-                    row
-                })
-                .flatten()
-                .collect();
-
-            image
-        }
-        Algo::BarnsleyFern => {
-            /// # Safety
-            ///
-            /// Width and height of `a` & `b` must be equal.
-            unsafe fn combine_images(a: &mut Image, b: &mut Image) {
-                for y in 0..a.height {
-                    for x in 0..a.width {
-                        let idx = y * a.width + x;
-                        let pix = a.contents.get_unchecked(idx);
-                        let acc_pix = b.contents.get_unchecked_mut(idx);
-                        *acc_pix += *pix;
-                    }
-                }
+        Algo::Mandelbrot => get_rows(config, 0..config.height),
+        Algo::BarnsleyFern => get_ifs_image(config, &ifs::Ifs::barnsley(), seed),
+    }
+}
+/// Plots `ifs` like the fern of `config`, which is always the same for the same `seed`, like
+/// [`get_image_seeded`].
+pub fn get_ifs_image(config: &Config, ifs: &ifs::Ifs, seed: Option<u64>) -> Vec<RGB> {
+    plot_points(config, seed, |config, rng, plot| {
+        ifs_points(config, ifs, rng, plot)
+    })
+}
+/// The number of points the boundary of [`get_julia_boundary_image`] plots for every pixel.
+pub const JULIA_BOUNDARY_POINTS_PER_PIXEL: u32 = 8;
+/// Plots the boundary of the Julia set of [`Config::julia`], or of 0 outside of Julia mode, by
+/// inverse iteration, like the fern, which is always the same for the same `seed`.
+///
+/// Instead of [`Config::iterations`], [`JULIA_BOUNDARY_POINTS_PER_PIXEL`] points are plotted
+/// for every pixel. This is orders of magnitude faster than the escape-time render, but the
+/// boundary is plotted unevenly: its deep inlets are faint.
+///
+/// ```
+/// # use fractal_renderer::{get_julia_boundary_image, Algo, Config, Imaginary};
+/// let mut config = Config::new_julia(Algo::Mandelbrot, Imaginary { re: -0.8, im: 0.156 });
+/// config.width = 120;
+/// config.height = 80;
+/// let image = get_julia_boundary_image(&config, Some(1));
+/// assert_eq!(image, get_julia_boundary_image(&config, Some(1)));
+/// let plotted = image
+///     .iter()
+///     .filter(|pixel| **pixel != config.secondary_color)
+///     .count();
+/// // The boundary is a thin curve.
+/// assert!(plotted > 100 && plotted < image.len() / 2);
+/// ```
+pub fn get_julia_boundary_image(config: &Config, seed: Option<u64>) -> Vec<RGB> {
+    let mut config = config.clone();
+    config.iterations = config
+        .width
+        .saturating_mul(config.height)
+        .saturating_mul(JULIA_BOUNDARY_POINTS_PER_PIXEL);
+    plot_points(&config, seed, julia_boundary_points)
+}
+/// One seed for every one of the `parts` parts of a render seeded with `seed`, so they don't
+/// repeat the same points.
+pub(crate) fn part_seeds(seed: u64, parts: u32) -> Vec<u64> {
+    let mut rng = rand::rngs::SmallRng::seed_from_u64(seed);
+    (0..parts).map(|_| rng.gen()).collect()
+}
+/// The hit counts of the points of a part, for [`Config::fern_normalize`]. `points` plots the
+/// points of the config of the part with the random numbers of `rng`, see [`plot_points`].
+pub(crate) fn plot_part_hits(
+    config: &Config,
+    rng: &mut rand::rngs::SmallRng,
+    points: &impl Fn(&Config, &mut rand::rngs::SmallRng, &mut dyn FnMut(usize, usize)),
+) -> Vec<u32> {
+    let (width, height) = (config.width as usize, config.height as usize);
+    let mut hits = vec![0_u32; width * height];
+    points(config, rng, &mut |x, y| {
+        if x < width && y < height {
+            let hits = &mut hits[y * width + x];
+            *hits = hits.saturating_add(1);
+        }
+    });
+    hits
+}
+/// The points of a part subtracted from the [background](fern_background).
+pub(crate) fn plot_part_colors(
+    config: &Config,
+    rng: &mut rand::rngs::SmallRng,
+    points: &impl Fn(&Config, &mut rand::rngs::SmallRng, &mut dyn FnMut(usize, usize)),
+) -> Vec<RGB> {
+    let mut contents = fern_background(config);
+    let mut image = Image::new(&mut contents, config.width as usize, config.height as usize);
+    let color = config.primary_color;
+    points(config, rng, &mut |x, y| {
+        image.subtract_pixel(x, y, color, config.color_weight)
+    });
+    contents
+}
+/// Combines the hit counts of two parts. They're added with saturation, so the order doesn't
+/// matter.
+pub(crate) fn combine_hits(mut a: Vec<u32>, b: Vec<u32>) -> Vec<u32> {
+    for (a, b) in a.iter_mut().zip(&b) {
+        *a = a.saturating_add(*b);
+    }
+    a
+}
+/// Combines the colors of two parts of `config`. An empty part is none yet.
+pub(crate) fn combine_colors(config: &Config, mut a: Vec<RGB>, mut b: Vec<RGB>) -> Vec<RGB> {
+    /// # Safety
+    ///
+    /// Width and height of `a` & `b` must be equal.
+    unsafe fn combine_images(a: &mut Image, b: &mut Image) {
+        for y in 0..a.height {
+            for x in 0..a.width {
+                let idx = y * a.width + x;
+                let pix = a.contents.get_unchecked(idx);
+                let acc_pix = b.contents.get_unchecked_mut(idx);
+                *acc_pix += *pix;
             }
-            let threads = rayon::current_num_threads() as u32;
-            let per_thread_iterations = config.iterations / threads;
-
-            let mut config = config.clone();
-            config.iterations = per_thread_iterations;
-
-            // we can use a parallel iterator, as this fractal is random, so we should
-            // probabilistically get the same result as when using 1 thread.
-            let images = (0..threads).into_par_iter().map(|_| {
-                let mut contents =
-                    vec![config.secondary_color; (config.width * config.height) as usize];
-
-                let mut image =
-                    Image::new(&mut contents, config.width as usize, config.height as usize);
-                fern(&config, &mut image);
-                contents
-            });
+        }
+    }
+    match (a.is_empty(), b.is_empty()) {
+        (true, false) => b,
+        (false, true) => a,
+        (true, true) => unreachable!(),
+        (false, false) => {
+            let mut im_a = Image::new(&mut a, config.width as usize, config.height as usize);
+            let mut im_b = Image::new(&mut b, config.width as usize, config.height as usize);
 
-            images.reduce(Vec::new, |mut a, mut b| {
-                match (a.is_empty(), b.is_empty()) {
-                    (true, false) => b,
-                    (false, true) => a,
-                    (true, true) => unreachable!(),
-                    (false, false) => {
-                        let mut im_a =
-                            Image::new(&mut a, config.width as usize, config.height as usize);
-                        let mut im_b =
-                            Image::new(&mut b, config.width as usize, config.height as usize);
-
-                        unsafe { combine_images(&mut im_a, &mut im_b) };
-                        a
-                    }
-                }
-            })
+            unsafe { combine_images(&mut im_a, &mut im_b) };
+            a
         }
     }
 }
+/// Plots the points of `points` like the fern, see [`get_ifs_image`]. `points` is called with
+/// the config of each part, which has its share of the iterations.
+fn plot_points(
+    config: &Config,
+    seed: Option<u64>,
+    points: impl Fn(&Config, &mut rand::rngs::SmallRng, &mut dyn FnMut(usize, usize)) + Sync,
+) -> Vec<RGB> {
+    // The colors are added with saturation, so the order doesn't matter.
+    let threads = match seed {
+        Some(_) => SEEDED_FERN_PARTS,
+        None => rayon::current_num_threads() as u32,
+    };
+    let per_thread_iterations = config.iterations / threads;
+    let seeds: Option<Vec<u64>> = seed.map(|seed| part_seeds(seed, threads));
+    let rng = |part: u64| match &seeds {
+        Some(seeds) => rand::rngs::SmallRng::seed_from_u64(seeds[part as usize]),
+        None => rand::rngs::SmallRng::from_entropy(),
+    };
 
-#[cfg(feature = "avif")]
-pub fn write_image(options: &Options, mut contents: Vec<RGB>) {
-    let config = &options.config;
-    let img_config = ravif::Config {
-        speed: 8,
-        quality: 100.0,
-        threads: 0,
-        color_space: ravif::ColorSpace::YCbCr,
-        alpha_quality: 0.0,
-        premultiplied_alpha: false,
-    };
-    let img = Image::new(
-        contents.as_mut_slice(),
-        config.width as usize,
-        config.height as usize,
-    );
+    let mut config = config.clone();
+    config.iterations = per_thread_iterations;
 
-    let data = image_to_data(img, &img_config, options);
-    let mut file =
-        std::fs::File::create(&options.filename).expect("failed to create output image file");
-    file.write_all(&data).expect("failed to write image data");
-    file.flush().expect("failed to flush file");
-
-    if options.open {
-        fn start_shell(cmd: &str, command_arg: &str, exec: &str) {
-            std::process::Command::new(cmd)
-                .arg(command_arg)
-                .arg(exec)
-                .spawn()
-                .expect("failed to open image");
-        }
-        #[cfg(windows)]
-        {
-            start_shell("cmd", "/C", &format!("start {}", options.filename));
-        }
-        #[cfg(target_os = "macos")]
-        {
-            start_shell("sh", "-c", &format!("open {:?}", options.filename));
-        };
-        #[cfg(all(not(target_os = "macos"), unix))]
-        {
-            start_shell("sh", "-c", &format!("xdg-open {:?}", options.filename));
+    if config.fern_normalize {
+        let pixels = config.width as usize * config.height as usize;
+        let plot = |part: u64| plot_part_hits(&config, &mut rng(part), &points);
+        // The parts of a seeded render are combined in the same order for every number of
+        // threads.
+        let hits = match seed {
+            Some(_) => deterministic::tree_reduce(0..threads as u64, &plot, &combine_hits),
+            None => (0..threads as u64)
+                .into_par_iter()
+                .map(plot)
+                .reduce(|| vec![0; pixels], combine_hits),
         };
+        return fern_density(&config, &hits);
+    }
+
+    // we can use a parallel iterator, as this fractal is random, so we should
+    // probabilistically get the same result as when using 1 thread.
+    let plot = |part: u64| plot_part_colors(&config, &mut rng(part), &points);
+    let combine = |a, b| combine_colors(&config, a, b);
+    match seed {
+        Some(_) => deterministic::tree_reduce(0..threads as u64, &plot, &combine),
+        None => (0..threads as u64)
+            .into_par_iter()
+            .map(plot)
+            .reduce(Vec::new, combine),
+    }
+}
+
+/// Encodes `image` in `format`, without writing it anywhere, see [`write_image`] for that.
+///
+/// ```
+/// # use fractal_renderer::{encode_image, format::{EncoderSettings, Format}, ImageBuffer, RGB};
+/// let image = ImageBuffer::new(3, 2, RGB::new(255, 0, 0));
+/// let data = encode_image(&image, Format::Ppm, &EncoderSettings::default()).unwrap();
+/// assert!(data.starts_with(b"P6\n3 2\n255\n"));
+/// assert_eq!(data.len(), 11 + 3 * 2 * 3);
+/// ```
+// Only AVIF has settings.
+#[cfg_attr(not(feature = "avif"), allow(unused_variables))]
+pub fn encode_image(
+    image: &ImageBuffer,
+    format: format::Format,
+    settings: &EncoderSettings,
+) -> std::io::Result<Vec<u8>> {
+    let start = Instant::now();
+    let data = match format {
+        #[cfg(feature = "avif")]
+        format::Format::Avif => {
+            let img_config = ravif::Config {
+                speed: settings.avif_speed,
+                quality: settings.avif_quality,
+                threads: 0,
+                color_space: ravif::ColorSpace::YCbCr,
+                alpha_quality: 0.0,
+                premultiplied_alpha: false,
+            };
+            let img = ravif::Img::new(
+                transmute_rgb_slice(image.contents()),
+                image.width(),
+                image.height(),
+            );
+            ravif::encode_rgb(img, &img_config)
+                .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err.to_string()))?
+                .0
+        }
+        #[cfg(not(feature = "avif"))]
+        format::Format::Avif => return Err(unsupported_format(format)),
+        #[cfg(feature = "png")]
+        format::Format::Png => {
+            let mut data = Vec::new();
+            image::codecs::png::PngEncoder::new(&mut data)
+                .encode(
+                    &rgb_bytes(image.contents()),
+                    image.width() as u32,
+                    image.height() as u32,
+                    image::ColorType::Rgb8,
+                )
+                .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+            data
+        }
+        #[cfg(not(feature = "png"))]
+        format::Format::Png => return Err(unsupported_format(format)),
+        format::Format::Ppm => {
+            let mut data = format!("P6\n{} {}\n255\n", image.width(), image.height()).into_bytes();
+            data.extend_from_slice(&rgb_bytes(image.contents()));
+            data
+        }
+    };
+    log::debug!(
+        "Encoded {}×{} {} in {:.2?}.",
+        image.width(),
+        image.height(),
+        format,
+        start.elapsed()
+    );
+    Ok(data)
+}
+#[cfg(any(not(feature = "avif"), not(feature = "png")))]
+fn unsupported_format(format: format::Format) -> std::io::Error {
+    // The features are named after the formats.
+    std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        format!(
+            "can't write {} files; the {} feature isn't enabled",
+            format, format
+        ),
+    )
+}
+fn rgb_bytes(contents: &[RGB]) -> Vec<u8> {
+    contents
+        .iter()
+        .flat_map(|pixel| [pixel.r, pixel.g, pixel.b])
+        .collect()
+}
+
+/// Draws the overlays enabled in `options` on `image`, which must have the size of
+/// `options.config`. The orbit is drawn on top of the grid and the caption on top of both.
+pub fn draw_overlays(options: &Options, image: &mut Image) {
+    let config = &options.config;
+    if options.grid {
+        axes::draw(config, image, options.grid_color);
+    }
+    if let Some(point) = options.orbit {
+        orbit::draw(config, image, point, options.orbit_color);
+    }
+    if let Some(annotate) = &options.annotate {
+        annotate::draw(config, image, annotate);
+    }
+}
+
+/// Encodes `contents`, which has the size of `options.config`, with [`encode_image`] and writes
+/// it with [`write_encoded`].
+///
+/// Returns the path written to, see [`write_encoded`].
+pub fn write_image(options: &Options, contents: Vec<RGB>) -> std::io::Result<String> {
+    let config = &options.config;
+    let pixels = contents.len();
+    let image = ImageBuffer::from_contents(contents, config.width as usize, config.height as usize)
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "the image has {} pixels, not {}×{}",
+                    pixels, config.width, config.height
+                ),
+            )
+        })?;
+    let data = encode_image(&image, options.output.format, &options.output.encoder)?;
+    write_encoded(&options.output, &data)
+}
+/// Writes the encoded `data` to [`OutputOptions::filename`], or stdout if it's
+/// [`format::STDOUT`], and opens it if [`OutputOptions::open`] is set. Files are written with
+/// [`format::write_atomically`].
+///
+/// Returns the path written to, which differs from the filename if it was renamed because of
+/// [`OutputOptions::on_conflict`].
+pub fn write_encoded(output: &OutputOptions, data: &[u8]) -> std::io::Result<String> {
+    let start = Instant::now();
+    let path = if output.filename == format::STDOUT {
+        let stdout = std::io::stdout();
+        let mut stdout = stdout.lock();
+        stdout.write_all(data)?;
+        stdout.flush()?;
+        output.filename.clone()
+    } else {
+        let path = format::write_atomically(&output.filename, output.on_conflict, |file| {
+            file.write_all(data)
+        })?;
+        if path != output.filename {
+            log::debug!(
+                "{:?} already exists, writing to {:?} instead.",
+                output.filename,
+                path
+            );
+        }
+        path
+    };
+    log::debug!("Wrote {} bytes in {:.2?}.", data.len(), start.elapsed());
+
+    if output.open && path != format::STDOUT {
+        open_image(&path, output.open_with.as_deref());
+    }
+    Ok(path)
+}
+
+/// Opens `path` with `program`, or the default viewer of the platform.
+///
+/// The path is passed as a single argument, never through a shell.
+/// We don't wait for the viewer; if it can't be started, a warning is logged.
+pub fn open_image(path: &str, program: Option<&str>) {
+    let (name, mut command) = match program {
+        Some(program) => (program, std::process::Command::new(program)),
+        None => {
+            #[cfg(windows)]
+            let opener = {
+                // The empty argument is the window title,
+                // else a quoted path would be taken as the title.
+                let mut command = std::process::Command::new("cmd");
+                command.args(["/C", "start", ""]);
+                ("cmd", command)
+            };
+            #[cfg(target_os = "macos")]
+            let opener = ("open", std::process::Command::new("open"));
+            #[cfg(all(unix, not(target_os = "macos")))]
+            let opener = ("xdg-open", std::process::Command::new("xdg-open"));
+            #[cfg(not(any(windows, unix)))]
+            {
+                log::warn!("Can't open images on this platform; use --open-with.");
+                return;
+            }
+            #[cfg(any(windows, unix))]
+            opener
+        }
+    };
+    if let Err(err) = command.arg(path).spawn() {
+        log::warn!("Failed to open {:?} with {:?}: {}", path, name, err);
+    }
+}
+
+/// An owned image, stored in rows from the top left.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageBuffer {
+    contents: Vec<RGB>,
+    width: usize,
+    height: usize,
+}
+impl ImageBuffer {
+    /// An image filled with `color`.
+    pub fn new(width: usize, height: usize, color: RGB) -> Self {
+        Self {
+            contents: vec![color; width * height],
+            width,
+            height,
+        }
+    }
+    /// Returns [`None`] if the length of `contents` isn't `width * height`.
+    pub fn from_contents(contents: Vec<RGB>, width: usize, height: usize) -> Option<Self> {
+        if width.checked_mul(height) != Some(contents.len()) {
+            return None;
+        }
+        Some(Self {
+            contents,
+            width,
+            height,
+        })
+    }
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    pub fn contents(&self) -> &[RGB] {
+        &self.contents
+    }
+    pub fn into_contents(self) -> Vec<RGB> {
+        self.contents
+    }
+    /// The image scaled to `width`×`height` with `filter`, see [`resize::resize`].
+    pub fn resized(&self, width: usize, height: usize, filter: resize::Filter) -> Self {
+        resize::resize(self, width, height, filter)
+    }
+    /// Borrows the buffer, to draw on it.
+    pub fn as_image(&mut self) -> Image<'_> {
+        Image::new(&mut self.contents, self.width, self.height)
+    }
+    /// The pixels as RGB bytes.
+    #[cfg(feature = "image-interop")]
+    pub fn as_bytes(&self) -> &[u8] {
+        bytemuck::cast_slice(&self.contents)
     }
 }
 
@@ -379,15 +3417,128 @@ impl<'a> Image<'a> {
             height,
         }
     }
-    pub fn pixel_mut(&mut self, x: usize, y: usize) -> Option<&mut RGB> {
-        if x > self.width {
+    /// The pixels as RGB bytes.
+    #[cfg(feature = "image-interop")]
+    pub fn as_bytes(&self) -> &[u8] {
+        bytemuck::cast_slice(self.contents)
+    }
+    /// Returns [`None`] if (`x`, `y`) is outside the image.
+    pub fn pixel(&self, x: usize, y: usize) -> Option<&RGB> {
+        if x >= self.width || y >= self.height {
             return None;
         }
-        let index = y * self.width + x;
-        if self.contents.len() < index {
+        self.contents.get(y * self.width + x)
+    }
+    /// Returns [`None`] if (`x`, `y`) is outside the image.
+    ///
+    /// ```
+    /// # use fractal_renderer::{Image, RGB};
+    /// let mut contents = vec![RGB::new(0, 0, 0); 4 * 3];
+    /// let mut image = Image::new(&mut contents, 4, 3);
+    /// *image.pixel_mut(0, 0).unwrap() = RGB::new(1, 0, 0);
+    /// // The last column and row.
+    /// *image.pixel_mut(3, 1).unwrap() = RGB::new(2, 0, 0);
+    /// *image.pixel_mut(1, 2).unwrap() = RGB::new(3, 0, 0);
+    /// *image.pixel_mut(3, 2).unwrap() = RGB::new(4, 0, 0);
+    /// // Right of the last column and below the last row, which don't wrap to the next row.
+    /// for (x, y) in [(4, 0), (4, 1), (0, 3), (3, 3), (4, 3), (usize::MAX, 0), (0, usize::MAX)] {
+    ///     assert!(image.pixel_mut(x, y).is_none(), "({}, {})", x, y);
+    ///     assert!(image.pixel(x, y).is_none(), "({}, {})", x, y);
+    /// }
+    /// assert_eq!(image.pixel(3, 2), Some(&RGB::new(4, 0, 0)));
+    /// assert_eq!(contents[0], RGB::new(1, 0, 0));
+    /// assert_eq!(contents[4 + 3], RGB::new(2, 0, 0));
+    /// assert_eq!(contents[8 + 1], RGB::new(3, 0, 0));
+    /// assert_eq!(contents[11], RGB::new(4, 0, 0));
+    /// assert_eq!(contents.iter().filter(|pixel| pixel.r != 0).count(), 4);
+    /// ```
+    pub fn pixel_mut(&mut self, x: usize, y: usize) -> Option<&mut RGB> {
+        if x >= self.width || y >= self.height {
             return None;
         }
-        self.contents.get_mut(index)
+        self.contents.get_mut(y * self.width + x)
+    }
+    /// Copies `source` into `self` with its top left corner at (`x`, `y`).
+    /// Parts outside of `self` are clipped.
+    pub fn blit(&mut self, source: &Image, x: usize, y: usize) {
+        self.blit_flipped(source, x, y, false, false);
+    }
+    /// [`Self::blit`], with `source` mirrored left to right if `flip_h` and upside down if
+    /// `flip_v`.
+    pub fn blit_flipped(&mut self, source: &Image, x: usize, y: usize, flip_h: bool, flip_v: bool) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let columns = cmp::min(source.width, self.width - x);
+        let rows = cmp::min(source.height, self.height - y);
+        for row in 0..rows {
+            let source_row = if flip_v { source.height - 1 - row } else { row };
+            let from = source_row * source.width;
+            let to = (y + row) * self.width + x;
+            let target = &mut self.contents[to..to + columns];
+            if flip_h {
+                // The columns which are left after clipping are the last of the source.
+                let end = from + source.width;
+                target.copy_from_slice(&source.contents[end - columns..end]);
+                target.reverse();
+            } else {
+                target.copy_from_slice(&source.contents[from..from + columns]);
+            }
+        }
+    }
+    /// Draws a one pixel wide line between the fractional pixel coordinates `from` and `to`,
+    /// using Bresenham's algorithm. Parts outside of the image are clipped.
+    pub fn draw_line(&mut self, from: (f64, f64), to: (f64, f64), color: RGB) {
+        let (from, to) = match clip_line(from, to, self.width as f64, self.height as f64) {
+            Some(clipped) => clipped,
+            None => return,
+        };
+        let (mut x, mut y) = (from.0.floor() as i64, from.1.floor() as i64);
+        let (end_x, end_y) = (to.0.floor() as i64, to.1.floor() as i64);
+        let dx = (end_x - x).abs();
+        let dy = -(end_y - y).abs();
+        let step_x = if x < end_x { 1 } else { -1 };
+        let step_y = if y < end_y { 1 } else { -1 };
+        let mut error = dx + dy;
+        loop {
+            // The end of the clipped line can be on the right or bottom edge.
+            if let Some(pixel) = self.pixel_mut(x as usize, y as usize) {
+                *pixel = color;
+            }
+            if x == end_x && y == end_y {
+                break;
+            }
+            let doubled = error * 2;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+    /// Fills the pixels whose centers are within `radius` of `center`.
+    /// Parts outside of the image are clipped.
+    pub fn fill_circle(&mut self, center: (f64, f64), radius: f64, color: RGB) {
+        if !(center.0.is_finite() && center.1.is_finite()) {
+            return;
+        }
+        let clamp = |v: f64, max: usize| v.max(0.0).min(max as f64) as usize;
+        let columns =
+            clamp(center.0 - radius, self.width)..clamp(center.0 + radius + 1.0, self.width);
+        let rows =
+            clamp(center.1 - radius, self.height)..clamp(center.1 + radius + 1.0, self.height);
+        for y in rows {
+            for x in columns.clone() {
+                let dx = x as f64 + 0.5 - center.0;
+                let dy = y as f64 + 0.5 - center.1;
+                if dx * dx + dy * dy <= radius * radius {
+                    self.contents[y * self.width + x] = color;
+                }
+            }
+        }
     }
     fn subtract_pixel(&mut self, x: usize, y: usize, value: RGB, amount: f64) {
         let pixel = if let Some(p) = self.pixel_mut(x, y) {
@@ -407,6 +3558,49 @@ impl<'a> Image<'a> {
         *pixel = new;
     }
 }
+/// Clips the line between `from` and `to` to the rectangle from (0, 0) to (`width`, `height`),
+/// using the Liang-Barsky algorithm.
+///
+/// Returns [`None`] if no part of the line is inside or if a coordinate isn't finite.
+fn clip_line(
+    from: (f64, f64),
+    to: (f64, f64),
+    width: f64,
+    height: f64,
+) -> Option<((f64, f64), (f64, f64))> {
+    if ![from.0, from.1, to.0, to.1].iter().all(|v| v.is_finite()) {
+        return None;
+    }
+    let dx = to.0 - from.0;
+    let dy = to.1 - from.1;
+    let mut start = 0.0_f64;
+    let mut end = 1.0_f64;
+    // For each edge, the direction towards the outside and the distance to it.
+    for (direction, distance) in [
+        (-dx, from.0),
+        (dx, width - from.0),
+        (-dy, from.1),
+        (dy, height - from.1),
+    ] {
+        if direction == 0.0 {
+            if distance < 0.0 {
+                return None;
+            }
+            continue;
+        }
+        let t = distance / direction;
+        if direction < 0.0 {
+            start = start.max(t);
+        } else {
+            end = end.min(t);
+        }
+    }
+    if start > end {
+        return None;
+    }
+    let at = |t: f64| (from.0 + t * dx, from.1 + t * dy);
+    Some((at(start), at(end)))
+}
 #[cfg(feature = "avif")]
 impl<'a> From<Image<'a>> for ravif::Img<&'a [ravif::RGB8]> {
     fn from(me: Image<'a>) -> Self {
@@ -414,50 +3608,164 @@ impl<'a> From<Image<'a>> for ravif::Img<&'a [ravif::RGB8]> {
     }
 }
 
+/// The bounding box of the fern's attractor, as `(min, max)`, see [`ifs::Ifs::bounds`].
+pub fn fern_bounds() -> (Imaginary, Imaginary) {
+    ifs::Ifs::barnsley().bounds()
+}
+
+/// Plots `config.iterations` points of the fern on `image`.
+///
+/// At the default scale, the fern fits the image with a small margin. Like the other
+/// algorithms, the image shows `1 / scale` units vertically and `pos` is its center, where the
+/// units are chosen so that `pos = 0` is the center of the fern.
 #[inline(always)]
 pub fn fern(config: &Config, image: &mut Image) {
+    fern_with_rng(config, image, &mut rand::rngs::SmallRng::from_entropy())
+}
+/// [`fern`] with the random numbers of `rng`.
+#[inline(always)]
+pub fn fern_with_rng(config: &Config, image: &mut Image, rng: &mut impl Rng) {
+    ifs_with_rng(config, &ifs::Ifs::barnsley(), image, rng)
+}
+/// [`fern_with_rng`] with the transforms of `ifs` instead of the fern.
+#[inline(always)]
+pub fn ifs_with_rng(config: &Config, ifs: &ifs::Ifs, image: &mut Image, rng: &mut impl Rng) {
+    let color = config.primary_color;
+    ifs_points(config, ifs, rng, |x, y| {
+        image.subtract_pixel(x, y, color, config.color_weight)
+    });
+}
+/// Adds `config.iterations` points of the fern to the hit counts of the pixels in `hits`, for
+/// [`Config::fern_normalize`].
+#[inline(always)]
+pub fn fern_hits_with_rng(config: &Config, hits: &mut [u32], rng: &mut impl Rng) {
+    ifs_hits_with_rng(config, &ifs::Ifs::barnsley(), hits, rng)
+}
+/// [`fern_hits_with_rng`] with the transforms of `ifs` instead of the fern.
+#[inline(always)]
+pub fn ifs_hits_with_rng(config: &Config, ifs: &ifs::Ifs, hits: &mut [u32], rng: &mut impl Rng) {
+    let width = config.width as usize;
+    let height = config.height as usize;
+    ifs_points(config, ifs, rng, |x, y| {
+        if x < width && y < height {
+            let hits = &mut hits[y * width + x];
+            *hits = hits.saturating_add(1);
+        }
+    });
+}
+/// The [`Config::background`] of the fern, which it's plotted on.
+pub fn fern_background(config: &Config) -> Vec<RGB> {
+    let width = config.width;
+    (0..config.width as usize * config.height as usize)
+        .map(|i| {
+            let (x, y) = (i as u32 % width, i as u32 / width);
+            config.background.color(x, y, config.secondary_color)
+        })
+        .collect()
+}
+/// Colors the hit counts of [`fern_hits_with_rng`]: the density `hits / max_hits` is mapped
+/// through `1 - exp(-color_weight × density)`, from the [background](fern_background) to the
+/// primary color.
+///
+/// This makes the fern look the same for any number of iterations.
+pub fn fern_density(config: &Config, hits: &[u32]) -> Vec<RGB> {
+    let max = hits.iter().copied().max().unwrap_or(0);
+    let to = config.primary_color;
+    let channel = |from: u8, to: u8, t: f64| (from as f64 + (to as f64 - from as f64) * t) as u8;
+    hits.iter()
+        .zip(fern_background(config))
+        .map(|(&hits, from)| {
+            if hits == 0 {
+                return from;
+            }
+            let t = 1.0 - (-config.color_weight * hits as f64 / max as f64).exp();
+            RGB::new(
+                channel(from.r, to.r, t),
+                channel(from.g, to.g, t),
+                channel(from.b, to.b, t),
+            )
+        })
+        .collect()
+}
+/// The points inverse iteration starts with, which aren't plotted, as they're still on their way
+/// to the boundary.
+const JULIA_BOUNDARY_SKIPPED: u32 = 32;
+/// Calls `plot` with the pixel of each of the `config.iterations` points of the boundary of the
+/// Julia set of `config` which is in the image.
+///
+/// Every point is one of the two preimages `±√(z - c)` of the last, chosen at random. They're
+/// drawn to the boundary, like the points of the fern to its attractor.
+fn julia_boundary_points(config: &Config, rng: &mut impl Rng, plot: &mut dyn FnMut(usize, usize)) {
+    let c = config.julia.unwrap_or(Imaginary::ZERO);
     let width = config.width as f64;
     let height = config.height as f64;
-    let mut x = (config.pos.re) * width;
-    let mut y = (config.pos.im) * height;
-
-    // 0.006 just works fine, to get the scale in line with the other algos
-    let effective_scale_x = 65.0 * config.scale.re * config.height as f64 * 0.006;
-    let effective_scale_y = 37.0 * config.scale.im * config.height as f64 * 0.006;
-
-    let mut rng = rand::rngs::SmallRng::from_entropy();
+    let mut z = Imaginary { re: 1.0, im: 0.0 };
+    for i in 0..config.iterations.saturating_add(JULIA_BOUNDARY_SKIPPED) {
+        z = Imaginary {
+            re: z.re - c.re,
+            im: z.im - c.im,
+        }
+        .sqrt();
+        if rng.gen() {
+            z = z * -1.0;
+        }
+        if i < JULIA_BOUNDARY_SKIPPED {
+            continue;
+        }
+        let (x, y) = mapping::complex_to_pixel(config, z);
+        // Also skips NaN.
+        if x >= 0.0 && y >= 0.0 && x < width && y < height {
+            plot(x as usize, y as usize);
+        }
+    }
+}
+/// Calls `plot` with the pixel of each of the `config.iterations` points of `ifs` which is in the
+/// image. Like the fern, the attractor fits the image at the default scale.
+#[inline(always)]
+pub(crate) fn ifs_points(
+    config: &Config,
+    ifs: &ifs::Ifs,
+    rng: &mut impl Rng,
+    mut plot: impl FnMut(usize, usize),
+) {
+    // The fern is drawn upright and then oriented pixel by pixel.
+    let (width, height) = config.unoriented_size();
+    let width = width as f64;
+    let height = height as f64;
 
-    let color = config.primary_color;
+    let (min, max) = ifs.bounds();
+    let center = Imaginary {
+        re: (min.re + max.re) / 2.0,
+        im: (min.im + max.im) / 2.0,
+    };
+    // How many units of the fern fit vertically at the default scale, with a 5% margin.
+    let visible = f64::max(max.im - min.im, (max.re - min.re) * height / width) * 1.1;
+    let unit = visible * Config::default().scale.im;
+    let view_x = center.re + config.pos.re * unit;
+    // The y axis of the fern points up.
+    let view_y = center.im - config.pos.im * unit;
+    let pixels_per_unit_x = height * config.scale.re / unit;
+    let pixels_per_unit_y = height * config.scale.im / unit;
 
+    let (mut x, mut y) = (0.0, 0.0);
     for _ in 0..config.iterations {
-        image.subtract_pixel(
-            (((x - config.pos.re) * effective_scale_x) + width / 2.0) as usize,
-            // 5.0 seems to work fine
-            (height - ((y + (config.pos.im - 5.0) - 0.5) * effective_scale_y + height / 2.0))
-                as usize,
-            color,
-            config.color_weight,
-        );
-
-        let r: f64 = rng.gen();
-
-        // https://en.wikipedia.org/wiki/Barnsley_fern#Python
-        if r < 0.01 {
-            let old_x = x;
-            x = 0.00 * x + 0.00 * y;
-            y = 0.00 * old_x + 0.16 * y + 0.00;
-        } else if r < 0.86 {
-            let old_x = x;
-            x = 0.85 * x + 0.04 * y;
-            y = -0.04 * old_x + 0.85 * y + 1.60;
-        } else if r < 0.93 {
-            let old_x = x;
-            x = 0.20 * x - 0.26 * y;
-            y = 0.23 * old_x + 0.22 * y + 1.60;
-        } else {
-            let old_x = x;
-            x = -0.15 * x + 0.28 * y;
-            y = 0.26 * old_x + 0.24 * y + 0.44;
+        let pixel_x = (x - view_x) * pixels_per_unit_x + width / 2.0;
+        let pixel_y = height / 2.0 - (y - view_y) * pixels_per_unit_y;
+        // Casting a negative float to usize saturates at 0, which would smear the points left of
+        // and above the image onto its edges.
+        if pixel_x >= 0.0 && pixel_y >= 0.0 && pixel_x < width && pixel_y < height {
+            let (x, y) = mapping::orient(
+                config.orientation,
+                config.width as f64,
+                config.height as f64,
+                pixel_x.floor(),
+                pixel_y.floor(),
+            );
+            plot(x as usize, y as usize);
         }
+
+        let (next_x, next_y) = ifs.step(x, y, rng.gen());
+        x = next_x;
+        y = next_y;
     }
 }
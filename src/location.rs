@@ -0,0 +1,171 @@
+//! Import locations shared by the deep-zoom community.
+//!
+//! Currently, the key/value text format of Kalles Fraktaler (`.kfr`) is supported.
+
+use crate::{dd, Config, Imaginary};
+use std::fmt::{self, Display};
+
+/// Beyond this zoom, `f64` can't resolve neighbouring pixels.
+pub const F64_MAX_ZOOM: f64 = 1e13;
+/// Beyond this zoom, double-double precision can't resolve neighbouring pixels.
+pub const DOUBLE_DOUBLE_MAX_ZOOM: f64 = 1e28;
+
+/// Keys of `.kfr` files which don't affect the location.
+const IGNORED_KFR_KEYS: &[&str] = &[
+    "Colors",
+    "ColorMethod",
+    "ColorOffset",
+    "ColorPhaseStrength",
+    "Differences",
+    "IterDiv",
+    "InsideColor",
+    "MultiColor",
+    "MultiColorBlend",
+    "Period",
+    "Slopes",
+    "SlopeAngle",
+    "SlopePower",
+    "SlopeRatio",
+    "Smooth",
+    "SmoothMethod",
+    "TextureBlend",
+    "TextureEnabled",
+    "TextureFile",
+    "TexturePower",
+    "TextureRatio",
+    "Version",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    pub center: Imaginary,
+    /// What's lost when rounding the center to `f64`, see [`Config::pos_low`].
+    pub center_low: Imaginary,
+    /// `1` shows the complex plane from -2 to 2 vertically.
+    pub zoom: f64,
+    pub iterations: Option<u32>,
+}
+impl Location {
+    /// Parses the text format of Kalles Fraktaler.
+    ///
+    /// Keys which change the shape of the fractal (e.g. `Power` or `Rotate`) are logged as
+    /// warnings when they aren't the default, as they aren't supported.
+    pub fn parse_kfr(s: &str) -> Result<Self, LocationError> {
+        let mut re = None;
+        let mut im = None;
+        let mut zoom = None;
+        let mut iterations = None;
+        for (line_number, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line
+                .split_once(':')
+                .ok_or(LocationError::Syntax(line_number + 1))?;
+            let value = value.trim();
+            let number = || {
+                value
+                    .parse::<f64>()
+                    .map_err(|_| LocationError::Value(key.to_owned(), value.to_owned()))
+            };
+            // The center can have many more digits than fit in an f64.
+            let precise = || {
+                value
+                    .parse::<dd::DoubleDouble>()
+                    .map_err(|_| LocationError::Value(key.to_owned(), value.to_owned()))
+            };
+            match key {
+                "Re" => re = Some(precise()?),
+                "Im" => im = Some(precise()?),
+                "Zoom" => zoom = Some(number()?),
+                "Iterations" => {
+                    iterations = Some(
+                        value
+                            .parse()
+                            .map_err(|_| LocationError::Value(key.to_owned(), value.to_owned()))?,
+                    )
+                }
+                "Rotate" | "FractalType" if number()? != 0.0 => {
+                    log::warn!("{} {} isn't supported and is ignored.", key, value)
+                }
+                "Ratio" if number()? != 360.0 => {
+                    log::warn!("Stretching with ratio {} isn't supported.", value)
+                }
+                "Power" if number()? != 2.0 => {
+                    log::warn!("Power {} isn't supported and is ignored.", value)
+                }
+                "Rotate" | "FractalType" | "Ratio" | "Power" => {}
+                key if IGNORED_KFR_KEYS.contains(&key) => {}
+                key => log::warn!("Unknown key {:?} in location file, ignoring it.", key),
+            }
+        }
+        let missing = |key: &'static str| LocationError::Missing(key);
+        let re = re.ok_or_else(|| missing("Re"))?;
+        let im = im.ok_or_else(|| missing("Im"))?;
+        let location = Self {
+            center: Imaginary {
+                re: re.hi,
+                im: im.hi,
+            },
+            center_low: Imaginary {
+                re: re.lo,
+                im: im.lo,
+            },
+            zoom: zoom.ok_or_else(|| missing("Zoom"))?,
+            iterations,
+        };
+        if !(location.zoom > 0.0 && location.zoom.is_finite()) {
+            return Err(LocationError::Value(
+                "Zoom".into(),
+                location.zoom.to_string(),
+            ));
+        }
+        if location.zoom > DOUBLE_DOUBLE_MAX_ZOOM {
+            log::warn!(
+                "The location has a zoom of {:e}, but only {:e} is supported without loss of precision. \
+                 The image will be blocky.",
+                location.zoom,
+                DOUBLE_DOUBLE_MAX_ZOOM
+            );
+        } else if location.zoom > F64_MAX_ZOOM {
+            log::info!(
+                "The location has a zoom of {:e}, beyond the precision of f64. \
+                 It's rendered with the slower double-double precision, unless `--precision f64` is given.",
+                location.zoom
+            );
+        }
+        Ok(location)
+    }
+    /// Sets the position, scale and (if present) iterations of `config`.
+    pub fn apply(&self, config: &mut Config) {
+        config.pos = self.center;
+        config.pos_low = self.center_low;
+        // Our scale shows 1 / scale vertically.
+        config.scale = Imaginary {
+            re: self.zoom / 4.0,
+            im: self.zoom / 4.0,
+        };
+        if let Some(iterations) = self.iterations {
+            config.iterations = iterations;
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LocationError {
+    /// The line (starting at 1) isn't `key: value`.
+    Syntax(usize),
+    Value(String, String),
+    Missing(&'static str),
+}
+impl Display for LocationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax(line) => write!(f, "line {} isn't `key: value`", line),
+            Self::Value(key, value) => write!(f, "invalid value {:?} for {}", value, key),
+            Self::Missing(key) => write!(f, "the required key {} is missing", key),
+        }
+    }
+}
+impl std::error::Error for LocationError {}
@@ -1,9 +1,132 @@
 pub mod lib;
 pub use lib::*;
 
+/// Writes all log messages to stderr, so stdout can be used for image data.
+struct Logger;
+impl log::Log for Logger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        match record.level() {
+            log::Level::Info => eprintln!("{}", record.args()),
+            log::Level::Warn => eprintln!("warning: {}", record.args()),
+            level => eprintln!("{}: {}", level.as_str().to_lowercase(), record.args()),
+        }
+    }
+    fn flush(&self) {}
+}
+static LOGGER: Logger = Logger;
+
 fn main() {
-    #[cfg(feature = "avif")]
-    let options = lib::get_options();
+    log::set_logger(&LOGGER).expect("no other logger is set");
+    log::set_max_level(log::LevelFilter::Info);
+
+    let mut options = lib::get_options();
+    log::debug!(
+        "Built with the features {}.",
+        lib::features::enabled()
+            .map(|feature| feature.name())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    log::debug!("Effective config: {:#?}", options.config);
+    log::debug!(
+        "Center {} + {}i, scale {} × {}.",
+        options.config.pos.re,
+        options.config.pos.im,
+        options.config.scale.re,
+        options.config.scale.im
+    );
+
+    if let Some(random) = &options.random_julia {
+        if let Err(err) = lib::discovery::run(&options, random) {
+            log::error!("Failed to write the contact sheet: {}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if options.dry_run {
+        let config = &options.config;
+        print!("{}", lib::config_to_toml(config));
+        let available = match lib::resources::available_memory() {
+            Some(bytes) => format!(", of {} available", lib::resources::format_bytes(bytes)),
+            None => String::new(),
+        };
+        log::info!(
+            "Built with the features {}.",
+            lib::features::enabled()
+                .map(|feature| feature.name())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        log::info!(
+            "{}×{} = {} pixels, estimated peak memory usage {}{}.",
+            config.width,
+            config.height,
+            config.width as u64 * config.height as u64,
+            lib::resources::format_bytes(lib::resources::peak_memory(&options)),
+            available
+        );
+        return;
+    }
+
+    if options.verify {
+        let reports = lib::verify::verify_backends();
+        println!("{}", lib::verify::to_table(&reports));
+        if !lib::verify::passed(&reports) {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    #[cfg(feature = "serve")]
+    if let Some(serve) = &options.serve {
+        if let Err(err) = lib::serve::run(&options, serve) {
+            log::error!("{}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(compare) = &options.compare {
+        match lib::diff::run(&options, compare) {
+            Ok(true) => {}
+            Ok(false) => {
+                log::error!("More than {}% of the pixels differ.", compare.threshold);
+                std::process::exit(1);
+            }
+            Err(err) => {
+                log::error!("{}", err);
+                std::process::exit(2);
+            }
+        }
+        return;
+    }
+
+    if let Some(benchmark) = &options.benchmark {
+        if benchmark.runs == 0 {
+            log::error!("The benchmark needs at least one run.");
+            std::process::exit(1);
+        }
+        let result = match lib::benchmark::run(&options.config, benchmark.runs) {
+            Ok(result) => result,
+            Err(err) => {
+                log::error!("{}", err);
+                std::process::exit(1);
+            }
+        };
+        if benchmark.json {
+            println!("{}", result.to_json());
+        } else {
+            println!("{}", result.to_table());
+        }
+        return;
+    }
 
     #[cfg(feature = "gui")]
     if options.gui {
@@ -11,13 +134,421 @@ fn main() {
         return;
     }
 
-    #[cfg(feature = "avif")]
+    if let Some(batch) = &options.batch {
+        if !lib::batch::run(&options, batch) {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(explore) = &options.explore {
+        if let Err(err) = lib::explore::run(&options, explore) {
+            log::error!("Failed to explore: {}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(animation) = &options.animation {
+        if let Some(path) = &animation.plot_path {
+            let (contents, config) = lib::animation::plot_path(&options.config, animation);
+            let mut options = lib::Options {
+                config,
+                ..options.clone()
+            };
+            options.output.set_path(path, lib::format::Format::Png);
+            match lib::write_image(&options, contents) {
+                Ok(path) => log::info!("Wrote the path of the animation to {:?}.", path),
+                Err(err) => {
+                    log::error!("Failed to write the path of the animation: {}", err);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        let start = std::time::Instant::now();
+        match lib::animation::run(&options, animation) {
+            Ok(skipped) => log::info!(
+                "Wrote {} frames in {:.2?}{}.",
+                animation.frames,
+                start.elapsed(),
+                if skipped > 0 {
+                    format!(", {} were a single color and weren't rendered", skipped)
+                } else {
+                    String::new()
+                }
+            ),
+            Err(err) => {
+                log::error!("Failed to write frame: {}", err);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    // The other modes name their images themselves.
+    options.output.filename = options
+        .output
+        .expanded(&lib::template::Values::from_options(&options))
+        .expect("the fields of the output are validated with the options");
+
+    #[cfg(feature = "watch")]
+    if let Some(watch) = &options.watch {
+        if let Err(err) = lib::watch::run(&options, watch) {
+            log::error!("Failed to watch {:?}: {}", watch.path, err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(slice) = options.slice {
+        let path = slice.filename(&options.output.filename);
+        if let Err(err) = lib::slice::render_part(&options.config, slice, &path) {
+            log::error!("{}", err);
+            std::process::exit(1);
+        }
+        log::info!(
+            "Wrote part {} of {} to {:?}.",
+            slice.index,
+            slice.count,
+            path
+        );
+        return;
+    }
+
+    if let Some(files) = &options.merge {
+        let (contents, width, height) = match lib::slice::merge(&files[1..]) {
+            Ok(merged) => merged,
+            Err(err) => {
+                log::error!("Failed to merge: {}", err);
+                std::process::exit(1);
+            }
+        };
+        let mut options = options.clone();
+        let format = options.output.format;
+        options.output.set_path(&files[0], format);
+        options.config.width = width;
+        options.config.height = height;
+        if let Err(err) = lib::write_image(&options, contents) {
+            log::error!("Failed to write image: {}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // Don't render just to find out it can't be written.
+    if options.output.on_conflict == lib::format::OnConflict::Error
+        && options.output.filename != lib::format::STDOUT
+        && std::path::Path::new(&options.output.filename).exists()
     {
-        let contents = lib::get_image(&options.config);
-        lib::write_image(&options, contents);
+        log::error!(
+            "{:?} already exists; pass --overwrite or --on-conflict rename.",
+            options.output.filename
+        );
+        std::process::exit(1);
     }
-    #[cfg(not(feature = "avif"))]
+
     {
-        eprintln!("Failed to write file; avif feature isn't enabled.")
+        let start = std::time::Instant::now();
+        let seed = options.seed();
+        // The time of each tile, for `--timing-map`.
+        let mut timings = None;
+        let mut contents = if let Some(grid) = &options.julia_grid {
+            lib::grid::render(&options.config, grid)
+        } else if let Some(buddhabrot) = &options.buddhabrot {
+            lib::buddhabrot::render(&options.config, buddhabrot, seed)
+        } else if let Some(mutation) = &options.ifs_mutation {
+            let ifs = options.ifs.clone().unwrap_or_default();
+            lib::ifs::mutation_sheet(&options.config, &ifs, mutation, seed)
+        } else if let Some(ifs) = &options.ifs {
+            lib::get_ifs_image(&options.config, ifs, seed)
+        } else if options.boundary_preview {
+            lib::get_julia_boundary_image(&options.config, seed)
+        } else if let Some(checkpoint) = &options.checkpoint {
+            match lib::checkpoint::render(&options.config, checkpoint, seed) {
+                Ok(contents) => contents,
+                Err(err) => {
+                    log::error!("{}", err);
+                    std::process::exit(1);
+                }
+            }
+        } else if let Some(limit) = options.time_limit {
+            match lib::refine::render(&options.config, limit) {
+                Ok(refined) => {
+                    log::debug!(
+                        "Reached quality {} of {}: {}.",
+                        refined.level + 1,
+                        refined.levels,
+                        refined.quality
+                    );
+                    refined.contents
+                }
+                Err(err) => {
+                    log::error!("{}", err);
+                    std::process::exit(1);
+                }
+            }
+        } else if options.ao.is_some()
+            || options.aa.is_some()
+            || options.palette.is_some()
+            || options.export_iterations.is_some()
+            || options.deepen.is_some()
+            || options.timing_map.is_some()
+            || options.export_mask.is_some()
+        {
+            let config = &options.config;
+            let samples = match &options.deepen {
+                Some(path) => match lib::iterations::load(path)
+                    .and_then(|export| lib::iterations::deepen(config, &export))
+                {
+                    Ok(deepened) => {
+                        log::debug!(
+                            "Deepened {:?}, iterating {:.1}% of the pixels again.",
+                            path,
+                            deepened.iterated as f64 * 100.0 / deepened.samples.len() as f64
+                        );
+                        deepened.samples
+                    }
+                    Err(err) => {
+                        log::error!("Failed to deepen {:?}: {}", path, err);
+                        std::process::exit(1);
+                    }
+                },
+                None if options.timing_map.is_some() => {
+                    let (samples, tile_timings) = lib::get_samples_timed(config);
+                    timings = Some(tile_timings);
+                    samples
+                }
+                None => lib::get_samples(config),
+            };
+            if let Some(path) = &options.export_iterations {
+                match lib::iterations::save(path, options.output.on_conflict, &samples, config) {
+                    Ok(path) => log::info!("Exported the iterations to {:?}.", path),
+                    Err(err) => {
+                        log::error!("Failed to export the iterations: {}", err);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            if let Some(mask) = &options.export_mask {
+                let mut output = options.output.clone();
+                output.set_path(&mask.path, lib::format::Format::Png);
+                let written = lib::mask::encode_png(
+                    &lib::mask::mask(&samples, config.width as usize, mask.mode),
+                    config.width,
+                    config.height,
+                )
+                .and_then(|data| lib::write_encoded(&output, &data));
+                match written {
+                    Ok(path) => log::info!("Wrote the {} mask to {:?}.", mask.mode, path),
+                    Err(err) => {
+                        log::error!("Failed to write the mask: {}", err);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            let mut contents = lib::view::colorize(
+                &samples,
+                config,
+                lib::view::View::Color,
+                options.palette.as_ref(),
+            );
+            if let Some(aa) = &options.aa {
+                let antialiased = lib::aa::apply(
+                    &mut contents,
+                    config,
+                    options.palette.as_ref(),
+                    aa.threshold,
+                    aa.debug.is_some(),
+                );
+                log::debug!(
+                    "Supersampled {:.1}% of the pixels.",
+                    antialiased.supersampled as f64 * 100.0 / contents.len() as f64
+                );
+                if let (Some(path), Some(counts)) = (&aa.debug, &antialiased.counts) {
+                    let mut options = options.clone();
+                    options.output.set_path(path, lib::format::Format::Png);
+                    match lib::write_image(&options, lib::aa::counts_image(counts)) {
+                        Ok(path) => log::info!("Wrote the samples of each pixel to {:?}.", path),
+                        Err(err) => {
+                            log::error!("Failed to write the samples of each pixel: {}", err);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+            if let Some(ao) = &options.ao {
+                lib::ao::apply(&mut contents, &samples, config, ao);
+            }
+            contents
+        } else {
+            let output = match seed {
+                Some(seed) => lib::render::render_seeded(&options.config, seed),
+                None => lib::render(&options.config),
+            };
+            match output {
+                Ok(output) => {
+                    let stats = &output.stats;
+                    log::debug!(
+                        "Rendered on the {} in {:.2?}: {:.1}% inside, {:.2}% starved, at most {:.1} of {} iterations outside.",
+                        stats.used_backend,
+                        stats.duration,
+                        stats.interior_fraction * 100.0,
+                        stats.starved_fraction * 100.0,
+                        stats.max_iterations_hit,
+                        options.config.iterations
+                    );
+                    if stats.needs_more_iterations() {
+                        // The interior of the set always hits the limit; only the pixels next to
+                        // ones which barely escaped would escape with more iterations.
+                        log::warn!(
+                            "Too few iterations for this view: {:.1}% of the pixels hit the limit \
+                             of {} next to pixels which barely escaped. Try `-i {}`.",
+                            stats.starved_fraction * 100.0,
+                            options.config.iterations,
+                            lib::render::suggested_iterations(&options.config)
+                        );
+                    }
+                    output.image.into_contents()
+                }
+                Err(err) => {
+                    log::error!("{}", err);
+                    std::process::exit(1);
+                }
+            }
+        };
+        // The digest is of the render, without the post-processing and overlays.
+        let checksum = if options.checksum {
+            let config = &options.config;
+            let checksum = lib::digest::digest(&contents, config.width, config.height);
+            if options.output.filename == lib::format::STDOUT {
+                log::info!("Checksum {}", checksum);
+            } else {
+                println!("{}", checksum);
+            }
+            Some(checksum)
+        } else {
+            None
+        };
+        {
+            let config = &options.config;
+            let (width, height) = (config.width as usize, config.height as usize);
+            lib::post::apply(&mut contents, width, height, &options.post);
+            let mut image = lib::Image::new(&mut contents, width, height);
+            lib::draw_overlays(&options, &mut image);
+            if let Some(cvd) = options.simulate_cvd {
+                lib::color::simulate_image(&mut contents, cvd);
+            }
+        }
+        let render_time = start.elapsed();
+        log::debug!("Rendered in {:.2?}.", render_time);
+
+        // The wallpaper is written instead of the render it's composed of.
+        let composed = match &options.wallpaper {
+            Some(wallpaper) => {
+                let config = &options.config;
+                let copy = lib::ImageBuffer::from_contents(
+                    contents,
+                    config.width as usize,
+                    config.height as usize,
+                )
+                .expect("the render has the size of the config");
+                contents = lib::wallpaper::compose(copy, wallpaper).into_contents();
+                log::debug!(
+                    "Composed the {}×{} wallpaper of {}×{} copies.",
+                    wallpaper.width,
+                    wallpaper.height,
+                    wallpaper.columns,
+                    wallpaper.rows
+                );
+                let config = lib::Config {
+                    width: wallpaper.width,
+                    height: wallpaper.height,
+                    ..config.clone()
+                };
+                Some(lib::Options {
+                    config,
+                    ..options.clone()
+                })
+            }
+            None => None,
+        };
+        let written = composed.as_ref().unwrap_or(&options);
+
+        let start = std::time::Instant::now();
+        let path = match lib::write_image(written, contents) {
+            Ok(path) => path,
+            Err(err) => {
+                log::error!("Failed to write image: {}", err);
+                std::process::exit(1);
+            }
+        };
+        if let Some(checkpoint) = &options.checkpoint {
+            if let Err(err) = lib::checkpoint::remove(checkpoint) {
+                log::warn!(
+                    "Failed to remove the checkpoint {:?}: {}",
+                    checkpoint.path,
+                    err
+                );
+            }
+        }
+        log::info!(
+            "Rendered {}×{} in {:.2?}, encoded {:?} in {:.2?}.",
+            options.config.width,
+            options.config.height,
+            render_time,
+            path,
+            start.elapsed()
+        );
+
+        if let Some(path) = &options.error_map {
+            let config = &options.config;
+            if config.uses_double_double() {
+                log::warn!(
+                    "The render already uses double-double precision, so the error map is empty."
+                );
+            }
+            let map = lib::error_map::estimate(config);
+            log::info!("Error estimate: {}", map.stats());
+            let mut options = options.clone();
+            options.output.set_path(path, lib::format::Format::Png);
+            if let Err(err) = lib::write_image(&options, map.heat_map(config)) {
+                log::error!("Failed to write the error map: {}", err);
+                std::process::exit(1);
+            }
+        }
+        if let (Some(path), Some(timings)) = (&options.timing_map, &timings) {
+            log::info!("Slowest tiles:");
+            for timing in lib::timing::slowest(timings, 10) {
+                let tile = timing.tile;
+                log::info!(
+                    "  {}×{} at ({}, {}): {:.2?}",
+                    tile.width,
+                    tile.height,
+                    tile.x,
+                    tile.y,
+                    timing.duration()
+                );
+            }
+            let config = &options.config;
+            let mut options = options.clone();
+            options.output.set_path(path, lib::format::Format::Png);
+            let map = lib::timing::heat_map(config, timings, &lib::timing::heat_palette());
+            if let Err(err) = lib::write_image(&options, map) {
+                log::error!("Failed to write the timing map: {}", err);
+                std::process::exit(1);
+            }
+        }
+
+        if let (Some(checksum), Some(expected)) = (checksum, &options.expect_checksum) {
+            if &checksum != expected {
+                log::error!(
+                    "The checksum {} doesn't match the expected {}.",
+                    checksum,
+                    expected
+                );
+                std::process::exit(1);
+            }
+        }
     }
 }
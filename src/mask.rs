@@ -0,0 +1,134 @@
+//! Masks of the inside of escape-time fractals, for compositing, see `--export-mask`.
+//!
+//! The masks come from the [`Sample`]s of the render, so nothing is iterated again. They're
+//! written as 8-bit grayscale PNGs, white where the mask is set and black elsewhere.
+
+use crate::Sample;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaskMode {
+    /// The points which don't escape.
+    Interior,
+    /// The points which escape.
+    Exterior,
+    /// The pixels with a neighbour on the other side, above, below or beside them. An edge matte.
+    Boundary,
+}
+impl MaskMode {
+    pub const ALL: [Self; 3] = [Self::Interior, Self::Exterior, Self::Boundary];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Interior => "interior",
+            Self::Exterior => "exterior",
+            Self::Boundary => "boundary",
+        }
+    }
+}
+impl Display for MaskMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+impl FromStr for MaskMode {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|mode| mode.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                format!(
+                    "unknown mask mode {:?}; use interior, exterior or boundary",
+                    s
+                )
+            })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaskOptions {
+    /// The PNG file to write the mask to.
+    pub path: String,
+    pub mode: MaskMode,
+}
+
+/// The mask of the `width` wide `samples` in `mode`: 255 where it's set, else 0.
+///
+/// ```
+/// # use fractal_renderer::{get_samples, mask::{mask, MaskMode}, Algo, Config};
+/// let mut config = Config::new(Algo::Mandelbrot);
+/// config.width = 60;
+/// config.height = 40;
+/// let samples = get_samples(&config);
+///
+/// let interior = mask(&samples, 60, MaskMode::Interior);
+/// let exterior = mask(&samples, 60, MaskMode::Exterior);
+/// for ((sample, inside), outside) in samples.iter().zip(&interior).zip(&exterior) {
+///     assert_eq!(*inside, if sample.escaped { 0 } else { 255 });
+///     assert_eq!(*inside, 255 - outside);
+/// }
+///
+/// // The boundary is on both sides of the edge, and thinner than either.
+/// let boundary = mask(&samples, 60, MaskMode::Boundary);
+/// let set = |mask: &[u8]| mask.iter().filter(|value| **value == 255).count();
+/// assert!(set(&boundary) > 0);
+/// assert!(set(&boundary) < set(&interior));
+/// assert!(boundary.iter().zip(&interior).any(|(edge, inside)| *edge == 255 && *inside == 255));
+/// assert!(boundary.iter().zip(&exterior).any(|(edge, outside)| *edge == 255 && *outside == 255));
+/// ```
+pub fn mask(samples: &[Sample], width: usize, mode: MaskMode) -> Vec<u8> {
+    let value = |set: bool| if set { 255 } else { 0 };
+    match mode {
+        MaskMode::Interior => samples
+            .iter()
+            .map(|sample| value(!sample.escaped))
+            .collect(),
+        MaskMode::Exterior => samples.iter().map(|sample| value(sample.escaped)).collect(),
+        MaskMode::Boundary => {
+            if width == 0 {
+                return Vec::new();
+            }
+            let height = samples.len() / width;
+            let escaped = |x: usize, y: usize| samples[y * width + x].escaped;
+            (0..samples.len())
+                .map(|i| {
+                    let (x, y) = (i % width, i / width);
+                    let here = escaped(x, y);
+                    let neighbours = [
+                        (x > 0).then(|| (x - 1, y)),
+                        (x + 1 < width).then(|| (x + 1, y)),
+                        (y > 0).then(|| (x, y - 1)),
+                        (y + 1 < height).then(|| (x, y + 1)),
+                    ];
+                    value(
+                        neighbours
+                            .iter()
+                            .flatten()
+                            .any(|&(x, y)| escaped(x, y) != here),
+                    )
+                })
+                .collect()
+        }
+    }
+}
+
+/// Encodes the `width`×`height` `mask` as an 8-bit grayscale PNG.
+#[cfg(feature = "png")]
+pub fn encode_png(mask: &[u8], width: u32, height: u32) -> std::io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    image::codecs::png::PngEncoder::new(&mut data)
+        .encode(mask, width, height, image::ColorType::L8)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+    Ok(data)
+}
+/// Encodes the `width`×`height` `mask` as an 8-bit grayscale PNG.
+#[cfg(not(feature = "png"))]
+pub fn encode_png(_mask: &[u8], _width: u32, _height: u32) -> std::io::Result<Vec<u8>> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "can't write png files; the png feature isn't enabled",
+    ))
+}
@@ -0,0 +1,49 @@
+//! Draw the orbit of a point over the image of an escape-time fractal.
+//!
+//! For Mandelbrot, the point is `c`. In Julia mode, the point is the start and `c` is
+//! [`Config::julia`].
+
+use crate::{mapping, Algo, Config, Image, Imaginary, RGB};
+
+/// The radius in pixels of the markers at each point of the orbit.
+pub const MARKER_RADIUS: f64 = 2.0;
+
+/// The points visited when iterating `point`, starting with `point` itself.
+///
+/// Ends with the first point outside of [`Config::limit`] and [`Config::bailout`] if it escapes, else it has
+/// `config.iterations + 1` points. The fern has no orbits, so it's empty then.
+pub fn orbit(config: &Config, point: Imaginary) -> Vec<Imaginary> {
+    let c = match config.algo {
+        Algo::Mandelbrot => config.julia.unwrap_or(point),
+        Algo::BarnsleyFern => return Vec::new(),
+    };
+    let mut orbit = vec![point];
+    let mut z = point;
+    for _ in 0..config.iterations {
+        z = z.square() + c;
+        orbit.push(z);
+        let dist = z.squared_distance();
+        // Also stops if the orbit overflows to infinity or NaN.
+        if config.bailout.escaped(z, dist, config.limit) || dist.is_nan() {
+            break;
+        }
+    }
+    orbit
+}
+
+/// Draws the orbit of `point` on `image` as markers connected by lines.
+/// Parts outside of the image are clipped.
+///
+/// `image` must have the size of `config`.
+pub fn draw(config: &Config, image: &mut Image, point: Imaginary, color: RGB) {
+    let pixels: Vec<_> = orbit(config, point)
+        .into_iter()
+        .map(|z| mapping::complex_to_pixel(config, z))
+        .collect();
+    for segment in pixels.windows(2) {
+        image.draw_line(segment[0], segment[1], color);
+    }
+    for &pixel in &pixels {
+        image.fill_circle(pixel, MARKER_RADIUS, color);
+    }
+}
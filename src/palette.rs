@@ -0,0 +1,335 @@
+//! Gradient palettes, which color the escaping points of escape-time fractals instead of
+//! [`Config::primary_color`].
+//!
+//! A palette is a list of stops, each a position in `0..=1` and a color. Between stops, the
+//! colors are interpolated linearly. The position of a point is
+//! `iterations / config.iterations × exposure`, like the brightness of the primary color.
+//!
+//! Palettes are given as `POSITION:COLOR,…` (e.g. `0:000764,0.4:edffff,1:ffaa00`) or as just the
+//! colors, which are spread evenly. They can also be read from Fractint `.map` files and GIMP
+//! `.ggr` gradients.
+
+use crate::{parse, Config, Sample, RGB};
+use std::fmt::{self, Display};
+use std::path::Path;
+use std::str::FromStr;
+use std::{fs, io};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stop {
+    /// In `0..=1`.
+    pub position: f64,
+    pub color: RGB,
+}
+
+/// A gradient of at least 2 [`Stop`]s, sorted by their position.
+///
+/// # Examples
+///
+/// ```
+/// use fractal_renderer::palette::Palette;
+/// use fractal_renderer::RGB;
+///
+/// let mut palette: Palette = "1:ffffff,0:000000".parse().unwrap();
+/// // The stops are sorted.
+/// assert_eq!(palette.to_string(), "0:000000,1:ffffff");
+/// assert_eq!(palette.color_at(0.5), RGB::new(127, 127, 127));
+///
+/// let index = palette.insert(0.25, RGB::new(255, 0, 0));
+/// assert_eq!(index, 1);
+/// assert_eq!(palette.color_at(0.25), RGB::new(255, 0, 0));
+/// // Dragging the stop past the last one moves it to the end.
+/// assert_eq!(palette.set_position(index, 1.5), 2);
+/// assert_eq!(palette.stops()[2].position, 1.0);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    stops: Vec<Stop>,
+}
+impl Palette {
+    /// Sorts `stops`, clamping the positions to `0..=1`.
+    ///
+    /// Returns [`None`] if there are fewer than 2 stops or a position isn't a number.
+    pub fn new(mut stops: Vec<Stop>) -> Option<Self> {
+        if stops.len() < 2 || stops.iter().any(|stop| stop.position.is_nan()) {
+            return None;
+        }
+        for stop in &mut stops {
+            stop.position = stop.position.clamp(0.0, 1.0);
+        }
+        // Stable, so stops at the same position keep their order, for hard edges.
+        stops.sort_by(|a, b| a.position.partial_cmp(&b.position).unwrap());
+        Some(Self { stops })
+    }
+    /// `colors`, spread evenly from 0 to 1.
+    pub fn even(colors: impl IntoIterator<Item = RGB>) -> Option<Self> {
+        let colors: Vec<_> = colors.into_iter().collect();
+        let last = colors.len().saturating_sub(1).max(1) as f64;
+        let stops = colors
+            .into_iter()
+            .enumerate()
+            .map(|(i, color)| Stop {
+                position: i as f64 / last,
+                color,
+            })
+            .collect();
+        Self::new(stops)
+    }
+
+    pub fn stops(&self) -> &[Stop] {
+        &self.stops
+    }
+    /// The color at `position`, which is clamped to `0..=1`.
+    pub fn color_at(&self, position: f64) -> RGB {
+        let position = position.clamp(0.0, 1.0);
+        let after = self
+            .stops
+            .iter()
+            .position(|stop| stop.position > position)
+            .unwrap_or(self.stops.len());
+        let (a, b) = match after {
+            0 => return self.stops[0].color,
+            i if i == self.stops.len() => return self.stops[i - 1].color,
+            i => (self.stops[i - 1], self.stops[i]),
+        };
+        let t = (position - a.position) / (b.position - a.position);
+        let channel = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t) as u8;
+        RGB::new(
+            channel(a.color.r, b.color.r),
+            channel(a.color.g, b.color.g),
+            channel(a.color.b, b.color.b),
+        )
+    }
+    /// Adds a stop, returning it's index.
+    pub fn insert(&mut self, position: f64, color: RGB) -> usize {
+        let position = if position.is_nan() {
+            0.0
+        } else {
+            position.clamp(0.0, 1.0)
+        };
+        let index = self
+            .stops
+            .iter()
+            .position(|stop| stop.position > position)
+            .unwrap_or(self.stops.len());
+        self.stops.insert(index, Stop { position, color });
+        index
+    }
+    /// Removes the stop at `index`, unless only 2 are left. Returns whether it was removed.
+    pub fn remove(&mut self, index: usize) -> bool {
+        if self.stops.len() <= 2 || index >= self.stops.len() {
+            return false;
+        }
+        self.stops.remove(index);
+        true
+    }
+    /// Moves the stop at `index` to `position`, returning it's new index.
+    pub fn set_position(&mut self, index: usize, position: f64) -> usize {
+        let color = self.stops.remove(index).color;
+        self.insert(position, color)
+    }
+    pub fn set_color(&mut self, index: usize, color: RGB) {
+        self.stops[index].color = color;
+    }
+
+    /// Reads a Fractint `.map` file or a GIMP `.ggr` gradient, depending on the extension.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let data = fs::read_to_string(path).map_err(|err| format!("{:?}: {}", path, err))?;
+        let palette = match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("map") => Self::parse_map(&data),
+            Some(extension) if extension.eq_ignore_ascii_case("ggr") => Self::parse_ggr(&data),
+            _ => Err("only .map and .ggr palettes are supported".into()),
+        };
+        palette.map_err(|err| format!("{:?}: {}", path, err))
+    }
+    /// Writes the palette as a Fractint `.map` or a GIMP `.ggr`, depending on the extension.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let data = match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("map") => self.to_map(),
+            Some(extension) if extension.eq_ignore_ascii_case("ggr") => self.to_ggr(),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "only .map and .ggr palettes are supported",
+                ))
+            }
+        };
+        fs::write(path, data)
+    }
+
+    /// Parses a Fractint map: up to 256 lines of `R G B`, optionally followed by a comment.
+    pub fn parse_map(data: &str) -> Result<Self, String> {
+        let colors = data
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let mut channels = line.split_whitespace().map(str::parse::<u8>);
+                match (channels.next(), channels.next(), channels.next()) {
+                    (Some(Ok(r)), Some(Ok(g)), Some(Ok(b))) => Ok(RGB::new(r, g, b)),
+                    _ => Err(format!("{:?} isn't a color like `255 136 0`", line)),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::even(colors).ok_or_else(|| "the map has fewer than 2 colors".into())
+    }
+    /// 256 lines of `R G B`, for Fractint.
+    pub fn to_map(&self) -> String {
+        (0..256)
+            .map(|i| {
+                let color = self.color_at(i as f64 / 255.0);
+                format!("{} {} {}\n", color.r, color.g, color.b)
+            })
+            .collect()
+    }
+    /// Parses a GIMP gradient. Every segment becomes two stops, at its ends. The midpoints,
+    /// blending functions and transparency are ignored.
+    pub fn parse_ggr(data: &str) -> Result<Self, String> {
+        let mut lines = data.lines().map(str::trim);
+        if lines.next() != Some("GIMP Gradient") {
+            return Err("missing the `GIMP Gradient` header".into());
+        }
+        let mut count = lines.next().ok_or("missing the number of segments")?;
+        if count.starts_with("Name:") {
+            count = lines.next().ok_or("missing the number of segments")?;
+        }
+        let count: usize = count
+            .parse()
+            .map_err(|_| format!("{:?} isn't a number of segments", count))?;
+        let channel = |v: f64| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+        // Not preallocated, since `count` may be anything.
+        let mut stops = Vec::new();
+        for line in lines.take(count) {
+            let values = line
+                .split_whitespace()
+                .take(11)
+                .map(str::parse::<f64>)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| format!("{:?} isn't a segment", line))?;
+            if let [left, _middle, right, r0, g0, b0, _a0, r1, g1, b1, _a1] = values[..] {
+                stops.push(Stop {
+                    position: left,
+                    color: RGB::new(channel(r0), channel(g0), channel(b0)),
+                });
+                stops.push(Stop {
+                    position: right,
+                    color: RGB::new(channel(r1), channel(g1), channel(b1)),
+                });
+            } else {
+                return Err(format!("{:?} isn't a segment", line));
+            }
+        }
+        if stops.len() / 2 != count {
+            return Err(format!("expected {} segments", count));
+        }
+        Self::new(stops).ok_or_else(|| "the gradient has no segments".into())
+    }
+    /// A GIMP gradient with a linear RGB segment between every two stops.
+    pub fn to_ggr(&self) -> String {
+        let channel = |v: u8| v as f64 / 255.0;
+        let mut ggr = format!(
+            "GIMP Gradient\nName: fractal-renderer\n{}\n",
+            self.stops.len() - 1
+        );
+        for pair in self.stops.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            ggr.push_str(&format!(
+                "{:.6} {:.6} {:.6} {:.6} {:.6} {:.6} 1.000000 {:.6} {:.6} {:.6} 1.000000 0 0\n",
+                a.position,
+                (a.position + b.position) / 2.0,
+                b.position,
+                channel(a.color.r),
+                channel(a.color.g),
+                channel(a.color.b),
+                channel(b.color.r),
+                channel(b.color.g),
+                channel(b.color.b),
+            ));
+        }
+        ggr
+    }
+}
+impl Default for Palette {
+    fn default() -> Self {
+        "0:000764,0.16:206bcb,0.42:edffff,0.6425:ffaa00,0.8575:000200,1:000764"
+            .parse()
+            .expect("the default palette is valid")
+    }
+}
+impl Display for Palette {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, stop) in self.stops.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            let color = stop.color;
+            write!(
+                f,
+                "{}:{:02x}{:02x}{:02x}",
+                stop.position, color.r, color.g, color.b
+            )?;
+        }
+        Ok(())
+    }
+}
+impl FromStr for Palette {
+    type Err = String;
+    /// Parses `POSITION:COLOR,…` or `COLOR,…`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<_> = s.split(',').map(str::trim).collect();
+        if parts.iter().all(|part| !part.contains(':')) {
+            let colors = parts
+                .iter()
+                .map(|color| parse::color(color))
+                .collect::<Result<Vec<_>, _>>()?;
+            return Self::even(colors).ok_or_else(|| "expected at least 2 colors".into());
+        }
+        let stops = parts
+            .iter()
+            .map(|part| {
+                let (position, color) = part
+                    .split_once(':')
+                    .ok_or_else(|| format!("expected POSITION:COLOR, got {:?}", part))?;
+                let position = position
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|position| (0.0..=1.0).contains(position))
+                    .ok_or_else(|| format!("{:?} isn't a position in 0..=1", position))?;
+                Ok(Stop {
+                    position,
+                    color: parse::color(color)?,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Self::new(stops).ok_or_else(|| "expected at least 2 stops".into())
+    }
+}
+
+/// Parses the value of `--palette`: a `.map` or `.ggr` file, or a palette string.
+pub fn parse(s: &str) -> Result<Palette, String> {
+    let extension = Path::new(s)
+        .extension()
+        .and_then(|extension| extension.to_str());
+    match extension {
+        Some(extension)
+            if extension.eq_ignore_ascii_case("map") || extension.eq_ignore_ascii_case("ggr") =>
+        {
+            Palette::load(s)
+        }
+        _ => s.parse(),
+    }
+}
+
+/// Colors `sample` with `palette` if it escaped, else like [`crate::colorize`].
+pub fn colorize(sample: Sample, config: &Config, palette: &Palette) -> RGB {
+    let dist = sample.final_z.squared_distance();
+    if dist > config.stable_limit * config.stable_limit {
+        let brightness = config
+            .smooth_mode
+            .brightness(sample.iterations, config.iterations);
+        palette.color_at(brightness * config.exposure)
+    } else {
+        crate::colorize(sample, config)
+    }
+}
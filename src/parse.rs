@@ -0,0 +1,395 @@
+//! The small grammars of values on the command line and in batch manifests: colors,
+//! backgrounds, complex numbers, points, regions, sizes, aspect ratios, grid dimensions, semi-axes and durations.
+//!
+//! Every parser returns a [`ParseError`]. They are used as the validators of the arguments, so
+//! clap shows the errors before anything is rendered.
+
+use crate::{Background, ComplexRect, Imaginary, RGB};
+use std::fmt::{self, Display};
+use std::time::Duration;
+
+/// The smallest width and height of an image, see [`side`].
+pub const MIN_SIDE: u32 = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The input doesn't have the shape of the grammar, described by `expected`.
+    Syntax {
+        input: String,
+        expected: &'static str,
+    },
+    /// A part of the input isn't a finite number.
+    Number(String),
+    /// A part of the input isn't a positive integer.
+    PositiveInteger(String),
+    /// The input is well formed, but the value isn't valid, because of `reason`.
+    Range { input: String, reason: &'static str },
+}
+impl ParseError {
+    fn syntax(input: &str, expected: &'static str) -> Self {
+        Self::Syntax {
+            input: input.to_owned(),
+            expected,
+        }
+    }
+    fn range(input: &str, reason: &'static str) -> Self {
+        Self::Range {
+            input: input.to_owned(),
+            reason,
+        }
+    }
+}
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax { input, expected } => write!(f, "expected {}, got {:?}", expected, input),
+            Self::Number(input) => write!(f, "{:?} is not a number", input),
+            Self::PositiveInteger(input) => write!(f, "{:?} is not a positive integer", input),
+            Self::Range { input, reason } => write!(f, "{:?} is invalid: {}", input, reason),
+        }
+    }
+}
+impl std::error::Error for ParseError {}
+impl From<ParseError> for String {
+    fn from(err: ParseError) -> Self {
+        err.to_string()
+    }
+}
+
+fn number(s: &str) -> Result<f64, ParseError> {
+    match s.trim().parse::<f64>() {
+        Ok(v) if v.is_finite() => Ok(v),
+        _ => Err(ParseError::Number(s.trim().to_owned())),
+    }
+}
+/// Parses a positive integer.
+///
+/// ```
+/// # use fractal_renderer::parse::positive_integer;
+/// assert_eq!(positive_integer(" 12"), Ok(12));
+/// assert_eq!(
+///     positive_integer("0").unwrap_err().to_string(),
+///     r#""0" is not a positive integer"#
+/// );
+/// ```
+pub fn positive_integer(s: &str) -> Result<u32, ParseError> {
+    match s.trim().parse::<u32>() {
+        Ok(0) | Err(_) => Err(ParseError::PositiveInteger(s.trim().to_owned())),
+        Ok(v) => Ok(v),
+    }
+}
+
+/// Parses the width or height of an image, which is at least [`MIN_SIDE`] pixels.
+///
+/// ```
+/// # use fractal_renderer::parse::side;
+/// assert_eq!(side("1080"), Ok(1080));
+/// assert_eq!(
+///     side("8").unwrap_err().to_string(),
+///     r#""8" is invalid: an image must be at least 16 pixels wide and high"#
+/// );
+/// ```
+pub fn side(s: &str) -> Result<u32, ParseError> {
+    let side = positive_integer(s)?;
+    if side < MIN_SIDE {
+        return Err(ParseError::range(
+            s,
+            "an image must be at least 16 pixels wide and high",
+        ));
+    }
+    Ok(side)
+}
+
+/// Parses `RRGGBB`, optionally prefixed by `#`.
+///
+/// ```
+/// # use fractal_renderer::{parse::color, RGB};
+/// assert_eq!(color("#ff8800"), Ok(RGB::new(255, 136, 0)));
+/// for value in [0x000000, 0x123abc, 0xffffff] {
+///     let rgb = RGB::new((value >> 16) as u8, (value >> 8) as u8, value as u8);
+///     assert_eq!(color(&format!("{:06x}", value)), Ok(rgb));
+///     assert_eq!(color(&format!("#{:06X}", value)), Ok(rgb));
+/// }
+/// assert_eq!(
+///     color("ff88").unwrap_err().to_string(),
+///     r#"expected a color like ff8800, got "ff88""#
+/// );
+/// // Not a panic, even though the byte length is 6.
+/// assert!(color("ffé8").is_err());
+/// ```
+pub fn color(s: &str) -> Result<RGB, ParseError> {
+    let error = || ParseError::syntax(s, "a color like ff8800");
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(error());
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| error());
+    Ok(RGB::new(channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// Parses `auto`, `transparent`, `checkerboard` or a solid [`color`].
+///
+/// ```
+/// # use fractal_renderer::{parse::background, Background, RGB};
+/// assert_eq!(background("Checkerboard"), Ok(Background::Checkerboard));
+/// assert_eq!(background("#102030"), Ok(Background::Solid(RGB::new(16, 32, 48))));
+/// assert_eq!(
+///     background("none").unwrap_err().to_string(),
+///     r#"expected auto, transparent, checkerboard or a color like ff8800, got "none""#
+/// );
+/// ```
+pub fn background(s: &str) -> Result<Background, ParseError> {
+    let named = [
+        ("auto", Background::Auto),
+        ("transparent", Background::Transparent),
+        ("checkerboard", Background::Checkerboard),
+    ];
+    if let Some((_, background)) = named.iter().find(|(name, _)| name.eq_ignore_ascii_case(s)) {
+        return Ok(*background);
+    }
+    color(s).map(Background::Solid).map_err(|_| {
+        ParseError::syntax(s, "auto, transparent, checkerboard or a color like ff8800")
+    })
+}
+
+/// Parses `W:H`, a decimal number or `golden`, returning the width divided by the height.
+///
+/// ```
+/// # use fractal_renderer::parse::aspect;
+/// assert_eq!(aspect("16:9"), Ok(16.0 / 9.0));
+/// assert_eq!(aspect("2.35"), Ok(2.35));
+/// assert!((aspect("golden").unwrap() - 1.618).abs() < 1e-3);
+/// assert_eq!(
+///     aspect("16:0").unwrap_err().to_string(),
+///     r#""16:0" is invalid: the aspect ratio must be positive"#
+/// );
+/// assert_eq!(aspect("wide").unwrap_err().to_string(), r#"expected W:H, a number or `golden`, got "wide""#);
+/// ```
+pub fn aspect(s: &str) -> Result<f64, ParseError> {
+    let aspect = if s.trim().eq_ignore_ascii_case("golden") {
+        (1.0 + 5.0_f64.sqrt()) / 2.0
+    } else if let Some((w, h)) = s.split_once(':') {
+        number(w)? / number(h)?
+    } else {
+        number(s).map_err(|_| ParseError::syntax(s, "W:H, a number or `golden`"))?
+    };
+    if !aspect.is_finite() || aspect <= 0.0 {
+        return Err(ParseError::range(s, "the aspect ratio must be positive"));
+    }
+    Ok(aspect)
+}
+
+/// Parses `720p`, `1080p`, `1440p`, `4k`, `8k` or `WxH`, with both sides at least [`MIN_SIDE`].
+///
+/// ```
+/// # use fractal_renderer::parse::size;
+/// assert_eq!(size("1080p"), Ok((1920, 1080)));
+/// assert_eq!(size("4K"), Ok((3840, 2160)));
+/// assert_eq!(size("300×200"), Ok((300, 200)));
+/// assert_eq!(
+///     size("300x0").unwrap_err().to_string(),
+///     r#""0" is not a positive integer"#
+/// );
+/// assert!(size("300x10").is_err());
+/// assert_eq!(
+///     size("huge").unwrap_err().to_string(),
+///     r#"expected 1080p, 4k, 8k or WxH, got "huge""#
+/// );
+/// ```
+pub fn size(s: &str) -> Result<(u32, u32), ParseError> {
+    Ok(match s.trim().to_ascii_lowercase().as_str() {
+        "720p" => (1280, 720),
+        "1080p" => (1920, 1080),
+        "1440p" => (2560, 1440),
+        "4k" | "2160p" => (3840, 2160),
+        "8k" | "4320p" => (7680, 4320),
+        _ => {
+            let (w, h) = s
+                .split_once(|c| c == 'x' || c == '×')
+                .ok_or_else(|| ParseError::syntax(s, "1080p, 4k, 8k or WxH"))?;
+            (side(w)?, side(h)?)
+        }
+    })
+}
+
+/// Parses `NxM`, like the grid of [`crate::grid::JuliaGrid`].
+///
+/// ```
+/// # use fractal_renderer::parse::dimensions;
+/// assert_eq!(dimensions("6x4"), Ok((6, 4)));
+/// assert_eq!(dimensions("6").unwrap_err().to_string(), r#"expected NxM, got "6""#);
+/// ```
+pub fn dimensions(s: &str) -> Result<(u32, u32), ParseError> {
+    let (columns, rows) = s
+        .split_once(|c| c == 'x' || c == '×')
+        .ok_or_else(|| ParseError::syntax(s, "NxM"))?;
+    Ok((positive_integer(columns)?, positive_integer(rows)?))
+}
+
+/// Parses `re_min,im_min,re_max,im_max`.
+///
+/// ```
+/// # use fractal_renderer::parse::region;
+/// let rect = region("-2,-1.25,0.5,1.25").unwrap();
+/// assert_eq!((rect.min.re, rect.max.im), (-2.0, 1.25));
+/// assert_eq!(
+///     region("1,1,0,2").unwrap_err().to_string(),
+///     r#""1,1,0,2" is invalid: the minimum must be less than the maximum on both axes"#
+/// );
+/// assert_eq!(
+///     region("1,2,3").unwrap_err().to_string(),
+///     r#"expected re_min,im_min,re_max,im_max, got "1,2,3""#
+/// );
+/// ```
+pub fn region(s: &str) -> Result<ComplexRect, ParseError> {
+    let values = s.split(',').map(number).collect::<Result<Vec<_>, _>>()?;
+    let (re_min, im_min, re_max, im_max) = match values[..] {
+        [re_min, im_min, re_max, im_max] => (re_min, im_min, re_max, im_max),
+        _ => return Err(ParseError::syntax(s, "re_min,im_min,re_max,im_max")),
+    };
+    if re_min >= re_max || im_min >= im_max {
+        return Err(ParseError::range(
+            s,
+            "the minimum must be less than the maximum on both axes",
+        ));
+    }
+    Ok(ComplexRect {
+        min: Imaginary {
+            re: re_min,
+            im: im_min,
+        },
+        max: Imaginary {
+            re: re_max,
+            im: im_max,
+        },
+    })
+}
+
+/// Parses a complex number like `-0.8+0.156i`, `0.5`, `-i` or `2.5e-3-1e-2i`.
+///
+/// ```
+/// # use fractal_renderer::{parse::complex, Imaginary};
+/// let parsed = |re, im| Ok(Imaginary { re, im });
+/// assert_eq!(complex("-0.8+0.156i"), parsed(-0.8, 0.156));
+/// assert_eq!(complex("0.5"), parsed(0.5, 0.0));
+/// assert_eq!(complex("-i"), parsed(0.0, -1.0));
+/// assert_eq!(complex("2.5e-3-1e-2i"), parsed(2.5e-3, -1e-2));
+/// for (re, im) in [(0.25, -0.5), (-1.5e-8, 3.0), (0.0, 0.0)] {
+///     assert_eq!(complex(&format!("{}{:+}i", re, im)), parsed(re, im));
+/// }
+/// assert_eq!(
+///     complex("1+2j").unwrap_err().to_string(),
+///     r#"expected a complex number like -0.8+0.156i, got "1+2j""#
+/// );
+/// ```
+pub fn complex(s: &str) -> Result<Imaginary, ParseError> {
+    let number = |v: &str| match v.parse::<f64>() {
+        Ok(v) if v.is_finite() => Ok(v),
+        _ => Err(ParseError::syntax(s, "a complex number like -0.8+0.156i")),
+    };
+    let trimmed = s.trim();
+    let imaginary = match trimmed.strip_suffix('i') {
+        Some(imaginary) => imaginary,
+        None => {
+            return Ok(Imaginary {
+                re: number(trimmed)?,
+                im: 0.0,
+            })
+        }
+    };
+    // The sign between the parts, which isn't the sign of an exponent.
+    let split = imaginary
+        .char_indices()
+        .skip(1)
+        .filter(|&(index, c)| {
+            (c == '+' || c == '-') && !imaginary[..index].ends_with(|c| c == 'e' || c == 'E')
+        })
+        .map(|(index, _)| index)
+        .last();
+    let (re, im) = match split {
+        Some(index) => (number(&imaginary[..index])?, &imaginary[index..]),
+        None => (0.0, imaginary),
+    };
+    let im = match im {
+        "" | "+" => 1.0,
+        "-" => -1.0,
+        im => number(im)?,
+    };
+    Ok(Imaginary { re, im })
+}
+
+/// Parses `re,im`.
+///
+/// ```
+/// # use fractal_renderer::{parse::point, Imaginary};
+/// assert_eq!(point("-0.5, 0.25"), Ok(Imaginary { re: -0.5, im: 0.25 }));
+/// assert_eq!(point("1,inf").unwrap_err().to_string(), r#""inf" is not a number"#);
+/// ```
+pub fn point(s: &str) -> Result<Imaginary, ParseError> {
+    let (re, im) = s
+        .split_once(',')
+        .ok_or_else(|| ParseError::syntax(s, "re,im"))?;
+    Ok(Imaginary {
+        re: number(re)?,
+        im: number(im)?,
+    })
+}
+
+/// Parses the positive semi-axes `a,b` of an ellipse.
+///
+/// ```
+/// # use fractal_renderer::parse::semi_axes;
+/// assert_eq!(semi_axes("1, 4"), Ok((1.0, 4.0)));
+/// assert_eq!(
+///     semi_axes("1,0").unwrap_err().to_string(),
+///     r#""1,0" is invalid: both semi-axes must be positive"#
+/// );
+/// ```
+pub fn semi_axes(s: &str) -> Result<(f64, f64), ParseError> {
+    let (a, b) = s
+        .split_once(',')
+        .ok_or_else(|| ParseError::syntax(s, "a,b"))?;
+    let (a, b) = (number(a)?, number(b)?);
+    if a <= 0.0 || b <= 0.0 {
+        return Err(ParseError::range(s, "both semi-axes must be positive"));
+    }
+    Ok((a, b))
+}
+
+/// Parses durations like `90`, `90s`, `5m` and `2h`. A number without a unit is in seconds.
+///
+/// ```
+/// # use fractal_renderer::parse::duration;
+/// # use std::time::Duration;
+/// assert_eq!(duration("30s"), Ok(Duration::from_secs(30)));
+/// assert_eq!(duration("1.5h"), Ok(Duration::from_secs(90 * 60)));
+/// assert_eq!(duration("5"), Ok(Duration::from_secs(5)));
+/// assert_eq!(
+///     duration("-5m").unwrap_err().to_string(),
+///     r#"expected a duration like 30s, 5m or 1h, got "-5m""#
+/// );
+/// assert_eq!(
+///     duration("1e300h").unwrap_err().to_string(),
+///     r#""1e300h" is invalid: the duration is too long"#
+/// );
+/// ```
+pub fn duration(s: &str) -> Result<Duration, ParseError> {
+    let s = s.trim();
+    let (number, multiplier) = match s.char_indices().last() {
+        Some((i, 's')) => (&s[..i], 1.0),
+        Some((i, 'm')) => (&s[..i], 60.0),
+        Some((i, 'h')) => (&s[..i], 60.0 * 60.0),
+        _ => (s, 1.0),
+    };
+    let seconds = match number.parse::<f64>() {
+        Ok(n) if n.is_finite() && n >= 0.0 => n * multiplier,
+        _ => return Err(ParseError::syntax(s, "a duration like 30s, 5m or 1h")),
+    };
+    // `Duration::from_secs_f64` panics at `Duration::MAX`, which is 2^64 seconds as a `f64`.
+    // `Duration::try_from_secs_f64` is newer than the `rust-version`.
+    if seconds < Duration::MAX.as_secs_f64() {
+        Ok(Duration::from_secs_f64(seconds))
+    } else {
+        Err(ParseError::range(s, "the duration is too long"))
+    }
+}
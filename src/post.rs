@@ -0,0 +1,338 @@
+//! Post-processing of the rendered image: gaussian blur, sharpening and bloom.
+//!
+//! The ops are chained with `--post "bloom:0.3,sharpen:0.5"` and applied in order to a
+//! [`FloatImage`], so the colors are only rounded to 8 bits once, after the last op. They run
+//! before the overlays are drawn, so the gridlines and captions stay sharp.
+//!
+//! Convolutions clamp at the edges: pixels outside the image have the color of the nearest edge
+//! pixel, so the edges don't darken.
+
+use crate::RGB;
+use rayon::iter::{IndexedParallelIterator, ParallelIterator};
+use rayon::slice::{ParallelSlice, ParallelSliceMut};
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+/// The standard deviation in pixels of the blur [`PostOp::Sharpen`] subtracts.
+pub const SHARPEN_SIGMA: f32 = 1.0;
+/// The brightness of a channel, from 0 to 1, above which [`PostOp::Bloom`] makes it glow.
+pub const BLOOM_THRESHOLD: f32 = 0.75;
+/// The standard deviation of the glow of [`PostOp::Bloom`], as a fraction of the smaller side of
+/// the image.
+pub const BLOOM_SIGMA_FRACTION: f32 = 0.01;
+/// The largest standard deviation in pixels of [`PostOp::Blur`], which keeps its kernel small.
+/// [`crate::validate`] also limits it to the larger side of the image.
+pub const MAX_BLUR_SIGMA: f32 = 10_000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PostOp {
+    /// Gaussian blur with this standard deviation in pixels.
+    Blur(f32),
+    /// Unsharp mask of this amount, see [`unsharp_mask`].
+    Sharpen(f32),
+    /// Glow of this strength around the bright parts, see [`bloom`].
+    Bloom(f32),
+}
+impl PostOp {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Blur(_) => "blur",
+            Self::Sharpen(_) => "sharpen",
+            Self::Bloom(_) => "bloom",
+        }
+    }
+    pub fn value(self) -> f32 {
+        match self {
+            Self::Blur(value) | Self::Sharpen(value) | Self::Bloom(value) => value,
+        }
+    }
+    /// Applies the op to `image`.
+    pub fn apply(self, image: &mut FloatImage) {
+        match self {
+            Self::Blur(sigma) => gaussian_blur(image, sigma),
+            Self::Sharpen(amount) => unsharp_mask(image, SHARPEN_SIGMA, amount),
+            Self::Bloom(strength) => {
+                let sigma = image.width.min(image.height) as f32 * BLOOM_SIGMA_FRACTION;
+                bloom(image, BLOOM_THRESHOLD, sigma.max(1.0), strength)
+            }
+        }
+    }
+}
+impl Display for PostOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.name(), self.value())
+    }
+}
+impl FromStr for PostOp {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, value) = s
+            .split_once(':')
+            .ok_or_else(|| format!("{:?} isn't `op:value`", s))?;
+        let value: f32 = value
+            .trim()
+            .parse()
+            .ok()
+            .filter(|value: &f32| value.is_finite() && *value >= 0.0)
+            .ok_or_else(|| format!("{:?} isn't a non-negative number", value))?;
+        let name = name.trim();
+        Ok(if name.eq_ignore_ascii_case("blur") {
+            if value > MAX_BLUR_SIGMA {
+                return Err(format!(
+                    "a blur of {} pixels is larger than the maximum, {}",
+                    value, MAX_BLUR_SIGMA
+                ));
+            }
+            Self::Blur(value)
+        } else if name.eq_ignore_ascii_case("sharpen") {
+            Self::Sharpen(value)
+        } else if name.eq_ignore_ascii_case("bloom") {
+            Self::Bloom(value)
+        } else {
+            return Err(format!("unknown op {:?}; use blur, sharpen or bloom", name));
+        })
+    }
+}
+
+/// Parses the comma-separated ops of `--post`.
+///
+/// # Examples
+///
+/// ```
+/// use fractal_renderer::post::{parse, PostOp};
+///
+/// assert_eq!(
+///     parse("bloom:0.3, sharpen:0.5").unwrap(),
+///     [PostOp::Bloom(0.3), PostOp::Sharpen(0.5)]
+/// );
+/// assert!(parse("glow:1").is_err());
+/// assert!(parse("blur:-1").is_err());
+/// assert!(parse("blur:1e30").is_err());
+/// ```
+pub fn parse(s: &str) -> Result<Vec<PostOp>, String> {
+    s.split(',').map(|op| op.trim().parse()).collect()
+}
+
+/// An image with `f32` channels from 0 to 1, which can go out of that range between ops.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FloatImage {
+    pub pixels: Vec<[f32; 3]>,
+    pub width: usize,
+    pub height: usize,
+}
+impl FloatImage {
+    /// # Panics
+    ///
+    /// Panics if the length of `contents` isn't `width * height`.
+    pub fn from_rgb(contents: &[RGB], width: usize, height: usize) -> Self {
+        assert_eq!(
+            contents.len(),
+            width * height,
+            "the image has the wrong size"
+        );
+        let channel = |value: u8| value as f32 / 255.0;
+        Self {
+            pixels: contents
+                .iter()
+                .map(|pixel| [channel(pixel.r), channel(pixel.g), channel(pixel.b)])
+                .collect(),
+            width,
+            height,
+        }
+    }
+    /// Writes the pixels to `contents`, clamped and rounded to 8 bits.
+    pub fn write_rgb(&self, contents: &mut [RGB]) {
+        let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        for (pixel, [r, g, b]) in contents.iter_mut().zip(&self.pixels) {
+            *pixel = RGB::new(channel(*r), channel(*g), channel(*b));
+        }
+    }
+}
+
+/// The normalized gaussian kernel of standard deviation `sigma`, from `-radius` to `radius`
+/// with a radius of `3 × sigma`.
+fn kernel(sigma: f32) -> Vec<f32> {
+    let radius = (sigma * 3.0).ceil() as isize;
+    let weights: Vec<f32> = (-radius..=radius)
+        .map(|offset| (-(offset * offset) as f32 / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let sum: f32 = weights.iter().sum();
+    weights.into_iter().map(|weight| weight / sum).collect()
+}
+
+/// Blurs `image` with a gaussian of standard deviation `sigma` pixels, as a horizontal and a
+/// vertical pass. Does nothing if `sigma` is 0, and blurs as much as with the larger side of the
+/// image if `sigma` is larger.
+///
+/// # Examples
+///
+/// A single bright pixel spreads out, but keeps its brightness and stays symmetric.
+///
+/// ```
+/// use fractal_renderer::post::{gaussian_blur, FloatImage};
+///
+/// let mut image = FloatImage {
+///     pixels: vec![[0.0; 3]; 9 * 9],
+///     width: 9,
+///     height: 9,
+/// };
+/// image.pixels[4 * 9 + 4] = [1.0; 3];
+/// gaussian_blur(&mut image, 1.0);
+///
+/// let at = |x: usize, y: usize| image.pixels[y * 9 + x][0];
+/// let sum: f32 = image.pixels.iter().map(|pixel| pixel[0]).sum();
+/// assert!((sum - 1.0).abs() < 1e-4);
+/// assert!(at(4, 4) < 0.2 && at(4, 4) > at(5, 4) && at(5, 4) > at(6, 4));
+/// assert!((at(3, 4) - at(5, 4)).abs() < 1e-6 && (at(4, 3) - at(4, 5)).abs() < 1e-6);
+///
+/// // The edges are clamped, so a flat image stays flat.
+/// let mut flat = FloatImage {
+///     pixels: vec![[0.5; 3]; 4 * 3],
+///     width: 4,
+///     height: 3,
+/// };
+/// gaussian_blur(&mut flat, 2.0);
+/// assert!(flat.pixels.iter().flatten().all(|value| (value - 0.5).abs() < 1e-5));
+/// ```
+pub fn gaussian_blur(image: &mut FloatImage, sigma: f32) {
+    if sigma <= 0.0 || image.pixels.is_empty() {
+        return;
+    }
+    let (width, height) = (image.width, image.height);
+    let kernel = kernel(sigma.min(width.max(height) as f32));
+    let radius = (kernel.len() / 2) as isize;
+    let clamp = |value: isize, size: usize| value.clamp(0, size as isize - 1) as usize;
+    let convolve = |sample: &dyn Fn(isize) -> [f32; 3]| {
+        let mut sum = [0.0; 3];
+        for (i, weight) in kernel.iter().enumerate() {
+            let pixel = sample(i as isize - radius);
+            for channel in 0..3 {
+                sum[channel] += pixel[channel] * weight;
+            }
+        }
+        sum
+    };
+
+    let source = image.pixels.clone();
+    image
+        .pixels
+        .par_chunks_mut(width)
+        .enumerate()
+        .for_each(|(y, row)| {
+            let source = &source[y * width..(y + 1) * width];
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = convolve(&|offset| source[clamp(x as isize + offset, width)]);
+            }
+        });
+    let source = image.pixels.clone();
+    image
+        .pixels
+        .par_chunks_mut(width)
+        .enumerate()
+        .for_each(|(y, row)| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = convolve(&|offset| source[clamp(y as isize + offset, height) * width + x]);
+            }
+        });
+}
+
+/// Sharpens `image` by adding `amount` times the difference to its blur of standard deviation
+/// `sigma`.
+///
+/// # Examples
+///
+/// The steps of an edge grow, while flat areas stay the same.
+///
+/// ```
+/// use fractal_renderer::post::{unsharp_mask, FloatImage};
+///
+/// let row = [0.25, 0.25, 0.25, 0.25, 0.75, 0.75, 0.75, 0.75];
+/// let mut image = FloatImage {
+///     pixels: row.iter().map(|value| [*value; 3]).collect(),
+///     width: 8,
+///     height: 1,
+/// };
+/// unsharp_mask(&mut image, 1.0, 0.5);
+///
+/// let at = |x: usize| image.pixels[x][0];
+/// assert!(at(3) < 0.25 && at(4) > 0.75);
+/// assert!((at(0) - 0.25).abs() < 0.01 && (at(7) - 0.75).abs() < 0.01);
+/// ```
+pub fn unsharp_mask(image: &mut FloatImage, sigma: f32, amount: f32) {
+    let mut blurred = image.clone();
+    gaussian_blur(&mut blurred, sigma);
+    image
+        .pixels
+        .par_chunks_mut(image.width.max(1))
+        .zip(blurred.pixels.par_chunks(image.width.max(1)))
+        .for_each(|(row, blurred)| {
+            for (pixel, blurred) in row.iter_mut().zip(blurred) {
+                for channel in 0..3 {
+                    pixel[channel] += amount * (pixel[channel] - blurred[channel]);
+                }
+            }
+        });
+}
+
+/// Adds a glow around the parts of `image` brighter than `threshold`: the channels above it are
+/// blurred with a standard deviation of `sigma` pixels and added, times `strength`.
+///
+/// # Examples
+///
+/// A bright pixel lights up its surroundings, while dark parts are left as they are.
+///
+/// ```
+/// use fractal_renderer::post::{bloom, FloatImage};
+///
+/// let mut image = FloatImage {
+///     pixels: vec![[0.5; 3]; 7 * 7],
+///     width: 7,
+///     height: 7,
+/// };
+/// image.pixels[3 * 7 + 3] = [1.0; 3];
+/// bloom(&mut image, 0.75, 1.0, 1.0);
+///
+/// let at = |x: usize, y: usize| image.pixels[y * 7 + x][0];
+/// assert!(at(4, 3) > 0.5 && at(3, 4) > 0.5);
+/// assert!(at(3, 3) > 1.0);
+/// assert!(at(0, 0) - 0.5 < 1e-3);
+///
+/// let mut dark = FloatImage {
+///     pixels: vec![[0.5; 3]; 7 * 7],
+///     width: 7,
+///     height: 7,
+/// };
+/// let before = dark.clone();
+/// bloom(&mut dark, 0.75, 1.0, 1.0);
+/// assert_eq!(dark, before);
+/// ```
+pub fn bloom(image: &mut FloatImage, threshold: f32, sigma: f32, strength: f32) {
+    let mut bright = FloatImage {
+        pixels: image
+            .pixels
+            .iter()
+            .map(|pixel| pixel.map(|value| (value - threshold).max(0.0)))
+            .collect(),
+        width: image.width,
+        height: image.height,
+    };
+    gaussian_blur(&mut bright, sigma);
+    for (pixel, bright) in image.pixels.iter_mut().zip(&bright.pixels) {
+        for channel in 0..3 {
+            pixel[channel] += strength * bright[channel];
+        }
+    }
+}
+
+/// Applies `ops` in order to `contents` of `width` × `height`, rounding to 8 bits only once at
+/// the end. Does nothing without any ops.
+pub fn apply(contents: &mut [RGB], width: usize, height: usize, ops: &[PostOp]) {
+    if ops.is_empty() {
+        return;
+    }
+    let mut image = FloatImage::from_rgb(contents, width, height);
+    for op in ops {
+        op.apply(&mut image);
+    }
+    image.write_rgb(contents);
+}
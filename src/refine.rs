@@ -0,0 +1,192 @@
+//! Render the best image possible within a time limit, see [`render`].
+//!
+//! The render starts at a low [`Quality`] and refines it following [`schedule`]: first the
+//! iterations are raised, then the resolution, then the pixels are supersampled. The image of the
+//! best quality which finished before the deadline is the result, scaled to the size of the
+//! config.
+
+use crate::render::{self, RenderError};
+use crate::resize::Filter;
+use crate::{Config, ImageBuffer, RGB};
+use std::fmt::{self, Display};
+use std::time::{Duration, Instant};
+
+/// The fewest iterations [`schedule`] starts with, unless the config has fewer.
+pub const MIN_ITERATIONS: u32 = 16;
+/// The largest divisor of the resolution [`schedule`] starts with.
+pub const MAX_DIVISOR: u32 = 4;
+/// The supersampling [`schedule`] ends with, in samples along each side of a pixel.
+pub const MAX_SUPERSAMPLING: u32 = 4;
+
+/// How close a render is to the config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quality {
+    pub iterations: u32,
+    /// The width and height of the render are those of the config divided by this, rounded up.
+    pub divisor: u32,
+    /// Each pixel is the mean of `supersampling × supersampling` samples.
+    pub supersampling: u32,
+}
+impl Quality {
+    /// The size of the image with this quality, before supersampling.
+    pub fn size(&self, config: &Config) -> (u32, u32) {
+        let divide = |n: u32| ((n + self.divisor - 1) / self.divisor).max(1);
+        (divide(config.width), divide(config.height))
+    }
+    /// The config to render for this quality.
+    pub fn config(&self, config: &Config) -> Config {
+        let (width, height) = self.size(config);
+        Config {
+            width: width.saturating_mul(self.supersampling),
+            height: height.saturating_mul(self.supersampling),
+            iterations: self.iterations,
+            ..config.clone()
+        }
+    }
+}
+impl Display for Quality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.divisor == 1 {
+            write!(f, "full resolution")?;
+        } else {
+            write!(f, "1/{} resolution", self.divisor)?;
+        }
+        write!(f, ", {} iterations", self.iterations)?;
+        if self.supersampling > 1 {
+            write!(
+                f,
+                ", {}×{} supersampling",
+                self.supersampling, self.supersampling
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The qualities to render `config` at, from the worst to the config itself and then
+/// supersampled.
+///
+/// ```
+/// # use fractal_renderer::{Algo, Config, refine::{schedule, Quality}};
+/// let mut config = Config::new(Algo::Mandelbrot);
+/// config.iterations = 800;
+/// let schedule = schedule(&config);
+/// assert_eq!(
+///     schedule[0],
+///     Quality { iterations: 100, divisor: 4, supersampling: 1 }
+/// );
+/// assert!(schedule.contains(&Quality { iterations: 800, divisor: 1, supersampling: 1 }));
+/// assert_eq!(
+///     schedule.last(),
+///     Some(&Quality { iterations: 800, divisor: 1, supersampling: 4 })
+/// );
+/// // Every step improves one thing.
+/// for pair in schedule.windows(2) {
+///     assert!(pair[1].iterations >= pair[0].iterations);
+///     assert!(pair[1].divisor <= pair[0].divisor);
+///     assert!(pair[1].supersampling >= pair[0].supersampling);
+///     assert_ne!(pair[0], pair[1]);
+/// }
+/// ```
+pub fn schedule(config: &Config) -> Vec<Quality> {
+    let full = config.iterations;
+    let mut schedule: Vec<Quality> = Vec::new();
+    let mut push = |quality: Quality| {
+        if schedule.last() != Some(&quality) {
+            schedule.push(quality);
+        }
+    };
+    for shift in (0..=3).rev() {
+        push(Quality {
+            iterations: (full >> shift).max(MIN_ITERATIONS).min(full),
+            divisor: MAX_DIVISOR,
+            supersampling: 1,
+        });
+    }
+    let mut divisor = MAX_DIVISOR;
+    while divisor > 1 {
+        divisor /= 2;
+        push(Quality {
+            iterations: full,
+            divisor,
+            supersampling: 1,
+        });
+    }
+    let mut supersampling = 1;
+    while supersampling < MAX_SUPERSAMPLING {
+        supersampling *= 2;
+        push(Quality {
+            iterations: full,
+            divisor: 1,
+            supersampling,
+        });
+    }
+    schedule
+}
+
+/// The result of [`render`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Refined {
+    /// The image, the size of the config.
+    pub contents: Vec<RGB>,
+    /// The best quality which finished.
+    pub quality: Quality,
+    /// The index of [`Self::quality`] in the [`schedule`].
+    pub level: usize,
+    /// The length of the [`schedule`].
+    pub levels: usize,
+}
+
+/// Renders `config` at better and better qualities of the [`schedule`] until `limit` has passed,
+/// and returns the best which finished.
+///
+/// The first quality is always finished, even if it takes longer than `limit`. Only escape-time
+/// fractals can be refined.
+pub fn render(config: &Config, limit: Duration) -> Result<Refined, RenderError> {
+    let deadline = Instant::now() + limit;
+    let schedule = schedule(config);
+    let mut best = None;
+    for (level, quality) in schedule.iter().enumerate() {
+        let output = if level == 0 {
+            render::render(&quality.config(config))
+        } else {
+            render::render_until(&quality.config(config), deadline)
+        };
+        match output {
+            Ok(output) => {
+                log::debug!("Finished {} in {:.2?}.", quality, output.stats.duration);
+                best = Some((level, output.image.into_contents()));
+            }
+            // The supersampled image may be too large, even if the config isn't.
+            Err(RenderError::TimedOut) | Err(RenderError::TooLarge(..)) if level > 0 => break,
+            Err(err) => return Err(err),
+        }
+        if Instant::now() >= deadline {
+            break;
+        }
+    }
+    let (level, contents) = best.expect("the first quality always finishes");
+    let quality = schedule[level];
+    Ok(Refined {
+        contents: resize(contents, quality, config),
+        quality,
+        level,
+        levels: schedule.len(),
+    })
+}
+
+/// Averages the supersamples of the render of `quality`, then scales it to the size of `config`
+/// with [`Filter::Lanczos3`].
+fn resize(contents: Vec<RGB>, quality: Quality, config: &Config) -> Vec<RGB> {
+    let (width, height) = quality.size(config);
+    let (width, height) = (width as usize, height as usize);
+    let samples = quality.supersampling as usize;
+    let image = ImageBuffer::from_contents(contents, width * samples, height * samples)
+        .expect("the render has the size of its quality");
+    // Shrinking by whole factors with the box filter is the mean of the samples of each pixel.
+    let image = image.resized(width, height, Filter::Box);
+    let (full_width, full_height) = (config.width as usize, config.height as usize);
+    image
+        .resized(full_width, full_height, Filter::Lanczos3)
+        .into_contents()
+}
@@ -0,0 +1,479 @@
+//! [`render`], which renders a [`Config`] on the [`Backend`] it prefers and reports how the render
+//! went.
+//!
+//! This is what the CLI, the GUI, animations and benchmarks render with, so the backend is chosen
+//! the same way everywhere.
+
+use crate::resolved::ResolvedConfig;
+use crate::{Algo, Backend, Config, ImageBuffer, Sample, RGB};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use std::fmt::{self, Display};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RenderError {
+    /// The backend isn't available in this build.
+    Unavailable(Backend),
+    /// The image of this width and height is too large to be addressed on this platform.
+    TooLarge(u32, u32),
+    /// The deadline of [`render_until`] passed before the render finished.
+    TimedOut,
+}
+impl Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unavailable(backend) => write!(
+                f,
+                "the {} backend isn't available, as this binary has no GPU renderer",
+                backend
+            ),
+            Self::TooLarge(width, height) => write!(
+                f,
+                "a {}×{} image is too large to be addressed on this platform",
+                width, height
+            ),
+            Self::TimedOut => write!(f, "the render didn't finish before the deadline"),
+        }
+    }
+}
+impl std::error::Error for RenderError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderStats {
+    pub duration: Duration,
+    /// The fraction of the pixels which didn't escape. 0 for the fern.
+    pub interior_fraction: f64,
+    /// The most iterations any escaping pixel took. If it's close to [`Config::iterations`], more
+    /// iterations would show more detail. 0 for the fern.
+    pub max_iterations_hit: f64,
+    /// The fraction of the pixels which need more iterations, see [`starved_fraction`]. 0 for the
+    /// fern.
+    pub starved_fraction: f64,
+    /// The clipping of the escaping pixels, or of every pixel of the fern. The inside is left
+    /// out, as it's often black on purpose.
+    pub clipping: Clipping,
+    /// Never [`Backend::Auto`].
+    pub used_backend: Backend,
+}
+impl RenderStats {
+    /// See [`needs_more_iterations`].
+    pub fn needs_more_iterations(&self) -> bool {
+        needs_more_iterations(self.starved_fraction)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderOutput {
+    pub image: ImageBuffer,
+    pub stats: RenderStats,
+}
+
+/// Above this fraction of black or white pixels, the exposure is clearly off, see [`Clipping`].
+pub const CLIPPED_THRESHOLD: f64 = 0.95;
+/// How far from 0 or 255 every channel of a pixel can be for it to count as black or white.
+pub const CLIP_MARGIN: u8 = 2;
+
+/// The fractions of the pixels which are black or white, see [`clipping`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Clipping {
+    pub dark: f64,
+    pub bright: f64,
+}
+impl Clipping {
+    /// Whether almost all of the pixels are black, see [`CLIPPED_THRESHOLD`].
+    pub fn is_dark(&self) -> bool {
+        self.dark > CLIPPED_THRESHOLD
+    }
+    /// Whether almost all of the pixels are white, see [`CLIPPED_THRESHOLD`].
+    pub fn is_bright(&self) -> bool {
+        self.bright > CLIPPED_THRESHOLD
+    }
+}
+
+/// The fractions of `colors` which are black or white, within [`CLIP_MARGIN`].
+///
+/// ```
+/// # use fractal_renderer::{render::clipping, RGB};
+/// let colors = [
+///     RGB::new(0, 1, 0),
+///     RGB::new(0, 0, 0),
+///     RGB::new(255, 254, 255),
+///     RGB::new(40, 40, 255),
+/// ];
+/// let clipped = clipping(&colors);
+/// assert_eq!((clipped.dark, clipped.bright), (0.5, 0.25));
+/// assert!(!clipped.is_dark());
+/// // Nothing to clip.
+/// assert_eq!(clipping(&[] as &[RGB]).dark, 0.0);
+/// ```
+pub fn clipping<'a>(colors: impl IntoIterator<Item = &'a RGB>) -> Clipping {
+    let (mut dark, mut bright, mut count) = (0_u64, 0_u64, 0_u64);
+    for color in colors {
+        let channels = [color.r, color.g, color.b];
+        if channels.iter().all(|&channel| channel <= CLIP_MARGIN) {
+            dark += 1;
+        } else if channels.iter().all(|&channel| channel >= 255 - CLIP_MARGIN) {
+            bright += 1;
+        }
+        count += 1;
+    }
+    let count = count.max(1) as f64;
+    Clipping {
+        dark: dark as f64 / count,
+        bright: bright as f64 / count,
+    }
+}
+
+/// The brightness the spot meter of the GUI exposes the metered point at, see
+/// [`metered_exposure`].
+pub const METER_TARGET: f64 = 0.7;
+
+/// The [`Config::exposure`] which gives the point of `sample` the brightness `target`, like spot
+/// metering on a camera. With a palette, `target` is the position in it.
+///
+/// [`None`] if the point is colored as inside, or escaped at once, so no exposure changes it. The
+/// exposure is clamped to the range of the algorithm, see [`crate::AlgoDefaults`].
+///
+/// ```
+/// # use fractal_renderer::{render::metered_exposure, sample_point, Algo, Config, Imaginary};
+/// let config = Config::new(Algo::Mandelbrot);
+/// let sample = sample_point(&config, Imaginary { re: -0.75, im: 0.1 });
+/// let exposure = metered_exposure(&config, sample, 0.7).unwrap();
+/// let brightness = config.smooth_mode.brightness(sample.iterations, config.iterations);
+/// assert!((brightness * exposure - 0.7).abs() < 1e-9);
+///
+/// // The inside is colored by the interior exposure.
+/// let inside = sample_point(&config, Imaginary::ZERO);
+/// assert_eq!(metered_exposure(&config, inside, 0.7), None);
+/// ```
+pub fn metered_exposure(config: &Config, sample: Sample, target: f64) -> Option<f64> {
+    let stable = config.stable_limit * config.stable_limit;
+    if sample.final_z.squared_distance() <= stable {
+        return None;
+    }
+    let brightness = config
+        .smooth_mode
+        .brightness(sample.iterations, config.iterations);
+    if brightness.is_nan() || brightness <= 0.0 {
+        return None;
+    }
+    let (min, max) = crate::defaults(&config.algo).exposure_range;
+    Some((target / brightness).clamp(min, max))
+}
+/// The [`Config::exposure`] which gives the escaped pixels of `samples` the mean brightness
+/// `target`, like [`metered_exposure`] for the whole image.
+///
+/// [`None`] if no pixel escaped after any iterations. The brightness is summed by rows with
+/// [`tree_reduce`](crate::deterministic::tree_reduce), so the exposure is the same for every
+/// number of threads.
+///
+/// ```
+/// # use fractal_renderer::{get_samples, render::auto_exposure, Algo, Config};
+/// let mut config = Config::new(Algo::Mandelbrot);
+/// config.width = 60;
+/// config.height = 40;
+/// let samples = get_samples(&config);
+/// let exposure = auto_exposure(&config, &samples, 0.7).unwrap();
+/// let escaped: Vec<f64> = samples
+///     .iter()
+///     .filter(|sample| sample.escaped)
+///     .map(|sample| config.smooth_mode.brightness(sample.iterations, config.iterations))
+///     .collect();
+/// let mean = escaped.iter().sum::<f64>() / escaped.len() as f64;
+/// assert!((mean * exposure - 0.7).abs() < 1e-9);
+/// ```
+pub fn auto_exposure(config: &Config, samples: &[Sample], target: f64) -> Option<f64> {
+    let width = (config.width as usize).max(1);
+    let rows = (samples.len() + width - 1) / width;
+    if rows == 0 {
+        return None;
+    }
+    let row = |y: u64| {
+        let start = y as usize * width;
+        samples[start..(start + width).min(samples.len())]
+            .iter()
+            .filter(|sample| sample.escaped)
+            .fold((0.0, 0_u64), |(sum, count), sample| {
+                let brightness = config
+                    .smooth_mode
+                    .brightness(sample.iterations, config.iterations);
+                (sum + brightness, count + 1)
+            })
+    };
+    let (sum, count) =
+        crate::deterministic::tree_reduce(0..rows as u64, &row, &|a, b| (a.0 + b.0, a.1 + b.1));
+    if count == 0 || sum.is_nan() || sum <= 0.0 {
+        return None;
+    }
+    let (min, max) = crate::defaults(&config.algo).exposure_range;
+    Some((target * count as f64 / sum).clamp(min, max))
+}
+
+/// The escaping neighbours of a pixel which didn't escape must have taken at least this share of
+/// the iterations for the pixel to count as starved, see [`starved_fraction`].
+pub const STARVED_SHARE: f64 = 0.75;
+/// Above this [`RenderStats::starved_fraction`], the iterations are clearly too few.
+///
+/// The default Mandelbrot view, which has enough iterations, has up to 1.5% starved pixels at
+/// the smallest sizes and under 0.5% at common ones.
+pub const STARVED_THRESHOLD: f64 = 0.02;
+
+/// The fraction of the pixels which hit the cap of `iterations` while a neighbour escaped after
+/// more than [`STARVED_SHARE`] of them: a cheap sign that more iterations would let them escape.
+///
+/// `escapes` has the iterations of the pixels which escaped, row by row.
+///
+/// ```
+/// # use fractal_renderer::render::starved_fraction;
+/// // A pixel which didn't escape next to one which escaped late.
+/// let escapes = [Some(1.0), Some(45.0), Some(2.0), None];
+/// assert_eq!(starved_fraction(&escapes, 2, 50), 0.25);
+/// // With a neighbour which escaped early, the pixel is likely inside.
+/// let escapes = [Some(1.0), Some(5.0), Some(2.0), None];
+/// assert_eq!(starved_fraction(&escapes, 2, 50), 0.0);
+/// ```
+pub fn starved_fraction(escapes: &[Option<f32>], width: usize, iterations: u32) -> f64 {
+    if escapes.is_empty() || width == 0 {
+        return 0.0;
+    }
+    let height = escapes.len() / width;
+    let late = (iterations as f64 * STARVED_SHARE) as f32;
+    let escaped_late = |x: usize, y: usize| escapes[y * width + x].map_or(false, |it| it >= late);
+    let starved = (0..height)
+        .into_par_iter()
+        .map(|y| {
+            (0..width)
+                .filter(|&x| {
+                    escapes[y * width + x].is_none()
+                        && ((x > 0 && escaped_late(x - 1, y))
+                            || (x + 1 < width && escaped_late(x + 1, y))
+                            || (y > 0 && escaped_late(x, y - 1))
+                            || (y + 1 < height && escaped_late(x, y + 1)))
+                })
+                .count()
+        })
+        .sum::<usize>();
+    starved as f64 / escapes.len() as f64
+}
+
+/// Whether a render with this fraction of the pixels [starved](starved_fraction) shows blobs
+/// where there should be detail: if more than [`STARVED_THRESHOLD`] of them are.
+///
+/// A view without escaping pixels doesn't, as more iterations can't show anything inside the set.
+pub fn needs_more_iterations(starved_fraction: f64) -> bool {
+    starved_fraction > STARVED_THRESHOLD
+}
+
+/// Iterations which resolve the detail of the zoom of `config`, growing with the number of
+/// decades zoomed in. 50 at the default view.
+///
+/// ```
+/// # use fractal_renderer::{render::auto_iterations, Config};
+/// let mut config = Config::default();
+/// assert_eq!(auto_iterations(&config), 50);
+/// config.scale = config.scale * 1e6;
+/// assert!(auto_iterations(&config) > 500);
+/// ```
+pub fn auto_iterations(config: &Config) -> u32 {
+    let decades = crate::template::zoom(config).log10().max(0.0);
+    (50.0 * (1.0 + decades).powf(1.5)).round() as u32
+}
+
+/// Why [`Config::iterations`] is out of the range worth rendering with, see [`iterations_warning`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IterationsWarning {
+    /// The fern plots too few points to be seen.
+    TooFewPoints { points: u32, min: u32, default: u32 },
+    /// The render takes very long, for little detail which fewer iterations don't show.
+    TooMany { iterations: u32, max: u32 },
+}
+impl Display for IterationsWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooFewPoints {
+                points,
+                min,
+                default,
+            } => write!(
+                f,
+                "the fern is plotted with only {} points, which leaves the image almost blank; \
+                 use at least {}, like the default of {}",
+                points, min, default
+            ),
+            Self::TooMany { iterations, max } => write!(
+                f,
+                "{} iterations take very long to render and rarely show more than {} do; \
+                 pass --force to silence this",
+                iterations, max
+            ),
+        }
+    }
+}
+
+/// Whether [`Config::iterations`] is out of the [range](crate::AlgoDefaults::iterations_range) of
+/// the algorithm. The fern and escape-time fractals need iterations of very different magnitudes.
+///
+/// ```
+/// # use fractal_renderer::{render::{iterations_warning, IterationsWarning}, Algo, Config};
+/// let mut fern = Config::new(Algo::BarnsleyFern);
+/// assert_eq!(iterations_warning(&fern), None);
+/// fern.iterations = 100;
+/// assert!(matches!(
+///     iterations_warning(&fern),
+///     Some(IterationsWarning::TooFewPoints { points: 100, .. })
+/// ));
+///
+/// let mut mandelbrot = Config::new(Algo::Mandelbrot);
+/// mandelbrot.iterations = 100;
+/// assert_eq!(iterations_warning(&mandelbrot), None);
+/// mandelbrot.iterations = 10_000_000;
+/// assert_eq!(iterations_warning(&mandelbrot), None);
+/// mandelbrot.iterations = 50_000_000;
+/// assert!(matches!(
+///     iterations_warning(&mandelbrot),
+///     Some(IterationsWarning::TooMany { max: 10_000_000, .. })
+/// ));
+/// ```
+pub fn iterations_warning(config: &Config) -> Option<IterationsWarning> {
+    let defaults = crate::defaults(&config.algo);
+    let (min, max) = defaults.iterations_range;
+    if config.iterations < min {
+        // Escape-time fractals render with any iterations, just without detail.
+        if let Algo::BarnsleyFern = config.algo {
+            return Some(IterationsWarning::TooFewPoints {
+                points: config.iterations,
+                min,
+                default: defaults.iterations,
+            });
+        }
+    } else if config.iterations > max {
+        return Some(IterationsWarning::TooMany {
+            iterations: config.iterations,
+            max,
+        });
+    }
+    None
+}
+
+/// The iterations to suggest if a render of `config` [needs more](RenderStats::needs_more_iterations):
+/// [`auto_iterations`], or twice the current ones if that's already reached.
+pub fn suggested_iterations(config: &Config) -> u32 {
+    auto_iterations(config).max(config.iterations.saturating_mul(2))
+}
+
+/// The backend a render of `config` uses, following [`Config::backend`].
+///
+/// This build only renders on the CPU; the GPU renderer lives on the `gpu` branch.
+pub fn backend(config: &Config) -> Result<Backend, RenderError> {
+    match config.backend {
+        Backend::Auto | Backend::Cpu => Ok(Backend::Cpu),
+        Backend::Gpu => Err(RenderError::Unavailable(Backend::Gpu)),
+    }
+}
+
+/// Renders `config`, with the same pixels as [`crate::get_image`].
+pub fn render(config: &Config) -> Result<RenderOutput, RenderError> {
+    render_with(config, None, None)
+}
+/// [`render`] with the fern always the same for the same `seed`, like
+/// [`crate::get_image_seeded`].
+pub fn render_seeded(config: &Config, seed: u64) -> Result<RenderOutput, RenderError> {
+    render_with(config, Some(seed), None)
+}
+/// [`render`], but gives up with [`RenderError::TimedOut`] if it hasn't finished at `deadline`.
+///
+/// The deadline is checked before each row, so the render stops at most a row of each thread
+/// late. The fern isn't interrupted.
+pub fn render_until(config: &Config, deadline: Instant) -> Result<RenderOutput, RenderError> {
+    render_with(config, None, Some(deadline))
+}
+
+/// The colors, [escapes](starved_fraction), interior pixels and most iterations of an escaping
+/// pixel of a row of a render.
+type Row = (Vec<RGB>, Vec<Option<f32>>, u64, f64);
+
+fn render_with(
+    config: &Config,
+    seed: Option<u64>,
+    deadline: Option<Instant>,
+) -> Result<RenderOutput, RenderError> {
+    let used_backend = backend(config)?;
+    if crate::resources::image_bytes(config).is_none() {
+        return Err(RenderError::TooLarge(config.width, config.height));
+    }
+    let start = Instant::now();
+    let (contents, interior, max_iterations_hit, starved_fraction, clipping) = match config.algo {
+        Algo::Mandelbrot => {
+            let resolved = ResolvedConfig::new(config);
+            let rows: Option<Vec<Row>> = (0..config.height)
+                .into_par_iter()
+                .map(|y| {
+                    if deadline.map_or(false, |deadline| Instant::now() >= deadline) {
+                        return None;
+                    }
+                    let mut row = Vec::with_capacity(config.width as usize);
+                    let mut escapes = Vec::with_capacity(config.width as usize);
+                    let (mut interior, mut max_iterations) = (0, 0.0_f64);
+                    for x in 0..config.width {
+                        let sample = resolved.sample(x as f64, y as f64);
+                        if sample.escaped {
+                            max_iterations = max_iterations.max(sample.iterations);
+                            escapes.push(Some(sample.iterations as f32));
+                        } else {
+                            interior += 1;
+                            escapes.push(None);
+                        }
+                        row.push(resolved.colorize(sample));
+                    }
+                    Some((row, escapes, interior, max_iterations))
+                })
+                .collect();
+            let rows = rows.ok_or(RenderError::TimedOut)?;
+            let pixels = config.width as usize * config.height as usize;
+            let mut contents = Vec::with_capacity(pixels);
+            let mut escapes = Vec::with_capacity(pixels);
+            let (mut interior, mut max_iterations) = (0, 0.0_f64);
+            for (row, row_escapes, row_interior, row_max_iterations) in rows {
+                contents.extend(row);
+                escapes.extend(row_escapes);
+                interior += row_interior;
+                max_iterations = max_iterations.max(row_max_iterations);
+            }
+            let starved = starved_fraction(&escapes, config.width as usize, config.iterations);
+            let clipping = clipping(
+                contents
+                    .iter()
+                    .zip(&escapes)
+                    .filter_map(|(color, escape)| escape.map(|_| color)),
+            );
+            (contents, interior, max_iterations, starved, clipping)
+        }
+        Algo::BarnsleyFern => {
+            let contents = match seed {
+                Some(seed) => crate::get_image_seeded(config, seed),
+                None => crate::get_image(config),
+            };
+            let clipping = clipping(&contents);
+            (contents, 0, 0.0, 0.0, clipping)
+        }
+    };
+    let duration = start.elapsed();
+    let pixels = config.width as u64 * config.height as u64;
+    let image = ImageBuffer::from_contents(contents, config.width as usize, config.height as usize)
+        .expect("the render has a pixel for every pixel of the config");
+    Ok(RenderOutput {
+        image,
+        stats: RenderStats {
+            duration,
+            interior_fraction: if pixels == 0 {
+                0.0
+            } else {
+                interior as f64 / pixels as f64
+            },
+            max_iterations_hit,
+            starved_fraction,
+            clipping,
+            used_backend,
+        },
+    })
+}
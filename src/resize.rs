@@ -0,0 +1,199 @@
+//! Resampling of images, for thumbnails and previews which keep the detail of the fractal, see
+//! [`ImageBuffer::resized`].
+//!
+//! The filters are separable: the rows are resampled, then the columns. Pixels outside the image
+//! have the color of the nearest edge pixel, like the convolutions of [`crate::post`].
+
+use crate::{ImageBuffer, RGB};
+use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
+use rayon::slice::ParallelSliceMut;
+use std::f32::consts::PI;
+
+/// When shrinking by more than this factor, [`Filter::auto`] picks [`Filter::Box`].
+pub const BOX_RATIO: f64 = 8.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    /// A windowed sinc with 3 lobes: sharp, with a little ringing at hard edges.
+    Lanczos3,
+    /// The mean of the covered pixels. Cheaper than [`Self::Lanczos3`], which needs `6 × ratio`
+    /// pixels for every pixel when shrinking, and as good when shrinking by large factors.
+    Box,
+}
+impl Filter {
+    /// The filter to scale `source` pixels to `target`.
+    pub fn auto(source: usize, target: usize) -> Self {
+        if source as f64 > target as f64 * BOX_RATIO {
+            Self::Box
+        } else {
+            Self::Lanczos3
+        }
+    }
+}
+
+fn sinc(x: f32) -> f32 {
+    if x == 0.0 {
+        1.0
+    } else {
+        let x = x * PI;
+        x.sin() / x
+    }
+}
+fn lanczos3(x: f32) -> f32 {
+    if x.abs() < 3.0 {
+        sinc(x) * sinc(x / 3.0)
+    } else {
+        0.0
+    }
+}
+
+/// The source pixels of a target pixel, along one axis.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contribution {
+    /// The first source pixel, which can be outside the image.
+    pub start: isize,
+    /// The weights of the source pixels from `start`, which sum to 1.
+    pub weights: Vec<f32>,
+}
+
+/// The contributions of the `source` pixels to each of the `target` pixels, along one axis.
+///
+/// The weights of every target pixel are normalized, so flat areas stay flat:
+///
+/// ```
+/// # use fractal_renderer::resize::{contributions, Filter};
+/// for filter in [Filter::Lanczos3, Filter::Box] {
+///     for (source, target) in [(100, 7), (7, 100), (64, 64), (10, 3)] {
+///         for contribution in contributions(source, target, filter) {
+///             let sum: f32 = contribution.weights.iter().sum();
+///             assert!((sum - 1.0).abs() < 1e-5);
+///         }
+///     }
+/// }
+/// // Shrinking by 4 averages 4 pixels with the box filter.
+/// let halved = contributions(8, 2, Filter::Box);
+/// assert_eq!(halved[1].start, 4);
+/// assert_eq!(halved[1].weights, [0.25; 4]);
+/// ```
+pub fn contributions(source: usize, target: usize, filter: Filter) -> Vec<Contribution> {
+    let scale = source as f32 / target as f32;
+    (0..target)
+        .map(|i| {
+            let (start, weights) = match filter {
+                Filter::Lanczos3 => {
+                    // The kernel is stretched when shrinking, so it covers every source pixel.
+                    let stretch = scale.max(1.0);
+                    let center = (i as f32 + 0.5) * scale - 0.5;
+                    let radius = 3.0 * stretch;
+                    let start = (center - radius).ceil() as isize;
+                    let end = (center + radius).floor() as isize;
+                    let weights: Vec<f32> = (start..=end)
+                        .map(|j| lanczos3((j as f32 - center) / stretch))
+                        .collect();
+                    (start, weights)
+                }
+                Filter::Box => {
+                    // The overlap of each source pixel with the span of the target pixel.
+                    let (from, to) = (i as f32 * scale, (i + 1) as f32 * scale);
+                    let start = from.floor() as isize;
+                    let end = (to.ceil() as isize).max(start + 1);
+                    let weights: Vec<f32> = (start..end)
+                        .map(|j| (to.min(j as f32 + 1.0) - from.max(j as f32)).max(0.0))
+                        .collect();
+                    (start, weights)
+                }
+            };
+            let sum: f32 = weights.iter().sum();
+            Contribution {
+                start,
+                weights: weights.into_iter().map(|weight| weight / sum).collect(),
+            }
+        })
+        .collect()
+}
+
+fn resample(
+    contribution: &Contribution,
+    len: usize,
+    pixel: impl Fn(usize) -> [f32; 3],
+) -> [f32; 3] {
+    let mut sum = [0.0; 3];
+    for (offset, weight) in contribution.weights.iter().enumerate() {
+        let index = (contribution.start + offset as isize).clamp(0, len as isize - 1) as usize;
+        let pixel = pixel(index);
+        for channel in 0..3 {
+            sum[channel] += pixel[channel] * weight;
+        }
+    }
+    sum
+}
+
+/// Resamples `image` to `width`×`height` with `filter`.
+///
+/// A bright pixel spreads out, but keeps its brightness, and a gradient stays a gradient:
+///
+/// ```
+/// # use fractal_renderer::{resize::{resize, Filter}, ImageBuffer, RGB};
+/// // On grey, so the ringing isn't clamped.
+/// let mut impulse = ImageBuffer::new(16, 16, RGB::new(64, 64, 64));
+/// *impulse.as_image().pixel_mut(8, 8).unwrap() = RGB::new(191, 191, 191);
+/// for filter in [Filter::Lanczos3, Filter::Box] {
+///     let brightness = |image: &ImageBuffer| -> i32 {
+///         image.contents().iter().map(|pixel| pixel.r as i32 - 64).sum()
+///     };
+///     // 4 times the pixels, within the rounding.
+///     let upscaled = resize(&impulse, 32, 32, filter);
+///     assert!((brightness(&upscaled) - 4 * 127).abs() < 4 * 127 / 10, "{:?}", filter);
+///     let downscaled = resize(&impulse, 8, 8, filter);
+///     assert!((brightness(&downscaled) - 127 / 4).abs() <= 3, "{:?}", filter);
+/// }
+///
+/// let gradient: Vec<RGB> = (0..256)
+///     .flat_map(|_| (0..=255).map(|value| RGB::new(value, value, value)))
+///     .collect();
+/// let gradient = ImageBuffer::from_contents(gradient, 256, 256).unwrap();
+/// for filter in [Filter::Lanczos3, Filter::Box] {
+///     let shrunk = resize(&gradient, 16, 16, filter);
+///     let row = &shrunk.contents()[8 * 16..9 * 16];
+///     for (x, pixel) in row.iter().enumerate() {
+///         // The mean of the 16 columns each pixel covers.
+///         let expected = x as f32 * 16.0 + 7.5;
+///         assert!((pixel.r as f32 - expected).abs() <= 1.0, "{:?} at {}", filter, x);
+///     }
+/// }
+/// ```
+pub fn resize(image: &ImageBuffer, width: usize, height: usize, filter: Filter) -> ImageBuffer {
+    let (source_width, source_height) = (image.width(), image.height());
+    if (width, height) == (source_width, source_height) {
+        return image.clone();
+    }
+    if image.contents().is_empty() || width == 0 || height == 0 {
+        return ImageBuffer::new(width, height, RGB::new(0, 0, 0));
+    }
+    let channels = |pixel: &RGB| [pixel.r as f32, pixel.g as f32, pixel.b as f32];
+    let source = image.contents();
+
+    let columns = contributions(source_width, width, filter);
+    let mut rows = vec![[0.0_f32; 3]; width * source_height];
+    rows.par_chunks_mut(width).enumerate().for_each(|(y, row)| {
+        let source = &source[y * source_width..(y + 1) * source_width];
+        for (pixel, contribution) in row.iter_mut().zip(&columns) {
+            *pixel = resample(contribution, source_width, |x| channels(&source[x]));
+        }
+    });
+
+    let lines = contributions(source_height, height, filter);
+    let channel = |value: f32| value.round().clamp(0.0, 255.0) as u8;
+    let mut contents = vec![RGB::new(0, 0, 0); width * height];
+    contents
+        .par_chunks_mut(width)
+        .zip(lines.into_par_iter())
+        .for_each(|(row, contribution)| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let [r, g, b] = resample(&contribution, source_height, |y| rows[y * width + x]);
+                *pixel = RGB::new(channel(r), channel(g), channel(b));
+            }
+        });
+    ImageBuffer::from_contents(contents, width, height)
+        .expect("the image has the size it's resized to")
+}
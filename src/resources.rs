@@ -0,0 +1,152 @@
+//! Estimates of the resources a render needs, and the memory available for it.
+
+use crate::{animation, buddhabrot, refine, Algo, Config, Options, Sample, RGB};
+use std::mem::size_of;
+
+/// Estimated peak memory usage in bytes when rendering and encoding `config`.
+///
+/// This is a heuristic, mostly useful to catch renders which won't fit in memory.
+///
+/// # Examples
+///
+/// ```
+/// use fractal_renderer::{resources::estimate_memory, Config};
+///
+/// let config = Config {
+///     width: 1000,
+///     height: 1000,
+///     ..Config::default()
+/// };
+/// // Encoding, with the image and 6 bytes per pixel of working buffers, uses the most.
+/// assert_eq!(estimate_memory(&config), 1_000_000 * (3 + 6));
+/// ```
+pub fn estimate_memory(config: &Config) -> u64 {
+    let pixels = config.width as u64 * config.height as u64;
+    let image = pixels.saturating_mul(size_of::<RGB>() as u64);
+    let threads = rayon::current_num_threads() as u64;
+    let render = match config.algo {
+        // The rows are collected and then flattened into the image.
+        Algo::Mandelbrot => image.saturating_mul(2),
+        // Every thread counts its own hits, which are summed and then colored.
+        Algo::BarnsleyFern if config.fern_normalize => pixels
+            .saturating_mul(size_of::<u32>() as u64 * (threads + 1))
+            .saturating_add(image),
+        // Every thread has its own image.
+        Algo::BarnsleyFern => image.saturating_mul(threads + 1),
+    };
+    // The AVIF encoder converts the image to YUV planes and keeps some working buffers.
+    let encode = pixels.saturating_mul(6);
+    render.max(image.saturating_add(encode))
+}
+
+/// Estimated peak memory usage in bytes of rendering `options`: [`estimate_memory`] of the largest
+/// image rendered (only the rows of a slice), with the samples kept to recolor with a palette or
+/// to reuse in the next frame, the post-processing buffers and the Buddhabrot buffers.
+pub fn peak_memory(options: &Options) -> u64 {
+    let config = match (&options.animation, &options.slice) {
+        (Some(animation), _) if animation.exp_map => {
+            animation::strip_config(&options.config, animation)
+        }
+        (_, Some(slice)) => Config {
+            height: slice.rows(options.config.height).len() as u32,
+            ..options.config.clone()
+        },
+        _ => options.config.clone(),
+    };
+    let config = &config;
+    let pixels = config.width as u64 * config.height as u64;
+    let mut bytes = estimate_memory(config);
+    if options.palette.is_some() {
+        bytes = bytes.saturating_add(pixels.saturating_mul(size_of::<Sample>() as u64));
+    }
+    if options
+        .animation
+        .as_ref()
+        .map_or(false, |animation| animation.reuse_frames)
+    {
+        // The samples of the previous and the current frame.
+        bytes = bytes.saturating_add(pixels.saturating_mul(2 * size_of::<Sample>() as u64));
+    }
+    if !options.post.is_empty() {
+        // The float image, a blurred copy and the copy it's blurred from.
+        bytes = bytes.saturating_add(pixels.saturating_mul(3 * size_of::<[f32; 3]>() as u64));
+    }
+    if options.ao.is_some() {
+        // The samples, their heights and occlusion.
+        let per_pixel = size_of::<Sample>() + 2 * size_of::<f64>();
+        bytes = bytes.saturating_add(pixels.saturating_mul(per_pixel as u64));
+    }
+    if let Some(aa) = &options.aa {
+        // The colors the contrast is found from, and the samples of each pixel.
+        let mut per_pixel = size_of::<RGB>();
+        if aa.debug.is_some() {
+            per_pixel += size_of::<u8>();
+        }
+        bytes = bytes.saturating_add(pixels.saturating_mul(per_pixel as u64));
+    }
+    if options.time_limit.is_some() {
+        // The most supersampled render and the best finished image.
+        let samples = refine::MAX_SUPERSAMPLING as u64 * refine::MAX_SUPERSAMPLING as u64;
+        bytes =
+            bytes.saturating_add(pixels.saturating_mul((samples + 1) * size_of::<RGB>() as u64));
+    }
+    if let Some(wallpaper) = &options.wallpaper {
+        let pixels = wallpaper.width as u64 * wallpaper.height as u64;
+        bytes = bytes.saturating_add(pixels.saturating_mul(size_of::<RGB>() as u64));
+    }
+    if let Some(options) = &options.buddhabrot {
+        bytes = bytes.saturating_add(buddhabrot::buffer_bytes(config, options).unwrap_or(u64::MAX));
+    }
+    bytes
+}
+
+/// The memory which can be used without swapping, in bytes, or [`None`] if it's unknown.
+///
+/// Always unknown without the `sysinfo` feature.
+pub fn available_memory() -> Option<u64> {
+    #[cfg(feature = "sysinfo")]
+    {
+        use sysinfo::{RefreshKind, System, SystemExt};
+        let system = System::new_with_specifics(RefreshKind::new().with_memory());
+        // In kB, and 0 if it isn't supported on this platform.
+        Some(system.available_memory() * 1000).filter(|bytes| *bytes > 0)
+    }
+    #[cfg(not(feature = "sysinfo"))]
+    {
+        None
+    }
+}
+
+/// The size of the image of `config` in bytes, or [`None`] if it's too large to be addressed on
+/// this platform.
+pub fn image_bytes(config: &Config) -> Option<usize> {
+    (config.width as usize)
+        .checked_mul(config.height as usize)?
+        .checked_mul(size_of::<RGB>())
+        .filter(|bytes| *bytes <= isize::MAX as usize)
+}
+
+/// Formats `bytes` using binary prefixes, e.g. `1.5 GiB`.
+///
+/// # Examples
+///
+/// ```
+/// use fractal_renderer::resources::format_bytes;
+///
+/// assert_eq!(format_bytes(1000), "1000 B");
+/// assert_eq!(format_bytes(3 << 29), "1.5 GiB");
+/// ```
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
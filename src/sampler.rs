@@ -0,0 +1,178 @@
+//! Sources of the random points of stochastic renders.
+//!
+//! Low-discrepancy sequences ([`Kind::Sobol`] and [`Kind::Halton`]) spread the points more evenly
+//! than [`Kind::Pseudorandom`] numbers, which gives less noise for the same number of samples.
+//!
+//! Renders are split in parts, which each take every `parts`th point of the sequence
+//! (leap-frogging). Together, the parts take exactly the first points of the sequence, whatever
+//! the number of parts.
+//!
+//! The Buddhabrot uses these for its constants. The fern always chooses its transforms with a
+//! pseudorandom [`rand::Rng`], as the chaos game relies on them being independent.
+
+use rand::{Rng, SeedableRng};
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+/// A source of points in the unit square.
+///
+/// # Examples
+///
+/// With the same seed, every part of a sampler gives the same points.
+///
+/// ```
+/// use fractal_renderer::sampler::Kind;
+///
+/// for kind in Kind::ALL {
+///     let mut a = kind.part(Some(7), 1, 4);
+///     let mut b = kind.part(Some(7), 1, 4);
+///     for _ in 0..100 {
+///         let point = a.next_point();
+///         assert_eq!(point, b.next_point());
+///         assert!(point.iter().all(|v| (0.0..1.0).contains(v)));
+///     }
+/// }
+/// ```
+pub trait Sampler: Send {
+    /// The next point, with both coordinates in `0..1`.
+    fn next_point(&mut self) -> [f64; 2];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Pseudorandom,
+    Sobol,
+    Halton,
+}
+impl Kind {
+    pub const ALL: [Self; 3] = [Self::Pseudorandom, Self::Sobol, Self::Halton];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Pseudorandom => "pseudorandom",
+            Self::Sobol => "sobol",
+            Self::Halton => "halton",
+        }
+    }
+    /// The sampler of `part` of `parts`.
+    ///
+    /// With a `seed`, the points are the same every time. Without, the pseudorandom numbers are
+    /// seeded from the OS and the sequences are shifted randomly.
+    pub fn part(self, seed: Option<u64>, part: u64, parts: u64) -> Box<dyn Sampler> {
+        let mut rng = match seed {
+            Some(seed) => rand::rngs::SmallRng::seed_from_u64(seed),
+            None => rand::rngs::SmallRng::from_entropy(),
+        };
+        match self {
+            Self::Pseudorandom => {
+                // One seed for every part, so they don't repeat the same numbers.
+                let seed = (0..=part).map(|_| rng.gen()).last().unwrap_or(0);
+                Box::new(Pseudorandom(rand::rngs::SmallRng::seed_from_u64(seed)))
+            }
+            Self::Sobol | Self::Halton => Box::new(Sequence {
+                kind: self,
+                index: part,
+                step: parts,
+                // Cranley-Patterson rotation, which keeps the evenness of the sequence.
+                shift: [rng.gen(), rng.gen()],
+            }),
+        }
+    }
+}
+impl Default for Kind {
+    fn default() -> Self {
+        Self::Pseudorandom
+    }
+}
+impl Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+impl FromStr for Kind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|kind| kind.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown sampler {:?}", s))
+    }
+}
+
+struct Pseudorandom(rand::rngs::SmallRng);
+impl Sampler for Pseudorandom {
+    fn next_point(&mut self) -> [f64; 2] {
+        [self.0.gen_range(0.0..1.0), self.0.gen_range(0.0..1.0)]
+    }
+}
+
+struct Sequence {
+    kind: Kind,
+    index: u64,
+    step: u64,
+    shift: [f64; 2],
+}
+impl Sampler for Sequence {
+    fn next_point(&mut self) -> [f64; 2] {
+        let point = match self.kind {
+            Kind::Sobol => sobol(self.index),
+            _ => [
+                radical_inverse(self.index, 2),
+                radical_inverse(self.index, 3),
+            ],
+        };
+        self.index = self.index.wrapping_add(self.step);
+        let shift = |v: f64, shift: f64| {
+            let v = v + shift;
+            // Rounding can give exactly 1.
+            if v >= 1.0 {
+                (v - 1.0).min(1.0 - f64::EPSILON)
+            } else {
+                v
+            }
+        };
+        [
+            shift(point[0], self.shift[0]),
+            shift(point[1], self.shift[1]),
+        ]
+    }
+}
+
+/// `2^-53`, to map the top 53 bits of `u64`s, which fit in a `f64`, to `0..1`.
+const UNIT: f64 = 1.0 / (1_u64 << 53) as f64;
+
+/// The point `index` of the two dimensional Sobol sequence.
+///
+/// The first dimension is the van der Corput sequence, the second uses the primitive polynomial
+/// `x + 1`.
+fn sobol(index: u64) -> [f64; 2] {
+    let (mut x, mut y) = (0_u64, 0_u64);
+    let mut direction = 1 << 63;
+    let mut bits = index;
+    for bit in 0..64 {
+        if bits == 0 {
+            break;
+        }
+        if bits & 1 == 1 {
+            x ^= 1 << (63 - bit);
+            y ^= direction;
+        }
+        bits >>= 1;
+        direction ^= direction >> 1;
+    }
+    [(x >> 11) as f64 * UNIT, (y >> 11) as f64 * UNIT]
+}
+
+/// The digits of `index` in `base`, mirrored around the decimal point.
+fn radical_inverse(mut index: u64, base: u64) -> f64 {
+    let inverse_base = 1.0 / base as f64;
+    let mut factor = inverse_base;
+    let mut value = 0.0;
+    while index > 0 {
+        value += (index % base) as f64 * factor;
+        index /= base;
+        factor *= inverse_base;
+    }
+    value
+}
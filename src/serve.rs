@@ -0,0 +1,297 @@
+//! Serve tiles of the fractal over HTTP, for slippy maps like Leaflet or OpenLayers.
+//!
+//! `GET /tile/{z}/{x}/{y}.png` renders a [`TILE_SIZE`] PNG. At zoom 0, the single tile covers
+//! [`ServeOptions::extent`], and every zoom level splits each tile in four, with `y` growing
+//! downwards (like the imaginary axis of our images).
+//!
+//! These query parameters override the config given on the command line:
+//! `algo` (`mandelbrot` or `julia`, its Julia set), `iterations` (at most [`MAX_ITERATIONS`]),
+//! `exposure`, `julia_re` and `julia_im` (the constant of [`Config::julia`]), `primary` and
+//! `secondary` (colors like `ff8800`).
+
+use crate::{Algo, ComplexRect, Config, Imaginary};
+
+/// The width and height of the tiles.
+pub const TILE_SIZE: u32 = 256;
+/// Deeper tiles can't be resolved by `f64`.
+pub const MAX_ZOOM: u32 = 48;
+/// The most iterations a request can ask for, so a single request can't keep a thread busy for
+/// minutes.
+pub const MAX_ITERATIONS: u32 = 100_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServeOptions {
+    /// The address to listen on, like `127.0.0.1:8080`.
+    pub address: String,
+    /// The rectangle of the complex plane covered by the tile at zoom 0.
+    pub extent: ComplexRect,
+    /// The number of tiles to keep in memory.
+    pub cache_size: usize,
+    /// Requests are rejected with `503` when this many are being rendered.
+    pub max_requests: usize,
+}
+
+/// Derives the config of the tile at (`x`, `y`) at zoom `z` from `base`.
+///
+/// Returns a message suitable for a `400` response if the request is invalid.
+pub fn tile_config(base: &Config, extent: ComplexRect, url: &str) -> Result<Config, String> {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let coordinates = path
+        .strip_prefix("/tile/")
+        .and_then(|path| path.strip_suffix(".png"))
+        .ok_or_else(|| "expected /tile/{z}/{x}/{y}.png".to_owned())?;
+    let mut parts = coordinates.split('/').map(|part| {
+        part.parse::<u64>()
+            .map_err(|_| format!("{:?} is not a tile coordinate", part))
+    });
+    let (z, x, y) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(z), Some(x), Some(y), None) => (z?, x?, y?),
+        _ => return Err("expected /tile/{z}/{x}/{y}.png".into()),
+    };
+    if z > MAX_ZOOM as u64 {
+        return Err(format!("the zoom can't be larger than {}", MAX_ZOOM));
+    }
+    let tiles = 1_u64 << z;
+    if x >= tiles || y >= tiles {
+        return Err(format!("the tile must be in 0..{} at zoom {}", tiles, z));
+    }
+
+    let mut config = Config {
+        width: TILE_SIZE,
+        height: TILE_SIZE,
+        ..base.clone()
+    };
+    // The constant is kept while switching, so the parameters can come in any order.
+    let mut julia = config.julia.is_some();
+    let mut constant = config.julia.unwrap_or(Imaginary::ZERO);
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let number = || {
+            value
+                .parse::<f64>()
+                .ok()
+                .filter(|n| n.is_finite())
+                .ok_or_else(|| format!("invalid value {:?} for {}", value, key))
+        };
+        match key {
+            "algo" => {
+                julia = match value {
+                    "mandelbrot" => false,
+                    "julia" => true,
+                    _ => return Err(format!("unsupported algorithm {:?}", value)),
+                };
+                config.algo = Algo::Mandelbrot;
+            }
+            "iterations" => {
+                config.iterations = value
+                    .parse()
+                    .ok()
+                    .filter(|iterations| (1..=MAX_ITERATIONS).contains(iterations))
+                    .ok_or_else(|| {
+                        format!(
+                            "invalid value {:?} for {}, expected 1 to {}",
+                            value, key, MAX_ITERATIONS
+                        )
+                    })?
+            }
+            "exposure" => config.exposure = number()?,
+            "julia_re" => constant.re = number()?,
+            "julia_im" => constant.im = number()?,
+            "primary" => config.primary_color = crate::parse::color(value)?,
+            "secondary" => config.secondary_color = crate::parse::color(value)?,
+            _ => return Err(format!("unknown query parameter {:?}", key)),
+        }
+    }
+    config.julia = julia.then(|| constant);
+    if let Algo::BarnsleyFern = config.algo {
+        return Err("only escape-time fractals can be tiled".into());
+    }
+
+    let size = Imaginary {
+        re: extent.width() / tiles as f64,
+        im: extent.height() / tiles as f64,
+    };
+    let min = Imaginary {
+        re: extent.min.re + x as f64 * size.re,
+        im: extent.min.im + y as f64 * size.im,
+    };
+    config.set_viewport(ComplexRect {
+        min,
+        max: min + size,
+    });
+    Ok(config)
+}
+
+#[cfg(feature = "serve")]
+pub use server::run;
+
+#[cfg(feature = "serve")]
+mod server {
+    use super::{tile_config, ServeOptions};
+    use crate::format::Format;
+    use crate::{ImageBuffer, Options};
+    use std::collections::HashMap;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use tiny_http::{Header, Method, Request, Response, Server};
+
+    /// The path of the tile and the hash of its config.
+    type TileKey = (String, u64);
+    struct Entry {
+        data: Arc<Vec<u8>>,
+        /// The value of [`Cache::clock`] when this was last used.
+        used: u64,
+    }
+    /// A least recently used cache of encoded tiles.
+    struct Cache {
+        tiles: HashMap<TileKey, Entry>,
+        capacity: usize,
+        clock: u64,
+    }
+    impl Cache {
+        fn get(&mut self, key: &TileKey) -> Option<Arc<Vec<u8>>> {
+            self.clock += 1;
+            let clock = self.clock;
+            self.tiles.get_mut(key).map(|entry| {
+                entry.used = clock;
+                Arc::clone(&entry.data)
+            })
+        }
+        fn insert(&mut self, key: TileKey, data: Arc<Vec<u8>>) {
+            if self.capacity == 0 {
+                return;
+            }
+            if self.tiles.len() >= self.capacity && !self.tiles.contains_key(&key) {
+                let oldest = self
+                    .tiles
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.used)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    self.tiles.remove(&oldest);
+                }
+            }
+            self.clock += 1;
+            let used = self.clock;
+            self.tiles.insert(key, Entry { data, used });
+        }
+    }
+
+    /// A request being rendered, counted in the shared counter until this is dropped, even if
+    /// rendering panics.
+    struct InFlight(Arc<AtomicUsize>);
+    impl InFlight {
+        /// Counts another request, or returns [`None`] if `max` are already being rendered.
+        fn start(count: &Arc<AtomicUsize>, max: usize) -> Option<Self> {
+            if count.fetch_add(1, Ordering::SeqCst) >= max {
+                count.fetch_sub(1, Ordering::SeqCst);
+                return None;
+            }
+            Some(Self(Arc::clone(count)))
+        }
+    }
+    impl Drop for InFlight {
+        fn drop(&mut self) {
+            self.0.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    fn respond_text(request: Request, code: u16, message: &str) {
+        let response = Response::from_string(message).with_status_code(code);
+        if let Err(err) = request.respond(response) {
+            log::debug!("Failed to respond: {}", err);
+        }
+    }
+
+    /// Serves tiles of `options.config` until the process is killed.
+    pub fn run(options: &Options, serve: &ServeOptions) -> Result<(), String> {
+        let server = Server::http(&serve.address)
+            .map_err(|err| format!("failed to listen on {}: {}", serve.address, err))?;
+        log::info!(
+            "Serving tiles at http://{}/tile/{{z}}/{{x}}/{{y}}.png",
+            serve.address
+        );
+        let cache = Arc::new(Mutex::new(Cache {
+            tiles: HashMap::new(),
+            capacity: serve.cache_size,
+            clock: 0,
+        }));
+        let in_flight = Arc::new(AtomicUsize::new(0));
+
+        for request in server.incoming_requests() {
+            if *request.method() != Method::Get {
+                respond_text(request, 405, "only GET is supported");
+                continue;
+            }
+            let url = request.url().to_owned();
+            let config = match tile_config(&options.config, serve.extent, &url) {
+                Ok(config) => config,
+                Err(err) if url.starts_with("/tile/") => {
+                    respond_text(request, 400, &err);
+                    continue;
+                }
+                Err(_) => {
+                    respond_text(request, 404, "not found");
+                    continue;
+                }
+            };
+            let path = url.split('?').next().unwrap_or_default().to_owned();
+            let key = (path, crate::checkpoint::config_hash(&config));
+            let cached = cache.lock().unwrap().get(&key);
+            if let Some(data) = cached {
+                respond_png(request, &data);
+                continue;
+            }
+            let rendering = match InFlight::start(&in_flight, serve.max_requests) {
+                Some(rendering) => rendering,
+                None => {
+                    respond_text(request, 503, "too many requests are being rendered");
+                    continue;
+                }
+            };
+
+            let encoder = options.output.encoder;
+            let cache = Arc::clone(&cache);
+            rayon::spawn(move || {
+                let _rendering = rendering;
+                // A panic would otherwise kill the thread without responding.
+                let encoded = panic::catch_unwind(AssertUnwindSafe(|| {
+                    let image = ImageBuffer::from_contents(
+                        crate::get_image(&config),
+                        config.width as usize,
+                        config.height as usize,
+                    )
+                    .expect("the rendered image has the size of the config");
+                    crate::encode_image(&image, Format::Png, &encoder)
+                }));
+                match encoded {
+                    Ok(Ok(data)) => {
+                        let data = Arc::new(data);
+                        cache.lock().unwrap().insert(key, Arc::clone(&data));
+                        respond_png(request, &data);
+                    }
+                    Ok(Err(err)) => {
+                        log::error!("Failed to encode tile {}: {}", url, err);
+                        respond_text(request, 500, "failed to encode the tile");
+                    }
+                    Err(_) => {
+                        log::error!("Rendering tile {} panicked", url);
+                        respond_text(request, 500, "failed to render the tile");
+                    }
+                }
+            });
+        }
+        Ok(())
+    }
+
+    fn respond_png(request: Request, data: &[u8]) {
+        let response = Response::from_data(data).with_header(
+            Header::from_bytes(&b"Content-Type"[..], &b"image/png"[..])
+                .expect("the header is valid"),
+        );
+        if let Err(err) = request.respond(response) {
+            log::debug!("Failed to respond: {}", err);
+        }
+    }
+}
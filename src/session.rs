@@ -0,0 +1,230 @@
+//! The session log of the GUI: every view the GUI settles on is appended to
+//! `$XDG_DATA_HOME/fractal-renderer/session-<date>.jsonl` (`~/.local/share` by default), so views
+//! scrolled past can be found again.
+//!
+//! Every line is a JSON object with the UNIX `time` and the `config`, with only the fields which
+//! differ from [`Config::default`]. The lines are independent of each other, so the log is
+//! trimmed to the last [`MAX_ENTRIES`] by dropping the first lines.
+//!
+//! Logs from before [`Config::julia`], with `"algo": "julia"` and `julia_set`, are still read.
+
+use crate::{Config, Imaginary};
+use std::fmt::{self, Display};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{env, fs};
+
+/// The number of entries kept in a log.
+pub const MAX_ENTRIES: usize = 1000;
+
+#[derive(Debug)]
+pub enum SessionError {
+    Io(PathBuf, io::Error),
+    /// The line (starting at 1) isn't an entry, with the reason.
+    Parse(usize, String),
+    /// The index is out of the range of the log, which has this many entries.
+    NoEntry(usize, usize),
+}
+impl Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "{:?}: {}", path, err),
+            Self::Parse(line, reason) => write!(f, "line {}: {}", line, reason),
+            Self::NoEntry(index, len) => write!(
+                f,
+                "there's no entry {} in a session log of {} entries",
+                index, len
+            ),
+        }
+    }
+}
+impl std::error::Error for SessionError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    /// Seconds since the UNIX epoch.
+    pub time: u64,
+    pub config: Config,
+}
+impl Entry {
+    fn to_line(&self) -> String {
+        let config = serde_json::to_value(&self.config).expect("a config is serializable");
+        let default = serde_json::to_value(Config::default()).expect("a config is serializable");
+        let delta: serde_json::Map<_, _> = match (config, default) {
+            (serde_json::Value::Object(config), serde_json::Value::Object(default)) => config
+                .into_iter()
+                .filter(|(key, value)| default.get(key) != Some(value))
+                .collect(),
+            _ => unreachable!("a config is a struct"),
+        };
+        serde_json::json!({ "time": self.time, "config": delta }).to_string()
+    }
+    fn parse(line: &str) -> Result<Self, String> {
+        let value: serde_json::Value = serde_json::from_str(line).map_err(|err| err.to_string())?;
+        let time = value["time"].as_u64().ok_or("missing the time")?;
+        let mut delta = value["config"]
+            .as_object()
+            .ok_or("missing the config")?
+            .clone();
+        // The Julia sets of logs from before `Config::julia`.
+        let julia_set = delta.remove("julia_set");
+        if delta.get("algo").and_then(serde_json::Value::as_str) == Some("julia") {
+            let zero = || serde_json::to_value(Imaginary::ZERO).expect("a number is serializable");
+            delta.insert("algo".into(), "mandelbrot".into());
+            delta.insert("julia".into(), julia_set.unwrap_or_else(zero));
+        }
+        let mut config = serde_json::to_value(Config::default()).expect("a config is serializable");
+        for (key, value) in delta {
+            config[key] = value;
+        }
+        let config = serde_json::from_value(config).map_err(|err| err.to_string())?;
+        Ok(Self { time, config })
+    }
+}
+
+/// The entries of the log at `path`.
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<Entry>, SessionError> {
+    let path = path.as_ref();
+    let data = fs::read_to_string(path).map_err(|err| SessionError::Io(path.to_owned(), err))?;
+    data.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| Entry::parse(line).map_err(|reason| SessionError::Parse(i + 1, reason)))
+        .collect()
+}
+/// The config of entry `index` of the log at `path`, or of the last entry.
+pub fn replay(path: impl AsRef<Path>, index: Option<usize>) -> Result<Config, SessionError> {
+    let entries = load(path)?;
+    let index = index.unwrap_or_else(|| entries.len().saturating_sub(1));
+    entries
+        .get(index)
+        .map(|entry| entry.config.clone())
+        .ok_or(SessionError::NoEntry(index, entries.len()))
+}
+
+/// `(year, month, day)` of the UNIX `time`, in UTC.
+fn date(time: u64) -> (i64, u32, u32) {
+    // From Howard Hinnant's `civil_from_days`.
+    let days = (time / 86400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs())
+}
+
+/// The directory of the session logs, or [`None`] if neither `XDG_DATA_HOME` nor `HOME` is set.
+pub fn directory() -> Option<PathBuf> {
+    let data = env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))?;
+    Some(data.join("fractal-renderer"))
+}
+
+/// The log of a session, which is appended to and trimmed as views are added.
+#[derive(Debug)]
+pub struct SessionLog {
+    path: PathBuf,
+    entries: Vec<Entry>,
+    /// The number of entries trimmed since opening.
+    trimmed: usize,
+}
+impl SessionLog {
+    /// Opens the log of today, continuing it if it exists.
+    pub fn open_today() -> Result<Self, SessionError> {
+        let dir = directory().ok_or_else(|| {
+            SessionError::Io(
+                PathBuf::new(),
+                io::Error::new(io::ErrorKind::NotFound, "HOME isn't set"),
+            )
+        })?;
+        fs::create_dir_all(&dir).map_err(|err| SessionError::Io(dir.clone(), err))?;
+        let (year, month, day) = date(now());
+        let path = dir.join(format!("session-{}-{:02}-{:02}.jsonl", year, month, day));
+        Self::open(path)
+    }
+    /// Opens the log at `path`, continuing it if it exists.
+    pub fn open(path: PathBuf) -> Result<Self, SessionError> {
+        let entries = if path.exists() {
+            load(&path)?
+        } else {
+            Vec::new()
+        };
+        Ok(Self {
+            path,
+            entries,
+            trimmed: 0,
+        })
+    }
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    /// The entries, oldest first.
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+    /// The id of the first of [`Self::entries`]. The following entries have the next ids.
+    ///
+    /// Unlike the indices, the ids of entries don't change when the log is trimmed.
+    pub fn first_id(&self) -> usize {
+        self.trimmed
+    }
+    /// Appends `config`, unless it's the same as the last entry. The oldest entries are removed to
+    /// keep at most [`MAX_ENTRIES`].
+    pub fn append(&mut self, config: &Config) -> Result<(), SessionError> {
+        if self.entries.last().map(|entry| &entry.config) == Some(config) {
+            return Ok(());
+        }
+        let io_error = |err| SessionError::Io(self.path.clone(), err);
+        let entry = Entry {
+            time: now(),
+            config: config.clone(),
+        };
+        let line = entry.to_line();
+        self.entries.push(entry);
+        if self.entries.len() > MAX_ENTRIES {
+            let trimmed = self.entries.len() - MAX_ENTRIES;
+            self.entries.drain(..trimmed);
+            self.trimmed += trimmed;
+            let data: String = self
+                .entries
+                .iter()
+                .map(|entry| entry.to_line() + "\n")
+                .collect();
+            return fs::write(&self.path, data).map_err(io_error);
+        }
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(io_error)?;
+        writeln!(file, "{}", line).map_err(io_error)
+    }
+}
+
+/// Formats the UNIX `time` as `HH:MM:SS` in UTC.
+pub fn format_time(time: u64) -> String {
+    let seconds = time % 86400;
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
@@ -0,0 +1,213 @@
+//! Split a render into horizontal bands, which can be rendered on different machines and merged.
+//!
+//! # Part format
+//!
+//! All integers are little endian.
+//!
+//! | bytes      | content                                   |
+//! |------------|-------------------------------------------|
+//! | 8          | magic `FRPART01`                          |
+//! | 8          | [`config_hash`](crate::checkpoint::config_hash) |
+//! | 4 + 4      | width, height of the whole image          |
+//! | 4 + 4      | index (starting at 1) and count of slices |
+//! | 3 × pixels | the rows of the band, as RGB              |
+
+use crate::checkpoint::config_hash;
+use crate::{Algo, Config, RGB};
+use std::fmt::{self, Display};
+use std::fs;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+const MAGIC: &[u8; 8] = b"FRPART01";
+const HEADER_LEN: usize = 8 + 8 + 4 + 4 + 4 + 4;
+
+/// The `index`th (starting at 1) of `count` horizontal bands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Slice {
+    pub index: u32,
+    pub count: u32,
+}
+impl Slice {
+    /// Parses `i/n`.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let (index, count) = s
+            .split_once('/')
+            .ok_or_else(|| format!("expected i/n, got {:?}", s))?;
+        let index = index
+            .trim()
+            .parse()
+            .map_err(|_| format!("{:?} is not an integer", index))?;
+        let count = count
+            .trim()
+            .parse()
+            .map_err(|_| format!("{:?} is not an integer", count))?;
+        if index == 0 || index > count {
+            return Err(format!("the slice index must be in 1..={}", count));
+        }
+        Ok(Self { index, count })
+    }
+    /// The rows of this band in an image of `height`.
+    pub fn rows(&self, height: u32) -> Range<u32> {
+        let band = |i: u32| (i as u64 * height as u64 / self.count as u64) as u32;
+        band(self.index - 1)..band(self.index)
+    }
+    /// `output.part2of8.raw` for the output file `output.avif`.
+    pub fn filename(&self, output: impl AsRef<Path>) -> PathBuf {
+        let mut name = output.as_ref().with_extension("").into_os_string();
+        name.push(format!(".part{}of{}.raw", self.index, self.count));
+        name.into()
+    }
+}
+
+#[derive(Debug)]
+pub enum SliceError {
+    Io(PathBuf, io::Error),
+    /// The fern can't be split, as every point can land anywhere in the image.
+    Unsupported,
+    Corrupt(PathBuf),
+    /// The part doesn't match the first part.
+    Mismatch(PathBuf),
+    Duplicate(u32),
+    Missing {
+        missing: Vec<u32>,
+        count: u32,
+    },
+}
+impl Display for SliceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "{:?}: {}", path, err),
+            Self::Unsupported => write!(f, "only escape-time fractals can be sliced"),
+            Self::Corrupt(path) => write!(f, "{:?} isn't a valid part", path),
+            Self::Mismatch(path) => write!(
+                f,
+                "{:?} was rendered with another configuration or size than the first part",
+                path
+            ),
+            Self::Duplicate(index) => write!(f, "part {} was given more than once", index),
+            Self::Missing { missing, count } => {
+                write!(f, "missing parts ")?;
+                for (i, index) in missing.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", index)?;
+                }
+                write!(f, " of {}", count)
+            }
+        }
+    }
+}
+impl std::error::Error for SliceError {}
+
+/// Renders the band `slice` of `config` and writes it as a part to `path`.
+pub fn render_part(config: &Config, slice: Slice, path: &Path) -> Result<(), SliceError> {
+    if let Algo::BarnsleyFern = config.algo {
+        return Err(SliceError::Unsupported);
+    }
+    let contents = crate::get_rows(config, slice.rows(config.height));
+
+    let mut data = Vec::with_capacity(HEADER_LEN + contents.len() * 3);
+    data.extend_from_slice(MAGIC);
+    data.extend_from_slice(&config_hash(config).to_le_bytes());
+    for n in [config.width, config.height, slice.index, slice.count] {
+        data.extend_from_slice(&n.to_le_bytes());
+    }
+    for pixel in &contents {
+        data.extend_from_slice(&[pixel.r, pixel.g, pixel.b]);
+    }
+    fs::File::create(path)
+        .and_then(|mut file| file.write_all(&data))
+        .map_err(|err| SliceError::Io(path.to_owned(), err))
+}
+
+struct Part {
+    hash: u64,
+    width: u32,
+    height: u32,
+    slice: Slice,
+    data: Vec<u8>,
+}
+fn read_part(path: &Path) -> Result<Part, SliceError> {
+    let mut data = fs::read(path).map_err(|err| SliceError::Io(path.to_owned(), err))?;
+    if data.len() < HEADER_LEN || &data[..8] != MAGIC {
+        return Err(SliceError::Corrupt(path.to_owned()));
+    }
+    let u32_at = |i: usize| u32::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]);
+    let mut hash = [0; 8];
+    hash.copy_from_slice(&data[8..16]);
+    let (width, height) = (u32_at(16), u32_at(20));
+    let slice = Slice {
+        index: u32_at(24),
+        count: u32_at(28),
+    };
+    if slice.index == 0 || slice.index > slice.count {
+        return Err(SliceError::Corrupt(path.to_owned()));
+    }
+    let rows = slice.rows(height);
+    if data.len() - HEADER_LEN != (rows.end - rows.start) as usize * width as usize * 3 {
+        return Err(SliceError::Corrupt(path.to_owned()));
+    }
+    data.drain(..HEADER_LEN);
+    Ok(Part {
+        hash: u64::from_le_bytes(hash),
+        width,
+        height,
+        slice,
+        data,
+    })
+}
+
+/// Merges the parts at `paths` (in any order).
+///
+/// Returns the image and its width and height.
+pub fn merge(paths: &[impl AsRef<Path>]) -> Result<(Vec<RGB>, u32, u32), SliceError> {
+    let mut parts = Vec::with_capacity(paths.len());
+    for path in paths {
+        let path = path.as_ref();
+        let part = read_part(path)?;
+        if let Some(first) = parts.first() {
+            let first: &Part = first;
+            if (part.hash, part.width, part.height, part.slice.count)
+                != (first.hash, first.width, first.height, first.slice.count)
+            {
+                return Err(SliceError::Mismatch(path.to_owned()));
+            }
+        }
+        parts.push(part);
+    }
+    let (width, height, count) = match parts.first() {
+        Some(first) => (first.width, first.height, first.slice.count),
+        None => {
+            return Err(SliceError::Missing {
+                missing: Vec::new(),
+                count: 0,
+            })
+        }
+    };
+    parts.sort_by_key(|part| part.slice.index);
+    for pair in parts.windows(2) {
+        if pair[0].slice.index == pair[1].slice.index {
+            return Err(SliceError::Duplicate(pair[0].slice.index));
+        }
+    }
+    let missing: Vec<u32> = (1..=count)
+        .filter(|index| parts.iter().all(|part| part.slice.index != *index))
+        .collect();
+    if !missing.is_empty() {
+        return Err(SliceError::Missing { missing, count });
+    }
+
+    let contents = parts
+        .iter()
+        .flat_map(|part| part.data.chunks_exact(3))
+        .map(|p| RGB {
+            r: p[0],
+            g: p[1],
+            b: p[2],
+        })
+        .collect();
+    Ok((contents, width, height))
+}
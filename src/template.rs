@@ -0,0 +1,436 @@
+//! Templates of output paths, like `renders/{algo}_{zoom:.0e}_{frame:04}_{date}`, which name the
+//! images of batches, animations, slices and Julia grids by what they show.
+//!
+//! A field is written `{name}` or `{name:format}`, and `{{` and `}}` are literal braces. The
+//! format is `[0][width][.precision][e]`, like Rust's: `0` pads with zeros instead of spaces and
+//! `e` writes the number in scientific notation. See [`help`] for the fields.
+
+use crate::{Algo, Config, Options};
+use std::fmt::{self, Display};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Algo,
+    /// The real part of the center.
+    Re,
+    /// The imaginary part of the center.
+    Im,
+    /// The magnification, relative to the default view.
+    Zoom,
+    /// The index of the frame of an animation, from 0.
+    Frame,
+    /// The name of the job of a batch.
+    Name,
+    /// The seed of `--ifs-mutate`.
+    Seed,
+    /// The day the render started, as `YYYY-MM-DD` in UTC.
+    Date,
+}
+impl Field {
+    pub const ALL: [Self; 8] = [
+        Self::Algo,
+        Self::Re,
+        Self::Im,
+        Self::Zoom,
+        Self::Frame,
+        Self::Name,
+        Self::Seed,
+        Self::Date,
+    ];
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Algo => "algo",
+            Self::Re => "re",
+            Self::Im => "im",
+            Self::Zoom => "zoom",
+            Self::Frame => "frame",
+            Self::Name => "name",
+            Self::Seed => "seed",
+            Self::Date => "date",
+        }
+    }
+    fn description(self) -> &'static str {
+        match self {
+            Self::Algo => "the fractal: mandelbrot, julia or fern",
+            Self::Re => "the real part of the center (number)",
+            Self::Im => "the imaginary part of the center (number)",
+            Self::Zoom => "the magnification, 1 at the default view (number)",
+            Self::Frame => "the index of the frame, from 0 (integer, --animate)",
+            Self::Name => "the name of the job (--batch)",
+            Self::Seed => "the seed of the mutations (integer, --ifs-mutate)",
+            Self::Date => "the day the render started, YYYY-MM-DD in UTC",
+        }
+    }
+    fn kind(self) -> Kind {
+        match self {
+            Self::Re | Self::Im | Self::Zoom => Kind::Number,
+            Self::Frame | Self::Seed => Kind::Integer,
+            Self::Algo | Self::Name | Self::Date => Kind::Text,
+        }
+    }
+}
+impl Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{{}}}", self.name())
+    }
+}
+
+/// Which formats a [`Field`] accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    /// Every format.
+    Number,
+    /// Only the width.
+    Integer,
+    /// No format.
+    Text,
+}
+
+/// The largest width and precision of a [`Spec`].
+pub const MAX_WIDTH: usize = 64;
+
+/// The format of a field, `[0][width][.precision][e]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Spec {
+    pub zero: bool,
+    pub width: usize,
+    pub precision: Option<usize>,
+    pub exponent: bool,
+}
+impl Spec {
+    fn parse(s: &str) -> Option<Self> {
+        let mut spec = Self::default();
+        let mut rest = s;
+        if let Some(stripped) = rest.strip_prefix('0') {
+            spec.zero = true;
+            rest = stripped;
+        }
+        if let Some(stripped) = rest.strip_suffix('e') {
+            spec.exponent = true;
+            rest = stripped;
+        }
+        let (width, precision) = match rest.split_once('.') {
+            Some((width, precision)) => (width, Some(precision)),
+            None => (rest, None),
+        };
+        let digits = |s: &str| -> Option<usize> {
+            if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            s.parse().ok()
+        };
+        if !width.is_empty() {
+            spec.width = digits(width)?;
+        }
+        if let Some(precision) = precision {
+            spec.precision = Some(digits(precision)?);
+        }
+        Some(spec)
+    }
+    fn allowed(&self, kind: Kind) -> bool {
+        match kind {
+            Kind::Number => true,
+            Kind::Integer => self.precision.is_none() && !self.exponent,
+            Kind::Text => *self == Self::default(),
+        }
+    }
+    fn number(&self, value: f64) -> String {
+        let s = match (self.precision, self.exponent) {
+            (Some(precision), true) => format!("{:.*e}", precision, value),
+            (None, true) => format!("{:e}", value),
+            (Some(precision), false) => format!("{:.*}", precision, value),
+            (None, false) => value.to_string(),
+        };
+        self.pad(s)
+    }
+    fn pad(&self, s: String) -> String {
+        let len = s.chars().count();
+        if len >= self.width {
+            return s;
+        }
+        let padding = self.width - len;
+        if self.zero {
+            // The zeros go after the sign.
+            let (sign, digits) = match s.strip_prefix('-') {
+                Some(digits) => ("-", digits),
+                None => ("", s.as_str()),
+            };
+            format!("{}{}{}", sign, "0".repeat(padding), digits)
+        } else {
+            format!("{}{}", " ".repeat(padding), s)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    /// There is no field with this name.
+    Unknown(String),
+    /// The format isn't valid for the field.
+    Spec { field: Field, spec: String },
+    /// The width or precision of the format is larger than [`MAX_WIDTH`].
+    TooWide { field: Field, spec: String },
+    /// A `{` isn't closed.
+    Unclosed,
+    /// A `}` isn't opened.
+    Unopened,
+    /// The field has no value where the template is used.
+    Missing(Field),
+}
+impl Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown(name) => write!(
+                f,
+                "unknown field {{{}}}, see --help-template for the fields",
+                name
+            ),
+            Self::Spec { field, spec } => {
+                write!(f, "{:?} is not a valid format of {}", spec, field)
+            }
+            Self::TooWide { field, spec } => write!(
+                f,
+                "the width and precision of {:?}, the format of {}, can be at most {}",
+                spec, field, MAX_WIDTH
+            ),
+            Self::Unclosed => write!(f, "a `{{` isn't closed; write `{{{{` for a literal brace"),
+            Self::Unopened => write!(f, "a `}}` isn't opened; write `}}}}` for a literal brace"),
+            Self::Missing(field) => write!(f, "{} has no value here", field),
+        }
+    }
+}
+impl std::error::Error for TemplateError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Field(Field, Spec),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+impl Template {
+    /// ```
+    /// # use fractal_renderer::template::{Field, Template, TemplateError};
+    /// let template = Template::parse("renders/{algo}_{frame:04}").unwrap();
+    /// assert!(template.uses(Field::Frame));
+    /// assert_eq!(
+    ///     Template::parse("{iterations}"),
+    ///     Err(TemplateError::Unknown("iterations".into()))
+    /// );
+    /// assert!(Template::parse("{name:.2}").is_err());
+    /// assert!(Template::parse("{re:64.64}").is_ok());
+    /// assert!(matches!(
+    ///     Template::parse("{re:.70000}"),
+    ///     Err(TemplateError::TooWide { .. })
+    /// ));
+    /// assert!(Template::parse("{{literal}}").is_ok());
+    /// ```
+    pub fn parse(s: &str) -> Result<Self, TemplateError> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '}' => return Err(TemplateError::Unopened),
+                '{' => {
+                    let rest = chars.as_str();
+                    let end = rest.find('}').ok_or(TemplateError::Unclosed)?;
+                    let (name, spec) = match rest[..end].split_once(':') {
+                        Some((name, spec)) => (name, Some(spec)),
+                        None => (&rest[..end], None),
+                    };
+                    let field = Field::ALL
+                        .iter()
+                        .copied()
+                        .find(|field| field.name() == name)
+                        .ok_or_else(|| TemplateError::Unknown(name.to_owned()))?;
+                    let spec = match spec {
+                        Some(spec) => {
+                            let parsed = Spec::parse(spec)
+                                .filter(|parsed| parsed.allowed(field.kind()))
+                                .ok_or_else(|| TemplateError::Spec {
+                                    field,
+                                    spec: spec.to_owned(),
+                                })?;
+                            // Padding or formatting to a huge width aborts.
+                            if parsed.width > MAX_WIDTH
+                                || parsed
+                                    .precision
+                                    .map_or(false, |precision| precision > MAX_WIDTH)
+                            {
+                                return Err(TemplateError::TooWide {
+                                    field,
+                                    spec: spec.to_owned(),
+                                });
+                            }
+                            parsed
+                        }
+                        None => Spec::default(),
+                    };
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Field(field, spec));
+                    chars = rest[end + 1..].chars();
+                }
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Self { parts })
+    }
+    pub fn fields(&self) -> impl Iterator<Item = Field> + '_ {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Field(field, _) => Some(*field),
+            Part::Text(_) => None,
+        })
+    }
+    pub fn uses(&self, field: Field) -> bool {
+        self.fields().any(|used| used == field)
+    }
+    /// Returns [`TemplateError::Missing`] if a field has no value in `values`.
+    ///
+    /// ```
+    /// # use fractal_renderer::{template::{Template, Values}, Config};
+    /// let mut config = Config::default();
+    /// config.pos.re = -0.5;
+    /// let mut values = Values::new(&config);
+    /// values.frame = Some(7);
+    /// let template = Template::parse("{algo}_{zoom:.0e}_{re:06.2}_{frame:04}").unwrap();
+    /// assert_eq!(template.render(&values).unwrap(), "mandelbrot_1e0_-00.50_0007");
+    /// values.frame = None;
+    /// assert!(template.render(&values).is_err());
+    /// ```
+    pub fn render(&self, values: &Values) -> Result<String, TemplateError> {
+        let config = values.config;
+        let mut s = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => s.push_str(text),
+                Part::Field(field, spec) => {
+                    let missing = || TemplateError::Missing(*field);
+                    let value = match field {
+                        Field::Algo => match (&config.algo, config.julia) {
+                            (Algo::Mandelbrot, None) => "mandelbrot",
+                            (Algo::Mandelbrot, Some(_)) => "julia",
+                            (Algo::BarnsleyFern, _) => "fern",
+                        }
+                        .to_owned(),
+                        Field::Re => spec.number(config.pos.re),
+                        Field::Im => spec.number(config.pos.im),
+                        Field::Zoom => spec.number(zoom(config)),
+                        Field::Frame => spec.pad(values.frame.ok_or_else(missing)?.to_string()),
+                        Field::Name => values.name.ok_or_else(missing)?.to_owned(),
+                        Field::Seed => spec.pad(values.seed.ok_or_else(missing)?.to_string()),
+                        Field::Date => values.date.clone(),
+                    };
+                    s.push_str(&value);
+                }
+            }
+        }
+        Ok(s)
+    }
+}
+
+/// The values of the fields of a [`Template`]. Only the fields of the config are always known.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Values<'a> {
+    pub config: &'a Config,
+    pub frame: Option<u32>,
+    pub name: Option<&'a str>,
+    pub seed: Option<u64>,
+    /// `YYYY-MM-DD`, see [`today`].
+    pub date: String,
+}
+impl<'a> Values<'a> {
+    pub fn new(config: &'a Config) -> Self {
+        Self {
+            config,
+            frame: None,
+            name: None,
+            seed: None,
+            date: today(),
+        }
+    }
+    /// The values of the config and seed of `options`.
+    pub fn from_options(options: &'a Options) -> Self {
+        Self {
+            seed: options.ifs_mutation.as_ref().map(|mutation| mutation.seed),
+            ..Self::new(&options.config)
+        }
+    }
+}
+
+/// The zoom of `config`, relative to the default view, like the caption of
+/// [`crate::annotate`].
+pub fn zoom(config: &Config) -> f64 {
+    config.scale.im / Config::default().scale.im
+}
+
+/// The current day as `YYYY-MM-DD` in UTC.
+pub fn today() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let (year, month, day) = civil_date((seconds / 86_400) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+/// The year, month and day of the `days` since 1970-01-01, from
+/// <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+///
+/// ```
+/// # use fractal_renderer::template::civil_date;
+/// assert_eq!(civil_date(0), (1970, 1, 1));
+/// assert_eq!(civil_date(19_782), (2024, 2, 29));
+/// ```
+pub fn civil_date(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// The text of `--help-template`.
+pub fn help() -> String {
+    let mut help = format!(
+        "The output path can contain fields, like `renders/{{algo}}_{{zoom:.0e}}_{{frame:04}}_{{date}}`.\n\
+         A field is `{{name}}` or `{{name:format}}`, where the format is `[0][width][.precision][e]`:\n\
+         `0` pads with zeros, `e` uses scientific notation. Integers only take a width and text no\n\
+         format. The width and precision are at most {}. Write `{{{{` and `}}}}` for literal braces.\n\n\
+         Fields:\n",
+        MAX_WIDTH
+    );
+    for field in Field::ALL {
+        help.push_str(&format!(
+            "  {:<9}{}\n",
+            field.to_string(),
+            field.description()
+        ));
+    }
+    help
+}
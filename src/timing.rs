@@ -0,0 +1,84 @@
+//! Where the time of a render goes, tile by tile, see `--timing-map`.
+//!
+//! The slowest tiles show whether the time goes to the inside, which iterates to the limit, or to
+//! the filaments around it, and so whether skipping the inside or balancing the load better is
+//! worth it for a scene.
+
+use crate::palette::Palette;
+use crate::{grid, Config, RGB};
+use std::cmp;
+use std::time::{Duration, Instant};
+
+/// When a tile of [`get_samples_timed`](crate::get_samples_timed) was rendered.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileTiming {
+    pub tile: grid::Cell,
+    pub start: Instant,
+    pub end: Instant,
+}
+impl TileTiming {
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+}
+
+/// The `count` slowest of `timings`, the slowest first.
+pub fn slowest(timings: &[TileTiming], count: usize) -> Vec<&TileTiming> {
+    let mut slowest: Vec<&TileTiming> = timings.iter().collect();
+    slowest.sort_by_key(|timing| cmp::Reverse(timing.duration()));
+    slowest.truncate(count);
+    slowest
+}
+
+/// The colors of [`heat_map`]: black for no time, through red and yellow, to white for the
+/// slowest tile.
+pub fn heat_palette() -> Palette {
+    Palette::even([
+        RGB::new(0, 0, 0),
+        RGB::new(255, 0, 0),
+        RGB::new(255, 255, 0),
+        RGB::new(255, 255, 255),
+    ])
+    .expect("the palette has stops")
+}
+
+/// The image of `config` with each tile of `timings` colored by its time, relative to the
+/// slowest tile, with `palette`.
+///
+/// ```
+/// # use fractal_renderer::{get_samples, get_samples_timed, timing, Algo, Config};
+/// let mut config = Config::new(Algo::Mandelbrot);
+/// config.width = 150;
+/// config.height = 100;
+/// let (samples, timings) = get_samples_timed(&config);
+/// assert_eq!(samples, get_samples(&config));
+/// // 3×2 tiles.
+/// assert_eq!(timings.len(), 6);
+///
+/// let palette = timing::heat_palette();
+/// let map = timing::heat_map(&config, &timings, &palette);
+/// assert_eq!(map.len(), 150 * 100);
+/// let slowest = timing::slowest(&timings, 10)[0].tile;
+/// assert_eq!(map[slowest.y * 150 + slowest.x], palette.color_at(1.0));
+/// ```
+pub fn heat_map(config: &Config, timings: &[TileTiming], palette: &Palette) -> Vec<RGB> {
+    let width = config.width as usize;
+    let mut contents = vec![palette.color_at(0.0); width * config.height as usize];
+    let slowest = timings
+        .iter()
+        .map(|timing| timing.duration().as_secs_f64())
+        .fold(0.0, f64::max);
+    for timing in timings {
+        let heat = if slowest > 0.0 {
+            timing.duration().as_secs_f64() / slowest
+        } else {
+            0.0
+        };
+        let color = palette.color_at(heat);
+        let tile = timing.tile;
+        for y in tile.y..tile.y + tile.height {
+            contents[y * width + tile.x..y * width + tile.x + tile.width].fill(color);
+        }
+    }
+    contents
+}
@@ -0,0 +1,191 @@
+//! Render a fixed scene on every backend of this binary and compare it to the CPU `f64`
+//! reference, to tell whether a backend renders wrong on this machine.
+
+use crate::diff::{self, DiffReport};
+use crate::render::{self, RenderError};
+use crate::{Algo, Backend, Config, ImageBuffer, Imaginary, Precision};
+use calc::inner::InnerConfig;
+use std::fmt::Write;
+
+/// The width and height of the scene.
+pub const SIZE: u32 = 256;
+/// Channels which differ by at most this much from the reference are equal.
+pub const TOLERANCE: u8 = 2;
+/// The percentage of pixels which may differ from the reference. Points on the border of the set
+/// can escape or not depending on the rounding, so a few pixels always differ between precisions.
+pub const THRESHOLD: f64 = 0.5;
+
+/// The scene every backend renders: Seahorse valley, with both the inside and the smoothed
+/// outside.
+pub fn scene() -> Config {
+    let mut config = Config::new(Algo::Mandelbrot);
+    config.width = SIZE;
+    config.height = SIZE;
+    config.iterations = 500;
+    config.pos = Imaginary {
+        re: -0.745,
+        im: 0.11,
+    };
+    config.scale = Imaginary { re: 20.0, im: 20.0 };
+    config.precision = Precision::F64;
+    config.backend = Backend::Cpu;
+    config
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Status {
+    /// The reference the others are compared to.
+    Reference,
+    Pass,
+    /// More than [`THRESHOLD`] percent of the pixels differ.
+    Fail,
+    /// The backend isn't compiled into this binary.
+    Unavailable,
+    /// The render failed, with the reason.
+    Error(String),
+}
+impl Status {
+    pub fn is_failure(&self) -> bool {
+        matches!(self, Self::Fail | Self::Error(_))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackendReport {
+    pub name: &'static str,
+    pub status: Status,
+    /// The comparison to the reference, if the backend rendered.
+    pub diff: Option<DiffReport>,
+}
+
+/// Renders [`scene`] on every backend and compares them to the CPU `f64` reference.
+///
+/// The backends are
+/// - `cpu f64`, the reference,
+/// - `cpu double-double`, with [`Precision::DoubleDouble`],
+/// - `gpu layout`, on the CPU with the config read back from the [`InnerConfig`] uploaded to the
+///   GPU, which catches fields lost on the way to the GPU, even without one,
+/// - `gpu`, which is [`Status::Unavailable`] in builds without the GPU renderer.
+pub fn verify_backends() -> Vec<BackendReport> {
+    let reference_config = scene();
+    let reference = match render::render(&reference_config) {
+        Ok(output) => output.image,
+        Err(err) => {
+            return vec![BackendReport {
+                name: "cpu f64",
+                status: Status::Error(err.to_string()),
+                diff: None,
+            }]
+        }
+    };
+    let compare = |name, config: Result<Config, String>| {
+        let image = config.and_then(|config| {
+            render::render(&config)
+                .map(|output| output.image)
+                .map_err(|err| err.to_string())
+        });
+        report(name, &reference, image)
+    };
+
+    let mut double_double = reference_config.clone();
+    double_double.precision = Precision::DoubleDouble;
+    let gpu_layout = InnerConfig::new(&reference_config)
+        .to_config()
+        .map(|mut config| {
+            // The backend isn't part of the layout.
+            config.backend = Backend::Cpu;
+            config
+        })
+        .ok_or_else(|| "the GPU config can't be read back".to_owned());
+    let mut gpu = reference_config.clone();
+    gpu.backend = Backend::Gpu;
+
+    vec![
+        BackendReport {
+            name: "cpu f64",
+            status: Status::Reference,
+            diff: None,
+        },
+        compare("cpu double-double", Ok(double_double)),
+        compare("gpu layout", gpu_layout),
+        match render::render(&gpu) {
+            Err(RenderError::Unavailable(_)) => BackendReport {
+                name: "gpu",
+                status: Status::Unavailable,
+                diff: None,
+            },
+            result => report(
+                "gpu",
+                &reference,
+                result
+                    .map(|output| output.image)
+                    .map_err(|err| err.to_string()),
+            ),
+        },
+    ]
+}
+
+fn report(
+    name: &'static str,
+    reference: &ImageBuffer,
+    image: Result<ImageBuffer, String>,
+) -> BackendReport {
+    let diff = image.and_then(|image| {
+        diff::compare(reference, &image, TOLERANCE).map_err(|err| err.to_string())
+    });
+    match diff {
+        Ok(diff) => BackendReport {
+            name,
+            status: if diff.differing_percent() > THRESHOLD {
+                Status::Fail
+            } else {
+                Status::Pass
+            },
+            diff: Some(diff),
+        },
+        Err(err) => BackendReport {
+            name,
+            status: Status::Error(err),
+            diff: None,
+        },
+    }
+}
+
+/// Whether no backend failed. Unavailable backends don't fail.
+pub fn passed(reports: &[BackendReport]) -> bool {
+    !reports.iter().any(|report| report.status.is_failure())
+}
+
+/// A table of `reports`, with a row per backend.
+pub fn to_table(reports: &[BackendReport]) -> String {
+    let mut table = format!(
+        "{:<20}{:<14}{:<16}{}",
+        "backend", "result", "max r/g/b", "differing"
+    );
+    for report in reports {
+        let status = match &report.status {
+            Status::Reference => "reference",
+            Status::Pass => "pass",
+            Status::Fail => "FAIL",
+            Status::Unavailable => "not compiled",
+            Status::Error(_) => "ERROR",
+        };
+        let (max, differing) = match &report.diff {
+            Some(diff) => (
+                format!("{}/{}/{}", diff.max[0], diff.max[1], diff.max[2]),
+                format!("{:.3}%", diff.differing_percent()),
+            ),
+            None => ("-".to_owned(), "-".to_owned()),
+        };
+        write!(
+            table,
+            "\n{:<20}{:<14}{:<16}{}",
+            report.name, status, max, differing
+        )
+        .expect("writing to a string doesn't fail");
+        if let Status::Error(err) = &report.status {
+            write!(table, "  ({})", err).expect("writing to a string doesn't fail");
+        }
+    }
+    table
+}
@@ -0,0 +1,283 @@
+//! Ways of showing the [`Sample`]s of an escape-time fractal, other than the palette of the
+//! config.
+//!
+//! The raw views show whether detail is lost to [`Config::iterations`] before any colors are
+//! chosen. They only recolor the samples, so switching between them doesn't iterate the image
+//! again.
+
+use crate::palette::{self, Palette};
+use crate::{mapping, Config, Imaginary, Sample, RGB};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+const BLACK: RGB = RGB::new(0, 0, 0);
+const WHITE: RGB = RGB::new(255, 255, 255);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum View {
+    /// The colors of the config, see [`crate::colorize`], or of the palette.
+    Color,
+    /// The smoothed iterations of the escaping points in grayscale, stretched from the fewest
+    /// (black) to the most iterations (white). Points which don't escape are white.
+    Iterations,
+    /// Points which don't escape are white, the others black.
+    InteriorMask,
+}
+impl View {
+    pub const ALL: [Self; 3] = [Self::Color, Self::Iterations, Self::InteriorMask];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Color => "color",
+            Self::Iterations => "iterations",
+            Self::InteriorMask => "interior-mask",
+        }
+    }
+    /// The name shown in the GUI.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Color => "Color",
+            Self::Iterations => "Iterations",
+            Self::InteriorMask => "Interior mask",
+        }
+    }
+}
+impl Default for View {
+    fn default() -> Self {
+        Self::Color
+    }
+}
+impl Display for View {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+impl FromStr for View {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|view| view.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown view {:?}", s))
+    }
+}
+
+/// Whether the samples of `old` are those of `new`, so `new` only recolors them: the configs
+/// only differ in the exposures, colors and what counts as inside, which [`crate::colorize`]
+/// applies to the samples.
+///
+/// ```
+/// # use fractal_renderer::{view::recolors, Algo, Config, RGB};
+/// let old = Config::new(Algo::Mandelbrot);
+/// let mut new = old.clone();
+/// new.exposure *= 2.0;
+/// new.inside = !new.inside;
+/// new.primary_color = RGB::new(255, 0, 0);
+/// assert!(recolors(&old, &new));
+///
+/// new.iterations *= 2;
+/// assert!(!recolors(&old, &new));
+/// ```
+pub fn recolors(old: &Config, new: &Config) -> bool {
+    let recolored = Config {
+        exposure: old.exposure,
+        interior_exposure: old.interior_exposure,
+        interior_mapping: old.interior_mapping,
+        inside: old.inside,
+        stable_limit: old.stable_limit,
+        primary_color: old.primary_color,
+        secondary_color: old.secondary_color,
+        ..new.clone()
+    };
+    recolored == *old
+}
+
+/// The colors of `samples`, iterated with `config`, in `view`.
+///
+/// [`View::Color`] uses `palette` if it's given, and colors the samples in parallel, so
+/// recoloring a large image stays quick.
+pub fn colorize(
+    samples: &[Sample],
+    config: &Config,
+    view: View,
+    palette: Option<&Palette>,
+) -> Vec<RGB> {
+    match view {
+        View::Color => samples
+            .par_iter()
+            .map(|sample| match palette {
+                Some(palette) => palette::colorize(*sample, config, palette),
+                None => crate::colorize(*sample, config),
+            })
+            .collect(),
+        View::Iterations => {
+            // Auto-contrast: only the escaping points, as the others all have the maximum.
+            let (min, max) = samples
+                .iter()
+                .filter(|sample| sample.escaped)
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), sample| {
+                    (min.min(sample.iterations), max.max(sample.iterations))
+                });
+            let range = max - min;
+            samples
+                .iter()
+                .map(|sample| {
+                    if !sample.escaped {
+                        return WHITE;
+                    }
+                    let value = if range > 0.0 {
+                        ((sample.iterations - min) / range * 255.0).round() as u8
+                    } else {
+                        0
+                    };
+                    RGB::new(value, value, value)
+                })
+                .collect()
+        }
+        View::InteriorMask => samples
+            .iter()
+            .map(|sample| if sample.escaped { BLACK } else { WHITE })
+            .collect(),
+    }
+}
+
+/// How the image of one view looks in another, to show until the other is rendered, see
+/// [`approximation`]. The corners are `[min, max]`, relative to the size of the images.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Approximation {
+    /// The part of the rendered image which is in the other view.
+    pub uv: [[f64; 2]; 2],
+    /// Where that part is in the other view.
+    pub placement: [[f64; 2]; 2],
+}
+
+/// How the image of `shown` looks in the view of `target`, if `target` only moves, zooms or
+/// resizes the view. The sides can have any aspect ratio, so the rendered image can be cropped
+/// and have borders in `target`.
+///
+/// [`None`] if anything else changed, like the algorithm or the colors, which scaling the image
+/// can't show, with [`Config::exp_map`], or if none of the image is in view.
+///
+/// ```
+/// # use fractal_renderer::{view::{approximation, Approximation}, Algo, Config};
+/// let shown = Config::new(Algo::Mandelbrot);
+/// let mut target = shown.clone();
+/// target.scale = shown.scale * 2.0;
+/// assert_eq!(
+///     approximation(&shown, &target),
+///     Some(Approximation {
+///         uv: [[0.25, 0.25], [0.75, 0.75]],
+///         placement: [[0.0, 0.0], [1.0, 1.0]],
+///     })
+/// );
+///
+/// // Twice as wide, zoomed out: the image is in the middle, with borders on all sides.
+/// target.scale = shown.scale * 0.5;
+/// target.width = shown.width * 2;
+/// assert_eq!(
+///     approximation(&shown, &target),
+///     Some(Approximation {
+///         uv: [[0.0, 0.0], [1.0, 1.0]],
+///         placement: [[0.375, 0.25], [0.625, 0.75]],
+///     })
+/// );
+///
+/// target.exposure *= 2.0;
+/// assert_eq!(approximation(&shown, &target), None);
+/// ```
+pub fn approximation(shown: &Config, target: &Config) -> Option<Approximation> {
+    let same_colors = Config {
+        pos: shown.pos,
+        pos_low: shown.pos_low,
+        scale: shown.scale,
+        width: shown.width,
+        height: shown.height,
+        ..target.clone()
+    };
+    if same_colors != *shown || shown.exp_map || shown.width == 0 || shown.height == 0 {
+        return None;
+    }
+    // Precise at any zoom, as the centers are close.
+    let moved = Imaginary {
+        re: (target.pos.re - shown.pos.re) + (target.pos_low.re - shown.pos_low.re),
+        im: (target.pos.im - shown.pos.im) + (target.pos_low.im - shown.pos_low.im),
+    };
+    // The point at (`x`, `y`) of `target`, relative to its size, in `shown`.
+    let in_shown = |x: f64, y: f64| {
+        let offset =
+            mapping::pixel_offset(target, x * target.width as f64, y * target.height as f64);
+        let (x, y) = mapping::offset_to_pixel(
+            shown,
+            Imaginary {
+                re: offset.re + moved.re,
+                im: offset.im + moved.im,
+            },
+        );
+        [x / shown.width as f64, y / shown.height as f64]
+    };
+    // Flips and turns are the same in both, so the corners stay corners.
+    let (a, b) = (in_shown(0.0, 0.0), in_shown(1.0, 1.0));
+    let mut uv = [[0.0; 2]; 2];
+    let mut placement = [[0.0; 2]; 2];
+    for axis in 0..2 {
+        let (min, max) = (a[axis].min(b[axis]), a[axis].max(b[axis]));
+        let (from, to) = (min.max(0.0), max.min(1.0));
+        if from >= to {
+            return None;
+        }
+        uv[0][axis] = from;
+        uv[1][axis] = to;
+        let relative = |uv: f64| (uv - min) / (max - min);
+        placement[0][axis] = relative(from);
+        placement[1][axis] = relative(to);
+    }
+    Some(Approximation { uv, placement })
+}
+
+/// The `pixels` of an image of `size` as they look in an image of `target_size`, placed by
+/// `approximation` with the nearest pixels. The rest is `background`.
+///
+/// ```
+/// # use fractal_renderer::view::{approximate_image, Approximation};
+/// let zoomed = Approximation {
+///     uv: [[0.5, 0.0], [1.0, 1.0]],
+///     placement: [[0.0, 0.0], [0.5, 1.0]],
+/// };
+/// let image = [1, 2, 3, 4];
+/// // The right half of the image, zoomed in twice, then the background.
+/// assert_eq!(
+///     approximate_image(&image, [4, 1], &zoomed, [4, 1], 0),
+///     [3, 4, 0, 0]
+/// );
+/// ```
+pub fn approximate_image<T: Copy>(
+    pixels: &[T],
+    size: [usize; 2],
+    approximation: &Approximation,
+    target_size: [usize; 2],
+    background: T,
+) -> Vec<T> {
+    let Approximation { uv, placement } = approximation;
+    // The index in `pixels` along `axis` of the pixel `i` of the target.
+    let source = |axis: usize, i: usize| {
+        let t = (i as f64 + 0.5) / target_size[axis] as f64;
+        let (from, to) = (placement[0][axis], placement[1][axis]);
+        if t < from || t >= to {
+            return None;
+        }
+        let u = uv[0][axis] + (t - from) / (to - from) * (uv[1][axis] - uv[0][axis]);
+        Some(((u * size[axis] as f64) as usize).min(size[axis].saturating_sub(1)))
+    };
+    let columns: Vec<Option<usize>> = (0..target_size[0]).map(|x| source(0, x)).collect();
+    let mut approximated = Vec::with_capacity(target_size[0] * target_size[1]);
+    for y in 0..target_size[1] {
+        let row = source(1, y);
+        approximated.extend(columns.iter().map(|column| match (column, row) {
+            (Some(x), Some(y)) => pixels[y * size[0] + x],
+            _ => background,
+        }));
+    }
+    approximated
+}
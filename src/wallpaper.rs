@@ -0,0 +1,128 @@
+//! Wallpapers larger than the render, composed of copies of it, see [`compose`].
+//!
+//! Only one copy is rendered, so a `2x2` wallpaper renders a quarter of the pixels.
+
+use crate::{ImageBuffer, RGB};
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Every other column and row of copies is flipped, so the edges of neighbouring copies
+    /// match.
+    Mirror,
+    /// The copies are side by side, unchanged.
+    Tile,
+}
+impl Mode {
+    pub const ALL: [Self; 2] = [Self::Mirror, Self::Tile];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Mirror => "mirror",
+            Self::Tile => "tile",
+        }
+    }
+}
+impl Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+impl FromStr for Mode {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|mode| mode.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown wallpaper mode {:?}", s))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WallpaperOptions {
+    pub mode: Mode,
+    pub columns: u32,
+    pub rows: u32,
+    /// The size of the wallpaper. The render is the size of a copy, see [`Self::copy_size`].
+    pub width: u32,
+    pub height: u32,
+}
+impl WallpaperOptions {
+    /// The size of the render, so `columns`×`rows` copies cover the wallpaper. The copies at the
+    /// right and bottom edges are cut off if it doesn't divide evenly.
+    ///
+    /// ```
+    /// # use fractal_renderer::wallpaper::{Mode, WallpaperOptions};
+    /// let wallpaper = WallpaperOptions {
+    ///     mode: Mode::Mirror,
+    ///     columns: 2,
+    ///     rows: 3,
+    ///     width: 3840,
+    ///     height: 2160,
+    /// };
+    /// assert_eq!(wallpaper.copy_size(), (1920, 720));
+    /// let odd = WallpaperOptions { width: 3841, ..wallpaper };
+    /// assert_eq!(odd.copy_size(), (1921, 720));
+    /// ```
+    pub fn copy_size(&self) -> (u32, u32) {
+        let divide = |len: u32, parts: u32| ((len + parts - 1) / parts).max(1);
+        (
+            divide(self.width, self.columns),
+            divide(self.height, self.rows),
+        )
+    }
+}
+
+/// Composes the wallpaper of `options` from copies of `copy`, which should be the size of
+/// [`WallpaperOptions::copy_size`].
+///
+/// ```
+/// # use fractal_renderer::{wallpaper::{compose, Mode, WallpaperOptions}, ImageBuffer, RGB};
+/// let (a, b, c) = (RGB::new(1, 0, 0), RGB::new(2, 0, 0), RGB::new(3, 0, 0));
+/// let copy = ImageBuffer::from_contents(vec![a, b, c, c, c, c], 3, 2).unwrap();
+/// let options = WallpaperOptions {
+///     mode: Mode::Mirror,
+///     columns: 2,
+///     rows: 2,
+///     width: 5,
+///     height: 4,
+/// };
+/// let wallpaper = compose(copy.clone(), &options);
+/// assert_eq!(
+///     wallpaper.contents(),
+///     [
+///         a, b, c, c, b,
+///         c, c, c, c, c,
+///         c, c, c, c, c,
+///         a, b, c, c, b,
+///     ]
+/// );
+///
+/// let tiled = compose(copy, &WallpaperOptions { mode: Mode::Tile, ..options });
+/// assert_eq!(&tiled.contents()[..5], [a, b, c, a, b]);
+/// ```
+pub fn compose(mut copy: ImageBuffer, options: &WallpaperOptions) -> ImageBuffer {
+    let (width, height) = (copy.width(), copy.height());
+    let copy = copy.as_image();
+    let mut wallpaper = ImageBuffer::new(
+        options.width as usize,
+        options.height as usize,
+        RGB::new(0, 0, 0),
+    );
+    let mut image = wallpaper.as_image();
+    let mirrored = options.mode == Mode::Mirror;
+    for row in 0..options.rows as usize {
+        for column in 0..options.columns as usize {
+            image.blit_flipped(
+                &copy,
+                column * width,
+                row * height,
+                mirrored && column % 2 == 1,
+                mirrored && row % 2 == 1,
+            );
+        }
+    }
+    wallpaper
+}
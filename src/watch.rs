@@ -0,0 +1,243 @@
+//! Load a config from a file with `--config`, and render again whenever it changes with
+//! `--watch`, see [`run`].
+//!
+//! The file is a partial [`Config`], with the same keys and shorthands as the tables of a batch
+//! manifest, see [`crate::batch`]. It's applied on top of the config of the command line, so
+//! removing a key from the file goes back to the value of the command line.
+//!
+//! ```toml
+//! julia = "-0.8+0.156i"
+//! iterations = 500
+//! exposure = 4
+//! ```
+
+use crate::{batch, Config};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Display};
+use std::{fs, io};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchOptions {
+    /// The file of `--config`.
+    pub path: String,
+    /// The config of the command line, which the file is applied on top of.
+    pub base: Config,
+}
+
+/// How long the file has to stay the same before rendering, so an editor saving in several
+/// steps renders once.
+#[cfg(feature = "watch")]
+const DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(200);
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    Resolve(batch::ResolveError),
+}
+impl Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "failed to read config: {}", err),
+            Self::Parse(err) => write!(f, "failed to parse config: {}", err),
+            Self::Resolve(err) => write!(f, "invalid config: {}", err),
+        }
+    }
+}
+impl std::error::Error for LoadError {}
+
+/// Applies the config in the file at `path` on top of `base`.
+pub fn load(path: &str, base: &Config) -> Result<Config, LoadError> {
+    let data = fs::read_to_string(path).map_err(LoadError::Io)?;
+    let table = toml::from_str(&data).map_err(LoadError::Parse)?;
+    batch::apply(base, &table).map_err(LoadError::Resolve)
+}
+
+/// The fields which differ between `old` and `new`, like `iterations 50 → 200`. Nested fields
+/// are joined by dots, like `orientation.rotation`.
+///
+/// ```
+/// # use fractal_renderer::{watch::changes, Algo, Config, Imaginary};
+/// let old = Config::new(Algo::Mandelbrot);
+/// let mut new = old.clone();
+/// assert!(changes(&old, &new).is_empty());
+///
+/// new.iterations = 200;
+/// new.julia = Some(Imaginary { re: 0.0, im: 0.5 });
+/// assert_eq!(
+///     changes(&old, &new),
+///     ["iterations 50 → 200", "julia.im → 0.5", "julia.re → 0.0"]
+/// );
+/// ```
+pub fn changes(old: &Config, new: &Config) -> Vec<String> {
+    let fields = |config: &Config| {
+        let mut fields = BTreeMap::new();
+        let value = toml::Value::try_from(config).expect("a config is always serializable to TOML");
+        flatten(&mut fields, "", value);
+        fields
+    };
+    let (old, new) = (fields(old), fields(new));
+    let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    keys.into_iter()
+        .filter_map(|key| match (old.get(key), new.get(key)) {
+            (old, new) if old == new => None,
+            (Some(old), Some(new)) => Some(format!("{} {} → {}", key, old, new)),
+            (None, Some(new)) => Some(format!("{} → {}", key, new)),
+            (Some(old), None) => Some(format!("{} {} → unset", key, old)),
+            (None, None) => None,
+        })
+        .collect()
+}
+fn flatten(fields: &mut BTreeMap<String, toml::Value>, prefix: &str, value: toml::Value) {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table {
+                let key = if prefix.is_empty() {
+                    key
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten(fields, &key, value);
+            }
+        }
+        value => {
+            fields.insert(prefix.to_owned(), value);
+        }
+    }
+}
+
+/// Renders `options`, with the post-processing and overlays, and writes it.
+#[cfg(feature = "watch")]
+fn render(options: &crate::Options) -> io::Result<String> {
+    let config = &options.config;
+    let start = std::time::Instant::now();
+    let mut contents = match &options.palette {
+        Some(palette) => crate::view::colorize(
+            &crate::get_samples(config),
+            config,
+            crate::view::View::Color,
+            Some(palette),
+        ),
+        None => match options.seed() {
+            Some(seed) => crate::render::render_seeded(config, seed),
+            None => crate::render(config),
+        }
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))?
+        .image
+        .into_contents(),
+    };
+    let (width, height) = (config.width as usize, config.height as usize);
+    crate::post::apply(&mut contents, width, height, &options.post);
+    crate::draw_overlays(
+        options,
+        &mut crate::Image::new(&mut contents, width, height),
+    );
+    if let Some(cvd) = options.simulate_cvd {
+        crate::color::simulate_image(&mut contents, cvd);
+    }
+    let path = crate::write_image(options, contents)?;
+    log::info!(
+        "Rendered {}×{} to {:?} in {:.2?}.",
+        config.width,
+        config.height,
+        path,
+        start.elapsed()
+    );
+    Ok(path)
+}
+
+#[cfg(feature = "watch")]
+enum Message {
+    Changed(notify::Result<notify::Event>),
+    Stop,
+}
+
+/// Renders `options`, then again whenever the file of `watch` changes, until Ctrl-C is pressed.
+///
+/// The output is overwritten atomically, so it keeps the last successful render if the file is
+/// invalid or the render fails.
+#[cfg(feature = "watch")]
+pub fn run(options: &crate::Options, watch: &WatchOptions) -> io::Result<()> {
+    use notify::Watcher;
+    use std::path::Path;
+    use std::sync::mpsc;
+
+    let to_io = |err: notify::Error| io::Error::new(io::ErrorKind::Other, err.to_string());
+    let (tx, rx) = mpsc::channel();
+    let events = tx.clone();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        // The receiver is only gone when stopping.
+        let _ = events.send(Message::Changed(event));
+    })
+    .map_err(to_io)?;
+    // Editors often replace the file instead of writing to it, which ends a watch of the file.
+    let path = Path::new(&watch.path);
+    let directory = match path.parent() {
+        Some(parent) if parent != Path::new("") => parent,
+        _ => Path::new("."),
+    };
+    watcher
+        .watch(directory, notify::RecursiveMode::NonRecursive)
+        .map_err(to_io)?;
+    ctrlc::set_handler(move || {
+        let _ = tx.send(Message::Stop);
+    })
+    .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))?;
+
+    let mut options = options.clone();
+    options.output.on_conflict = crate::format::OnConflict::Overwrite;
+    if let Err(err) = render(&options) {
+        log::error!("Failed to render: {}", err);
+    }
+    log::info!("Watching {:?}; press Ctrl-C to stop.", path);
+
+    let concerns_file = |event: &notify::Event| {
+        matches!(
+            event.kind,
+            notify::EventKind::Create(_) | notify::EventKind::Modify(_)
+        ) && event
+            .paths
+            .iter()
+            .any(|changed| changed.file_name() == path.file_name())
+    };
+    while let Ok(message) = rx.recv() {
+        match message {
+            Message::Stop => break,
+            Message::Changed(Ok(event)) if concerns_file(&event) => {}
+            Message::Changed(Ok(_)) => continue,
+            Message::Changed(Err(err)) => {
+                log::warn!("Failed to watch {:?}: {}", path, err);
+                continue;
+            }
+        }
+        loop {
+            match rx.recv_timeout(DEBOUNCE) {
+                Ok(Message::Stop) | Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    log::info!("Stopped watching.");
+                    return Ok(());
+                }
+                Ok(Message::Changed(_)) => {}
+                Err(mpsc::RecvTimeoutError::Timeout) => break,
+            }
+        }
+        let config = match load(&watch.path, &watch.base) {
+            Ok(config) => config,
+            Err(err) => {
+                log::error!("{}; keeping the last render.", err);
+                continue;
+            }
+        };
+        let changes = changes(&options.config, &config);
+        if changes.is_empty() {
+            log::info!("Nothing changed.");
+            continue;
+        }
+        log::info!("Changed {}.", changes.join(", "));
+        options.config = config;
+        if let Err(err) = render(&options) {
+            log::error!("Failed to render: {}; keeping the last render.", err);
+        }
+    }
+    log::info!("Stopped watching.");
+    Ok(())
+}
@@ -0,0 +1,54 @@
+//! `--aspect` computes the missing side from the one given.
+
+use fractal_renderer::OptionsError;
+
+mod common;
+use common::options;
+
+fn size(args: &[&str]) -> (u32, u32) {
+    let config = options(args).expect("the options are valid").config;
+    (config.width, config.height)
+}
+
+#[test]
+fn from_the_width() {
+    assert_eq!(size(&["--aspect", "16:9"]), (750, 422));
+    assert_eq!(size(&["1920", "--aspect", "16:9"]), (1920, 1080));
+    assert_eq!(size(&["-W", "1920", "--aspect", "16:9"]), (1920, 1080));
+}
+
+#[test]
+fn from_the_height() {
+    assert_eq!(size(&["-H", "1080", "--aspect", "16:9"]), (1920, 1080));
+    assert_eq!(size(&["-H", "64", "--aspect", "1:4"]), (16, 64));
+}
+
+#[test]
+fn derived_sides_are_at_least_the_minimum() {
+    for args in [
+        &["-H", "60", "--aspect", "1:4"][..],
+        &["100", "--aspect", "16:1"],
+    ] {
+        match options(args) {
+            Err(OptionsError::Conflict(message)) => {
+                assert!(message.contains("at least 16 pixels"), "{}", message)
+            }
+            other => panic!("{:?} parsed to {:?}", args, other),
+        }
+    }
+}
+
+#[test]
+fn not_with_both_sides() {
+    for args in [
+        &["1920", "1080", "--aspect", "16:9"][..],
+        &["-W", "1920", "-H", "1080", "--aspect", "16:9"],
+    ] {
+        match options(args) {
+            Err(OptionsError::Conflict(message)) => {
+                assert!(message.contains("both width and height"), "{}", message)
+            }
+            other => panic!("{:?} parsed to {:?}", args, other),
+        }
+    }
+}
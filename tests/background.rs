@@ -0,0 +1,166 @@
+//! `Config::background` is what the scatter-style algorithms plot on, while the escape-time
+//! fractals color every pixel and ignore it.
+
+use fractal_renderer::buddhabrot::{self, BuddhabrotOptions};
+use fractal_renderer::{
+    get_image, get_image_seeded, get_julia_boundary_image, sampler, Algo, Background, Config,
+    Imaginary, CHECKERBOARD_SQUARE, RGB,
+};
+
+const NAVY: RGB = RGB::new(0, 0, 64);
+const BACKGROUNDS: [Background; 4] = [
+    Background::Auto,
+    Background::Solid(NAVY),
+    Background::Transparent,
+    Background::Checkerboard,
+];
+
+fn with_background(config: &Config, background: Background) -> Config {
+    Config {
+        background,
+        ..config.clone()
+    }
+}
+
+fn fern(normalize: bool) -> Config {
+    let mut config = Config::new(Algo::BarnsleyFern);
+    config.width = 64;
+    config.height = 64;
+    config.iterations = 20_000;
+    config.fern_normalize = normalize;
+    config
+}
+
+/// The top left corner, where the fern doesn't plot anything.
+fn corner(image: &[RGB], width: u32) -> Vec<RGB> {
+    let side = CHECKERBOARD_SQUARE * 2;
+    (0..side)
+        .flat_map(|y| (0..side).map(move |x| image[(y * width + x) as usize]))
+        .collect()
+}
+fn background_of(config: &Config, auto: RGB) -> Vec<RGB> {
+    let side = CHECKERBOARD_SQUARE * 2;
+    (0..side)
+        .flat_map(|y| (0..side).map(move |x| config.background.color(x, y, auto)))
+        .collect()
+}
+
+#[test]
+fn escape_time_ignores_the_background() {
+    let mut mandelbrot = Config::new(Algo::Mandelbrot);
+    mandelbrot.width = 48;
+    mandelbrot.height = 32;
+    let julia = Config::new_julia(
+        Algo::Mandelbrot,
+        Imaginary {
+            re: -0.8,
+            im: 0.156,
+        },
+    );
+    let julia = Config {
+        width: 48,
+        height: 32,
+        ..julia
+    };
+    for config in [mandelbrot, julia] {
+        let image = get_image(&config);
+        for background in BACKGROUNDS {
+            assert_eq!(
+                get_image(&with_background(&config, background)),
+                image,
+                "{:?} on {:?}",
+                config.algo,
+                background
+            );
+        }
+    }
+}
+
+#[test]
+fn fern_is_plotted_on_the_background() {
+    for normalize in [false, true] {
+        let config = fern(normalize);
+        let auto = get_image_seeded(&config, 3);
+        assert!(corner(&auto, config.width)
+            .iter()
+            .all(|pixel| *pixel == config.secondary_color));
+        for background in BACKGROUNDS {
+            let config = with_background(&config, background);
+            let image = get_image_seeded(&config, 3);
+            assert_eq!(
+                corner(&image, config.width),
+                background_of(&config, config.secondary_color),
+                "{:?}, normalized: {}",
+                background,
+                normalize
+            );
+            // The fern is still there.
+            let plotted = image
+                .iter()
+                .enumerate()
+                .filter(|(i, pixel)| {
+                    let (x, y) = (*i as u32 % config.width, *i as u32 / config.width);
+                    **pixel != config.background.color(x, y, config.secondary_color)
+                })
+                .count();
+            assert!(plotted > 100, "{:?}, normalized: {}", background, normalize);
+        }
+    }
+}
+
+#[test]
+fn julia_boundary_is_plotted_on_the_background() {
+    let mut config = Config::new_julia(
+        Algo::Mandelbrot,
+        Imaginary {
+            re: -0.8,
+            im: 0.156,
+        },
+    );
+    config.width = 64;
+    config.height = 48;
+    let auto = get_julia_boundary_image(&config, Some(1));
+    let config = with_background(&config, Background::Solid(NAVY));
+    let image = get_julia_boundary_image(&config, Some(1));
+    let mut plotted = 0;
+    for (pixel, auto) in image.iter().zip(&auto) {
+        if *auto == config.secondary_color {
+            assert_eq!(*pixel, NAVY);
+        } else {
+            plotted += 1;
+        }
+    }
+    assert!(plotted > 100);
+}
+
+#[test]
+fn buddhabrot_glows_on_the_background() {
+    let mut config = Config::new(Algo::Mandelbrot);
+    config.width = 64;
+    config.height = 64;
+    config.iterations = 200;
+    let options = BuddhabrotOptions {
+        mode: buddhabrot::Mode::Normal,
+        samples: 4,
+        nebula_iterations: [200, 100, 50],
+        sampler: sampler::Kind::Halton,
+        importance: false,
+    };
+    let black = RGB::new(0, 0, 0);
+    let glow = buddhabrot::render(&config, &options, Some(5));
+    assert!(glow.contains(&black));
+    for background in BACKGROUNDS {
+        let config = with_background(&config, background);
+        let image = buddhabrot::render(&config, &options, Some(5));
+        for (i, (pixel, glow)) in image.iter().zip(&glow).enumerate() {
+            let (x, y) = (i as u32 % config.width, i as u32 / config.width);
+            let under = config.background.color(x, y, black);
+            if *glow == black {
+                assert_eq!(*pixel, under, "{:?} at {}", background, i);
+            } else {
+                // The glow lightens the background.
+                assert!(pixel.g >= under.g.max(glow.g), "{:?} at {}", background, i);
+            }
+        }
+    }
+}
@@ -0,0 +1,44 @@
+//! Batch jobs are checked like renders on the command line, and an invalid one fails alone.
+
+use fractal_renderer::batch;
+
+mod common;
+
+#[test]
+fn invalid_jobs_fail_alone() {
+    let dir = std::env::temp_dir().join(format!("fractal-renderer-batch-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let manifest = dir.join("jobs.toml");
+    std::fs::write(
+        &manifest,
+        "[[job]]\nname = \"narrow\"\nwidth = 3\n\n\
+         [[job]]\nname = \"inverted\"\nstable_limit = 10.0\nlimit = 2.0\n\n\
+         [[job]]\nname = \"fine\"\n",
+    )
+    .unwrap();
+    let output = dir.join("{name}");
+    let options = common::options(&[
+        "32",
+        "32",
+        "--batch",
+        manifest.to_str().unwrap(),
+        "-o",
+        output.to_str().unwrap(),
+        "--format",
+        "ppm",
+    ])
+    .expect("the options are valid");
+
+    let succeeded = batch::run(&options, options.batch.as_ref().unwrap());
+    assert!(!succeeded);
+    assert!(!dir.join("narrow.ppm").exists());
+    assert!(!dir.join("inverted.ppm").exists());
+    assert!(dir.join("fine.ppm").exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn parallel_jobs_are_positive() {
+    let err = common::matches(&["--batch", "jobs.toml", "--batch-parallel", "0"]).unwrap_err();
+    assert_eq!(err.kind(), clap::ErrorKind::ValueValidation);
+}
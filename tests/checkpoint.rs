@@ -0,0 +1,42 @@
+//! A checkpointed render is the same image as one rendered at once, also when it's resumed.
+
+use fractal_renderer::checkpoint::{self, CheckpointOptions};
+use fractal_renderer::{digest::SEED, get_image_seeded, Algo, Config};
+use std::time::Duration;
+
+fn assert_same_as_without_checkpoints(config: &Config, name: &str) {
+    let path = std::env::temp_dir().join(format!(
+        "fractal-renderer-checkpoint-{}-{}",
+        std::process::id(),
+        name
+    ));
+    let mut options = CheckpointOptions {
+        path,
+        interval: Duration::ZERO,
+        resume: false,
+    };
+    let expected = get_image_seeded(config, SEED);
+    let rendered = checkpoint::render(config, &options, Some(SEED)).unwrap();
+    assert!(rendered == expected, "{} differs from the render", name);
+    // The last checkpoint has everything plotted.
+    options.resume = true;
+    let resumed = checkpoint::render(config, &options, Some(SEED)).unwrap();
+    assert!(resumed == expected, "{} differs when resumed", name);
+    checkpoint::remove(&options).unwrap();
+}
+
+#[test]
+fn same_as_without_checkpoints() {
+    let mut mandelbrot = Config::new(Algo::Mandelbrot);
+    mandelbrot.width = 64;
+    mandelbrot.height = 48;
+    assert_same_as_without_checkpoints(&mandelbrot, "mandelbrot");
+
+    let mut fern = Config::new(Algo::BarnsleyFern);
+    fern.width = 60;
+    fern.height = 80;
+    fern.iterations = 100_000;
+    assert_same_as_without_checkpoints(&fern, "fern");
+    fern.fern_normalize = true;
+    assert_same_as_without_checkpoints(&fern, "normalized-fern");
+}
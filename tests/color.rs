@@ -0,0 +1,85 @@
+//! Colors go from a hex string through `RGB` to the encoded pixel bytes with their channels in
+//! order. `RGB::new` used to take `(r, b, g)`, which swapped green and blue in every color given
+//! on the command line and in the defaults; the fern was rendered blue rather than green.
+
+use fractal_renderer::{
+    diff, encode_image,
+    format::{EncoderSettings, Format},
+    get_image_seeded, parse, Algo, Config, ImageBuffer, RGB,
+};
+
+const COLORS: [(&str, [u8; 3]); 5] = [
+    ("ff8000", [0xff, 0x80, 0x00]),
+    ("#00ff80", [0x00, 0xff, 0x80]),
+    ("8000ff", [0x80, 0x00, 0xff]),
+    ("#123456", [0x12, 0x34, 0x56]),
+    ("F0AA00", [0xf0, 0xaa, 0x00]),
+];
+
+fn encode(color: RGB, format: Format) -> Vec<u8> {
+    let image = ImageBuffer::new(16, 16, color);
+    encode_image(&image, format, &EncoderSettings::default()).unwrap()
+}
+
+#[test]
+fn hex_to_ppm_bytes() {
+    for (hex, bytes) in COLORS {
+        let color = parse::color(hex).unwrap();
+        assert_eq!([color.r, color.g, color.b], bytes, "{}", hex);
+        let data = encode(color, Format::Ppm);
+        let pixels = data.strip_prefix(b"P6\n16 16\n255\n".as_ref()).unwrap();
+        assert_eq!(pixels.len(), 16 * 16 * 3);
+        assert!(
+            pixels.chunks_exact(3).all(|pixel| pixel == bytes),
+            "{}",
+            hex
+        );
+    }
+}
+
+#[cfg(feature = "png")]
+#[test]
+fn hex_to_png_pixels() {
+    for (hex, bytes) in COLORS {
+        let data = encode(parse::color(hex).unwrap(), Format::Png);
+        let image = diff::decode(&data).unwrap();
+        assert!(
+            image
+                .contents()
+                .iter()
+                .all(|pixel| [pixel.r, pixel.g, pixel.b] == bytes),
+            "{}",
+            hex
+        );
+    }
+}
+
+#[test]
+fn ppm_round_trips() {
+    for (hex, _) in COLORS {
+        let color = parse::color(hex).unwrap();
+        let image = diff::decode(&encode(color, Format::Ppm)).unwrap();
+        assert!(
+            image.contents().iter().all(|pixel| *pixel == color),
+            "{}",
+            hex
+        );
+    }
+}
+
+#[test]
+fn fern_is_green() {
+    let mut config = Config::new(Algo::BarnsleyFern);
+    config.width = 120;
+    config.height = 80;
+    config.iterations = 20_000;
+    let image = get_image_seeded(&config, 1);
+    let plotted: Vec<_> = image
+        .iter()
+        .filter(|pixel| **pixel != config.secondary_color)
+        .collect();
+    assert!(!plotted.is_empty());
+    assert!(plotted
+        .iter()
+        .all(|pixel| pixel.g > pixel.r && pixel.g > pixel.b));
+}
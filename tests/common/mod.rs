@@ -0,0 +1,18 @@
+//! Helpers shared by the integration tests.
+
+// Every test crate includes this, but not every one uses all of it.
+#![allow(dead_code)]
+
+use clap::ArgMatches;
+use fractal_renderer::{build_cli, options_from_matches, Options, OptionsError};
+
+/// The matches of the command line `args`, without the name of the program.
+pub fn matches(args: &[&str]) -> clap::Result<ArgMatches> {
+    build_cli()
+        .try_get_matches_from(std::iter::once("fractal-renderer").chain(args.iter().copied()))
+}
+
+/// The [`Options`] of the command line `args`, which clap must accept.
+pub fn options(args: &[&str]) -> Result<Options, OptionsError> {
+    options_from_matches(&matches(args).expect("the arguments are valid"))
+}
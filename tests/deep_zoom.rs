@@ -0,0 +1,104 @@
+//! A render at a zoom of 1e20, which `f64` can't resolve, shows structure in double-double
+//! precision, and matches a shallow reference render.
+//!
+//! The view is centered on `β = φ`, the repelling fixed point of the basilica Julia set
+//! `z² - 1`. Near it, the Julia set is self-similar: the map multiplies the distance to `β` by
+//! `λ = 2β`, so a point `k` iterations from escaping at a zoom `Z` is where the view at a zoom
+//! `Z / λ^k` has a point escaping at once.
+
+use fractal_renderer::{get_samples, Algo, Config, Imaginary, Precision, Sample};
+
+const ZOOM: f64 = 1e20;
+const SIZE: u32 = 64;
+
+fn deep() -> Config {
+    let mut config = Config::new_julia(Algo::Mandelbrot, Imaginary { re: -1.0, im: 0.0 });
+    // The golden ratio in double-double precision.
+    config.pos = Imaginary {
+        re: 1.618033988749895,
+        im: 0.0,
+    };
+    config.pos_low = Imaginary {
+        re: -5.432115203682506e-17,
+        im: 0.0,
+    };
+    config.scale = Imaginary { re: ZOOM, im: ZOOM };
+    config.width = SIZE;
+    config.height = SIZE;
+    config.iterations = 1000;
+    config
+}
+
+/// The share of horizontally adjacent escaping pixels with exactly the same iterations.
+fn equal_neighbors(samples: &[Sample]) -> f64 {
+    let width = SIZE as usize;
+    let (mut equal, mut pairs) = (0, 0);
+    for row in samples.chunks(width) {
+        for pair in row.windows(2) {
+            if pair[0].escaped && pair[1].escaped {
+                pairs += 1;
+                equal += (pair[0].iterations == pair[1].iterations) as u32;
+            }
+        }
+    }
+    assert!(pairs > 0, "nothing escaped");
+    equal as f64 / pairs as f64
+}
+
+#[test]
+fn double_double_shows_structure() {
+    let config = deep();
+    assert!(config.uses_double_double());
+    let samples = get_samples(&config);
+    assert!(samples.iter().any(|sample| !sample.escaped));
+    assert!(
+        equal_neighbors(&samples) < 0.01,
+        "{}",
+        equal_neighbors(&samples)
+    );
+
+    // f64 can't tell the pixels of a row apart.
+    let blocks = get_samples(&Config {
+        precision: Precision::F64,
+        ..config
+    });
+    assert!(
+        equal_neighbors(&blocks) > 0.99,
+        "{}",
+        equal_neighbors(&blocks)
+    );
+}
+
+#[test]
+fn matches_the_shallow_reference() {
+    let config = deep();
+    let k = 28;
+    let lambda = 1.0 + 5_f64.sqrt();
+    let scale = ZOOM / lambda.powi(k as i32);
+    let reference = Config {
+        scale: Imaginary {
+            re: scale,
+            im: scale,
+        },
+        iterations: config.iterations - k,
+        precision: Precision::F64,
+        ..config.clone()
+    };
+    assert!(!reference.uses_double_double());
+
+    let deep = get_samples(&config);
+    let reference = get_samples(&reference);
+    for (i, (deep, reference)) in deep.iter().zip(&reference).enumerate() {
+        assert_eq!(deep.escaped, reference.escaped, "pixel {}", i);
+        if deep.escaped {
+            let difference = deep.iterations - (reference.iterations + k as f64);
+            assert!(
+                difference.abs() < 0.01,
+                "pixel {} took {} iterations, but {} in the reference",
+                i,
+                deep.iterations,
+                reference.iterations
+            );
+        }
+    }
+}
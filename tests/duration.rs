@@ -0,0 +1,83 @@
+//! `parse::duration` parses random durations in every unit to the number of seconds they are,
+//! and answers an error instead of panicking for any other input, however large.
+
+use fractal_renderer::parse::{duration, ParseError};
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+use std::time::Duration;
+
+const CASES: usize = 2000;
+const UNITS: [(&str, f64); 4] = [("", 1.0), ("s", 1.0), ("m", 60.0), ("h", 3600.0)];
+
+#[test]
+fn durations_parse_to_their_seconds() {
+    let mut rng = SmallRng::seed_from_u64(172);
+    for _ in 0..CASES {
+        let (unit, multiplier) = UNITS[rng.gen_range(0..UNITS.len())];
+        let n: f64 = if rng.gen() {
+            rng.gen_range(0..100_000) as f64
+        } else {
+            rng.gen_range(0.0..1e6)
+        };
+        let s = format!("{}{}", n, unit);
+        let parsed = duration(&s).unwrap().as_secs_f64();
+        let expected = n * multiplier;
+        assert!(
+            (parsed - expected).abs() <= 1e-9 + expected * 1e-12,
+            "{} parsed to {}s",
+            s,
+            parsed
+        );
+    }
+}
+
+#[test]
+fn huge_durations_are_errors() {
+    let mut rng = SmallRng::seed_from_u64(172);
+    for _ in 0..CASES {
+        let (unit, multiplier) = UNITS[rng.gen_range(0..UNITS.len())];
+        let n = 10_f64.powf(rng.gen_range(15.0..308.0));
+        let s = format!("{:e}{}", n, unit);
+        match duration(&s) {
+            Ok(parsed) => assert!(
+                n * multiplier < 2_f64.powi(64),
+                "{} parsed to {:?}",
+                s,
+                parsed
+            ),
+            Err(ParseError::Range { .. }) => assert!(n * multiplier >= 2_f64.powi(64), "{}", s),
+            Err(err) => panic!("{}: {}", s, err),
+        }
+    }
+    for s in [
+        "1e309",
+        "inf",
+        "infh",
+        "NaN",
+        "1.8446744073709552e19",
+        "1e300h",
+    ] {
+        assert!(duration(s).is_err(), "{}", s);
+    }
+    // The largest `f64` below 2^64.
+    assert_eq!(
+        duration("18446744073709549568"),
+        Ok(Duration::from_secs(18_446_744_073_709_549_568))
+    );
+}
+
+#[test]
+fn garbage_is_an_error() {
+    let mut rng = SmallRng::seed_from_u64(172);
+    let alphabet: Vec<char> = "0123456789.-+eEsmhinfNa ,:".chars().collect();
+    for _ in 0..CASES {
+        let length = rng.gen_range(0..12);
+        let s: String = (0..length)
+            .map(|_| alphabet[rng.gen_range(0..alphabet.len())])
+            .collect();
+        // Only shouldn't panic: some of these are durations.
+        if let Ok(parsed) = duration(&s) {
+            assert!(parsed.as_secs_f64().is_finite(), "{}", s);
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! Checks the capabilities this build reports against the features it was built with, for
+//! whichever features the tests run with. `cargo xtask feature-matrix` runs these for every
+//! combination.
+
+use fractal_renderer::features::{self, Feature};
+use fractal_renderer::format::Format;
+use fractal_renderer::{
+    get_image, render, validate, Algo, Backend, Config, Imaginary, OptionsError, RenderError,
+};
+
+mod common;
+
+fn validated(args: &[&str]) -> Result<(), OptionsError> {
+    validate(&common::options(args)?)
+}
+
+#[test]
+fn reports_the_enabled_features() {
+    let expected = [
+        (Feature::Avif, cfg!(feature = "avif")),
+        (Feature::Png, cfg!(feature = "png")),
+        (Feature::Gui, cfg!(feature = "gui")),
+        (Feature::ImageInterop, cfg!(feature = "image-interop")),
+        (Feature::Sysinfo, cfg!(feature = "sysinfo")),
+        (Feature::Serve, cfg!(feature = "serve")),
+        (Feature::Watch, cfg!(feature = "watch")),
+        (Feature::Ffi, cfg!(feature = "ffi")),
+    ];
+    assert_eq!(expected.len(), Feature::ALL.len());
+    for (feature, enabled) in expected {
+        assert_eq!(feature.is_enabled(), enabled, "{}", feature);
+        assert_eq!(feature.name().parse(), Ok(feature));
+    }
+    assert!(features::enabled().all(Feature::is_enabled));
+}
+
+#[test]
+fn default_format_is_writable() {
+    assert!(Format::preferred().is_supported());
+    assert!(validated(&["--dry-run"]).is_ok());
+    // The output isn't written with --dry-run, so write to a path without an extension.
+    let options = common::options(&["-o", "output"]).unwrap();
+    assert_eq!(options.output.format, Format::preferred());
+    assert!(validate(&options).is_ok());
+}
+
+#[test]
+fn formats_follow_their_features() {
+    for format in Format::ALL {
+        let output = format!("output.{}", format);
+        let result = validated(&["-o", &output]);
+        if format.is_supported() {
+            assert!(result.is_ok(), "{}: {:?}", format, result);
+        } else {
+            assert!(
+                matches!(result, Err(OptionsError::Unsupported(_))),
+                "{}: {:?}",
+                format,
+                result
+            );
+        }
+    }
+}
+
+#[test]
+fn missing_features_are_unsupported() {
+    let config = std::env::temp_dir().join("fractal-renderer-feature-matrix.toml");
+    std::fs::write(&config, "iterations = 100\n").unwrap();
+    let config = config.to_str().unwrap();
+    let cases: [(&[&str], Feature); 3] = [
+        (&["--gui"], Feature::Gui),
+        (&["--serve", "127.0.0.1:0"], Feature::Serve),
+        (&["--config", config, "--watch"], Feature::Watch),
+    ];
+    for (args, feature) in cases {
+        if feature.is_enabled() {
+            continue;
+        }
+        match validated(args) {
+            Err(OptionsError::Unsupported(reason)) => {
+                assert!(reason.contains(feature.name()), "{}", reason)
+            }
+            result => panic!("{:?} without the {} feature: {:?}", args, feature, result),
+        }
+    }
+}
+
+#[test]
+fn every_algorithm_renders() {
+    let julia = Config::new_julia(Algo::Mandelbrot, Imaginary::ZERO);
+    for mut config in [
+        Config::new(Algo::Mandelbrot),
+        julia,
+        Config::new(Algo::BarnsleyFern),
+    ] {
+        config.width = 32;
+        config.height = 16;
+        config.iterations = 100;
+        let name = (&config.algo, config.julia);
+        assert_eq!(get_image(&config).len(), 32 * 16, "{:?}", name);
+        let image = render(&config).unwrap().image;
+        assert_eq!((image.width(), image.height()), (32, 16), "{:?}", name);
+    }
+}
+
+#[test]
+fn gpu_backend_is_a_typed_error() {
+    let config = Config {
+        backend: Backend::Gpu,
+        ..Config::new(Algo::Mandelbrot)
+    };
+    assert!(matches!(
+        render(&config),
+        Err(RenderError::Unavailable(Backend::Gpu))
+    ));
+    assert!(matches!(
+        validated(&["--backend", "gpu"]),
+        Err(OptionsError::Unsupported(_))
+    ));
+}
@@ -0,0 +1,119 @@
+//! The fern is framed from the bounding box of its attractor: at the defaults it's centered and
+//! fills the height of the image but for a margin, without being stretched, and
+//! `Config::pos`/`Config::scale` pan and zoom from there.
+//!
+//! These pin down the default framing, as there are no reference images of the fern.
+
+use fractal_renderer::{fern_bounds, get_image_seeded, Algo, Config, Imaginary};
+
+fn fern() -> Config {
+    let mut config = Config::new(Algo::BarnsleyFern);
+    config.width = 750;
+    config.height = 500;
+    config.iterations = 200_000;
+    config
+}
+
+/// The left, top, right and bottom (inclusive) of the plotted pixels.
+fn plotted(config: &Config) -> [f64; 4] {
+    let image = get_image_seeded(config, 1);
+    let width = config.width as usize;
+    let mut bounds = [f64::MAX, f64::MAX, f64::MIN, f64::MIN];
+    for (i, _) in image
+        .iter()
+        .enumerate()
+        .filter(|(_, pixel)| **pixel != config.secondary_color)
+    {
+        let (x, y) = ((i % width) as f64, (i / width) as f64);
+        bounds = [
+            bounds[0].min(x),
+            bounds[1].min(y),
+            bounds[2].max(x),
+            bounds[3].max(y),
+        ];
+    }
+    bounds
+}
+
+fn assert_near(value: f64, expected: f64, tolerance: f64, what: &str) {
+    assert!(
+        (value - expected).abs() <= tolerance,
+        "{}: {} isn't within {} of {}",
+        what,
+        value,
+        tolerance,
+        expected
+    );
+}
+
+#[test]
+fn default_framing() {
+    let config = fern();
+    let [left, top, right, bottom] = plotted(&config);
+    let (min, max) = fern_bounds();
+    // The attractor is 10% smaller than the image vertically.
+    let height = config.height as f64 / 1.1;
+    assert_near(bottom - top + 1.0, height, 4.0, "height");
+    assert_near(
+        (top + bottom) / 2.0,
+        config.height as f64 / 2.0,
+        2.0,
+        "vertical center",
+    );
+    assert_near(
+        (left + right) / 2.0,
+        config.width as f64 / 2.0,
+        2.0,
+        "horizontal center",
+    );
+    // The same number of pixels per unit on both axes.
+    let aspect = (max.re - min.re) / (max.im - min.im);
+    assert_near(
+        (right - left + 1.0) / (bottom - top + 1.0),
+        aspect,
+        0.02,
+        "aspect ratio",
+    );
+}
+
+#[test]
+fn pos_pans_and_scale_zooms() {
+    let config = fern();
+    let [left, top, right, bottom] = plotted(&config);
+
+    // `pos` is in units of `1 / scale` image heights, like for the other algorithms.
+    let panned = Config {
+        pos: Imaginary { re: 0.1, im: 0.05 },
+        ..config.clone()
+    };
+    let shift = (
+        0.1 * config.height as f64 * config.scale.re,
+        0.05 * config.height as f64 * config.scale.im,
+    );
+    let [panned_left, panned_top, panned_right, panned_bottom] = plotted(&panned);
+    assert_near(panned_left, left - shift.0, 1.0, "left after panning");
+    assert_near(panned_right, right - shift.0, 1.0, "right after panning");
+    assert_near(panned_top, top - shift.1, 1.0, "top after panning");
+    assert_near(panned_bottom, bottom - shift.1, 1.0, "bottom after panning");
+
+    let zoomed = Config {
+        scale: Imaginary {
+            re: config.scale.re / 2.0,
+            im: config.scale.im / 2.0,
+        },
+        ..config.clone()
+    };
+    let [zoomed_left, zoomed_top, zoomed_right, zoomed_bottom] = plotted(&zoomed);
+    assert_near(
+        zoomed_right - zoomed_left,
+        (right - left) / 2.0,
+        2.0,
+        "width after zooming out",
+    );
+    assert_near(
+        zoomed_bottom - zoomed_top,
+        (bottom - top) / 2.0,
+        2.0,
+        "height after zooming out",
+    );
+}
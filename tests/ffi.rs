@@ -0,0 +1,48 @@
+//! The C bindings reject configs that can't be rendered instead of rendering them.
+#![cfg(feature = "ffi")]
+
+use fractal_renderer::ffi::*;
+use std::ffi::CStr;
+
+fn last_error() -> String {
+    let message = fr_last_error_message();
+    assert!(!message.is_null());
+    unsafe { CStr::from_ptr(message) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[test]
+fn invalid_values_are_rejected() {
+    let config = fr_config_new(FR_ALGO_MANDELBROT);
+    assert!(!config.is_null());
+    unsafe {
+        assert_eq!(fr_config_set_iterations(config, 0), FR_INVALID_ARGUMENT);
+        assert_eq!(
+            fr_config_set_limits(config, f64::NAN, 1.0),
+            FR_INVALID_ARGUMENT
+        );
+        assert!(last_error().contains("finite"), "{}", last_error());
+        assert_eq!(
+            fr_config_set_scale(config, f64::INFINITY, 1.0),
+            FR_INVALID_ARGUMENT
+        );
+        assert_eq!(
+            fr_config_set_position(config, 0.0, f64::NAN),
+            FR_INVALID_ARGUMENT
+        );
+        assert_eq!(
+            fr_config_set_exposure(config, f64::INFINITY),
+            FR_INVALID_ARGUMENT
+        );
+        assert_eq!(
+            fr_config_set_julia(config, f64::NEG_INFINITY, 0.0),
+            FR_INVALID_ARGUMENT
+        );
+
+        assert_eq!(fr_config_set_size(config, 4, 4), FR_OK);
+        let mut out = [0; 4 * 4 * 3];
+        assert_eq!(fr_render(config, out.as_mut_ptr(), out.len()), FR_OK);
+        fr_config_free(config);
+    }
+}
@@ -0,0 +1,26 @@
+//! The checked in `include/fractal_renderer.h` is the header cbindgen generates from `src/ffi.rs`.
+//!
+//! Run with `UPDATE_FFI_HEADER=1` to update it.
+#![cfg(feature = "ffi")]
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+const GENERATED: &str = include_str!(concat!(env!("OUT_DIR"), "/fractal_renderer.h"));
+
+#[test]
+fn header_is_current() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("include/fractal_renderer.h");
+    if env::var_os("UPDATE_FFI_HEADER").is_some() {
+        fs::write(&path, GENERATED).unwrap();
+        return;
+    }
+    let checked_in = fs::read_to_string(&path).unwrap();
+    assert!(
+        checked_in == GENERATED,
+        "{} is out of date with src/ffi.rs; run the ffi_header test with UPDATE_FFI_HEADER=1 to \
+         update it",
+        path.display()
+    );
+}
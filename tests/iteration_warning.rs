@@ -0,0 +1,32 @@
+//! The warning about too few iterations stays quiet for the default views.
+
+use fractal_renderer::{render, Imaginary};
+
+mod common;
+use common::options;
+
+fn needs_more_iterations(args: &[&str]) -> bool {
+    let config = options(args).expect("the options are valid").config;
+    render(&config).unwrap().stats.needs_more_iterations()
+}
+
+#[test]
+fn default_views_are_quiet() {
+    for size in [&["16", "16"][..], &["32", "32"], &["64", "48"], &[]] {
+        assert!(!needs_more_iterations(size), "{:?}", size);
+        let julia: Vec<&str> = ["-a", "julia", "--julia-real", "0", "--julia-imaginary", "0"]
+            .iter()
+            .chain(size)
+            .copied()
+            .collect();
+        assert!(!needs_more_iterations(&julia), "{:?}", julia);
+    }
+    let constant = Imaginary {
+        re: -0.8,
+        im: 0.156,
+    };
+    let mut config = options(&["--julia", "-0.8+0.156i"]).unwrap().config;
+    assert_eq!(config.julia, Some(constant));
+    config.iterations = 50;
+    assert!(!render(&config).unwrap().stats.needs_more_iterations());
+}
@@ -0,0 +1,116 @@
+//! Julia mode is orthogonal to the formula, and the Julia sets from before it still parse.
+
+use fractal_renderer::{
+    batch, get_image, get_image_seeded, session, Algo, Config, Imaginary, OptionsError,
+};
+
+mod common;
+use common::{matches, options};
+
+const CONSTANT: Imaginary = Imaginary {
+    re: -0.8,
+    im: 0.156,
+};
+
+#[test]
+fn legacy_arguments_are_julia_mode() {
+    let legacy = options(&[
+        "-a",
+        "julia",
+        "--julia-real",
+        "-0.8",
+        "--julia-imaginary",
+        "0.156",
+    ])
+    .unwrap()
+    .config;
+    let julia = options(&["--julia", "-0.8+0.156i"]).unwrap().config;
+    assert_eq!(legacy, julia);
+    assert_eq!(julia.algo, Algo::Mandelbrot);
+    assert_eq!(julia.julia, Some(CONSTANT));
+    // Both axes are centered on the origin, not on the main cardioid.
+    assert_eq!(julia.pos, Imaginary::ZERO);
+    assert_eq!(
+        options(&["-a", "mandelbrot", "--julia", "-0.8+0.156i"])
+            .unwrap()
+            .config,
+        julia
+    );
+
+    assert!(matches(&["-a", "julia", "--julia-real", "-0.8"]).is_err());
+    assert!(matches(&["--julia", "-0.8+0.156i", "--julia-real", "-0.8"]).is_err());
+    assert!(matches!(
+        options(&["-a", "fern", "--julia", "-0.8+0.156i"]),
+        Err(OptionsError::Conflict(_))
+    ));
+}
+
+#[test]
+fn julia_mode_iterates_from_the_pixel() {
+    let mut mandelbrot = Config::new(Algo::Mandelbrot);
+    mandelbrot.width = 48;
+    mandelbrot.height = 32;
+    let julia = Config {
+        julia: Some(CONSTANT),
+        ..mandelbrot.clone()
+    };
+    assert_ne!(get_image(&mandelbrot), get_image(&julia));
+    // The fern has no Julia set.
+    let fern = Config {
+        iterations: 10_000,
+        algo: Algo::BarnsleyFern,
+        ..mandelbrot
+    };
+    let fern_julia = Config {
+        julia: Some(CONSTANT),
+        ..fern.clone()
+    };
+    assert_eq!(get_image_seeded(&fern, 1), get_image_seeded(&fern_julia, 1));
+}
+
+#[test]
+fn legacy_manifests_are_julia_mode() {
+    let table = |s: &str| -> toml::value::Table { toml::from_str(s).unwrap() };
+    let base = Config::default();
+    let legacy = batch::apply(
+        &base,
+        &table("algo = \"julia\"\njulia_set = { re = -0.8, im = 0.156 }"),
+    )
+    .unwrap();
+    let julia = batch::apply(&base, &table("julia = \"-0.8+0.156i\"")).unwrap();
+    assert_eq!(legacy, julia);
+    assert_eq!(julia.julia, Some(CONSTANT));
+
+    let zero = batch::apply(&base, &table("algo = \"julia\"")).unwrap();
+    assert_eq!(zero.julia, Some(Imaginary::ZERO));
+    assert_eq!(zero.algo, Algo::Mandelbrot);
+
+    // Like `-a`, a new `algo` leaves Julia mode unless `julia` is given with it.
+    let fern = batch::apply(&julia, &table("algo = \"fern\"")).unwrap();
+    assert_eq!(fern.julia, None);
+}
+
+#[test]
+fn legacy_session_logs_are_julia_mode() {
+    let path = std::env::temp_dir().join(format!(
+        "fractal-renderer-julia-session-{}.jsonl",
+        std::process::id()
+    ));
+    std::fs::write(
+        &path,
+        "{\"time\":1,\"config\":{\"algo\":\"julia\",\"julia_set\":{\"re\":-0.8,\"im\":0.156}}}\n\
+         {\"time\":2,\"config\":{\"algo\":\"julia\"}}\n\
+         {\"time\":3,\"config\":{\"julia\":{\"re\":-0.8,\"im\":0.156}}}\n",
+    )
+    .unwrap();
+    let entries = session::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let julia: Vec<_> = entries.iter().map(|entry| entry.config.julia).collect();
+    assert_eq!(
+        julia,
+        [Some(CONSTANT), Some(Imaginary::ZERO), Some(CONSTANT)]
+    );
+    assert!(entries
+        .iter()
+        .all(|entry| entry.config.algo == Algo::Mandelbrot));
+}
@@ -0,0 +1,87 @@
+//! `Location::parse_kfr`, with the `.kfr` files in `tests/fixtures/locations` and malformed ones.
+
+use fractal_renderer::location::{Location, LocationError};
+use fractal_renderer::{Algo, Config};
+
+const SEAHORSE_VALLEY: &str = include_str!("fixtures/locations/seahorse-valley.kfr");
+const DEEP_SPIRAL: &str = include_str!("fixtures/locations/deep-spiral.kfr");
+
+#[test]
+fn seahorse_valley() {
+    let location = Location::parse_kfr(SEAHORSE_VALLEY).unwrap();
+    // The closest f64; the rest of the digits are in `center_low`.
+    let re: f64 = "-0.743643887037158704752191506114774".parse().unwrap();
+    let im: f64 = "0.131825904205311970493132056385139".parse().unwrap();
+    assert_eq!(location.center.re, re);
+    assert_eq!(location.center.im, im);
+    assert_eq!(location.zoom, 2.5e5);
+    assert_eq!(location.iterations, Some(2000));
+
+    let mut config = Config::new(Algo::Mandelbrot);
+    location.apply(&mut config);
+    assert_eq!(config.pos, location.center);
+    assert_eq!(config.pos_low, location.center_low);
+    // A zoom of 1 shows 4 units vertically, a scale of 1 shows 1.
+    assert_eq!(config.scale.re, 2.5e5 / 4.0);
+    assert_eq!(config.scale.im, 2.5e5 / 4.0);
+    assert_eq!(config.iterations, 2000);
+}
+
+#[test]
+fn deep_spiral_keeps_the_digits_beyond_f64() {
+    let location = Location::parse_kfr(DEEP_SPIRAL).unwrap();
+    assert_eq!(location.zoom, 4e20);
+    assert_eq!(location.iterations, Some(20000));
+    let re: f64 = "-1.768610493014677074503175653270226520239677907588665494812927"
+        .parse()
+        .unwrap();
+    assert_eq!(location.center.re, re);
+    assert_ne!(location.center_low.re, 0.0);
+    assert_ne!(location.center_low.im, 0.0);
+    // The low parts are below the last digit of the high parts.
+    assert!(location.center_low.re.abs() <= f64::EPSILON * location.center.re.abs());
+    assert!(location.center_low.im.abs() <= f64::EPSILON * location.center.im.abs());
+}
+
+#[test]
+fn iterations_and_unknown_keys_are_optional() {
+    let location = Location::parse_kfr("Re: 0.25\nIm: -0.5\n\nZoom: 8\nSomethingNew: 1\n").unwrap();
+    assert_eq!(location.center.re, 0.25);
+    assert_eq!(location.center.im, -0.5);
+    assert_eq!(location.zoom, 8.0);
+    assert_eq!(location.iterations, None);
+}
+
+#[test]
+fn malformed() {
+    let parse = Location::parse_kfr;
+    assert!(matches!(
+        parse("Re: 0\nIm 0\nZoom: 1"),
+        Err(LocationError::Syntax(2))
+    ));
+    assert!(matches!(parse(""), Err(LocationError::Missing("Re"))));
+    assert!(matches!(
+        parse("Re: 0\nZoom: 1"),
+        Err(LocationError::Missing("Im"))
+    ));
+    assert!(matches!(
+        parse("Re: 0\nIm: 0"),
+        Err(LocationError::Missing("Zoom"))
+    ));
+    for (text, key) in [
+        ("Re: zero\nIm: 0\nZoom: 1", "Re"),
+        ("Re: 0\nIm: 1e\nZoom: 1", "Im"),
+        ("Re: 0\nIm: 0\nZoom: lots", "Zoom"),
+        ("Re: 0\nIm: 0\nZoom: 0", "Zoom"),
+        ("Re: 0\nIm: 0\nZoom: -4", "Zoom"),
+        ("Re: 0\nIm: 0\nZoom: inf", "Zoom"),
+        ("Re: 0\nIm: 0\nZoom: 1\nIterations: 1.5", "Iterations"),
+        ("Re: 0\nIm: 0\nZoom: 1\nIterations: -1", "Iterations"),
+        ("Re: 0\nIm: 0\nZoom: 1\nPower: two", "Power"),
+    ] {
+        match parse(text) {
+            Err(LocationError::Value(invalid, _)) => assert_eq!(invalid, key, "{:?}", text),
+            other => panic!("{:?} parsed to {:?}", text, other),
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! `mapping::pixel_to_complex` and `mapping::complex_to_pixel` are inverses, for random views
+//! with every orientation, both directions of the imaginary axis and exponential maps.
+
+use fractal_renderer::{mapping, Algo, Config, Imaginary, Orientation, Rotation};
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+const CASES: usize = 2000;
+
+fn random_config(rng: &mut SmallRng) -> Config {
+    let mut config = Config::new(Algo::Mandelbrot);
+    config.width = rng.gen_range(16..4096);
+    config.height = rng.gen_range(16..4096);
+    config.pos = Imaginary {
+        re: rng.gen_range(-2.0..2.0),
+        im: rng.gen_range(-2.0..2.0),
+    };
+    // From 1 / 1000 to 10 000, and not necessarily the same on both axes.
+    config.scale = Imaginary {
+        re: 10_f64.powf(rng.gen_range(-3.0..4.0)),
+        im: 10_f64.powf(rng.gen_range(-3.0..4.0)),
+    };
+    let rotations = [
+        Rotation::None,
+        Rotation::Quarter,
+        Rotation::Half,
+        Rotation::ThreeQuarters,
+    ];
+    config.orientation = Orientation {
+        rotation: rotations[rng.gen_range(0..4)],
+        flip_h: rng.gen(),
+        flip_v: rng.gen(),
+    };
+    config.y_up = rng.gen();
+    config.pixel_centers = rng.gen();
+    config.exp_map = rng.gen_bool(0.2);
+    if config.exp_map {
+        // Far down the strip, the offsets from the center vanish when added to it.
+        config.pos = Imaginary::default();
+    }
+    config
+}
+
+/// The most a round trip may move a pixel: a few units in the last place of the coordinates,
+/// in pixels.
+fn tolerance(config: &Config) -> f64 {
+    let viewport = config.viewport();
+    let magnitude = viewport
+        .min
+        .re
+        .abs()
+        .max(viewport.max.re.abs())
+        .max(viewport.min.im.abs().max(viewport.max.im.abs()));
+    let pixels_per_unit =
+        config.scale.re.max(config.scale.im) * config.width.max(config.height) as f64;
+    1e-9 + 16.0 * f64::EPSILON * magnitude * pixels_per_unit
+}
+
+#[test]
+fn pixel_round_trips() {
+    let mut rng = SmallRng::seed_from_u64(134);
+    for _ in 0..CASES {
+        let config = random_config(&mut rng);
+        let (width, height) = (config.width as f64, config.height as f64);
+        let (x, y) = (rng.gen_range(0.0..width), rng.gen_range(0.0..height));
+        let z = mapping::pixel_to_complex(&config, x, y);
+        let (x2, y2) = mapping::complex_to_pixel(&config, z);
+        let mut dx = (x2 - x).abs();
+        if config.exp_map {
+            // The angle wraps around the width, in the unoriented image.
+            dx = dx.min((dx - width).abs()).min((dx - height).abs());
+        }
+        let tolerance = tolerance(&config);
+        assert!(
+            dx <= tolerance && (y2 - y).abs() <= tolerance,
+            "({}, {}) went to {:?} and back to ({}, {}) with {:?}",
+            x,
+            y,
+            z,
+            x2,
+            y2,
+            (
+                config.width,
+                config.height,
+                config.pos,
+                config.scale,
+                config.orientation,
+                config.y_up,
+                config.exp_map
+            )
+        );
+    }
+}
+
+#[test]
+fn point_round_trips() {
+    let mut rng = SmallRng::seed_from_u64(134);
+    for _ in 0..CASES {
+        let mut config = random_config(&mut rng);
+        config.exp_map = false;
+        let viewport = config.viewport();
+        let z = Imaginary {
+            re: rng.gen_range(viewport.min.re..viewport.max.re),
+            im: rng.gen_range(viewport.min.im..viewport.max.im),
+        };
+        let (x, y) = mapping::complex_to_pixel(&config, z);
+        let z2 = mapping::pixel_to_complex(&config, x, y);
+        // The tolerance in pixels, in units.
+        let tolerance = tolerance(&config)
+            / (config.scale.re.min(config.scale.im) * config.height.min(config.width) as f64);
+        assert!(
+            (z2.re - z.re).abs() <= tolerance && (z2.im - z.im).abs() <= tolerance,
+            "{:?} went to ({}, {}) and back to {:?}",
+            z,
+            x,
+            y,
+            z2
+        );
+    }
+}
+
+#[test]
+fn samples_round_trip() {
+    let mut rng = SmallRng::seed_from_u64(134);
+    for _ in 0..CASES {
+        let config = random_config(&mut rng);
+        let (x, y) = (
+            rng.gen_range(0..config.width),
+            rng.gen_range(0..config.height),
+        );
+        let (sample_x, sample_y) = mapping::sample_position(&config, x as f64, y as f64);
+        assert_eq!(
+            mapping::sample_index(&config, sample_x, sample_y),
+            (x as f64, y as f64)
+        );
+    }
+}
@@ -0,0 +1,84 @@
+//! The output path and format from `-o` and `--format`, and the file actually written.
+
+use fractal_renderer::format::{Format, STDOUT};
+use fractal_renderer::{write_image, Options, OptionsError, RGB};
+
+mod common;
+
+fn options(args: &[&str]) -> Options {
+    common::options(args).expect("the options are valid")
+}
+
+#[test]
+fn from_the_command_line() {
+    let output = |args: &[&str]| {
+        let options = options(args);
+        (options.output.filename, options.output.format)
+    };
+    let preferred = Format::preferred();
+    assert_eq!(
+        output(&["-o", "out"]),
+        (format!("out.{}", preferred.extension()), preferred)
+    );
+    assert_eq!(output(&[]), output(&["-o", "output"]));
+    assert_eq!(output(&["-o", "-"]), (STDOUT.into(), preferred));
+    assert_eq!(
+        output(&["-o", "out", "--format", "ppm"]),
+        ("out.ppm".into(), Format::Ppm)
+    );
+    // The extension wins over `--format`.
+    assert_eq!(
+        output(&["-o", "out.ppm", "--format", "png"]),
+        ("out.ppm".into(), Format::Ppm)
+    );
+    assert_eq!(
+        output(&["-o", "-", "--format", "ppm"]),
+        (STDOUT.into(), Format::Ppm)
+    );
+}
+
+#[test]
+fn formats_of_optional_features() {
+    for (path, format) in [("out.png", Format::Png), ("dir/out.avif", Format::Avif)] {
+        let result = common::options(&["-o", path]);
+        if format.is_supported() {
+            let options = result.expect("the options are valid");
+            assert_eq!(
+                (options.output.filename.as_str(), options.output.format),
+                (path, format)
+            );
+        } else {
+            assert!(
+                matches!(result, Err(OptionsError::Unsupported(_))),
+                "{}: {:?}",
+                path,
+                result
+            );
+        }
+    }
+}
+
+#[test]
+fn writes_to_the_resolved_path() {
+    let dir = std::env::temp_dir().join(format!("fractal-renderer-output-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let output = dir.join("out");
+    let mut options = options(&[
+        "-o",
+        output.to_str().unwrap(),
+        "--format",
+        "ppm",
+        "16",
+        "16",
+    ]);
+    options.output.open = false;
+
+    let written = write_image(&options, vec![RGB::new(1, 2, 3); 16 * 16]).unwrap();
+    let expected = dir.join("out.ppm");
+    assert_eq!(written, expected.to_str().unwrap());
+    assert!(!output.exists());
+    let data = std::fs::read(&expected).unwrap();
+    assert!(data.starts_with(b"P6\n16 16\n255\n"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,77 @@
+//! `Palette::parse_map` and `Palette::parse_ggr` reject malformed Fractint maps and GIMP
+//! gradients with an error instead of panicking or allocating what the file claims to need.
+
+use fractal_renderer::palette::Palette;
+use fractal_renderer::RGB;
+
+const SEGMENT: &str = "0.0 0.25 0.5 1.0 0.5 0.0 1.0 0.0 0.0 1.0 1.0 0 0";
+
+#[test]
+fn maps_round_trip() {
+    let palette = Palette::parse_map("255 136 0 orange\n\n0 0 255\n").unwrap();
+    assert_eq!(palette.color_at(0.0), RGB::new(255, 136, 0));
+    assert_eq!(palette.color_at(1.0), RGB::new(0, 0, 255));
+    let map = palette.to_map();
+    assert_eq!(map.lines().count(), 256);
+    assert_eq!(
+        Palette::parse_map(&map).unwrap().color_at(1.0),
+        RGB::new(0, 0, 255)
+    );
+}
+
+#[test]
+fn malformed_maps() {
+    for map in [
+        "",
+        "\n\n",
+        "255 136 0",
+        "255 136 0\n0 0",
+        "255 136 0\n0 0 256",
+        "255 136 0\n0 0 -1",
+        "255 136 0\n0 0 1.5",
+        "255 136 0\nff 88 00",
+        "255,136,0\n0,0,255",
+    ] {
+        assert!(Palette::parse_map(map).is_err(), "{:?}", map);
+    }
+}
+
+#[test]
+fn gradients_round_trip() {
+    let ggr = format!("GIMP Gradient\nName: Test\n1\n{}\n", SEGMENT);
+    let palette = Palette::parse_ggr(&ggr).unwrap();
+    assert_eq!(palette.color_at(0.0), RGB::new(255, 128, 0));
+    assert_eq!(palette.color_at(0.5), RGB::new(0, 0, 255));
+    assert_eq!(Palette::parse_ggr(&palette.to_ggr()).unwrap(), palette);
+    // Without a name.
+    assert!(Palette::parse_ggr(&format!("GIMP Gradient\n1\n{}", SEGMENT)).is_ok());
+}
+
+#[test]
+fn malformed_gradients() {
+    let short = SEGMENT.rsplitn(4, ' ').last().unwrap();
+    for ggr in [
+        String::new(),
+        "GIMP Palette\n1\n".to_owned() + SEGMENT,
+        "GIMP Gradient".to_owned(),
+        "GIMP Gradient\nName: Test".to_owned(),
+        "GIMP Gradient\nName: Test\nmany\n".to_owned() + SEGMENT,
+        "GIMP Gradient\n-1\n".to_owned() + SEGMENT,
+        "GIMP Gradient\n0\n".to_owned(),
+        "GIMP Gradient\n2\n".to_owned() + SEGMENT,
+        "GIMP Gradient\n1\n".to_owned() + short,
+        "GIMP Gradient\n1\n".to_owned() + &SEGMENT.replace("1.0", "one"),
+        "GIMP Gradient\n1\nNaN 0.5 1 0 0 0 1 1 1 1 1 0 0".to_owned(),
+    ] {
+        assert!(Palette::parse_ggr(&ggr).is_err(), "{:?}", ggr);
+    }
+}
+
+#[test]
+fn huge_segment_counts_are_errors() {
+    // Preallocating for these would abort, or overflow when doubled.
+    for count in [u32::MAX as usize, usize::MAX / 2, usize::MAX] {
+        let ggr = format!("GIMP Gradient\nName: Huge\n{}\n{}\n", count, SEGMENT);
+        assert!(Palette::parse_ggr(&ggr).is_err(), "{}", count);
+    }
+}
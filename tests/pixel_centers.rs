@@ -0,0 +1,94 @@
+//! Renders of the same view at different sizes line up with `Config::pixel_centers`.
+
+use fractal_renderer::{get_image, get_samples, Algo, Config, Imaginary, RGB};
+
+/// A view outside of the set, where the colors change smoothly, and the same view at twice the
+/// size.
+fn views(pixel_centers: bool) -> (Config, Config) {
+    let mut config = Config::new(Algo::Mandelbrot);
+    config.pos = Imaginary { re: 0.9, im: 0.7 };
+    config.scale = Imaginary { re: 2.0, im: 2.0 };
+    config.width = 96;
+    config.height = 64;
+    config.pixel_centers = pixel_centers;
+    let mut double = config.clone();
+    double.width *= 2;
+    double.height *= 2;
+    (config, double)
+}
+
+/// The mean of every 2×2 values of `image`, of `width` by `height`.
+fn downsample<T: Copy>(
+    image: &[T],
+    width: usize,
+    height: usize,
+    mean: impl Fn([T; 4]) -> T,
+) -> Vec<T> {
+    let mut downsampled = Vec::with_capacity(width / 2 * height / 2);
+    for y in (0..height).step_by(2) {
+        for x in (0..width).step_by(2) {
+            downsampled.push(mean([
+                image[y * width + x],
+                image[y * width + x + 1],
+                image[(y + 1) * width + x],
+                image[(y + 1) * width + x + 1],
+            ]));
+        }
+    }
+    downsampled
+}
+
+#[test]
+fn downsampled_render_matches() {
+    let (config, double) = views(true);
+    let small = get_image(&config);
+    let large = get_image(&double);
+    let large = downsample(&large, 192, 128, |pixels| {
+        let channel = |channel: fn(&RGB) -> u8| {
+            let sum: u32 = pixels.iter().map(|pixel| channel(pixel) as u32).sum();
+            ((sum + 2) / 4) as u8
+        };
+        RGB::new(
+            channel(|pixel| pixel.r),
+            channel(|pixel| pixel.g),
+            channel(|pixel| pixel.b),
+        )
+    });
+    for (a, b) in small.iter().zip(&large) {
+        let close = |a: u8, b: u8| (a as i16 - b as i16).abs() <= 1;
+        assert!(
+            close(a.r, b.r) && close(a.g, b.g) && close(a.b, b.b),
+            "{:?} and the downsampled {:?} differ by more than rounding",
+            a,
+            b
+        );
+    }
+}
+
+/// The mean difference between the iterations of the render and the downsampled iterations of
+/// twice the size.
+fn iterations_error(pixel_centers: bool) -> f64 {
+    let (config, double) = views(pixel_centers);
+    let iterations = |config: &Config| -> Vec<f64> {
+        get_samples(config)
+            .iter()
+            .map(|sample| sample.iterations)
+            .collect()
+    };
+    let small = iterations(&config);
+    let large = downsample(&iterations(&double), 192, 128, |values| {
+        values.iter().sum::<f64>() / 4.0
+    });
+    small
+        .iter()
+        .zip(&large)
+        .map(|(a, b)| (a - b).abs())
+        .sum::<f64>()
+        / small.len() as f64
+}
+
+#[test]
+fn corners_are_offset() {
+    let (centers, corners) = (iterations_error(true), iterations_error(false));
+    assert!(centers * 4.0 < corners, "{} and {}", centers, corners);
+}
@@ -0,0 +1,44 @@
+//! The standard deviation of `--post blur:SIGMA` is limited, so its kernel, 6 sigma wide, is
+//! never too large to allocate.
+
+use fractal_renderer::post::{gaussian_blur, parse, FloatImage, PostOp, MAX_BLUR_SIGMA};
+use fractal_renderer::OptionsError;
+
+mod common;
+use common::{matches, options};
+
+#[test]
+fn huge_blurs_are_rejected() {
+    let max = format!("blur:{}", MAX_BLUR_SIGMA);
+    assert_eq!(parse(&max).unwrap(), [PostOp::Blur(MAX_BLUR_SIGMA)]);
+    for ops in ["blur:10001", "blur:1e30", "bloom:0.3,blur:3.4e38"] {
+        assert!(parse(ops).is_err(), "{}", ops);
+        assert!(matches(&["--post", ops]).is_err(), "{}", ops);
+    }
+}
+
+#[test]
+fn blurs_are_limited_to_the_image() {
+    assert!(options(&["200", "100", "--post", "blur:200"]).is_ok());
+    match options(&["200", "100", "--post", "sharpen:1,blur:201"]) {
+        Err(OptionsError::Value { flag, reason }) => {
+            assert_eq!(flag, "--post");
+            assert!(reason.contains("larger than the image"), "{}", reason);
+        }
+        other => panic!("validated to {:?}", other),
+    }
+}
+
+#[test]
+fn blurs_larger_than_the_image_are_limited_to_it() {
+    let mut image = FloatImage {
+        pixels: vec![[0.0; 3]; 4 * 3],
+        width: 4,
+        height: 3,
+    };
+    image.pixels[0] = [1.0; 3];
+    let mut limited = image.clone();
+    gaussian_blur(&mut image, MAX_BLUR_SIGMA);
+    gaussian_blur(&mut limited, 4.0);
+    assert_eq!(image, limited);
+}
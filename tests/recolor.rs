@@ -0,0 +1,60 @@
+//! Recoloring the samples of a config, like the GUI does when only the colors change, gives the
+//! same image as rendering the new config from scratch.
+
+use fractal_renderer::view::{self, View};
+use fractal_renderer::{get_image, get_samples, Algo, Config, Imaginary, InteriorMapping, RGB};
+
+fn recolored(old: &Config, new: &Config) {
+    assert!(view::recolors(old, new), "{:?}", new);
+    let samples = get_samples(old);
+    assert_eq!(
+        view::colorize(&samples, new, View::Color, None),
+        get_image(new),
+        "{:?}",
+        new
+    );
+}
+
+#[test]
+fn recoloring_is_a_fresh_render() {
+    let mut mandelbrot = Config::new(Algo::Mandelbrot);
+    mandelbrot.width = 48;
+    mandelbrot.height = 32;
+    mandelbrot.iterations = 200;
+    let julia = Config {
+        julia: Some(Imaginary {
+            re: -0.8,
+            im: 0.156,
+        }),
+        ..mandelbrot.clone()
+    };
+    for old in [mandelbrot, julia] {
+        let changes = [
+            Config {
+                exposure: old.exposure * 3.0,
+                ..old.clone()
+            },
+            Config {
+                interior_exposure: 0.5,
+                interior_mapping: InteriorMapping::Sqrt,
+                ..old.clone()
+            },
+            Config {
+                inside: !old.inside,
+                ..old.clone()
+            },
+            Config {
+                stable_limit: old.stable_limit / 2.0,
+                ..old.clone()
+            },
+            Config {
+                primary_color: RGB::new(255, 0, 128),
+                secondary_color: RGB::new(0, 64, 0),
+                ..old.clone()
+            },
+        ];
+        for new in &changes {
+            recolored(&old, new);
+        }
+    }
+}
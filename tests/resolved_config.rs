@@ -0,0 +1,147 @@
+//! The renders through [`ResolvedConfig`] are exactly the same as those of the functions for
+//! single pixels, whatever the config.
+
+use fractal_renderer::resolved::ResolvedConfig;
+use fractal_renderer::{
+    get_image, get_recursive_pixel, get_samples, sample, Algo, Config, Imaginary, Orientation,
+    Precision, Rotation,
+};
+
+/// Configs which take every path of the coordinate mapping and the precisions.
+fn configs() -> Vec<(&'static str, Config)> {
+    let mut base = Config::new(Algo::Mandelbrot);
+    base.width = 48;
+    base.height = 32;
+    base.iterations = 100;
+    let julia = Config::new_julia(
+        Algo::Mandelbrot,
+        Imaginary {
+            re: -0.8,
+            im: 0.156,
+        },
+    );
+    vec![
+        ("default", base.clone()),
+        (
+            "julia",
+            Config {
+                width: 48,
+                height: 32,
+                ..julia
+            },
+        ),
+        (
+            "turned and flipped",
+            Config {
+                orientation: Orientation {
+                    rotation: Rotation::Quarter,
+                    flip_h: true,
+                    flip_v: false,
+                },
+                ..base.clone()
+            },
+        ),
+        (
+            "y up",
+            Config {
+                y_up: true,
+                ..base.clone()
+            },
+        ),
+        (
+            "pixel centers",
+            Config {
+                pixel_centers: true,
+                ..base.clone()
+            },
+        ),
+        (
+            "stretched",
+            Config {
+                scale: Imaginary { re: 0.8, im: 0.3 },
+                ..base.clone()
+            },
+        ),
+        (
+            "exp map",
+            Config {
+                exp_map: true,
+                ..base.clone()
+            },
+        ),
+        (
+            "double-double",
+            Config {
+                precision: Precision::DoubleDouble,
+                ..base.clone()
+            },
+        ),
+        (
+            "unsmoothed outside only",
+            Config {
+                smooth: false,
+                inside: false,
+                stable_limit: 8.0,
+                ..base
+            },
+        ),
+    ]
+}
+
+#[test]
+fn pixels_match() {
+    for (name, config) in configs() {
+        let resolved = ResolvedConfig::new(&config);
+        for y in 0..config.height {
+            for x in 0..config.width {
+                let (fx, fy) = (x as f64, y as f64);
+                assert_eq!(
+                    resolved.sample(fx, fy),
+                    sample(&config, fx, fy),
+                    "{} at ({}, {})",
+                    name,
+                    x,
+                    y
+                );
+                assert_eq!(
+                    resolved.pixel(x, y),
+                    get_recursive_pixel(&config, x, y),
+                    "{} at ({}, {})",
+                    name,
+                    x,
+                    y
+                );
+                // Supersamples between the pixels.
+                assert_eq!(
+                    resolved.sample(fx + 0.25, fy + 0.75),
+                    sample(&config, fx + 0.25, fy + 0.75),
+                    "{} at ({}, {})",
+                    name,
+                    x,
+                    y
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn renders_match() {
+    for (name, config) in configs() {
+        let pixels: Vec<_> = (0..config.height)
+            .flat_map(|y| (0..config.width).map(move |x| (x, y)))
+            .collect();
+        let image: Vec<_> = pixels
+            .iter()
+            .map(|&(x, y)| get_recursive_pixel(&config, x, y))
+            .collect();
+        let samples: Vec<_> = pixels
+            .iter()
+            .map(|&(x, y)| sample(&config, x as f64, y as f64))
+            .collect();
+        assert_eq!(get_image(&config), image, "{}", name);
+        assert_eq!(get_samples(&config), samples, "{}", name);
+        let rendered = fractal_renderer::render(&config).unwrap().image;
+        assert_eq!(rendered.contents(), image.as_slice(), "{}", name);
+    }
+}
@@ -0,0 +1,116 @@
+//! `serve::tile_config`, which turns the URL of a tile request into the config to render, or the
+//! message of a `400` response for bad coordinates, tiles out of range and bad query parameters.
+
+use fractal_renderer::serve::{tile_config, MAX_ITERATIONS, MAX_ZOOM, TILE_SIZE};
+use fractal_renderer::{Algo, ComplexRect, Config, Imaginary, RGB};
+
+const EXTENT: ComplexRect = ComplexRect {
+    min: Imaginary { re: -2.0, im: -2.0 },
+    max: Imaginary { re: 2.0, im: 2.0 },
+};
+
+fn tile(url: &str) -> Result<Config, String> {
+    tile_config(&Config::new(Algo::Mandelbrot), EXTENT, url)
+}
+
+#[test]
+fn tiles_split_the_extent() {
+    let config = tile("/tile/0/0/0.png").unwrap();
+    assert_eq!((config.width, config.height), (TILE_SIZE, TILE_SIZE));
+    assert_eq!(config.viewport(), EXTENT);
+
+    // The bottom right quarter.
+    let config = tile("/tile/1/1/1.png").unwrap();
+    assert_eq!(
+        config.viewport(),
+        ComplexRect {
+            min: Imaginary { re: 0.0, im: 0.0 },
+            max: Imaginary { re: 2.0, im: 2.0 },
+        }
+    );
+    let deepest = format!("/tile/{}/0/0.png", MAX_ZOOM);
+    assert!(tile(&deepest).is_ok());
+}
+
+#[test]
+fn query_parameters_override_the_config() {
+    let config = tile(
+        "/tile/2/1/3.png?algo=julia&iterations=500&exposure=1.5&julia_re=-0.8&julia_im=0.156\
+         &primary=ff8000&secondary=000000",
+    )
+    .unwrap();
+    assert_eq!(config.algo, Algo::Mandelbrot);
+    assert_eq!(config.iterations, 500);
+    assert_eq!(config.exposure, 1.5);
+    assert_eq!(
+        config.julia,
+        Some(Imaginary {
+            re: -0.8,
+            im: 0.156
+        })
+    );
+    assert_eq!(config.primary_color, RGB::new(255, 128, 0));
+    assert_eq!(config.secondary_color, RGB::new(0, 0, 0));
+}
+
+#[test]
+fn bad_coordinates() {
+    for url in [
+        "/tile/0/0/0",
+        "/tile/0/0.png",
+        "/tile/0/0/0/0.png",
+        "/tile/a/0/0.png",
+        "/tile/0/-1/0.png",
+        "/tile/0/0/1.5.png",
+        "/tile//0/0.png",
+        "/tile/99999999999999999999/0/0.png",
+    ] {
+        assert!(tile(url).is_err(), "{}", url);
+    }
+}
+
+#[test]
+fn out_of_range_tiles() {
+    for url in [
+        "/tile/0/1/0.png",
+        "/tile/0/0/1.png",
+        "/tile/3/8/0.png",
+        "/tile/3/0/8.png",
+        "/tile/49/0/0.png",
+        "/tile/64/0/0.png",
+    ] {
+        assert!(tile(url).is_err(), "{}", url);
+    }
+}
+
+#[test]
+fn bad_query_parameters() {
+    let too_many = format!("iterations={}", MAX_ITERATIONS as u64 + 1);
+    for query in [
+        "algo=fern",
+        "algo=barnsleyfern",
+        "iterations=0",
+        "iterations=-1",
+        "iterations=1.5",
+        "iterations=99999999999",
+        &too_many,
+        "exposure=inf",
+        "exposure=NaN",
+        "exposure=",
+        "julia_re=one",
+        "primary=orange",
+        "secondary=fff",
+        "zoom=2",
+    ] {
+        let url = format!("/tile/0/0/0.png?{}", query);
+        assert!(tile(&url).is_err(), "{}", url);
+    }
+    let most = format!("/tile/0/0/0.png?iterations={}", MAX_ITERATIONS);
+    assert_eq!(tile(&most).unwrap().iterations, MAX_ITERATIONS);
+}
+
+#[test]
+fn the_fern_cannot_be_tiled() {
+    let fern = Config::new(Algo::BarnsleyFern);
+    assert!(tile_config(&fern, EXTENT, "/tile/0/0/0.png").is_err());
+}
@@ -0,0 +1,85 @@
+//! Pixel counts near the `u32` boundary are widened before they're multiplied, and images too
+//! large to address are rejected before anything is allocated.
+
+use fractal_renderer::resources::{estimate_memory, image_bytes, peak_memory};
+use fractal_renderer::{render, Algo, Config, OptionsError, RenderError};
+
+mod common;
+use common::options;
+
+fn sized(width: u32, height: u32) -> Config {
+    Config {
+        width,
+        height,
+        ..Config::new(Algo::Mandelbrot)
+    }
+}
+
+#[test]
+fn one_side_at_the_maximum() {
+    let bytes = u32::MAX as usize * 3;
+    assert_eq!(image_bytes(&sized(u32::MAX, 1)), Some(bytes));
+    assert_eq!(image_bytes(&sized(1, u32::MAX)), Some(bytes));
+}
+
+#[cfg(target_pointer_width = "64")]
+#[test]
+fn product_beyond_u32() {
+    // 2^32 pixels, which is 0 when multiplied as `u32`.
+    assert_eq!(image_bytes(&sized(1 << 16, 1 << 16)), Some(3 << 32));
+    assert_eq!(
+        image_bytes(&sized(u32::MAX, 2)),
+        Some(u32::MAX as usize * 2 * 3)
+    );
+    // Widened, the pixels fit in a `usize`, but the bytes don't.
+    assert_eq!(image_bytes(&sized(u32::MAX, u32::MAX)), None);
+}
+
+#[cfg(target_pointer_width = "32")]
+#[test]
+fn product_beyond_u32() {
+    assert_eq!(image_bytes(&sized(1 << 16, 1 << 16)), None);
+    assert_eq!(image_bytes(&sized(u32::MAX, 2)), None);
+    assert_eq!(image_bytes(&sized(u32::MAX, u32::MAX)), None);
+}
+
+#[test]
+fn estimates_saturate() {
+    let config = sized(u32::MAX, u32::MAX);
+    assert_eq!(estimate_memory(&config), u64::MAX);
+    let fern = Config {
+        algo: Algo::BarnsleyFern,
+        ..config
+    };
+    assert_eq!(estimate_memory(&fern), u64::MAX);
+    let mut options = options(&["--post", "blur:2"]).unwrap();
+    options.config.width = u32::MAX;
+    options.config.height = u32::MAX;
+    assert_eq!(peak_memory(&options), u64::MAX);
+
+    // Just past `u32::MAX` pixels.
+    let config = sized(1 << 16, (1 << 16) + 1);
+    let pixels = (1_u64 << 16) * ((1 << 16) + 1);
+    assert!(estimate_memory(&config) >= pixels * 3);
+}
+
+#[test]
+fn render_rejects_unaddressable_sizes() {
+    match render(&sized(u32::MAX, u32::MAX)) {
+        Err(RenderError::TooLarge(width, height)) => {
+            assert_eq!((width, height), (u32::MAX, u32::MAX));
+        }
+        other => panic!("rendered {:?}", other.map(|output| output.stats)),
+    }
+}
+
+#[test]
+fn validation_rejects_unaddressable_sizes() {
+    let max = u32::MAX.to_string();
+    match options(&[&max, &max]) {
+        Err(OptionsError::Unsupported(reason)) => {
+            assert!(reason.contains("too large"), "{}", reason)
+        }
+        other => panic!("validated to {:?}", other),
+    }
+}
@@ -0,0 +1,19 @@
+//! `--slice` renders a band directly, so the flags of the other modes can't be used with it.
+
+mod common;
+use common::matches;
+
+#[test]
+fn conflicts_with_other_modes() {
+    assert!(matches(&["--slice", "1/4"]).is_ok());
+    for args in [
+        &["--checkpoint", "render.ckpt"][..],
+        &["--time-limit", "10s"],
+        &["--julia-grid", "3x3"],
+        &["--buddhabrot"],
+    ] {
+        let args: Vec<&str> = ["--slice", "1/4"].iter().chain(args).copied().collect();
+        let err = matches(&args).unwrap_err();
+        assert_eq!(err.kind(), clap::ErrorKind::ArgumentConflict, "{:?}", args);
+    }
+}
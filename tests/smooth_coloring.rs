@@ -0,0 +1,83 @@
+//! Smooth coloring stays finite and continuous with a low `limit`, where points escape close to
+//! the limit and, within the unit circle, the smoothing term is undefined.
+
+use fractal_renderer::{get_image, get_samples, Algo, Config, Imaginary, RGB};
+
+/// The most a color channel may change from one pixel to the next outside of the set.
+const THRESHOLD: u8 = 24;
+
+/// The whole set, at `--limit <limit> --stable-limit <limit>`.
+fn config(limit: f64) -> Config {
+    let mut config = Config::new(Algo::Mandelbrot);
+    config.pos = Imaginary { re: -0.5, im: 0.0 };
+    config.scale = Imaginary { re: 0.4, im: 0.4 };
+    config.width = 96;
+    config.height = 64;
+    config.iterations = 50;
+    config.limit = limit;
+    config.stable_limit = limit;
+    config
+}
+
+fn assert_in_range(config: &Config) {
+    for sample in get_samples(config) {
+        assert!(
+            sample.iterations.is_finite()
+                && (0.0..=config.iterations as f64).contains(&sample.iterations),
+            "with a limit of {}, the orbit ended at {:?} after {} of {} iterations",
+            config.limit,
+            sample.final_z,
+            sample.iterations,
+            config.iterations
+        );
+    }
+}
+
+fn difference(a: RGB, b: RGB) -> u8 {
+    let channel = |a: u8, b: u8| if a > b { a - b } else { b - a };
+    channel(a.r, b.r)
+        .max(channel(a.g, b.g))
+        .max(channel(a.b, b.b))
+}
+
+/// Asserts that no pixel outside of the set differs from its neighbors by more than
+/// [`THRESHOLD`].
+fn assert_continuous(config: &Config) {
+    let image = get_image(config);
+    let width = config.width as usize;
+    for (i, &pixel) in image.iter().enumerate() {
+        let (x, y) = (i % width, i / width);
+        let right = if x + 1 < width { Some(i + 1) } else { None };
+        let below = Some(i + width).filter(|&j| j < image.len());
+        for neighbor in right.into_iter().chain(below) {
+            assert!(
+                difference(pixel, image[neighbor]) <= THRESHOLD,
+                "with a limit of {}, ({}, {}) is {:?} but its neighbor is {:?}",
+                config.limit,
+                x,
+                y,
+                pixel,
+                image[neighbor]
+            );
+        }
+    }
+}
+
+#[test]
+fn limit_of_two() {
+    let config = config(2.0);
+    assert_in_range(&config);
+    // Away from the set, where the iterations change slowly.
+    assert_continuous(&Config {
+        pos: Imaginary { re: 0.9, im: 0.7 },
+        scale: Imaginary { re: 2.0, im: 2.0 },
+        ..config
+    });
+}
+
+#[test]
+fn limit_within_the_unit_circle() {
+    for limit in [1.0, 0.75, 0.5] {
+        assert_in_range(&config(limit));
+    }
+}
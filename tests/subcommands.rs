@@ -0,0 +1,74 @@
+//! Every subcommand takes only the arguments which apply to it, so the flags of the other modes
+//! are unexpected instead of silently ignored.
+
+use clap::ErrorKind;
+use fractal_renderer::build_cli;
+
+mod common;
+use common::{matches, options};
+
+#[test]
+fn every_subcommand_builds() {
+    // In debug builds, clap checks that the conflicts and requirements of the arguments of every
+    // subcommand are its arguments.
+    build_cli().debug_assert();
+}
+
+#[test]
+fn flags_of_other_modes_are_unexpected() {
+    for args in [
+        &["compare", "a.ppm", "b.ppm", "--exposure", "9"][..],
+        &["compare", "a.ppm", "b.ppm", "--serve-cache", "8"],
+        &["info", "--animate", "5"],
+        &["info", "--zoom-to", "1e-5"],
+        &["info", "--batch", "jobs.toml"],
+        &["animate", "5", "--serve", "127.0.0.1:8080"],
+        &["animate", "5", "--batch-parallel", "2"],
+        &["animate", "5", "--buddhabrot"],
+        &["batch", "jobs.toml", "--zoom-to", "1e-5"],
+        &["batch", "jobs.toml", "--compare-tolerance", "2"],
+        &["serve", "127.0.0.1:8080", "--keyframes", "path.json"],
+        &["serve", "127.0.0.1:8080", "--gui"],
+    ] {
+        let err = matches(args).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnknownArgument, "{:?}", args);
+    }
+}
+
+#[test]
+fn own_and_shared_flags_are_taken() {
+    for args in [
+        &[
+            "compare",
+            "a.ppm",
+            "b.ppm",
+            "--compare-tolerance",
+            "2",
+            "-v",
+        ][..],
+        &["info", "400", "300", "--exposure", "9"],
+        &["animate", "5", "400", "300", "--zoom-to", "1e-5", "-e", "9"],
+        &["batch", "jobs.toml", "--batch-parallel", "2", "-i", "80"],
+        &[
+            "serve",
+            "127.0.0.1:8080",
+            "--serve-cache",
+            "8",
+            "-a",
+            "fern",
+        ],
+        // `render` is the bare invocation, which takes the flags of every mode.
+        &["render", "--batch", "jobs.toml", "--batch-parallel", "2"],
+    ] {
+        assert!(matches(args).is_ok(), "{:?}", args);
+    }
+    // The arguments a subcommand doesn't take have their defaults.
+    let compare = options(&["compare", "a.ppm", "b.ppm"]).unwrap();
+    assert_eq!(
+        compare.config,
+        options(&["--compare", "a.ppm", "b.ppm"]).unwrap().config
+    );
+    let info = options(&["info", "-e", "9"]).unwrap();
+    assert!(info.dry_run);
+    assert_eq!(info.config.exposure, 9.0);
+}
@@ -0,0 +1,152 @@
+//! The corner pixels of square and non-square images map to the corners of the viewport, with the
+//! horizontal and vertical scales independent of each other.
+//!
+//! The sizes and scales are powers of two, so the coordinates are exact.
+
+use fractal_renderer::{mapping, Algo, ComplexRect, Config, Imaginary};
+
+fn config(width: u32, height: u32, scale: Imaginary) -> Config {
+    let mut config = Config::new(Algo::Mandelbrot);
+    config.width = width;
+    config.height = height;
+    config.pos = Imaginary { re: -0.5, im: 0.25 };
+    config.scale = scale;
+    config
+}
+
+fn point(re: f64, im: f64) -> Imaginary {
+    Imaginary { re, im }
+}
+
+/// The points sampled for the top left, top right, bottom left and bottom right pixels.
+fn corner_pixels(config: &Config) -> [Imaginary; 4] {
+    let (right, bottom) = (config.width - 1, config.height - 1);
+    [
+        config.pixel_to_point(0, 0),
+        config.pixel_to_point(right, 0),
+        config.pixel_to_point(0, bottom),
+        config.pixel_to_point(right, bottom),
+    ]
+}
+
+/// Asserts that the corners of the image are those of the viewport `expected`.
+fn assert_viewport(config: &Config, expected: ComplexRect) {
+    assert_eq!(config.viewport(), expected);
+    let (width, height) = (config.width as f64, config.height as f64);
+    let corner = |x, y| mapping::pixel_to_complex(config, x, y);
+    assert_eq!(corner(0.0, 0.0), expected.min);
+    assert_eq!(corner(width, 0.0), point(expected.max.re, expected.min.im));
+    assert_eq!(corner(0.0, height), point(expected.min.re, expected.max.im));
+    assert_eq!(corner(width, height), expected.max);
+}
+
+#[test]
+fn square() {
+    let config = config(128, 128, Imaginary { re: 0.5, im: 0.5 });
+    // 2 units on both axes around the center.
+    assert_viewport(
+        &config,
+        ComplexRect {
+            min: point(-1.5, -0.75),
+            max: point(0.5, 1.25),
+        },
+    );
+    // A pixel is 1/64 units.
+    assert_eq!(
+        corner_pixels(&config),
+        [
+            point(-1.5, -0.75),
+            point(0.484375, -0.75),
+            point(-1.5, 1.234375),
+            point(0.484375, 1.234375),
+        ]
+    );
+}
+
+#[test]
+fn wide() {
+    let config = config(256, 128, Imaginary { re: 0.5, im: 0.25 });
+    // The aspect ratio, 2, over the horizontal scale and 1 over the vertical.
+    assert_viewport(
+        &config,
+        ComplexRect {
+            min: point(-2.5, -1.75),
+            max: point(1.5, 2.25),
+        },
+    );
+    // A pixel is 1/64 units wide and 1/32 tall.
+    assert_eq!(
+        corner_pixels(&config),
+        [
+            point(-2.5, -1.75),
+            point(1.484375, -1.75),
+            point(-2.5, 2.21875),
+            point(1.484375, 2.21875),
+        ]
+    );
+}
+
+#[test]
+fn tall() {
+    let config = config(64, 256, Imaginary { re: 2.0, im: 0.125 });
+    assert_viewport(
+        &config,
+        ComplexRect {
+            min: point(-0.5625, -3.75),
+            max: point(-0.4375, 4.25),
+        },
+    );
+    // A pixel is 1/512 units wide and 1/32 tall.
+    assert_eq!(
+        corner_pixels(&config),
+        [
+            point(-0.5625, -3.75),
+            point(-0.439453125, -3.75),
+            point(-0.5625, 4.21875),
+            point(-0.439453125, 4.21875),
+        ]
+    );
+}
+
+#[test]
+fn pixel_centers() {
+    let mut config = config(256, 128, Imaginary { re: 0.5, im: 0.25 });
+    config.pixel_centers = true;
+    // Half a pixel in from the corners of the viewport.
+    assert_eq!(
+        corner_pixels(&config),
+        [
+            point(-2.4921875, -1.734375),
+            point(1.4921875, -1.734375),
+            point(-2.4921875, 2.234375),
+            point(1.4921875, 2.234375),
+        ]
+    );
+}
+
+#[test]
+fn y_up() {
+    let mut config = config(256, 128, Imaginary { re: 0.5, im: 0.25 });
+    config.y_up = true;
+    // The viewport stays the same, but the top row has the largest imaginary part.
+    assert_eq!(
+        corner_pixels(&config),
+        [
+            point(-2.5, 2.25),
+            point(1.484375, 2.25),
+            point(-2.5, -1.71875),
+            point(1.484375, -1.71875),
+        ]
+    );
+}
+
+#[test]
+fn with_viewport_covers_the_rect() {
+    let rect = ComplexRect {
+        min: point(-2.5, -1.75),
+        max: point(1.5, 2.25),
+    };
+    let config = config(256, 128, Imaginary { re: 1.0, im: 1.0 }).with_viewport(rect);
+    assert_eq!(config.scale, Imaginary { re: 0.5, im: 0.25 });
+    assert_viewport(&config, rect);
+}
@@ -0,0 +1,151 @@
+//! Development tasks, run with `cargo xtask <task>`.
+//!
+//! - `feature-matrix [--test] [CARGO_ARGS]...`: checks fractal-renderer without features, with
+//!   each feature and with every pair of them, like `cargo hack --feature-powerset --depth 2`.
+//!   With `--test`, the tests are run for each combination too. The remaining arguments are passed
+//!   to cargo.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+
+/// Features which only group others, and are covered by those.
+const SKIPPED: &[&str] = &["default"];
+
+fn main() {
+    let mut args = env::args().skip(1);
+    match args.next().as_deref() {
+        Some("feature-matrix") => {
+            let mut test = false;
+            let cargo_args: Vec<String> = args
+                .filter(|arg| {
+                    let is_test = arg == "--test";
+                    test |= is_test;
+                    !is_test
+                })
+                .collect();
+            if !feature_matrix(test, &cargo_args) {
+                process::exit(1);
+            }
+        }
+        Some(task) => {
+            eprintln!("error: unknown task {:?}\n\n{}", task, USAGE);
+            process::exit(2);
+        }
+        None => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    }
+}
+
+const USAGE: &str = "usage: cargo xtask feature-matrix [--test] [CARGO_ARGS]...";
+
+fn root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("xtask is in the workspace")
+        .to_owned()
+}
+
+/// The features of the manifest of fractal-renderer, except those in [`SKIPPED`], and the
+/// optional dependencies no feature enables, like `sysinfo`, which are features of their own.
+fn features(root: &Path) -> Vec<String> {
+    let manifest =
+        std::fs::read_to_string(root.join("Cargo.toml")).expect("failed to read Cargo.toml");
+    let manifest: toml::Value = manifest.parse().expect("Cargo.toml is valid TOML");
+    let declared = manifest.get("features").and_then(toml::Value::as_table);
+    let mut features: Vec<String> = declared
+        .map(|features| features.keys().cloned().collect())
+        .unwrap_or_default();
+    // What the features enable, like `image` for `image/png`.
+    let enabled: Vec<&str> = declared
+        .into_iter()
+        .flatten()
+        .filter(|(feature, _)| !SKIPPED.contains(&feature.as_str()))
+        .filter_map(|(_, enables)| enables.as_array())
+        .flatten()
+        .filter_map(toml::Value::as_str)
+        .map(|enabled| enabled.split('/').next().unwrap_or(enabled))
+        .collect();
+    if let Some(dependencies) = manifest.get("dependencies").and_then(toml::Value::as_table) {
+        for (name, dependency) in dependencies {
+            let optional = dependency.get("optional").and_then(toml::Value::as_bool);
+            if optional == Some(true) && !enabled.contains(&name.as_str()) {
+                features.push(name.clone());
+            }
+        }
+    }
+    features.retain(|feature| !SKIPPED.contains(&feature.as_str()));
+    features
+}
+
+/// No features, each feature, then each pair of features.
+fn combinations(features: &[String]) -> Vec<Vec<&str>> {
+    let mut combinations = vec![Vec::new()];
+    combinations.extend(features.iter().map(|feature| vec![feature.as_str()]));
+    for (i, a) in features.iter().enumerate() {
+        for b in &features[i + 1..] {
+            combinations.push(vec![a.as_str(), b.as_str()]);
+        }
+    }
+    combinations
+}
+
+/// Returns whether every combination succeeded.
+fn feature_matrix(test: bool, cargo_args: &[String]) -> bool {
+    let root = root();
+    let features = features(&root);
+    let combinations = combinations(&features);
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_owned());
+    let mut failed = Vec::new();
+    for (i, combination) in combinations.iter().enumerate() {
+        let features = combination.join(",");
+        let name = if features.is_empty() {
+            "no features"
+        } else {
+            &features
+        };
+        eprintln!("[{}/{}] {}", i + 1, combinations.len(), name);
+        let commands: &[&str] = if test { &["check", "test"] } else { &["check"] };
+        for command in commands {
+            let mut cargo = Command::new(&cargo);
+            cargo
+                .current_dir(&root)
+                .args([*command, "--package", "fractal-renderer"]);
+            // `cargo test --all-targets` skips the doc tests.
+            if *command == "check" {
+                cargo.arg("--all-targets");
+            }
+            let status = cargo
+                .args(["--no-default-features", "--features", &features])
+                .args(cargo_args)
+                .status();
+            match status {
+                Ok(status) if status.success() => {}
+                Ok(_) => {
+                    failed.push(format!("cargo {} with {}", command, name));
+                    break;
+                }
+                Err(err) => {
+                    eprintln!("error: failed to run cargo: {}", err);
+                    return false;
+                }
+            }
+        }
+    }
+    if failed.is_empty() {
+        eprintln!("All {} combinations succeeded.", combinations.len());
+        true
+    } else {
+        eprintln!(
+            "{} of {} combinations failed:",
+            failed.len(),
+            combinations.len()
+        );
+        for failure in failed {
+            eprintln!("  {}", failure);
+        }
+        false
+    }
+}